@@ -1,3 +1,5 @@
+#![no_std]
+
 use crate::Category::{Char, Name, NameStart, PubId, Punct, Whitespace};
 
 fn search_table(c: char, table: &[(char, char)]) -> bool {
@@ -159,6 +161,13 @@ pub trait XmlAsciiChar {
     fn is_xml_whitespace(&self) -> bool;
 
     fn is_xml_punct(&self) -> bool;
+
+    /// https://www.w3.org/TR/REC-xml/#NT-Char for ASCII characters
+    ///
+    /// Only the ASCII subset `#x9 | #xA | #xD | [#x20-#x7F]` is accepted. Bytes from `0x80` on
+    /// are part of multi-byte UTF-8 sequences and never match, so they have to be decoded and
+    /// checked with [`XmlChar::is_xml_char`].
+    fn is_xml_ascii_char(&self) -> bool;
 }
 
 pub trait XmlChar: XmlAsciiChar {
@@ -185,6 +194,11 @@ impl XmlAsciiChar for u8 {
     fn is_xml_punct(&self) -> bool {
         check_ascii(*self as u8, Punct)
     }
+
+    #[inline]
+    fn is_xml_ascii_char(&self) -> bool {
+        check_ascii(*self, Char)
+    }
 }
 
 impl XmlAsciiChar for char {
@@ -197,6 +211,11 @@ impl XmlAsciiChar for char {
     fn is_xml_punct(&self) -> bool {
         self.is_ascii() && check_ascii(*self as u8, Punct)
     }
+
+    #[inline]
+    fn is_xml_ascii_char(&self) -> bool {
+        self.is_ascii() && check_ascii(*self as u8, Char)
+    }
 }
 
 impl XmlChar for char {
@@ -231,3 +250,36 @@ impl XmlChar for char {
         self.is_ascii() && check_ascii(*self as u8, PubId)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ascii_char_matches_char() {
+        for b in 0u8..=0x7F {
+            let c = b as char;
+            assert_eq!(c.is_xml_char(), b.is_xml_ascii_char(), "{:#x}", b);
+            assert_eq!(c.is_xml_char(), c.is_xml_ascii_char(), "{:#x}", b);
+        }
+    }
+
+    #[test]
+    fn ascii_char_rejects_non_ascii() {
+        for b in 0x80u8..=0xFF {
+            assert!(!b.is_xml_ascii_char(), "{:#x}", b);
+        }
+        assert!('\u{e9}'.is_xml_char());
+        assert!(!'\u{e9}'.is_xml_ascii_char());
+    }
+
+    #[test]
+    fn ascii_char_subset() {
+        for b in [0x09, 0x0A, 0x0D, 0x20, b'a', 0x7F] {
+            assert!(b.is_xml_ascii_char(), "{:#x}", b);
+        }
+        for b in [0x00, 0x08, 0x0B, 0x0C, 0x1F] {
+            assert!(!b.is_xml_ascii_char(), "{:#x}", b);
+        }
+    }
+}
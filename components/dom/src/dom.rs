@@ -1,6 +1,12 @@
 #![allow(dead_code)]
 
+use std::iter::once;
+
+use xrs_parser::XML_URI;
+
+use crate::chars::XmlByteExt;
 use crate::error::{Error, Reason, Result};
+use crate::select::Selector;
 use crate::Span;
 
 pub struct Document<'a> {
@@ -16,10 +22,39 @@ impl<'a> Document<'a> {
     pub fn get_root(&self) -> &Element {
         &self.root
     }
+
+    /// Text of `span` in the document, e.g. of a comment
+    pub fn slice(&self, span: Span) -> Result<&'a str> {
+        decode(span, self.bytes)
+    }
+
+    /// Elements matching the CSS selector `selector` in document order
+    ///
+    /// See [`crate::select`] for the supported selectors.
+    pub fn select(&self, selector: &str) -> Result<Vec<&Element>> {
+        Selector::parse(selector)?.select(self)
+    }
+}
+
+/// Kind of a node in the element tree
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Node {
+    Element,
+    /// Comment with its content
+    Comment(Span),
+    ProcessingInstruction {
+        target: Span,
+        data: Option<Span>,
+    },
 }
 
+/// Element of the tree
+///
+/// Comments and processing instructions are children as well, so the text following them is
+/// their tail. They have no tag, attributes and text.
 pub struct Element {
     offset: usize,
+    node: Node,
     tag: Span,
     text: Span,
     tail: Span,
@@ -39,6 +74,7 @@ impl Element {
     pub fn new(offset: usize, tag_len: usize, attrs_len: usize) -> Self {
         Self {
             offset,
+            node: Node::Element,
             tag: Span::new(offset + 1, tag_len),
             text: Span::empty(),
             tail: Span::empty(),
@@ -48,6 +84,40 @@ impl Element {
         }
     }
 
+    fn new_node(offset: usize, node: Node) -> Self {
+        Self {
+            offset,
+            node,
+            tag: Span::empty(),
+            text: Span::empty(),
+            tail: Span::empty(),
+            children: vec![],
+            attributes: Span::empty(),
+            namespaces: None,
+        }
+    }
+
+    /// Comment starting at `offset` with the content `content`
+    pub fn new_comment(offset: usize, content: Span) -> Self {
+        Self::new_node(offset, Node::Comment(content))
+    }
+
+    /// Processing instruction starting at `offset`
+    pub fn new_processing_instruction(offset: usize, target: Span, data: Option<Span>) -> Self {
+        Self::new_node(offset, Node::ProcessingInstruction { target, data })
+    }
+
+    // node
+
+    pub fn node(&self) -> Node {
+        self.node
+    }
+
+    /// Whether this is an element and not a comment or processing instruction
+    pub fn is_element(&self) -> bool {
+        self.node == Node::Element
+    }
+
     // offset
 
     pub fn offset(&self) -> usize {
@@ -64,6 +134,88 @@ impl Element {
         self.tag
     }
 
+    // attributes
+
+    /// Unparsed attributes of the start tag
+    pub fn attributes<'a>(&self, doc: &Document<'a>) -> Result<&'a str> {
+        decode(self.attributes, doc.bytes)
+    }
+
+    pub fn attributes_span(&self) -> Span {
+        self.attributes
+    }
+
+    /// Unexpanded value of the attribute `name`
+    pub fn attribute<'a>(&self, doc: &Document<'a>, name: &str) -> Result<Option<&'a str>> {
+        Ok(parse_attributes(self.attributes(doc)?)
+            .into_iter()
+            .find(|(attr_name, _)| *attr_name == name)
+            .map(|(_, value)| value))
+    }
+
+    /// Attributes as namespace URI, local name and unexpanded value
+    ///
+    /// Prefixes are resolved against the namespace declarations of the element and of its
+    /// `ancestors`, which start with the root element. The `xmlns` and `xmlns:*` declarations
+    /// themselves are skipped. Unprefixed attributes have no namespace, even within a default
+    /// namespace.
+    pub fn attributes_ns<'a>(
+        &self,
+        doc: &Document<'a>,
+        ancestors: &[&Element],
+    ) -> Result<impl Iterator<Item = (Option<&'a str>, &'a str, &'a str)>> {
+        let raw = self.attributes(doc)?;
+        let mut result = vec![];
+        for (name, value) in parse_attributes(raw) {
+            if name == "xmlns" || name.starts_with("xmlns:") {
+                continue;
+            }
+            let (namespace, local_name) = match name.split_once(':') {
+                None => (None, name),
+                Some(("xml", local_name)) => (Some(XML_URI), local_name),
+                Some((prefix, local_name)) => match self.resolve_prefix(doc, prefix, ancestors)? {
+                    Some(namespace) => (Some(namespace), local_name),
+                    None => {
+                        let span =
+                            Span::new(self.attributes.start + offset_in(raw, name), name.len());
+                        return Err(Error::new(
+                            span,
+                            Reason::UndeclaredNamespacePrefix(prefix.to_string()),
+                        ));
+                    }
+                },
+            };
+            result.push((namespace, local_name, value));
+        }
+        Ok(result.into_iter())
+    }
+
+    /// Unexpanded URI bound to `prefix` by the element or else by the nearest of `ancestors`
+    fn resolve_prefix<'a>(
+        &self,
+        doc: &Document<'a>,
+        prefix: &str,
+        ancestors: &[&Element],
+    ) -> Result<Option<&'a str>> {
+        for element in once(self).chain(ancestors.iter().rev().copied()) {
+            for (declared, uri) in element.namespaces() {
+                if !declared.is_empty() && doc.slice(*declared)? == prefix {
+                    return doc.slice(*uri).map(Some);
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    // namespaces
+
+    /// Namespace declarations of the start tag as prefix and unexpanded URI
+    ///
+    /// The prefix of a default namespace declaration is empty.
+    pub fn namespaces(&self) -> &[(Span, Span)] {
+        self.namespaces.as_deref().unwrap_or(&[])
+    }
+
     // children
 
     pub fn children(&self) -> &[Element] {
@@ -74,6 +226,11 @@ impl Element {
         &mut self.children
     }
 
+    /// Child elements without comments and processing instructions
+    pub fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter(|child| child.is_element())
+    }
+
     // text / tail
 
     #[inline]
@@ -117,4 +274,58 @@ impl Element {
     pub fn push_tail(&mut self, span: Span) {
         self.tail = span; // TODO
     }
+
+    pub fn push_namespace(&mut self, prefix: Span, uri: Span) {
+        self.namespaces
+            .get_or_insert_with(Vec::new)
+            .push((prefix, uri));
+    }
+}
+
+/// Offset of `part` in `whole`
+///
+/// `part` must be a slice of `whole` like the names and values of [`parse_attributes`].
+pub(crate) fn offset_in(whole: &str, part: &str) -> usize {
+    part.as_ptr() as usize - whole.as_ptr() as usize
+}
+
+/// Split the unparsed attributes of a start tag into name and raw value
+///
+/// Stops at the first malformed attribute, reporting it is the job of the well-formedness check.
+pub(crate) fn parse_attributes(raw: &str) -> Vec<(&str, &str)> {
+    let mut result = vec![];
+    let mut rest = raw;
+
+    loop {
+        rest = trim_xml_whitespace(rest);
+        let name_end = match rest.find(|c: char| c == '=' || is_xml_whitespace(c)) {
+            Some(name_end) if name_end > 0 => name_end,
+            _ => return result,
+        };
+        let (name, tail) = rest.split_at(name_end);
+        let tail = match trim_xml_whitespace(tail).strip_prefix('=') {
+            Some(tail) => trim_xml_whitespace(tail),
+            None => return result,
+        };
+        let quote = match tail.chars().next() {
+            Some(quote) if quote == '"' || quote == '\'' => quote,
+            _ => return result,
+        };
+        let tail = &tail[1..];
+        match tail.find(quote) {
+            Some(value_end) => {
+                result.push((name, &tail[..value_end]));
+                rest = &tail[value_end + 1..];
+            }
+            None => return result,
+        }
+    }
+}
+
+fn is_xml_whitespace(c: char) -> bool {
+    c.is_ascii() && (c as u8).is_xml_whitespace()
+}
+
+fn trim_xml_whitespace(s: &str) -> &str {
+    s.trim_start_matches(is_xml_whitespace)
 }
@@ -15,9 +15,25 @@ impl Error {
         Self { span, reason }
     }
 
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
     /// Return whether error is caused by not well formed XML
     pub fn is_not_wf(&self) -> bool {
-        !matches!(self.reason, Reason::Io(_))
+        !matches!(self.reason, Reason::Io(_) | Reason::InvalidSelector(_)) && !self.is_invalid()
+    }
+
+    /// Return whether error is caused by a well formed but invalid document
+    pub fn is_invalid(&self) -> bool {
+        matches!(
+            self.reason,
+            Reason::RootElementMismatch { .. }
+                | Reason::UndeclaredElement(_)
+                | Reason::InvalidContent(_)
+                | Reason::MissingAttribute(_)
+                | Reason::FixedAttributeMismatch { .. }
+        )
     }
 
     fn message(&self) -> String {
@@ -37,6 +53,9 @@ impl Error {
             Reason::NoEqAfterName => "missing `=` after attribute name".to_string(),
             Reason::UnquotedValue => "missing `\"` around attribute value".to_string(),
             Reason::DuplicatedAttribute(other) => format!("attribute already exists at {}", other),
+            Reason::UndeclaredNamespacePrefix(prefix) => {
+                format!("namespace prefix `{}` is not declared", prefix)
+            }
             Reason::InvalidEntity => "unknown or invalid entity".to_string(),
             Reason::UnexpectedDocType => "unexpected doctype".to_string(),
             Reason::UnexpectedDecl => "xml decl not at start of file".to_string(),
@@ -44,7 +63,23 @@ impl Error {
             Reason::IllegalPatternInComment => "`--` not allowed in comment".to_string(),
             Reason::PrologCharacters => "non-whitespace characters in prolog".to_string(),
             Reason::InvalidName => "invalid XML name".to_string(),
+            Reason::InvalidQName => "invalid qualified name".to_string(),
+            Reason::InvalidSelector(selector) => format!("invalid selector `{}`", selector),
             Reason::IllegalChar(c) => format!("illegal character: `{:?}`", c.escape_debug()),
+            Reason::RootElementMismatch { expected, found } => {
+                format!("expected root element <{}> but got <{}>", expected, found)
+            }
+            Reason::UndeclaredElement(name) => format!("element <{}> is not declared", name),
+            Reason::InvalidContent(name) => {
+                format!("content of <{}> does not match its declaration", name)
+            }
+            Reason::MissingAttribute(name) => format!("required attribute `{}` missing", name),
+            Reason::FixedAttributeMismatch { name, expected } => {
+                format!(
+                    "attribute `{}` must have the fixed value {:?}",
+                    name, expected
+                )
+            }
         }
     }
 }
@@ -80,14 +115,19 @@ pub enum Reason {
     // not-wf
     Utf8(Utf8Error),
     UnexpectedEof,
-    EndEventMismatch { expected: String, found: String },
+    EndEventMismatch {
+        expected: String,
+        found: String,
+    },
     UnexpectedToken(String),
     InvalidBang,
     XmlDeclWithoutVersion,
     NameWithQuote,
     NoEqAfterName,
     UnquotedValue,
+    /// Attribute name already used at the offset
     DuplicatedAttribute(usize),
+    UndeclaredNamespacePrefix(String),
     InvalidEntity,
     UnexpectedDocType,
     UnexpectedDecl,
@@ -95,5 +135,22 @@ pub enum Reason {
     PrologCharacters,
     IllegalPatternInComment,
     InvalidName,
+    InvalidQName,
     IllegalChar(char),
+
+    // query
+    InvalidSelector(String),
+
+    // invalid
+    RootElementMismatch {
+        expected: String,
+        found: String,
+    },
+    UndeclaredElement(String),
+    InvalidContent(String),
+    MissingAttribute(String),
+    FixedAttributeMismatch {
+        name: String,
+        expected: String,
+    },
 }
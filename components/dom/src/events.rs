@@ -0,0 +1,306 @@
+//! Conversion between pull events and the owned DOM
+//!
+//! A [`DomBuilder`] builds an [`OwnedDocument`] from [`XmlEvent`]s and
+//! [`OwnedElement::events`] emits the events of an element again, so a document can be read
+//! as stream, changed as tree and written as stream.
+
+use std::collections::VecDeque;
+use std::slice;
+
+use xrs_parser::{Attribute, XmlEvent};
+
+use crate::chars::XmlBytesExt;
+use crate::error::{Error, Reason, Result};
+use crate::owned::{NodeKind, OwnedDocument, OwnedElement};
+use crate::Span;
+
+/// Builder of an [`OwnedDocument`] from pull events
+///
+/// The attributes are taken from the start tag events, so events of a
+/// [`xrs_parser::Reader`] need their attributes from `Reader::drain_attributes` first. A start
+/// tag with the `empty` flag still needs its end tag. The XML and document type declarations
+/// and comments and processing instructions outside the root element are skipped.
+#[derive(Debug, Default)]
+pub struct DomBuilder {
+    /// Open elements with the root element first
+    stack: Vec<OwnedElement>,
+    root: Option<OwnedElement>,
+}
+
+impl DomBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the next event of the document
+    pub fn push(&mut self, event: XmlEvent) -> Result<()> {
+        match event {
+            XmlEvent::STag(stag) => self.start(stag.name.into_owned(), stag.attrs)?,
+            XmlEvent::EmptyElement(stag) => {
+                let name = stag.name.to_string();
+                self.start(stag.name.into_owned(), stag.attrs)?;
+                self.end(&name)?;
+            }
+            XmlEvent::ETag(etag) => self.end(&etag.name)?,
+            XmlEvent::Characters(text) | XmlEvent::CData(text) | XmlEvent::Whitespace(text) => {
+                self.characters(&text)?
+            }
+            XmlEvent::CharRef(char_ref) => {
+                self.characters(char_ref.character.encode_utf8(&mut [0; 4]))?
+            }
+            XmlEvent::Comment(comment) => {
+                if let Some(parent) = self.stack.last_mut() {
+                    parent.append_child(OwnedElement::comment(comment));
+                }
+            }
+            XmlEvent::PI(pi) => {
+                if let Some(parent) = self.stack.last_mut() {
+                    let data = pi.data.unwrap_or_default();
+                    parent.append_child(OwnedElement::processing_instruction(pi.target, data));
+                }
+            }
+            XmlEvent::Error(err) => {
+                return Err(Error::new(
+                    Span::empty(),
+                    Reason::UnexpectedToken(format!("{:?}", err)),
+                ))
+            }
+            XmlEvent::XmlDecl(_) | XmlEvent::Dtd(_) => {}
+        }
+        Ok(())
+    }
+
+    /// Return the document after the end tag of the root element was pushed
+    pub fn finish(self) -> Result<OwnedDocument> {
+        match self.root {
+            Some(root) if self.stack.is_empty() => Ok(OwnedDocument::new(root)),
+            _ => Err(Error::new(Span::empty(), Reason::UnexpectedEof)),
+        }
+    }
+
+    fn start(&mut self, name: String, attrs: Vec<Attribute>) -> Result<()> {
+        if self.root.is_some() {
+            return Err(Error::new(Span::empty(), Reason::TrailingContent));
+        }
+        let mut element = OwnedElement::new(name);
+        for attr in attrs {
+            element.set_attribute(attr.name, attr.value);
+        }
+        self.stack.push(element);
+        Ok(())
+    }
+
+    fn end(&mut self, name: &str) -> Result<()> {
+        let element = match self.stack.pop() {
+            Some(element) if element.name() == name => element,
+            Some(element) => {
+                return Err(Error::new(
+                    Span::empty(),
+                    Reason::EndEventMismatch {
+                        expected: element.name().to_string(),
+                        found: name.to_string(),
+                    },
+                ))
+            }
+            None => return Err(Error::new(Span::empty(), Reason::TrailingContent)),
+        };
+        match self.stack.last_mut() {
+            Some(parent) => parent.append_child(element),
+            None => self.root = Some(element),
+        }
+        Ok(())
+    }
+
+    fn characters(&mut self, text: &str) -> Result<()> {
+        match self.stack.last_mut() {
+            Some(element) => element.push_text(text),
+            None if text.as_bytes().only_xml_whitespace() => {}
+            None => return Err(Error::new(Span::empty(), Reason::PrologCharacters)),
+        }
+        Ok(())
+    }
+}
+
+impl OwnedElement {
+    /// Events of the element and its content in document order without its tail
+    ///
+    /// Elements without text and children have a start tag with the `empty` flag, which is
+    /// followed by the end tag like the events of a [`xrs_parser::Reader`]. Empty texts and
+    /// tails are skipped.
+    pub fn events(&self) -> Events<'_> {
+        let mut events = Events {
+            stack: vec![],
+            pending: VecDeque::new(),
+        };
+        events.open(self);
+        events
+    }
+}
+
+/// Iterator over the events of an [`OwnedElement`], see [`OwnedElement::events`]
+pub struct Events<'a> {
+    /// Open elements with their remaining children
+    stack: Vec<(&'a OwnedElement, slice::Iter<'a, OwnedElement>)>,
+    /// Events to return before the next node is visited
+    pending: VecDeque<XmlEvent<'a>>,
+}
+
+impl<'a> Events<'a> {
+    /// Queue the events of a node up to its content
+    fn open(&mut self, node: &'a OwnedElement) {
+        match node.kind() {
+            NodeKind::Comment => self.pending.push_back(XmlEvent::comment(node.text())),
+            NodeKind::ProcessingInstruction => {
+                let data = Some(node.text()).filter(|data| !data.is_empty());
+                self.pending
+                    .push_back(XmlEvent::pi(node.name(), data.map(Into::into)));
+            }
+            NodeKind::Element => {
+                let empty = node.text().is_empty() && node.children().is_empty();
+                let attrs: Vec<_> = node
+                    .attributes()
+                    .iter()
+                    .map(|(name, value)| Attribute::new(name.as_str(), value.as_str()))
+                    .collect();
+                self.pending
+                    .push_back(XmlEvent::stag_with_attrs(node.name(), empty, attrs));
+                if !empty {
+                    if !node.text().is_empty() {
+                        self.pending.push_back(XmlEvent::characters(node.text()));
+                    }
+                    self.stack.push((node, node.children().iter()));
+                    return;
+                }
+                self.pending.push_back(XmlEvent::etag(node.name()));
+            }
+        }
+        self.push_tail(node);
+    }
+
+    /// Queue the tail of a node unless it is the element the events were requested for
+    fn push_tail(&mut self, node: &'a OwnedElement) {
+        if !self.stack.is_empty() && !node.tail().is_empty() {
+            self.pending.push_back(XmlEvent::characters(node.tail()));
+        }
+    }
+}
+
+impl<'a> Iterator for Events<'a> {
+    type Item = XmlEvent<'a>;
+
+    fn next(&mut self) -> Option<XmlEvent<'a>> {
+        while self.pending.is_empty() {
+            let (element, children) = self.stack.last_mut()?;
+            match children.next() {
+                Some(child) => self.open(child),
+                None => {
+                    let element = *element;
+                    self.stack.pop();
+                    self.pending.push_back(XmlEvent::etag(element.name()));
+                    self.push_tail(element);
+                }
+            }
+        }
+        self.pending.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use xrs_parser::Reader;
+
+    use super::*;
+
+    /// Events of the reader with the attributes in the start tags
+    fn read_events(input: &str) -> Vec<XmlEvent<'static>> {
+        let mut reader = Reader::new(input);
+        let mut events = vec![];
+        while let Some(event) = reader.next().unwrap() {
+            let event = match event {
+                XmlEvent::STag(mut stag) => {
+                    stag.attrs = reader.drain_attributes();
+                    XmlEvent::STag(stag)
+                }
+                event => event,
+            };
+            events.push(event.into_owned());
+        }
+        events
+    }
+
+    fn build(events: &[XmlEvent]) -> Result<OwnedDocument> {
+        let mut builder = DomBuilder::new();
+        for event in events {
+            builder.push(event.clone())?;
+        }
+        builder.finish()
+    }
+
+    #[test]
+    fn round_trip() {
+        let events = read_events(
+            "<r a=\"1\" b=\"&lt;\"><x/>text<y c=\"2\">inner<!-- c --><?pi data?><?empty?></y>tail\
+             <z><e/></z></r>",
+        );
+        let doc = build(&events).unwrap();
+        let round_trip: Vec<_> = doc.root().events().collect();
+        assert_eq!(events, round_trip);
+    }
+
+    #[test]
+    fn build_document() {
+        let doc = build(&read_events(
+            "<?xml version='1.0'?>\n<!-- prolog --><r>a<b x='1'/>&#x21;<![CDATA[<c>]]></r>\n",
+        ))
+        .unwrap();
+        assert_eq!("<r>a<b x=\"1\"/>!&lt;c&gt;</r>", doc.to_xml_string());
+    }
+
+    #[test]
+    fn mutate_subtree() {
+        let mut doc = build(&read_events("<r><a><b/></a><c/></r>")).unwrap();
+        let a = &mut doc.root_mut().children_mut()[0];
+        a.set_attribute("id", "1");
+        a.children_mut()[0].set_text("text");
+
+        let events: Vec<_> = doc.root().children()[0].events().collect();
+        assert_eq!(
+            vec![
+                XmlEvent::stag_with_attrs("a", false, vec![Attribute::new("id", "1")]),
+                XmlEvent::stag("b", false),
+                XmlEvent::characters("text"),
+                XmlEvent::etag("b"),
+                XmlEvent::etag("a"),
+            ],
+            events
+        );
+    }
+
+    #[test]
+    fn empty_element_events() {
+        let mut builder = DomBuilder::new();
+        builder.push(XmlEvent::stag("r", false)).unwrap();
+        builder
+            .push(XmlEvent::EmptyElement(match XmlEvent::stag("e", true) {
+                XmlEvent::STag(stag) => stag,
+                _ => unreachable!(),
+            }))
+            .unwrap();
+        builder.push(XmlEvent::etag("r")).unwrap();
+        assert_eq!("<r><e/></r>", builder.finish().unwrap().to_xml_string());
+    }
+
+    #[test]
+    fn malformed() {
+        let err = build(&[XmlEvent::stag("a", false), XmlEvent::etag("b")]).unwrap_err();
+        assert!(err.is_not_wf());
+        assert!(build(&[XmlEvent::stag("a", false)]).is_err());
+        assert!(build(&[
+            XmlEvent::stag("a", false),
+            XmlEvent::etag("a"),
+            XmlEvent::stag("b", false),
+        ])
+        .is_err());
+        assert!(build(&[XmlEvent::characters("text")]).is_err());
+    }
+}
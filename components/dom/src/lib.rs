@@ -1,14 +1,23 @@
+use std::fmt;
 use std::str::{from_utf8, Utf8Error};
 
-pub use dom::{Document, Element};
+use crate::chars::XmlStrExt;
+use crate::error::{Error, Reason};
+
+pub use dom::{Document, Element, Node};
+pub use events::DomBuilder;
+pub use owned::{NodeKind, OwnedDocument, OwnedElement};
 
 pub mod chars;
 pub mod dom;
 pub mod error;
+pub mod events;
+pub mod owned;
 pub mod reader;
+pub mod select;
 pub mod validate;
 
-#[derive(Copy, Clone, Eq, PartialEq)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub struct Span {
     start: usize,
     len: usize,
@@ -40,6 +49,7 @@ impl Span {
     }
 }
 
+#[derive(Debug)]
 pub struct QName {
     namespace: Option<Span>,
     prefix: Span,
@@ -66,4 +76,111 @@ impl QName {
     pub fn local_name(&self) -> Span {
         self.local_name
     }
+
+    /// Split the name at `span` of `bytes` into prefix and local name
+    ///
+    /// The spans of the result refer to `bytes` as well. The namespace is not resolved.
+    pub fn parse(span: Span, bytes: &[u8]) -> error::Result<QName> {
+        let name = bytes
+            .get(span.start..span.start.saturating_add(span.len))
+            .ok_or_else(|| Error::new(span, Reason::InvalidQName))?;
+        let name = from_utf8(name).map_err(|err| Error::new(span, Reason::Utf8(err)))?;
+        let start = span.start;
+
+        let mut parts = name.splitn(3, ':');
+        let (prefix, local_name) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(local_name), None, None) => (None, local_name),
+            (Some(prefix), Some(local_name), None) => (Some(prefix), local_name),
+            _ => return Err(Error::new(span, Reason::InvalidQName)),
+        };
+        let prefix = match prefix {
+            Some(prefix) if prefix.is_xml_name() => Span::new(start, prefix.len()),
+            Some(_) => return Err(Error::new(span, Reason::InvalidQName)),
+            None => Span::empty(),
+        };
+        if !local_name.is_xml_name() {
+            return Err(Error::new(span, Reason::InvalidQName));
+        }
+        Ok(QName::new(
+            None,
+            prefix,
+            Span::new(start + name.len() - local_name.len(), local_name.len()),
+        ))
+    }
+
+    /// Prefix and local name
+    pub fn resolve<'a>(&self, bytes: &'a [u8]) -> (Option<&'a str>, &'a str) {
+        let prefix = if self.prefix.is_empty() {
+            None
+        } else {
+            self.prefix.to_str(bytes).ok()
+        };
+        (prefix, self.local_name.to_str(bytes).unwrap_or(""))
+    }
+
+    /// Display the name as `prefix:local`
+    pub fn display<'a>(&self, bytes: &'a [u8]) -> DisplayQName<'a> {
+        let (prefix, local_name) = self.resolve(bytes);
+        DisplayQName { prefix, local_name }
+    }
+}
+
+/// Name of a [`QName`] rendered with [`QName::display`]
+pub struct DisplayQName<'a> {
+    prefix: Option<&'a str>,
+    local_name: &'a str,
+}
+
+impl<'a> fmt::Display for DisplayQName<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.prefix {
+            Some(prefix) => write!(f, "{}:{}", prefix, self.local_name),
+            None => f.write_str(self.local_name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod qname {
+        use super::*;
+
+        #[test]
+        fn unprefixed() {
+            let bytes = b"<item/>";
+            let qname = QName::parse(Span::new(1, 4), bytes).unwrap();
+            assert_eq!((None, "item"), qname.resolve(bytes));
+            assert!(qname.namespace().is_none());
+            assert!(qname.prefix().is_empty());
+            assert_eq!("item", qname.display(bytes).to_string());
+        }
+
+        #[test]
+        fn prefixed() {
+            let bytes = b"<xsl:template>";
+            let qname = QName::parse(Span::new(1, 12), bytes).unwrap();
+            assert_eq!((Some("xsl"), "template"), qname.resolve(bytes));
+            assert_eq!(b"xsl", qname.prefix().to_slice(bytes));
+            assert_eq!(b"template", qname.local_name().to_slice(bytes));
+            assert_eq!("xsl:template", qname.display(bytes).to_string());
+        }
+
+        #[test]
+        fn illegal() {
+            for name in &["a:b:c", ":a", "a:", "1a", "a:1", ""] {
+                let span = Span::new(0, name.len());
+                let err = QName::parse(span, name.as_bytes()).unwrap_err();
+                assert!(err.is_not_wf(), "{}", name);
+                assert_eq!(span, err.span(), "{}", name);
+            }
+        }
+
+        #[test]
+        fn outside_of_bytes() {
+            let err = QName::parse(Span::new(3, 4), b"<item/>").unwrap_err();
+            assert_eq!(Span::new(3, 4), err.span());
+        }
+    }
 }
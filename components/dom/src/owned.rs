@@ -0,0 +1,523 @@
+//! Mutable DOM owning its names and texts
+//!
+//! A parsed [`Document`] only refers to the parsed bytes, so it can not be changed. It can
+//! be converted into an [`OwnedDocument`] with [`Document::to_owned_document`], which stores
+//! the names and the texts with expanded references and can be modified and serialized again.
+
+use std::fmt::Write;
+
+use crate::dom::{parse_attributes, Document, Element, Node};
+use crate::error::{Error, Reason, Result};
+
+/// Document with owned elements
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OwnedDocument {
+    root: OwnedElement,
+}
+
+impl OwnedDocument {
+    pub fn new(root: OwnedElement) -> Self {
+        Self { root }
+    }
+
+    pub fn root(&self) -> &OwnedElement {
+        &self.root
+    }
+
+    pub fn root_mut(&mut self) -> &mut OwnedElement {
+        &mut self.root
+    }
+
+    /// Serialize the document without XML declaration
+    pub fn to_xml_string(&self) -> String {
+        self.root.to_xml_string()
+    }
+
+    /// Serialize the document as Canonical XML in the format of the W3C conformance tests
+    ///
+    /// Attributes are sorted by name, empty elements get an end tag, comments are dropped and
+    /// tabs and line breaks in texts and attribute values are written as character references.
+    pub fn to_canonical_xml(&self) -> String {
+        let mut output = String::new();
+        self.root.write_canonical(&mut output);
+        output
+    }
+}
+
+/// Kind of an [`OwnedElement`]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum NodeKind {
+    #[default]
+    Element,
+    /// Comment with the content as text
+    Comment,
+    /// Processing instruction with the target as name and the data as text
+    ProcessingInstruction,
+}
+
+/// Element with owned name, attributes, texts and children
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct OwnedElement {
+    kind: NodeKind,
+    name: String,
+    attributes: Vec<(String, String)>,
+    text: String,
+    tail: String,
+    children: Vec<OwnedElement>,
+}
+
+impl OwnedElement {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Self::default()
+        }
+    }
+
+    pub fn comment(text: impl Into<String>) -> Self {
+        Self {
+            kind: NodeKind::Comment,
+            text: text.into(),
+            ..Self::default()
+        }
+    }
+
+    pub fn processing_instruction(target: impl Into<String>, data: impl Into<String>) -> Self {
+        Self {
+            kind: NodeKind::ProcessingInstruction,
+            name: target.into(),
+            text: data.into(),
+            ..Self::default()
+        }
+    }
+
+    // kind
+
+    pub fn kind(&self) -> NodeKind {
+        self.kind
+    }
+
+    /// Whether this is an element and not a comment or processing instruction
+    pub fn is_element(&self) -> bool {
+        self.kind == NodeKind::Element
+    }
+
+    // name
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    // attributes
+
+    /// Attributes in document order
+    pub fn attributes(&self) -> &[(String, String)] {
+        &self.attributes
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(attr_name, _)| attr_name == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Set the value of an attribute, a new attribute is added after the existing ones
+    pub fn set_attribute(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        let name = name.into();
+        let value = value.into();
+        match self
+            .attributes
+            .iter_mut()
+            .find(|(attr_name, _)| *attr_name == name)
+        {
+            Some((_, old_value)) => *old_value = value,
+            None => self.attributes.push((name, value)),
+        }
+    }
+
+    /// Remove an attribute and return its value
+    pub fn remove_attribute(&mut self, name: &str) -> Option<String> {
+        let index = self
+            .attributes
+            .iter()
+            .position(|(attr_name, _)| attr_name == name)?;
+        Some(self.attributes.remove(index).1)
+    }
+
+    // children
+
+    pub fn children(&self) -> &[OwnedElement] {
+        &self.children
+    }
+
+    pub fn children_mut(&mut self) -> &mut [OwnedElement] {
+        &mut self.children
+    }
+
+    /// Child elements without comments and processing instructions
+    pub fn elements(&self) -> impl Iterator<Item = &OwnedElement> {
+        self.children.iter().filter(|child| child.is_element())
+    }
+
+    pub fn append_child(&mut self, element: OwnedElement) {
+        self.children.push(element);
+    }
+
+    /// Remove the child at `index` together with its tail
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn remove_child(&mut self, index: usize) -> OwnedElement {
+        self.children.remove(index)
+    }
+
+    // text / tail
+
+    /// Text before the first child
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn set_text(&mut self, text: &str) {
+        self.text = text.to_string();
+    }
+
+    /// Text after the end tag up to the next sibling
+    pub fn tail(&self) -> &str {
+        &self.tail
+    }
+
+    pub fn set_tail(&mut self, tail: &str) {
+        self.tail = tail.to_string();
+    }
+
+    /// Append text after the last child or to the text without children
+    pub(crate) fn push_text(&mut self, text: &str) {
+        match self.children.last_mut() {
+            Some(child) => child.tail.push_str(text),
+            None => self.text.push_str(text),
+        }
+    }
+
+    // serialization
+
+    /// Serialize the element and its content without its tail
+    pub fn to_xml_string(&self) -> String {
+        let mut output = String::new();
+        self.write_xml(&mut output);
+        output
+    }
+
+    fn write_xml(&self, output: &mut String) {
+        match self.kind {
+            NodeKind::Element => {}
+            NodeKind::Comment => {
+                write!(output, "<!--{}-->", self.text).unwrap();
+                return;
+            }
+            NodeKind::ProcessingInstruction if self.text.is_empty() => {
+                write!(output, "<?{}?>", self.name).unwrap();
+                return;
+            }
+            NodeKind::ProcessingInstruction => {
+                write!(output, "<?{} {}?>", self.name, self.text).unwrap();
+                return;
+            }
+        }
+
+        output.push('<');
+        output.push_str(&self.name);
+        for (name, value) in &self.attributes {
+            write!(output, " {}=\"", name).unwrap();
+            escape(value, true, output);
+            output.push('"');
+        }
+
+        if self.text.is_empty() && self.children.is_empty() {
+            output.push_str("/>");
+            return;
+        }
+
+        output.push('>');
+        escape(&self.text, false, output);
+        for child in &self.children {
+            child.write_xml(output);
+            escape(&child.tail, false, output);
+        }
+        write!(output, "</{}>", self.name).unwrap();
+    }
+
+    fn write_canonical(&self, output: &mut String) {
+        match self.kind {
+            NodeKind::Element => {}
+            NodeKind::Comment => return,
+            NodeKind::ProcessingInstruction if self.text.is_empty() => {
+                write!(output, "<?{}?>", self.name).unwrap();
+                return;
+            }
+            NodeKind::ProcessingInstruction => {
+                write!(output, "<?{} {}?>", self.name, self.text).unwrap();
+                return;
+            }
+        }
+
+        output.push('<');
+        output.push_str(&self.name);
+        let mut attributes: Vec<_> = self.attributes.iter().collect();
+        attributes.sort_by(|(a, _), (b, _)| a.cmp(b));
+        for (name, value) in attributes {
+            write!(output, " {}=\"", name).unwrap();
+            escape_canonical(value, output);
+            output.push('"');
+        }
+
+        output.push('>');
+        escape_canonical(&self.text, output);
+        for child in &self.children {
+            child.write_canonical(output);
+            escape_canonical(&child.tail, output);
+        }
+        write!(output, "</{}>", self.name).unwrap();
+    }
+
+    fn from_element(doc: &Document, element: &Element) -> Result<Self> {
+        let tail = expand(element, element.tail(doc)?)?;
+        match element.node() {
+            Node::Element => {}
+            Node::Comment(content) => {
+                return Ok(Self {
+                    tail,
+                    ..Self::comment(doc.slice(content)?)
+                })
+            }
+            Node::ProcessingInstruction { target, data } => {
+                let data = match data {
+                    Some(data) => doc.slice(data)?,
+                    None => "",
+                };
+                return Ok(Self {
+                    tail,
+                    ..Self::processing_instruction(doc.slice(target)?, data)
+                });
+            }
+        }
+
+        let attributes = parse_attributes(element.attributes(doc)?)
+            .into_iter()
+            .map(|(name, value)| Ok((name.to_string(), expand(element, value)?)))
+            .collect::<Result<_>>()?;
+        let children = element
+            .children()
+            .iter()
+            .map(|child| Self::from_element(doc, child))
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            kind: NodeKind::Element,
+            name: element.tag(doc)?.to_string(),
+            attributes,
+            text: expand(element, element.text(doc)?)?,
+            tail,
+            children,
+        })
+    }
+}
+
+impl<'a> Document<'a> {
+    /// Copy the document into a mutable document
+    pub fn to_owned_document(&self) -> Result<OwnedDocument> {
+        Ok(OwnedDocument::new(OwnedElement::from_element(
+            self,
+            self.get_root(),
+        )?))
+    }
+
+    /// Serialize the root element as Canonical XML, see [`OwnedDocument::to_canonical_xml`]
+    pub fn to_canonical_xml(&self) -> Result<String> {
+        Ok(self.to_owned_document()?.to_canonical_xml())
+    }
+}
+
+/// Escape markup characters, in attribute values also white space which normalization would
+/// replace by spaces
+fn escape(input: &str, attribute: bool, output: &mut String) {
+    for c in input.chars() {
+        match c {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' if attribute => output.push_str("&quot;"),
+            '\t' if attribute => output.push_str("&#9;"),
+            '\n' if attribute => output.push_str("&#10;"),
+            '\r' if attribute => output.push_str("&#13;"),
+            c => output.push(c),
+        }
+    }
+}
+
+fn escape_canonical(input: &str, output: &mut String) {
+    for c in input.chars() {
+        match c {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' => output.push_str("&quot;"),
+            '\t' => output.push_str("&#9;"),
+            '\n' => output.push_str("&#10;"),
+            '\r' => output.push_str("&#13;"),
+            c => output.push(c),
+        }
+    }
+}
+
+/// Expand the predefined entities and character references
+fn expand(element: &Element, raw: &str) -> Result<String> {
+    let error = || Error::new(element.tag_span(), Reason::InvalidEntity);
+
+    let mut result = String::with_capacity(raw.len());
+    let mut rest = raw;
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        let end = rest[start..].find(';').ok_or_else(error)? + start;
+        let c = match &rest[start + 1..end] {
+            "lt" => '<',
+            "gt" => '>',
+            "amp" => '&',
+            "apos" => '\'',
+            "quot" => '"',
+            reference => {
+                let code = match reference.strip_prefix("#x") {
+                    Some(hex) => u32::from_str_radix(hex, 16),
+                    None => reference.strip_prefix('#').ok_or_else(error)?.parse(),
+                };
+                code.ok().and_then(char::from_u32).ok_or_else(error)?
+            }
+        };
+        result.push(c);
+        rest = &rest[end + 1..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::reader::quick_xml::QuickXmlDomReader;
+    use crate::reader::DomReader;
+    use crate::validate::NonValidator;
+
+    use super::*;
+
+    #[test]
+    fn build() {
+        let mut item = OwnedElement::new("item");
+        item.set_attribute("id", "1");
+        item.set_attribute("title", "\"A\" & B");
+        item.set_text("1 < 2");
+
+        let mut root = OwnedElement::new("list");
+        root.set_text("\n  ");
+        root.append_child(item);
+        root.append_child(OwnedElement::new("empty"));
+        root.children_mut()[1].set_tail("\n");
+        let doc = OwnedDocument::new(root);
+
+        assert_eq!(
+            "<list>\n  <item id=\"1\" title=\"&quot;A&quot; &amp; B\">1 &lt; 2</item>\
+             <empty/>\n</list>",
+            doc.to_xml_string()
+        );
+    }
+
+    #[test]
+    fn attributes() {
+        let mut element = OwnedElement::new("e");
+        element.set_attribute("a", "1");
+        element.set_attribute("b", "2");
+        element.set_attribute("a", "3");
+        assert_eq!(Some("3"), element.attribute("a"));
+        assert_eq!(Some("3".to_string()), element.remove_attribute("a"));
+        assert_eq!(None, element.remove_attribute("a"));
+        assert_eq!(&[("b".to_string(), "2".to_string())], element.attributes());
+        assert_eq!("<e b=\"2\"/>", element.to_xml_string());
+
+        element.set_attribute("c", "1\n2\t3\r");
+        assert_eq!(
+            "<e b=\"2\" c=\"1&#10;2&#9;3&#13;\"/>",
+            element.to_xml_string()
+        );
+    }
+
+    #[test]
+    fn remove_child() {
+        let mut root = OwnedElement::new("r");
+        root.append_child(OwnedElement::new("a"));
+        root.append_child(OwnedElement::new("b"));
+        assert_eq!("a", root.remove_child(0).name());
+        assert_eq!("<r><b/></r>", root.to_xml_string());
+    }
+
+    #[test]
+    fn modify_parsed() {
+        let input = b"<r x='&lt;1&gt;'>text &amp; more<a/>tail&#x21;<b>b</b></r>";
+        let doc = QuickXmlDomReader::new(input, NonValidator).parse().unwrap();
+        let mut owned = doc.to_owned_document().unwrap();
+        assert_eq!(Some("<1>"), owned.root().attribute("x"));
+        assert_eq!("text & more", owned.root().text());
+        assert_eq!("tail!", owned.root().children()[0].tail());
+
+        let root = owned.root_mut();
+        root.remove_attribute("x");
+        root.set_attribute("y", "2");
+        root.children_mut()[1].set_text("c");
+        root.remove_child(0);
+        assert_eq!(
+            "<r y=\"2\">text &amp; more<b>c</b></r>",
+            owned.to_xml_string()
+        );
+    }
+
+    #[test]
+    fn comments_and_processing_instructions() {
+        let input = b"<r>a<!-- one --><x/>b<?php echo 1; ?>c<?empty?></r>";
+        let doc = QuickXmlDomReader::new(input, NonValidator).parse().unwrap();
+        let root = doc.get_root();
+        assert_eq!("a", root.text(&doc).unwrap());
+        assert_eq!(4, root.children().len());
+        assert_eq!(1, root.elements().count());
+        match root.children()[0].node() {
+            Node::Comment(content) => assert_eq!(" one ", doc.slice(content).unwrap()),
+            node => panic!("unexpected {:?}", node),
+        }
+        match root.children()[2].node() {
+            Node::ProcessingInstruction {
+                target,
+                data: Some(data),
+            } => {
+                assert_eq!("php", doc.slice(target).unwrap());
+                assert_eq!("echo 1; ", doc.slice(data).unwrap());
+            }
+            node => panic!("unexpected {:?}", node),
+        }
+        assert_eq!("c", root.children()[2].tail(&doc).unwrap());
+
+        let owned = doc.to_owned_document().unwrap();
+        assert_eq!(1, owned.root().elements().count());
+        assert_eq!(NodeKind::Comment, owned.root().children()[0].kind());
+        assert_eq!(
+            "<r>a<!-- one --><x/>b<?php echo 1; ?>c<?empty?></r>",
+            owned.to_xml_string()
+        );
+    }
+
+    #[test]
+    fn canonical() {
+        let input = b"<doc z='1' a1='&quot;&lt;&amp;&gt;&apos;'>\n\t<e/><!-- c --><?pi some data ?><?x?></doc>";
+        let doc = QuickXmlDomReader::new(input, NonValidator).parse().unwrap();
+        assert_eq!(
+            "<doc a1=\"&quot;&lt;&amp;&gt;'\" z=\"1\">&#10;&#9;<e></e><?pi some data ?><?x?></doc>",
+            doc.to_canonical_xml().unwrap()
+        );
+    }
+}
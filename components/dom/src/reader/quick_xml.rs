@@ -1,10 +1,11 @@
 use std::io::Cursor;
+use std::iter::once;
 use std::str::from_utf8;
 
 use quick_xml::events::{BytesDecl, BytesStart, BytesText, Event};
 
-use crate::chars::XmlBytesExt;
-use crate::dom::{Document, Element};
+use crate::chars::{XmlByteExt, XmlBytesExt};
+use crate::dom::{offset_in, parse_attributes, Document, Element};
 use crate::error::{Error, Reason, Result};
 use crate::reader::DomReader;
 use crate::validate::{XmlValidator, XmlValidatorBuilder};
@@ -32,12 +33,88 @@ where
         }
     }
 
-    pub fn create_element(&self, start: BytesStart) -> Element {
-        Element::new(
+    /// Element of the start tag `start` with its namespace declarations
+    ///
+    /// Fails if the element or one of its attributes uses a prefix that is neither declared
+    /// by the element nor by one of its `ancestors`.
+    pub fn create_element(&self, start: BytesStart, ancestors: &[Element]) -> Result<Element> {
+        let mut element = Element::new(
             self.last_offset,
             start.name().len(),
             start.attributes_raw().len(),
-        )
+        );
+
+        let raw_span = element.attributes_span();
+        let raw = raw_span
+            .to_str(self.bytes)
+            .map_err(|err| Error::new(raw_span, Reason::Utf8(err)))?;
+        let attributes = parse_attributes(raw);
+        for (name, value) in &attributes {
+            let prefix = if *name == "xmlns" {
+                ""
+            } else if let Some(prefix) = name.strip_prefix("xmlns:") {
+                prefix
+            } else {
+                continue;
+            };
+            let name_end = raw_span.start + offset_in(raw, name) + name.len();
+            element.push_namespace(
+                Span::new(name_end - prefix.len(), prefix.len()),
+                Span::new(raw_span.start + offset_in(raw, value), value.len()),
+            );
+        }
+
+        self.check_prefix(element.tag_span(), &element, ancestors)?;
+        for (name, _) in &attributes {
+            if *name != "xmlns" && !name.starts_with("xmlns:") {
+                let span = Span::new(raw_span.start + offset_in(raw, name), name.len());
+                self.check_prefix(span, &element, ancestors)?;
+            }
+        }
+        Ok(element)
+    }
+
+    /// Check that the prefix of the name at `span` is declared
+    fn check_prefix(&self, span: Span, element: &Element, ancestors: &[Element]) -> Result<()> {
+        let name = span.to_slice(self.bytes);
+        let prefix = match name.iter().position(|&c| c == b':') {
+            Some(colon) => &name[..colon],
+            None => return Ok(()),
+        };
+        if prefix == b"xml" {
+            return Ok(());
+        }
+
+        let declared = ancestors
+            .iter()
+            .chain(once(element))
+            .flat_map(Element::namespaces)
+            .any(|(declared, _)| declared.to_slice(self.bytes) == prefix);
+        if declared {
+            Ok(())
+        } else {
+            Err(Error::new(
+                span,
+                Reason::UndeclaredNamespacePrefix(String::from_utf8_lossy(prefix).into_owned()),
+            ))
+        }
+    }
+
+    /// Processing instruction with the content `instr` between `<?` and `?>`
+    fn create_processing_instruction(&self, instr: &[u8]) -> Element {
+        let start = self.last_offset + 2;
+        let target_len = instr
+            .iter()
+            .position(XmlByteExt::is_xml_whitespace)
+            .unwrap_or(instr.len());
+        let data = instr[target_len..]
+            .iter()
+            .position(|c| !c.is_xml_whitespace())
+            .map(|pos| {
+                let data_start = target_len + pos;
+                Span::new(start + data_start, instr.len() - data_start)
+            });
+        Element::new_processing_instruction(self.last_offset, Span::new(start, target_len), data)
     }
 
     pub fn error(&self, reason: Reason) -> Error {
@@ -105,7 +182,8 @@ where
                         start.name(),
                         start.attributes_raw(),
                     )?;
-                    stack.push(self.create_element(start));
+                    let element = self.create_element(start, &stack)?;
+                    stack.push(element);
                 }
                 Event::End(end) => {
                     if let Some(element) = stack.pop() {
@@ -127,7 +205,7 @@ where
                         start.name(),
                         start.attributes_raw(),
                     )?;
-                    let element = self.create_element(start);
+                    let element = self.create_element(start, &stack)?;
                     if stack.is_empty() {
                         return Ok(element);
                     } else {
@@ -153,11 +231,21 @@ where
                 }
                 Event::DocType(_) => return Err(self.error(Reason::UnexpectedDocType)),
                 Event::Decl(_) => return Err(self.error(Reason::UnexpectedDecl)),
-                Event::Comment(comment) => self
-                    .validator
-                    .validate_comment(self.last_offset, comment.escaped())?,
+                Event::Comment(comment) => {
+                    self.validator
+                        .validate_comment(self.last_offset, comment.escaped())?;
+                    let content = Span::new(self.last_offset + 4, comment.len());
+                    let stack_len = stack.len();
+                    stack[stack_len - 1]
+                        .push_child(Element::new_comment(self.last_offset, content));
+                }
                 Event::CData(_) => unimplemented!(),
-                Event::PI(pi) => self.validator.validate_pi(self.last_offset, pi.escaped())?,
+                Event::PI(pi) => {
+                    self.validator.validate_pi(self.last_offset, pi.escaped())?;
+                    let pi = self.create_processing_instruction(pi.escaped());
+                    let stack_len = stack.len();
+                    stack[stack_len - 1].push_child(pi);
+                }
                 Event::Eof => return Err(self.error(Reason::UnexpectedEof)),
             }
         }
@@ -189,7 +277,7 @@ where
                         start.name(),
                         start.attributes_raw(),
                     )?;
-                    let root = self.create_element(start);
+                    let root = self.create_element(start, &[])?;
                     break self.parse_inner_xml(&mut buffer, root)?;
                 }
                 Event::Empty(start) => {
@@ -198,7 +286,7 @@ where
                         start.name(),
                         start.attributes_raw(),
                     )?;
-                    break self.create_element(start);
+                    break self.create_element(start, &[])?;
                 }
                 Event::Text(text) if text.as_ref().only_xml_whitespace() => continue,
                 Event::Comment(comment) => self
@@ -249,6 +337,7 @@ mod tests {
     use crate::reader::quick_xml::QuickXmlDomReader;
     use crate::reader::DomReader;
     use crate::validate::NonValidator;
+    use crate::Span;
 
     #[test]
     fn only_root() {
@@ -394,5 +483,86 @@ mod tests {
                 Err(Reason::InvalidName)
             ));
         }
+
+        #[test]
+        fn duplicated_attribute() {
+            let input = b"<root><a x='1' y='2'  x=\"3\"/></root>";
+            let reader = QuickXmlDomReader::new(input, WellFormedValidatorBuilder);
+            let err = reader.parse().err().unwrap();
+            assert_eq!(Span::new(22, 1), err.span());
+            assert!(matches!(err.reason, Reason::DuplicatedAttribute(9)));
+        }
+
+        #[test]
+        fn undeclared_element_prefix() {
+            let input = b"<root xmlns:a='urn:a'><a:x/><b:y/></root>";
+            let reader = QuickXmlDomReader::new(input, NonValidator);
+            let err = reader.parse().err().unwrap();
+            assert_eq!(Span::new(29, 3), err.span());
+            assert!(
+                matches!(err.reason, Reason::UndeclaredNamespacePrefix(prefix) if prefix == "b")
+            );
+        }
+
+        #[test]
+        fn undeclared_attribute_prefix() {
+            let input = b"<root><x xmlns:a='urn:a' a:v='1'/><y a:v='2'/></root>";
+            let reader = QuickXmlDomReader::new(input, NonValidator);
+            let err = reader.parse().err().unwrap();
+            assert_eq!(Span::new(37, 3), err.span());
+            assert!(
+                matches!(err.reason, Reason::UndeclaredNamespacePrefix(prefix) if prefix == "a")
+            );
+        }
+    }
+
+    #[test]
+    fn namespaces() {
+        let input = b"<root xmlns='urn:d' xmlns:a='urn:a'><a:x xml:lang='en'/></root>";
+        let doc = QuickXmlDomReader::new(input, NonValidator).parse().unwrap();
+        let namespaces: Vec<_> = doc
+            .get_root()
+            .namespaces()
+            .iter()
+            .map(|(prefix, uri)| (doc.slice(*prefix).unwrap(), doc.slice(*uri).unwrap()))
+            .collect();
+        assert_eq!(vec![("", "urn:d"), ("a", "urn:a")], namespaces);
+        assert!(doc.get_root().children()[0].namespaces().is_empty());
+    }
+
+    #[test]
+    fn attributes_ns() {
+        let input = b"<root xmlns='urn:d' xmlns:a='urn:a' id='r'>\
+            <x xmlns:b='urn:b' id='1' a:v='2' b:w='3' xml:lang='en'/></root>";
+        let doc = QuickXmlDomReader::new(input, NonValidator).parse().unwrap();
+        let root = doc.get_root();
+
+        let attributes: Vec<_> = root.attributes_ns(&doc, &[]).unwrap().collect();
+        assert_eq!(vec![(None, "id", "r")], attributes);
+
+        let attributes: Vec<_> = root.children()[0]
+            .attributes_ns(&doc, &[root])
+            .unwrap()
+            .collect();
+        assert_eq!(
+            vec![
+                (None, "id", "1"),
+                (Some("urn:a"), "v", "2"),
+                (Some("urn:b"), "w", "3"),
+                (Some("http://www.w3.org/XML/1998/namespace"), "lang", "en"),
+            ],
+            attributes
+        );
+    }
+
+    #[test]
+    fn attributes_ns_without_ancestors() {
+        let input = b"<root xmlns:a='urn:a'><x a:v='1'/></root>";
+        let doc = QuickXmlDomReader::new(input, NonValidator).parse().unwrap();
+        let err = doc.get_root().children()[0]
+            .attributes_ns(&doc, &[])
+            .err()
+            .unwrap();
+        assert_eq!(Span::new(25, 3), err.span());
     }
 }
@@ -0,0 +1,359 @@
+//! Querying elements with CSS selectors
+//!
+//! Supported is a subset of CSS selectors: type selectors (`tag` and `*`), ID selectors
+//! (`#id`), attribute selectors (`[attr]` and `[attr="value"]`) and the descendant (` `) and
+//! child (`>`) combinators. Attribute values are compared unexpanded.
+
+use crate::dom::{Document, Element};
+use crate::error::{Error, Reason, Result};
+use crate::Span;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Combinator {
+    Descendant,
+    Child,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Filter {
+    Id(String),
+    Attribute { name: String, value: Option<String> },
+}
+
+/// Conditions for a single element, e.g. `item#first[lang]`
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+struct Compound {
+    /// Tag name or `None` for any element
+    tag: Option<String>,
+    filters: Vec<Filter>,
+}
+
+/// Parsed CSS selector
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Selector {
+    /// Compound selectors from left to right with the combinator to the previous one
+    compounds: Vec<(Combinator, Compound)>,
+}
+
+impl Selector {
+    /// Parse a selector
+    ///
+    /// The span of a returned error refers to the position in `selector`.
+    pub fn parse(selector: &str) -> Result<Selector> {
+        SelectorParser {
+            input: selector,
+            pos: 0,
+        }
+        .parse()
+    }
+
+    /// Elements of the document matching the selector in document order
+    pub fn select<'d>(&self, doc: &'d Document) -> Result<Vec<&'d Element>> {
+        let mut result = vec![];
+        let mut ancestors = vec![];
+        self.collect(doc, doc.get_root(), &mut ancestors, &mut result)?;
+        Ok(result)
+    }
+
+    fn collect<'d>(
+        &self,
+        doc: &Document,
+        element: &'d Element,
+        ancestors: &mut Vec<&'d Element>,
+        result: &mut Vec<&'d Element>,
+    ) -> Result<()> {
+        if self.matches(doc, self.compounds.len() - 1, element, ancestors)? {
+            result.push(element);
+        }
+
+        ancestors.push(element);
+        for child in element.elements() {
+            self.collect(doc, child, ancestors, result)?;
+        }
+        ancestors.pop();
+        Ok(())
+    }
+
+    /// Whether the compounds up to `index` match `element` with its `ancestors`
+    fn matches(
+        &self,
+        doc: &Document,
+        index: usize,
+        element: &Element,
+        ancestors: &[&Element],
+    ) -> Result<bool> {
+        let (combinator, compound) = &self.compounds[index];
+        if !compound.matches(doc, element)? {
+            return Ok(false);
+        }
+        if index == 0 {
+            return Ok(true);
+        }
+
+        match combinator {
+            Combinator::Child => match ancestors.split_last() {
+                Some((parent, ancestors)) => self.matches(doc, index - 1, parent, ancestors),
+                None => Ok(false),
+            },
+            Combinator::Descendant => {
+                for i in (0..ancestors.len()).rev() {
+                    if self.matches(doc, index - 1, ancestors[i], &ancestors[..i])? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+        }
+    }
+}
+
+impl Compound {
+    fn matches(&self, doc: &Document, element: &Element) -> Result<bool> {
+        if let Some(tag) = &self.tag {
+            if element.tag(doc)? != tag {
+                return Ok(false);
+            }
+        }
+
+        for filter in &self.filters {
+            let matches = match filter {
+                Filter::Id(id) => element.attribute(doc, "id")? == Some(id.as_str()),
+                Filter::Attribute { name, value: None } => element.attribute(doc, name)?.is_some(),
+                Filter::Attribute {
+                    name,
+                    value: Some(value),
+                } => element.attribute(doc, name)? == Some(value.as_str()),
+            };
+            if !matches {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+struct SelectorParser<'s> {
+    input: &'s str,
+    pos: usize,
+}
+
+impl<'s> SelectorParser<'s> {
+    fn parse(mut self) -> Result<Selector> {
+        let mut compounds = vec![];
+        let mut combinator = Combinator::Descendant;
+
+        self.skip_whitespace();
+        loop {
+            compounds.push((combinator, self.parse_compound()?));
+
+            let whitespace = self.skip_whitespace();
+            combinator = match self.peek() {
+                None => return Ok(Selector { compounds }),
+                Some('>') => {
+                    self.pos += 1;
+                    self.skip_whitespace();
+                    Combinator::Child
+                }
+                Some(_) if whitespace => Combinator::Descendant,
+                Some(_) => return Err(self.error()),
+            };
+        }
+    }
+
+    fn parse_compound(&mut self) -> Result<Compound> {
+        let mut compound = Compound::default();
+        let universal = self.peek() == Some('*');
+        match self.peek() {
+            Some('*') => self.pos += 1,
+            Some(c) if is_name_char(c) => compound.tag = Some(self.parse_name()?.to_string()),
+            _ => {}
+        }
+
+        loop {
+            match self.peek() {
+                Some('#') => {
+                    self.pos += 1;
+                    compound
+                        .filters
+                        .push(Filter::Id(self.parse_name()?.to_string()));
+                }
+                Some('[') => {
+                    self.pos += 1;
+                    compound.filters.push(self.parse_attribute()?);
+                }
+                _ => break,
+            }
+        }
+
+        if compound.tag.is_none() && compound.filters.is_empty() && !universal {
+            return Err(self.error());
+        }
+        Ok(compound)
+    }
+
+    /// Attribute selector after `[`
+    fn parse_attribute(&mut self) -> Result<Filter> {
+        self.skip_whitespace();
+        let name = self.parse_name()?.to_string();
+        self.skip_whitespace();
+
+        let value = match self.peek() {
+            Some('=') => {
+                self.pos += 1;
+                self.skip_whitespace();
+                let value = match self.peek() {
+                    Some(quote) if quote == '"' || quote == '\'' => {
+                        let rest = &self.input[self.pos + 1..];
+                        let end = rest.find(quote).ok_or_else(|| self.error())?;
+                        self.pos += end + 2;
+                        &rest[..end]
+                    }
+                    _ => self.parse_name()?,
+                };
+                self.skip_whitespace();
+                Some(value.to_string())
+            }
+            _ => None,
+        };
+
+        if self.peek() != Some(']') {
+            return Err(self.error());
+        }
+        self.pos += 1;
+        Ok(Filter::Attribute { name, value })
+    }
+
+    fn parse_name(&mut self) -> Result<&'s str> {
+        let rest = &self.input[self.pos..];
+        let len = rest.find(|c| !is_name_char(c)).unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.error());
+        }
+        self.pos += len;
+        Ok(&rest[..len])
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    /// Skip whitespace and return whether there was any
+    fn skip_whitespace(&mut self) -> bool {
+        let rest = &self.input[self.pos..];
+        let len = rest.len() - rest.trim_start().len();
+        self.pos += len;
+        len > 0
+    }
+
+    fn error(&self) -> Error {
+        Error::new(
+            Span::new(self.pos, self.peek().map_or(0, char::len_utf8)),
+            Reason::InvalidSelector(self.input.to_string()),
+        )
+    }
+}
+
+/// Characters allowed in unquoted names, which includes `:` and `-`
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | ':' | '.')
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::reader::quick_xml::QuickXmlDomReader;
+    use crate::reader::DomReader;
+    use crate::validate::NonValidator;
+
+    use super::*;
+
+    const DOC: &str = r#"<library>
+        <shelf id="a">
+            <book lang="en"><title>One</title></book>
+            <book lang='de' id="b2"><title>Zwei</title><note><title>Nested</title></note></book>
+        </shelf>
+        <shelf id="b"><magazine><title>Three</title></magazine></shelf>
+    </library>"#;
+
+    fn select(selector: &str) -> Vec<String> {
+        let doc = QuickXmlDomReader::new(DOC.as_bytes(), NonValidator)
+            .parse()
+            .unwrap();
+        doc.select(selector)
+            .unwrap()
+            .into_iter()
+            .map(|element| match element.attribute(&doc, "id").unwrap() {
+                Some(id) => format!("{}#{}", element.tag(&doc).unwrap(), id),
+                None => element.tag(&doc).unwrap().to_string(),
+            })
+            .collect()
+    }
+
+    fn titles(selector: &str) -> Vec<String> {
+        let doc = QuickXmlDomReader::new(DOC.as_bytes(), NonValidator)
+            .parse()
+            .unwrap();
+        doc.select(selector)
+            .unwrap()
+            .into_iter()
+            .map(|element| element.text(&doc).unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn type_selector() {
+        assert_eq!(vec!["library"], select("library"));
+        assert_eq!(vec!["shelf#a", "shelf#b"], select("shelf"));
+        assert_eq!(titles("title"), vec!["One", "Zwei", "Nested", "Three"]);
+        assert!(select("missing").is_empty());
+        assert_eq!(11, select("*").len());
+    }
+
+    #[test]
+    fn id_selector() {
+        assert_eq!(vec!["shelf#b"], select("#b"));
+        assert_eq!(vec!["book#b2"], select("book#b2"));
+        assert!(select("shelf#b2").is_empty());
+    }
+
+    #[test]
+    fn attribute_selector() {
+        assert_eq!(vec!["book", "book#b2"], select("[lang]"));
+        assert_eq!(vec!["book#b2"], select("book[lang=\"de\"]"));
+        assert_eq!(vec!["book#b2"], select("[ lang = 'de' ][id]"));
+        assert_eq!(vec!["book"], select("[lang=en]"));
+        assert!(select("[lang=fr]").is_empty());
+    }
+
+    #[test]
+    fn descendant_combinator() {
+        assert_eq!(
+            titles("shelf title"),
+            vec!["One", "Zwei", "Nested", "Three"]
+        );
+        assert_eq!(titles("#a  book title"), vec!["One", "Zwei", "Nested"]);
+        assert_eq!(titles("book note title"), vec!["Nested"]);
+    }
+
+    #[test]
+    fn child_combinator() {
+        assert_eq!(titles("book > title"), vec!["One", "Zwei"]);
+        assert_eq!(titles("shelf>*>title"), vec!["One", "Zwei", "Three"]);
+        assert_eq!(titles("library > shelf book>title"), vec!["One", "Zwei"]);
+        assert!(select("library > book").is_empty());
+    }
+
+    #[test]
+    fn skip_comments_and_processing_instructions() {
+        let input = b"<r><!-- c --><a/><?pi data?><a><!----></a></r>";
+        let doc = QuickXmlDomReader::new(input, NonValidator).parse().unwrap();
+        assert_eq!(3, doc.select("*").unwrap().len());
+        assert_eq!(2, doc.select("r a").unwrap().len());
+    }
+
+    #[test]
+    fn invalid() {
+        for selector in &["", "a >", "> a", "[lang", "[lang='de]", "a!", "#", "a,b"] {
+            assert!(Selector::parse(selector).is_err(), "{}", selector);
+        }
+    }
+}
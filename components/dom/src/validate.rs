@@ -1,10 +1,12 @@
 use std::str::from_utf8;
 
 use crate::chars::XmlStrExt;
-use crate::dom::Element;
+use crate::dom::{offset_in, parse_attributes, Element};
 use crate::error::{Error, Reason};
 use crate::Span;
 
+pub mod dtd;
+
 pub trait XmlValidatorBuilder<'a> {
     type Item: XmlValidator<'a>;
 
@@ -130,7 +132,7 @@ impl<'a> XmlValidator<'a> for WellFormedValidator<'a> {
         let tag_str = from_utf8(tag).map_err(|err| {
             Error::new(Span::new(pos + err.valid_up_to() + 1, 0), Reason::Utf8(err))
         })?;
-        from_utf8(attributes).map_err(|err| {
+        let attributes_str = from_utf8(attributes).map_err(|err| {
             Error::new(
                 Span::new(pos + tag.len() + err.valid_up_to() + 1, 0),
                 Reason::Utf8(err),
@@ -141,6 +143,17 @@ impl<'a> XmlValidator<'a> for WellFormedValidator<'a> {
             return Err(Error::new(Span::new(pos, tag.len()), Reason::InvalidName));
         }
 
+        let attributes_pos = pos + tag.len() + 1;
+        let parsed = parse_attributes(attributes_str);
+        for (i, (name, _)) in parsed.iter().enumerate() {
+            if let Some((first, _)) = parsed[..i].iter().find(|(other, _)| other == name) {
+                return Err(Error::new(
+                    Span::new(attributes_pos + offset_in(attributes_str, name), name.len()),
+                    Reason::DuplicatedAttribute(attributes_pos + offset_in(attributes_str, first)),
+                ));
+            }
+        }
+
         Ok(())
     }
 
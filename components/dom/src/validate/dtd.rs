@@ -0,0 +1,436 @@
+//! Validation of a document against the element and attribute-list declarations of its DTD
+
+use std::collections::{BTreeSet, HashMap};
+
+use xrs_parser::dtd::{
+    AttDef, AttType, ContentParticle, ContentParticleEntry, ContentSpec, DefaultDecl, DocTypeDecl,
+    EntityDef, MarkupDeclEntry, Repetition,
+};
+use xrs_parser::parser::normalize_att_value;
+use xrs_parser::Entities;
+
+use crate::chars::XmlBytesExt;
+use crate::dom::{parse_attributes, Document, Element};
+use crate::error::{Error, Reason, Result};
+
+/// Validate `doc` against the declarations of the internal subset of `dtd`
+///
+/// Checks that the root element matches the document type name, that every element is
+/// declared and its children match the declared content, and that `#REQUIRED` and `#FIXED`
+/// attributes are present with the right value. The first violation is returned.
+pub fn validate(doc: &Document, dtd: &DocTypeDecl) -> Result<()> {
+    let root = doc.get_root();
+    let root_name = root.tag(doc)?;
+    if root_name != dtd.root_element_name() {
+        return Err(Error::new(
+            root.tag_span(),
+            Reason::RootElementMismatch {
+                expected: dtd.root_element_name().to_string(),
+                found: root_name.to_string(),
+            },
+        ));
+    }
+
+    DtdValidator::new(dtd).validate_element(doc, root)
+}
+
+struct DtdValidator<'d> {
+    elements: HashMap<&'d str, &'d ContentSpec>,
+    attributes: HashMap<&'d str, Vec<&'d AttDef>>,
+    /// General entities for references in attribute values
+    entities: Entities,
+}
+
+impl<'d> DtdValidator<'d> {
+    fn new(dtd: &'d DocTypeDecl) -> Self {
+        let mut elements = HashMap::new();
+        let mut attributes: HashMap<&str, Vec<&AttDef>> = HashMap::new();
+        let mut entities = Entities::default();
+
+        let decls = dtd
+            .internal_subset()
+            .as_ref()
+            .map(|subset| subset.decls())
+            .unwrap_or_default();
+        for decl in decls {
+            match decl {
+                MarkupDeclEntry::Element(element) => {
+                    elements
+                        .entry(&element.name as &str)
+                        .or_insert(&element.content_spec);
+                }
+                MarkupDeclEntry::AttList(att_list) => {
+                    let defs = attributes.entry(&att_list.name as &str).or_default();
+                    for def in &att_list.defs {
+                        // first declaration of an attribute is binding
+                        if defs.iter().all(|other| other.name != def.name) {
+                            defs.push(def);
+                        }
+                    }
+                }
+                // first declaration of an entity is binding
+                MarkupDeclEntry::GeneralEntity(entity)
+                    if entities.get_ref(&entity.name).is_none() =>
+                {
+                    match &entity.def {
+                        EntityDef::Internal(value) => {
+                            entities.register(&entity.name, value.as_str())
+                        }
+                        EntityDef::External { .. } => entities.register_external(&entity.name, ""),
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Self {
+            elements,
+            attributes,
+            entities,
+        }
+    }
+
+    fn validate_element(&self, doc: &Document, element: &Element) -> Result<()> {
+        let name = element.tag(doc)?;
+        let content_spec = self.elements.get(name).ok_or_else(|| {
+            Error::new(
+                element.tag_span(),
+                Reason::UndeclaredElement(name.to_string()),
+            )
+        })?;
+
+        self.validate_attributes(doc, element, name)?;
+
+        if !self.matches_content(doc, element, content_spec)? {
+            return Err(Error::new(
+                element.tag_span(),
+                Reason::InvalidContent(name.to_string()),
+            ));
+        }
+
+        for child in element.elements() {
+            self.validate_element(doc, child)?;
+        }
+
+        Ok(())
+    }
+
+    fn validate_attributes(&self, doc: &Document, element: &Element, name: &str) -> Result<()> {
+        let defs = match self.attributes.get(name) {
+            Some(defs) => defs,
+            None => return Ok(()),
+        };
+
+        let attributes = parse_attributes(element.attributes(doc)?);
+        for def in defs {
+            let value = attributes
+                .iter()
+                .find(|(attr_name, _)| *attr_name == def.name)
+                .map(|(_, value)| *value);
+            match (&def.default, value) {
+                (DefaultDecl::Fixed(expected), Some(value))
+                    if !self.matches_fixed(value, expected, def.att_type == AttType::CData) =>
+                {
+                    return Err(Error::new(
+                        element.tag_span(),
+                        Reason::FixedAttributeMismatch {
+                            name: def.name.clone(),
+                            expected: expected.clone(),
+                        },
+                    ));
+                }
+                (DefaultDecl::Required, None) => {
+                    return Err(Error::new(
+                        element.tag_span(),
+                        Reason::MissingAttribute(def.name.clone()),
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Compare the normalized attribute value with the `#FIXED` default
+    ///
+    /// The default is normalized like a value of the attribute type as well. A value which
+    /// can't be normalized is compared as written.
+    fn matches_fixed(&self, raw: &str, expected: &str, is_cdata_type: bool) -> bool {
+        let value =
+            normalize_att_value(raw, is_cdata_type, &self.entities).unwrap_or_else(|_| raw.into());
+        if is_cdata_type {
+            value == expected
+        } else {
+            let mut expected = expected.split(' ').filter(|token| !token.is_empty());
+            let mut value = value.split(' ').filter(|token| !token.is_empty());
+            loop {
+                match (expected.next(), value.next()) {
+                    (None, None) => return true,
+                    (expected, value) if expected != value => return false,
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    fn matches_content(
+        &self,
+        doc: &Document,
+        element: &Element,
+        content_spec: &ContentSpec,
+    ) -> Result<bool> {
+        let children = element.children();
+        Ok(match content_spec {
+            ContentSpec::Any => true,
+            ContentSpec::Empty => children.is_empty() && !element.has_text(),
+            ContentSpec::PCData => element.elements().next().is_none(),
+            ContentSpec::Mixed(names) => {
+                for child in element.elements() {
+                    let child_name = child.tag(doc)?;
+                    if !names.iter().any(|name| name == child_name) {
+                        return Ok(false);
+                    }
+                }
+                true
+            }
+            ContentSpec::Children(particle) => {
+                // only white space is allowed between child elements
+                if !element.text(doc)?.as_bytes().only_xml_whitespace() {
+                    return Ok(false);
+                }
+                let mut names = Vec::with_capacity(children.len());
+                for child in children {
+                    if !child.tail(doc)?.as_bytes().only_xml_whitespace() {
+                        return Ok(false);
+                    }
+                    if child.is_element() {
+                        names.push(child.tag(doc)?);
+                    }
+                }
+
+                match_particle(particle, &names, 0).contains(&names.len())
+            }
+        })
+    }
+}
+
+/// Return all positions in `names` where a match of `particle` starting at `pos` can end
+fn match_particle(particle: &ContentParticle, names: &[&str], pos: usize) -> BTreeSet<usize> {
+    let once = |pos| match_entry(&particle.entry, names, pos);
+
+    match particle.repetition {
+        Repetition::One => once(pos),
+        Repetition::ZeroOrOne => {
+            let mut ends = once(pos);
+            ends.insert(pos);
+            ends
+        }
+        Repetition::ZeroOrMore | Repetition::OneOrMore => {
+            let mut ends = once(pos);
+            let mut todo: Vec<usize> = ends.iter().copied().collect();
+            while let Some(next) = todo.pop() {
+                for end in once(next) {
+                    if ends.insert(end) {
+                        todo.push(end);
+                    }
+                }
+            }
+            if particle.repetition == Repetition::ZeroOrMore {
+                ends.insert(pos);
+            }
+            ends
+        }
+    }
+}
+
+fn match_entry(entry: &ContentParticleEntry, names: &[&str], pos: usize) -> BTreeSet<usize> {
+    match entry {
+        ContentParticleEntry::Name(name) => {
+            let mut ends = BTreeSet::new();
+            if names.get(pos) == Some(&name.as_str()) {
+                ends.insert(pos + 1);
+            }
+            ends
+        }
+        ContentParticleEntry::Choice(particles) => particles
+            .iter()
+            .flat_map(|particle| match_particle(particle, names, pos))
+            .collect(),
+        ContentParticleEntry::Seq(particles) => {
+            let mut ends = BTreeSet::new();
+            ends.insert(pos);
+            for particle in particles {
+                ends = ends
+                    .into_iter()
+                    .flat_map(|start| match_particle(particle, names, start))
+                    .collect();
+            }
+            ends
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use xrs_parser::{Reader, XmlEvent};
+
+    use crate::error::Reason;
+    use crate::reader::quick_xml::QuickXmlDomReader;
+    use crate::reader::DomReader;
+    use crate::validate::NonValidator;
+
+    use super::*;
+
+    fn check(input: &str) -> Result<()> {
+        let mut reader = Reader::new(input);
+        let dtd = loop {
+            match reader.next().unwrap() {
+                Some(XmlEvent::Dtd(dtd)) => break dtd,
+                Some(_) => continue,
+                None => panic!("missing DOCTYPE"),
+            }
+        };
+        let doc = QuickXmlDomReader::new(input.as_bytes(), NonValidator)
+            .parse()
+            .unwrap();
+        validate(&doc, &dtd)
+    }
+
+    fn reason(input: &str) -> Reason {
+        check(input).expect_err("document should be invalid").reason
+    }
+
+    #[test]
+    fn valid() {
+        check(
+            "<!DOCTYPE list [
+                <!ELEMENT list (head?, (item | note)+)>
+                <!ELEMENT head (#PCDATA)>
+                <!ELEMENT item (#PCDATA | b)*>
+                <!ELEMENT note EMPTY>
+                <!ELEMENT b ANY>
+                <!ATTLIST list id ID #REQUIRED version CDATA #FIXED '1'>
+            ]>
+            <list id='l1' version='1'>
+                <head>Title</head>
+                <item>one <b>two</b></item>
+                <note/>
+                <item/>
+            </list>",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn root_mismatch() {
+        assert!(matches!(
+            reason("<!DOCTYPE a [ <!ELEMENT b EMPTY> ]><b/>"),
+            Reason::RootElementMismatch { expected, found } if expected == "a" && found == "b"
+        ));
+    }
+
+    #[test]
+    fn undeclared_element() {
+        assert!(matches!(
+            reason("<!DOCTYPE a [ <!ELEMENT a ANY> ]><a><b/></a>"),
+            Reason::UndeclaredElement(name) if name == "b"
+        ));
+    }
+
+    #[test]
+    fn empty_with_content() {
+        assert!(matches!(
+            reason("<!DOCTYPE a [ <!ELEMENT a EMPTY> ]><a>text</a>"),
+            Reason::InvalidContent(name) if name == "a"
+        ));
+    }
+
+    #[test]
+    fn mixed_with_undeclared_child() {
+        assert!(matches!(
+            reason(
+                "<!DOCTYPE a [ <!ELEMENT a (#PCDATA|b)*> <!ELEMENT b EMPTY> <!ELEMENT c EMPTY> ]>\
+                 <a>x<b/><c/></a>"
+            ),
+            Reason::InvalidContent(name) if name == "a"
+        ));
+    }
+
+    #[test]
+    fn children_order() {
+        assert!(matches!(
+            reason(
+                "<!DOCTYPE a [ <!ELEMENT a (b, c)> <!ELEMENT b EMPTY> <!ELEMENT c EMPTY> ]>\
+                 <a><c/><b/></a>"
+            ),
+            Reason::InvalidContent(name) if name == "a"
+        ));
+    }
+
+    #[test]
+    fn children_with_text() {
+        assert!(matches!(
+            reason("<!DOCTYPE a [ <!ELEMENT a (b)> <!ELEMENT b EMPTY> ]><a>x<b/></a>"),
+            Reason::InvalidContent(name) if name == "a"
+        ));
+    }
+
+    #[test]
+    fn missing_required_attribute() {
+        assert!(matches!(
+            reason("<!DOCTYPE a [ <!ELEMENT a EMPTY> <!ATTLIST a id ID #REQUIRED> ]><a/>"),
+            Reason::MissingAttribute(name) if name == "id"
+        ));
+    }
+
+    #[test]
+    fn fixed_attribute_mismatch() {
+        let err = check(
+            "<!DOCTYPE a [ <!ELEMENT a EMPTY> <!ATTLIST a v CDATA #FIXED '1'> ]><a v=\"2\"/>",
+        )
+        .unwrap_err();
+        assert!(err.is_invalid());
+        assert!(!err.is_not_wf());
+        assert!(matches!(
+            err.reason,
+            Reason::FixedAttributeMismatch { name, expected } if name == "v" && expected == "1"
+        ));
+    }
+
+    #[test]
+    fn fixed_attribute_normalized() {
+        check(
+            "<!DOCTYPE a [ <!ELEMENT a EMPTY> <!ENTITY one '1'> \
+             <!ATTLIST a v CDATA #FIXED '1 2' t NMTOKENS #FIXED 'x y'> ]>\
+             <a v=\"&one;&#x20;2\" t=\" x\n y \"/>",
+        )
+        .unwrap();
+        assert!(matches!(
+            reason(
+                "<!DOCTYPE a [ <!ELEMENT a EMPTY> <!ATTLIST a v CDATA #FIXED '1'> ]>\
+                 <a v=\" 1\"/>"
+            ),
+            Reason::FixedAttributeMismatch { .. }
+        ));
+    }
+
+    #[test]
+    fn repetition() {
+        let names = ["b", "b", "c"];
+        let particle = |name: &str, repetition| ContentParticle {
+            entry: ContentParticleEntry::Name(name.to_string()),
+            repetition,
+        };
+        let seq = ContentParticle {
+            entry: ContentParticleEntry::Seq(vec![
+                particle("b", Repetition::OneOrMore),
+                particle("c", Repetition::ZeroOrOne),
+            ]),
+            repetition: Repetition::One,
+        };
+        assert!(match_particle(&seq, &names, 0).contains(&3));
+        assert!(match_particle(&seq, &names[..2], 0).contains(&2));
+        assert!(!match_particle(&seq, &names[2..], 0).contains(&1));
+    }
+}
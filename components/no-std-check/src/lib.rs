@@ -0,0 +1,73 @@
+//! Compile test for `xrs-chars` and `xrs-parser` without `std`
+//!
+//! The crate only builds when both crates work with `core` and `alloc` alone.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use xrs_chars::XmlChar;
+use xrs_parser::simple::{AttributeAccess, SimpleXmlParser, SimpleXmlVisitor};
+use xrs_parser::{XmlDecl, XmlError};
+
+/// Names of all elements of `input` in document order
+pub fn element_names(input: &str) -> Result<Vec<&str>, XmlError> {
+    let mut parser = SimpleXmlParser::from_str(input);
+    let mut names = Vec::new();
+    while let Some(name) = parser.parse_next(StartVisitor)? {
+        if let Some(name) = name {
+            names.push(name);
+        }
+    }
+    Ok(names)
+}
+
+/// `name` is a valid XML name
+pub fn is_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_xml_name_start_char() => chars.all(|c| c.is_xml_name_char()),
+        _ => false,
+    }
+}
+
+/// Returns the names of start tags and `None` for all other markup
+struct StartVisitor;
+
+impl<'i> SimpleXmlVisitor<'i> for StartVisitor {
+    type Value = Option<&'i str>;
+
+    fn visit_start_element<A: AttributeAccess<'i>>(
+        self,
+        tag: &'i str,
+        _attrs: A,
+    ) -> Result<Self::Value, XmlError> {
+        Ok(Some(tag))
+    }
+
+    fn visit_end_element(self, _tag: &'i str) -> Result<Self::Value, XmlError> {
+        Ok(None)
+    }
+
+    fn visit_declaration(self, _decl: XmlDecl) -> Result<Self::Value, XmlError> {
+        Ok(None)
+    }
+
+    fn visit_characters(self, _characters: &'i str) -> Result<Self::Value, XmlError> {
+        Ok(None)
+    }
+
+    fn visit_borrowed_characters(self, _characters: &str) -> Result<Self::Value, XmlError> {
+        Ok(None)
+    }
+
+    fn visit_pi(self, _target: &'i str, _data: Option<&'i str>) -> Result<Self::Value, XmlError> {
+        Ok(None)
+    }
+
+    fn visit_comment(self, _comment: &'i str) -> Result<Self::Value, XmlError> {
+        Ok(None)
+    }
+}
@@ -0,0 +1,100 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use xrs_parser::{Reader, XmlEvent};
+
+/// Allocator counting allocations and allocated bytes
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn table_document() -> String {
+    let mut doc = String::from("<table>");
+    for i in 0..10_000 {
+        doc.push_str(&format!(
+            "<row id='{}' class='data'><name>item {}</name><value unit='kg'>{}</value></row>",
+            i,
+            i,
+            i * 3
+        ));
+    }
+    doc.push_str("</table>");
+    doc
+}
+
+/// Names of all elements and attributes as owned strings
+fn collect_names(input: &str) -> Vec<String> {
+    let mut reader = Reader::new(input);
+    let mut names = vec![];
+    while let Some(evt) = reader.next().unwrap() {
+        if let XmlEvent::STag(stag) = evt {
+            names.push(stag.name.to_string());
+            for attr in reader.attributes() {
+                names.push(attr.name.to_string());
+            }
+        }
+    }
+    names
+}
+
+/// Names of all elements and attributes shared with an interner
+fn collect_interned_names(input: &str) -> Vec<Rc<str>> {
+    let mut reader = Reader::new(input).with_interner();
+    let mut names = vec![];
+    while let Some(evt) = reader.next().unwrap() {
+        if let XmlEvent::STag(stag) = evt {
+            names.push(reader.intern(&stag.name));
+            for i in 0..reader.attributes().len() {
+                let name = reader.attributes()[i].name.clone();
+                names.push(reader.intern(&name));
+            }
+        }
+    }
+    names
+}
+
+fn report_allocations<T>(name: &str, f: impl FnOnce() -> T) {
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let allocated = ALLOCATED.load(Ordering::Relaxed);
+    let result = f();
+    println!(
+        "{}: {} allocations, {} bytes",
+        name,
+        ALLOCATIONS.load(Ordering::Relaxed) - allocations,
+        ALLOCATED.load(Ordering::Relaxed) - allocated
+    );
+    drop(result);
+}
+
+pub fn interning_benchmark(c: &mut Criterion) {
+    let doc = table_document();
+    report_allocations("names", || collect_names(&doc));
+    report_allocations("interned names", || collect_interned_names(&doc));
+
+    c.bench_function("table names", |b| b.iter(|| collect_names(black_box(&doc))));
+    c.bench_function("table interned names", |b| {
+        b.iter(|| collect_interned_names(black_box(&doc)))
+    });
+}
+
+criterion_group!(benches, interning_benchmark);
+criterion_main!(benches);
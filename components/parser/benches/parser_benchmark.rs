@@ -1,5 +1,7 @@
+use std::alloc::{GlobalAlloc, Layout, System};
 use std::borrow::Cow;
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use quick_xml::events::Event as QXEvent;
@@ -11,6 +13,30 @@ use xrs_parser::simple::{
 };
 use xrs_parser::{Reader, XmlDecl, XmlError, XmlEvent};
 
+/// Allocator counting allocations, used to report the allocations of the char refs document
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
 fn parse_using_quick_xml(input: &[u8]) -> (HashMap<Cow<str>, usize>, HashMap<Cow<str>, usize>) {
     let mut buf = Vec::new();
     let mut reader = QXReader::from_reader(input);
@@ -203,5 +229,46 @@ pub fn atom_benchmark(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, minimal_benchmark, gpx_benchmark, atom_benchmark);
+const CHAR_REF_ELEMENTS: usize = 1000;
+
+fn char_refs_document() -> String {
+    let mut doc = String::from("<doc>");
+    for _ in 0..CHAR_REF_ELEMENTS {
+        doc.push_str("<p>a&#x20;b&#32;c&#x3C;&#x26;&#xE4;</p>");
+    }
+    doc.push_str("</doc>");
+    doc
+}
+
+pub fn char_refs_benchmark(c: &mut Criterion) {
+    let doc = char_refs_document();
+
+    // only the reference to the non-ASCII character allocates
+    let mut reader = Reader::new(&doc);
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    while reader.next().unwrap().is_some() {}
+    eprintln!(
+        "{} allocations for {} character references",
+        ALLOCATIONS.load(Ordering::Relaxed) - before,
+        5 * CHAR_REF_ELEMENTS
+    );
+
+    c.bench_function("char refs xrs", |b| {
+        b.iter(|| parse_using_xrs(black_box(doc.as_bytes())))
+    });
+    c.bench_function("char refs simple xrs", |b| {
+        b.iter(|| parse_using_simple_xrs(black_box(doc.as_bytes())))
+    });
+    c.bench_function("char refs quick-xml", |b| {
+        b.iter(|| parse_using_quick_xml(black_box(doc.as_bytes())))
+    });
+}
+
+criterion_group!(
+    benches,
+    minimal_benchmark,
+    gpx_benchmark,
+    atom_benchmark,
+    char_refs_benchmark
+);
 criterion_main!(benches);
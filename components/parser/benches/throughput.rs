@@ -0,0 +1,164 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+
+use xrs_parser::simple::{AttributeAccess, SimpleXmlParser, SimpleXmlVisitor, StringVisitor};
+use xrs_parser::{Reader, XmlDecl, XmlError};
+
+/// Visitor counting the events and attributes
+struct Counter;
+
+impl<'i> SimpleXmlVisitor<'i> for Counter {
+    type Value = usize;
+
+    fn visit_start_element<A: AttributeAccess<'i>>(
+        self,
+        _tag: &'i str,
+        mut attrs: A,
+    ) -> Result<Self::Value, XmlError> {
+        let mut count = 1;
+        while attrs.next_entry(StringVisitor, StringVisitor)?.is_some() {
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    fn visit_end_element(self, _tag: &'i str) -> Result<Self::Value, XmlError> {
+        Ok(1)
+    }
+
+    fn visit_declaration(self, _decl: XmlDecl) -> Result<Self::Value, XmlError> {
+        Ok(1)
+    }
+
+    fn visit_characters(self, _characters: &'i str) -> Result<Self::Value, XmlError> {
+        Ok(1)
+    }
+
+    fn visit_borrowed_characters(self, _characters: &str) -> Result<Self::Value, XmlError> {
+        Ok(1)
+    }
+
+    fn visit_pi(self, _target: &'i str, _data: Option<&'i str>) -> Result<Self::Value, XmlError> {
+        Ok(1)
+    }
+
+    fn visit_comment(self, _comment: &'i str) -> Result<Self::Value, XmlError> {
+        Ok(1)
+    }
+}
+
+fn count_using_reader(input: &str) -> usize {
+    let mut reader = Reader::new(input);
+    let mut count = 0;
+    while reader.next().unwrap().is_some() {
+        count += 1 + reader.attributes().len();
+    }
+    count
+}
+
+fn count_using_simple_parser(input: &str) -> usize {
+    let mut parser = SimpleXmlParser::from_str(input);
+    let mut count = 0;
+    while let Some(n) = parser.parse_next(Counter).unwrap() {
+        count += n;
+    }
+    count
+}
+
+fn nested_document() -> String {
+    let mut doc = String::new();
+    for _ in 0..100 {
+        doc.push_str("<section>");
+        for i in 0..100 {
+            doc.push_str(&format!("<level{}><title>t</title>", i));
+        }
+        for i in (0..100).rev() {
+            doc.push_str(&format!("</level{}>", i));
+        }
+        doc.push_str("</section>");
+    }
+    format!("<doc>{}</doc>", doc)
+}
+
+fn small_elements_document() -> String {
+    let mut doc = String::from("<doc>");
+    for i in 0..20_000 {
+        doc.push_str(&format!("<row><id>{}</id><name>n</name><v/></row>", i));
+    }
+    doc.push_str("</doc>");
+    doc
+}
+
+fn long_names_document() -> String {
+    let mut doc = String::from("<doc>");
+    for _ in 0..20_000 {
+        doc.push_str(
+            "<configuration_property_value_element>x</configuration_property_value_element>",
+        );
+    }
+    doc.push_str("</doc>");
+    doc
+}
+
+fn text_document() -> String {
+    let paragraph = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod \
+        tempor incididunt ut labore et dolore magna aliqua. Ut enim ad minim veniam, quis \
+        nostrud exercitation ullamco laboris nisi ut aliquip ex ea commodo consequat.\n";
+    let mut doc = String::from("<doc>");
+    for _ in 0..1000 {
+        doc.push_str("<p>");
+        doc.push_str(&paragraph.repeat(4));
+        doc.push_str("</p>");
+    }
+    doc.push_str("</doc>");
+    doc
+}
+
+fn attributes_document() -> String {
+    let mut doc = String::from("<doc>");
+    for i in 0..5000 {
+        doc.push_str(&format!(
+            "<item id='{}' name='item {}' class=\"a b c\" x='1' y='2' width='10' height='20' \
+             visible='true'/>",
+            i, i
+        ));
+    }
+    doc.push_str("</doc>");
+    doc
+}
+
+fn entities_document() -> String {
+    let mut doc = String::from("<doc>");
+    for _ in 0..5000 {
+        doc.push_str(
+            "<p>a &lt; b &amp;&amp; c &gt; d &quot;e&quot; &apos;f&apos; &#228;&#x20AC;</p>",
+        );
+    }
+    doc.push_str("</doc>");
+    doc
+}
+
+pub fn throughput_benchmark(c: &mut Criterion) {
+    let inputs = [
+        ("nested", nested_document()),
+        ("small elements", small_elements_document()),
+        ("long names", long_names_document()),
+        ("text", text_document()),
+        ("attributes", attributes_document()),
+        ("entities", entities_document()),
+    ];
+
+    for (name, input) in &inputs {
+        let mut group = c.benchmark_group(*name);
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_with_input("reader", input.as_str(), |b, i| {
+            b.iter(|| count_using_reader(black_box(i)))
+        });
+        group.bench_with_input("simple parser", input.as_str(), |b, i| {
+            b.iter(|| count_using_simple_parser(black_box(i)))
+        });
+        group.finish();
+    }
+}
+
+criterion_group!(benches, throughput_benchmark);
+criterion_main!(benches);
@@ -1,7 +1,10 @@
-use std::borrow::Cow;
+use alloc::borrow::Cow;
 
 use xrs_chars::XmlAsciiChar;
 
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+
 #[derive(Default)]
 pub(crate) struct CowStrBuilder<'a>(Cow<'a, str>);
 
@@ -1,3 +1,5 @@
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
 use crate::PI;
 
 /// Document Type Definition
@@ -95,6 +97,51 @@ pub enum Repetition {
     ZeroOrOne,
 }
 
+/// Attribute-list Declaration
+///
+/// Section 3.3
+#[derive(Clone, Debug, PartialEq)]
+pub struct AttListDecl {
+    pub name: String,
+    pub defs: Vec<AttDef>,
+}
+
+/// Attribute Definition
+#[derive(Clone, Debug, PartialEq)]
+pub struct AttDef {
+    pub name: String,
+    pub att_type: AttType,
+    pub default: DefaultDecl,
+}
+
+/// Attribute Type
+///
+/// Section 3.3.1
+#[derive(Clone, Debug, PartialEq)]
+pub enum AttType {
+    CData,
+    Id,
+    IdRef,
+    IdRefs,
+    Entity,
+    Entities,
+    NmToken,
+    NmTokens,
+    Notation(Vec<String>),
+    Enumeration(Vec<String>),
+}
+
+/// Attribute Default
+///
+/// Section 3.3.2
+#[derive(Clone, Debug, PartialEq)]
+pub enum DefaultDecl {
+    Required,
+    Implied,
+    Fixed(String),
+    Default(String),
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum EntityDef {
     Internal(String),
@@ -122,14 +169,29 @@ pub struct PEDecl {
     pub def: PEDef,
 }
 
+/// Notation Declaration
+///
+/// Section 4.7
+#[derive(Clone, Debug, PartialEq)]
+pub struct NotationDecl {
+    pub name: String,
+    pub id: NotationId,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum NotationId {
+    External(ExternalId),
+    Public(String),
+}
+
 /// Entry of Markup Declaration
 #[derive(Clone, Debug, PartialEq)]
 pub enum MarkupDeclEntry {
     Element(Element),
-    AttList(String),
+    AttList(AttListDecl),
     GeneralEntity(GEDecl),
     ParameterEntity(PEDecl),
-    Notation(String),
+    Notation(NotationDecl),
     PI(PI<'static>),
     Comment(String),
     PEReference(String),
@@ -143,6 +205,10 @@ impl MarkupDeclEntry {
         })
     }
 
+    pub fn new_att_list(name: String, defs: Vec<AttDef>) -> Self {
+        Self::AttList(AttListDecl { name, defs })
+    }
+
     pub fn new_entity(name: String, def: EntityDef) -> Self {
         Self::GeneralEntity(GEDecl { name, def })
     }
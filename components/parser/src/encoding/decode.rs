@@ -5,10 +5,11 @@ use std::marker::PhantomData;
 use std::mem::transmute;
 use std::{io, slice};
 
-use encoding_rs::{Encoding, UTF_8};
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
 
 use xrs_chars::XmlAsciiChar;
 
+use crate::encoding::{detect, DetectedEncoding};
 use crate::parser::Parser;
 use crate::{XmlDecl, XmlError};
 
@@ -245,23 +246,72 @@ impl<R: Read> BytesParse<R> for Eq {
     }
 }
 
+/// Number of bytes after the byte order mark decoded to read the XML declaration
+const DECL_PREFIX_LEN: usize = 1024;
+
+/// Encoding of the document from its first bytes or its XML declaration
+///
+/// A byte order mark takes precedence, but a declared encoding must match it. UTF-16 can only
+/// be detected by its byte order mark or by the `<` of the document.
 pub fn guess_encoding<'a>(input: &'a [u8]) -> Result<&'static Encoding, XmlError> {
-    if let Some((enc, _)) = Encoding::for_bom(input) {
-        return Ok(enc);
-    }
+    let detected = detect(input);
+    let detected_encoding = match detected {
+        DetectedEncoding::Utf8 { .. } => UTF_8,
+        DetectedEncoding::Utf16Be { .. } => UTF_16BE,
+        DetectedEncoding::Utf16Le { .. } => UTF_16LE,
+        _ => return Err(XmlError::UnsupportedEncoding(detected.name().to_string())),
+    };
+    let bom = detected.bom_len() > 0;
+
+    let declared = if detected_encoding == UTF_8 && !bom {
+        declared_encoding(input)?
+    } else {
+        let start = detected.bom_len();
+        let prefix = &input[start..min(input.len(), start + DECL_PREFIX_LEN)];
+        let (prefix, _) = detected_encoding.decode_without_bom_handling(prefix);
+        declared_encoding(prefix.as_bytes())?
+    };
 
-    let mut cursor = BytesStream::new(input, 0);
-    let decl = match XmlDeclParser.parse(&mut cursor) {
-        Err(XmlError::ExpectToken("<?xml")) => return Ok(UTF_8),
-        Err(err) => return Err(err),
-        Ok(decl) => decl,
+    let declared = match declared {
+        Some(declared) => declared,
+        None => return Ok(detected_encoding),
+    };
+    let declared_encoding = Encoding::for_label(declared.as_bytes())
+        .ok_or_else(|| XmlError::UnsupportedEncoding(declared.clone()))?;
+
+    let matches = if is_utf16(detected_encoding) {
+        is_utf16(declared_encoding)
+    } else if bom {
+        declared_encoding == detected_encoding
+    } else {
+        // the declaration was readable as ASCII
+        !is_utf16(declared_encoding)
     };
+    if !matches {
+        return Err(XmlError::EncodingMismatch {
+            detected: detected_encoding.name().to_string(),
+            declared,
+        });
+    }
 
-    match decl.encoding {
-        Some(enc) => {
-            Encoding::for_label(enc.as_bytes()).ok_or_else(|| XmlError::UnsupportedEncoding(enc))
-        }
-        None => Ok(UTF_8),
+    if detected_encoding == UTF_8 && !bom {
+        Ok(declared_encoding)
+    } else {
+        Ok(detected_encoding)
+    }
+}
+
+fn is_utf16(encoding: &'static Encoding) -> bool {
+    encoding == UTF_16LE || encoding == UTF_16BE
+}
+
+/// Encoding name of the XML declaration at the start of `input`
+fn declared_encoding(input: &[u8]) -> Result<Option<String>, XmlError> {
+    let mut cursor = BytesStream::new(input, 0);
+    match XmlDeclParser.parse(&mut cursor) {
+        Err(XmlError::ExpectToken("<?xml")) => Ok(None),
+        Err(err) => Err(err),
+        Ok(decl) => Ok(decl.encoding),
     }
 }
 
@@ -279,6 +329,25 @@ pub fn decode<'a>(
     Ok((res, enc.name(), errors))
 }
 
+/// Decode a document into UTF-8 for the parser
+///
+/// The document is borrowed when it is already UTF-8. Malformed byte sequences are reported
+/// as [`XmlError::Decoding`].
+///
+/// ```
+/// use xrs_parser::encoding::to_utf8;
+///
+/// let doc = to_utf8(b"<?xml version='1.0' encoding='ISO-8859-1'?><a>\xE4</a>").unwrap();
+/// assert!(doc.ends_with("<a>\u{E4}</a>"));
+/// ```
+pub fn to_utf8<'a>(input: &'a [u8]) -> Result<Cow<'a, str>, XmlError> {
+    let (res, enc, errors) = decode(input, None)?;
+    if errors {
+        return Err(XmlError::Decoding(format!("malformed {} input", enc)));
+    }
+    Ok(res)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -307,6 +376,8 @@ mod tests {
     mod decode {
         use encoding_rs::WINDOWS_1252;
 
+        use crate::{Reader, XmlEvent};
+
         use super::*;
 
         fn expect_decode<'a>(
@@ -359,6 +430,111 @@ mod tests {
             );
         }
 
+        fn utf16(input: &str, little_endian: bool) -> Vec<u8> {
+            let mut result = if little_endian {
+                vec![0xFF, 0xFE]
+            } else {
+                vec![0xFE, 0xFF]
+            };
+            for unit in input.encode_utf16() {
+                if little_endian {
+                    result.extend_from_slice(&unit.to_le_bytes());
+                } else {
+                    result.extend_from_slice(&unit.to_be_bytes());
+                }
+            }
+            result
+        }
+
+        fn characters(doc: &str) -> Vec<String> {
+            let mut reader = Reader::new(doc);
+            let mut result = vec![];
+            while let Some(evt) = reader.next().unwrap() {
+                if let XmlEvent::Characters(chars) = evt {
+                    result.push(chars.to_string());
+                }
+            }
+            result
+        }
+
+        #[test]
+        fn check_utf16le_document() {
+            let input = utf16(
+                "<?xml version='1.0' encoding='UTF-16'?><a>\u{E4}\u{E9}\u{20AC}</a>",
+                true,
+            );
+            assert_eq!(UTF_16LE, guess_encoding(&input).unwrap());
+            let doc = to_utf8(&input).unwrap();
+            assert_eq!(vec!["\u{E4}\u{E9}\u{20AC}"], characters(&doc));
+        }
+
+        #[test]
+        fn check_utf16be_document() {
+            let input = utf16("<a>\u{E4}</a>", false);
+            assert_eq!(UTF_16BE, guess_encoding(&input).unwrap());
+            assert_eq!(vec!["\u{E4}"], characters(&to_utf8(&input).unwrap()));
+        }
+
+        #[test]
+        fn check_latin1_document() {
+            let input =
+                b"<?xml version='1.0' encoding='ISO-8859-1'?><a>Caf\xE9 \xE0 la cr\xE8me</a>";
+            let doc = to_utf8(input).unwrap();
+            assert_eq!(vec!["Caf\u{E9} \u{E0} la cr\u{E8}me"], characters(&doc));
+        }
+
+        #[test]
+        fn check_bom_mismatch() {
+            let input = utf16("<?xml version='1.0' encoding='ISO-8859-1'?><a/>", true);
+            assert_eq!(
+                Err(XmlError::EncodingMismatch {
+                    detected: UTF_16LE.name().to_string(),
+                    declared: "ISO-8859-1".to_string(),
+                }),
+                guess_encoding(&input)
+            );
+
+            let input = b"\xEF\xBB\xBF<?xml version='1.0' encoding='UTF-16'?><a/>";
+            assert!(matches!(
+                guess_encoding(input),
+                Err(XmlError::EncodingMismatch { .. })
+            ));
+        }
+
+        #[test]
+        fn check_utf16_without_bom() {
+            assert_eq!(
+                Err(XmlError::EncodingMismatch {
+                    detected: UTF_8.name().to_string(),
+                    declared: "UTF-16".to_string(),
+                }),
+                guess_encoding(b"<?xml version='1.0' encoding='UTF-16'?><a/>")
+            );
+        }
+
+        #[test]
+        fn check_utf16le_document_without_bom() {
+            let input = utf16("<?xml version='1.0' encoding='UTF-16'?><a>\u{E4}</a>", true);
+            let input = &input[2..];
+            assert_eq!(UTF_16LE, guess_encoding(input).unwrap());
+            assert_eq!(vec!["\u{E4}"], characters(&to_utf8(input).unwrap()));
+        }
+
+        #[test]
+        fn check_ucs4_unsupported() {
+            assert_eq!(
+                Err(XmlError::UnsupportedEncoding("UCS-4".to_string())),
+                guess_encoding(b"\0\0\0<\0\0\0a\0\0\0/\0\0\0>")
+            );
+        }
+
+        #[test]
+        fn check_malformed_input() {
+            let mut input = utf16("<a>x</a>", true);
+            input.push(0);
+            assert!(matches!(to_utf8(&input), Err(XmlError::Decoding(_))));
+        }
+
         #[test]
         fn check_external_iso_8859_1() {
             assert_eq!(
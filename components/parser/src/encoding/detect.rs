@@ -0,0 +1,217 @@
+/// Byte order of UCS-4 code units as positions of the big endian bytes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Ucs4Order {
+    /// `1234`
+    BigEndian,
+    /// `4321`
+    LittleEndian,
+    /// `2143`
+    Unusual2143,
+    /// `3412`
+    Unusual3412,
+}
+
+/// Encoding family detected from the first bytes of a document
+///
+/// See [Appendix F.1](https://www.w3.org/TR/xml/#sec-guessing-no-ext-info) of the XML
+/// specification. Encodings compatible with ASCII are reported as UTF-8, their XML
+/// declaration names the actual encoding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DetectedEncoding {
+    Utf8 {
+        bom: bool,
+    },
+    Utf16Be {
+        bom: bool,
+    },
+    Utf16Le {
+        bom: bool,
+    },
+    Ucs4 {
+        order: Ucs4Order,
+        bom: bool,
+    },
+    /// EBCDIC with an unknown code page
+    Ebcdic,
+}
+
+impl DetectedEncoding {
+    /// Length of the byte order mark in bytes
+    pub fn bom_len(&self) -> usize {
+        match *self {
+            DetectedEncoding::Utf8 { bom: true } => 3,
+            DetectedEncoding::Utf16Be { bom: true } | DetectedEncoding::Utf16Le { bom: true } => 2,
+            DetectedEncoding::Ucs4 { bom: true, .. } => 4,
+            _ => 0,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DetectedEncoding::Utf8 { .. } => "UTF-8",
+            DetectedEncoding::Utf16Be { .. } => "UTF-16BE",
+            DetectedEncoding::Utf16Le { .. } => "UTF-16LE",
+            DetectedEncoding::Ucs4 { .. } => "UCS-4",
+            DetectedEncoding::Ebcdic => "EBCDIC",
+        }
+    }
+}
+
+/// Detect the encoding family from the byte order mark or the first `<` of a document
+///
+/// ```
+/// use xrs_parser::encoding::{detect, DetectedEncoding};
+///
+/// assert_eq!(DetectedEncoding::Utf16Le { bom: false }, detect(b"<\0?\0x\0"));
+/// ```
+pub fn detect(input: &[u8]) -> DetectedEncoding {
+    use DetectedEncoding::*;
+    use Ucs4Order::*;
+
+    match input {
+        [0x00, 0x00, 0xFE, 0xFF, ..] => Ucs4 {
+            order: BigEndian,
+            bom: true,
+        },
+        [0xFF, 0xFE, 0x00, 0x00, ..] => Ucs4 {
+            order: LittleEndian,
+            bom: true,
+        },
+        [0x00, 0x00, 0xFF, 0xFE, ..] => Ucs4 {
+            order: Unusual2143,
+            bom: true,
+        },
+        [0xFE, 0xFF, 0x00, 0x00, ..] => Ucs4 {
+            order: Unusual3412,
+            bom: true,
+        },
+        [0xFE, 0xFF, ..] => Utf16Be { bom: true },
+        [0xFF, 0xFE, ..] => Utf16Le { bom: true },
+        [0xEF, 0xBB, 0xBF, ..] => Utf8 { bom: true },
+        [0x00, 0x00, 0x00, 0x3C, ..] => Ucs4 {
+            order: BigEndian,
+            bom: false,
+        },
+        [0x3C, 0x00, 0x00, 0x00, ..] => Ucs4 {
+            order: LittleEndian,
+            bom: false,
+        },
+        [0x00, 0x00, 0x3C, 0x00, ..] => Ucs4 {
+            order: Unusual2143,
+            bom: false,
+        },
+        [0x00, 0x3C, 0x00, 0x00, ..] => Ucs4 {
+            order: Unusual3412,
+            bom: false,
+        },
+        [0x00, 0x3C, ..] => Utf16Be { bom: false },
+        [0x3C, 0x00, ..] => Utf16Le { bom: false },
+        [0x4C, 0x6F, 0xA7, 0x94, ..] => Ebcdic,
+        _ => Utf8 { bom: false },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DetectedEncoding::*;
+    use super::Ucs4Order::*;
+    use super::*;
+
+    #[test]
+    fn ucs4_bom() {
+        assert_eq!(
+            Ucs4 {
+                order: BigEndian,
+                bom: true
+            },
+            detect(b"\0\0\xFE\xFF\0\0\0<")
+        );
+        assert_eq!(
+            Ucs4 {
+                order: LittleEndian,
+                bom: true
+            },
+            detect(b"\xFF\xFE\0\0<\0\0\0")
+        );
+        assert_eq!(
+            Ucs4 {
+                order: Unusual2143,
+                bom: true
+            },
+            detect(b"\0\0\xFF\xFE")
+        );
+        assert_eq!(
+            Ucs4 {
+                order: Unusual3412,
+                bom: true
+            },
+            detect(b"\xFE\xFF\0\0")
+        );
+    }
+
+    #[test]
+    fn utf16_bom() {
+        assert_eq!(Utf16Be { bom: true }, detect(b"\xFE\xFF\0<\0?"));
+        assert_eq!(Utf16Le { bom: true }, detect(b"\xFF\xFE<\0?\0"));
+        assert_eq!(Utf16Be { bom: true }, detect(b"\xFE\xFF"));
+        assert_eq!(Utf16Le { bom: true }, detect(b"\xFF\xFE"));
+    }
+
+    #[test]
+    fn utf8_bom() {
+        assert_eq!(Utf8 { bom: true }, detect(b"\xEF\xBB\xBF<?xml"));
+        assert_eq!(3, detect(b"\xEF\xBB\xBF").bom_len());
+    }
+
+    #[test]
+    fn ucs4_without_bom() {
+        assert_eq!(
+            Ucs4 {
+                order: BigEndian,
+                bom: false
+            },
+            detect(b"\0\0\0<\0\0\0?")
+        );
+        assert_eq!(
+            Ucs4 {
+                order: LittleEndian,
+                bom: false
+            },
+            detect(b"<\0\0\0?\0\0\0")
+        );
+        assert_eq!(
+            Ucs4 {
+                order: Unusual2143,
+                bom: false
+            },
+            detect(b"\0\0<\0")
+        );
+        assert_eq!(
+            Ucs4 {
+                order: Unusual3412,
+                bom: false
+            },
+            detect(b"\0<\0\0")
+        );
+    }
+
+    #[test]
+    fn utf16_without_bom() {
+        assert_eq!(Utf16Be { bom: false }, detect(b"\0<\0?\0x"));
+        assert_eq!(Utf16Le { bom: false }, detect(b"<\0?\0x\0"));
+        assert_eq!(0, detect(b"<\0?\0x\0").bom_len());
+    }
+
+    #[test]
+    fn ebcdic() {
+        assert_eq!(Ebcdic, detect(b"\x4C\x6F\xA7\x94"));
+    }
+
+    #[test]
+    fn ascii_compatible() {
+        assert_eq!(Utf8 { bom: false }, detect(b"<?xml version='1.0'?>"));
+        assert_eq!(Utf8 { bom: false }, detect(b"<a/>"));
+        assert_eq!(Utf8 { bom: false }, detect(b""));
+        assert_eq!(Utf8 { bom: false }, detect(b"<"));
+    }
+}
@@ -0,0 +1,9 @@
+//! Character encoding of XML documents
+
+#[cfg(feature = "encoding")]
+mod decode;
+mod detect;
+
+#[cfg(feature = "encoding")]
+pub use decode::*;
+pub use detect::*;
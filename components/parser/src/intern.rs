@@ -0,0 +1,63 @@
+//! Sharing of repeated names
+
+use alloc::collections::BTreeSet;
+use alloc::rc::Rc;
+
+/// Pool of strings handing out one shared allocation per distinct string
+///
+/// ```
+/// use std::rc::Rc;
+/// use xrs_parser::Interner;
+///
+/// let mut interner = Interner::new();
+/// let a = interner.intern("item");
+/// let b = interner.intern("item");
+/// assert!(Rc::ptr_eq(&a, &b));
+/// assert_eq!(1, interner.len());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Interner {
+    strings: BTreeSet<Rc<str>>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Shared string equal to `s`, which is only allocated when it is not in the pool yet
+    pub fn intern(&mut self, s: &str) -> Rc<str> {
+        if let Some(interned) = self.strings.get(s) {
+            return interned.clone();
+        }
+
+        let interned: Rc<str> = Rc::from(s);
+        self.strings.insert(interned.clone());
+        interned
+    }
+
+    /// Number of distinct strings in the pool
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shares_allocation() {
+        let mut interner = Interner::new();
+        let a = interner.intern("a");
+        let b = interner.intern("b");
+        assert!(Rc::ptr_eq(&a, &interner.intern("a")));
+        assert!(!Rc::ptr_eq(&a, &b));
+        assert_eq!("b", &*b);
+        assert_eq!(2, interner.len());
+    }
+}
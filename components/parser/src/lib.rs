@@ -1,32 +1,58 @@
 #![allow(unused)]
+#![cfg_attr(not(feature = "std"), no_std)]
 
-use std::borrow::Cow;
+extern crate alloc;
+
+use alloc::borrow::Cow;
+use core::fmt;
+use core::fmt::{Display, Formatter};
+use core::str::from_utf8;
+#[cfg(feature = "std")]
 use std::error::Error;
-use std::fmt::{Display, Formatter};
-use std::fs::read_to_string;
-use std::str::from_utf8;
-use std::{fmt, io};
+#[cfg(feature = "std")]
+use std::io;
+
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
 
+pub use intern::Interner;
 pub use namespace::parser::*;
 pub use namespace::*;
 use parser::cursor::Cursor;
-pub use reader::Reader;
+pub use reader::{
+    parse_all, parse_all_owned, Entities, Entity, Events, InsignificantWhitespace, OwnedEvents,
+    Reader, ReaderBuilder, UnknownEntityPolicy, XmlSpace,
+};
 use xrs_chars::XmlAsciiChar;
 use xrs_chars::XmlChar;
 
 use crate::dtd::DocTypeDecl;
+use crate::namespace::stack::NamespaceStack;
 use crate::XmlError::{ExpectedElementEnd, IllegalNameStartChar};
 
 pub(crate) mod cow;
-mod dtd;
-#[cfg(feature = "encoding")]
+pub mod dtd;
 pub mod encoding;
+pub mod intern;
 mod namespace;
 pub mod parser;
+pub mod push;
 mod reader;
+pub mod schema;
+#[cfg(feature = "std")]
 pub mod shufti;
 pub mod simple;
 
+/// Items of the `std` prelude which have to be imported in `no_std` builds
+#[cfg(not(feature = "std"))]
+mod prelude {
+    pub use alloc::borrow::ToOwned;
+    pub use alloc::boxed::Box;
+    pub use alloc::string::{String, ToString};
+    pub use alloc::vec::Vec;
+    pub use alloc::{format, vec};
+}
+
 /// XML Declaration
 #[derive(Clone, Debug, PartialEq)]
 pub struct XmlDecl {
@@ -55,6 +81,9 @@ pub struct STag<'a> {
     pub name: Cow<'a, str>,
     pub empty: bool,
     pub attrs: Vec<Attribute<'a>>,
+    /// Attributes as written in the source including the whitespace around them, only set
+    /// by a reader in verbatim mode
+    pub raw_attrs: Option<Cow<'a, str>>,
 }
 
 impl<'a> STag<'a> {
@@ -70,24 +99,89 @@ impl<'a> STag<'a> {
         &self.attrs
     }
 
+    /// Source text between the name and the end of the tag, see [`Reader::with_verbatim`]
+    pub fn raw_attributes(&self) -> Option<&str> {
+        self.raw_attrs.as_deref()
+    }
+
+    /// Attributes as namespace URI, local name and value
+    ///
+    /// Prefixes are resolved against the declarations of this start tag and then against
+    /// `scope`, which holds the declarations of the ancestors. The `xmlns` and `xmlns:*`
+    /// declarations themselves are skipped. Unprefixed attributes have no namespace, even
+    /// within a default namespace.
+    ///
+    /// A [`Reader`] returns start tags without attributes, use
+    /// [`Reader::resolved_attributes`] there.
+    pub fn resolved_attributes<'s>(
+        &'s self,
+        scope: &'s NamespaceStack,
+    ) -> impl Iterator<Item = Result<ResolvedAttribute<'s>, XmlError>> + 's {
+        resolve_attributes(&self.attrs, scope)
+    }
+
     pub fn into_owned(self) -> STag<'static> {
         STag {
             name: self.name.into_owned().into(),
-            empty: false,
+            empty: self.empty,
             attrs: self
                 .attrs
                 .into_iter()
                 .map(|attr| attr.into_owned())
                 .collect(),
+            raw_attrs: self.raw_attrs.map(|raw| raw.into_owned().into()),
         }
     }
 }
 
+/// Namespace URI, local name and value of an attribute
+pub type ResolvedAttribute<'s> = (Option<&'s str>, &'s str, &'s str);
+
+/// Resolve the attributes of a start tag, see [`STag::resolved_attributes`]
+pub(crate) fn resolve_attributes<'s>(
+    attrs: &'s [Attribute<'s>],
+    scope: &'s NamespaceStack,
+) -> impl Iterator<Item = Result<ResolvedAttribute<'s>, XmlError>> + 's {
+    attrs
+        .iter()
+        .filter(|attr| attr.name != "xmlns" && !attr.name.starts_with("xmlns:"))
+        .map(move |attr| {
+            let (prefix, local_name) = match attr.name.split_once(':') {
+                Some((prefix, local_name)) => (Some(prefix), local_name),
+                None => (None, attr.name()),
+            };
+            let namespace = match prefix {
+                None => None,
+                Some("xml") => Some(XML_URI),
+                Some(prefix) => Some(
+                    resolve_attribute_prefix(attrs, prefix, scope)
+                        .ok_or_else(|| XmlError::UnknownNamespacePrefix(prefix.to_string()))?,
+                ),
+            };
+            Ok((namespace, local_name, attr.value()))
+        })
+}
+
+/// Namespace of `prefix` declared in `attrs` or else in `scope`
+fn resolve_attribute_prefix<'s>(
+    attrs: &'s [Attribute<'s>],
+    prefix: &str,
+    scope: &'s NamespaceStack,
+) -> Option<&'s str> {
+    attrs
+        .iter()
+        .find(|attr| attr.name.strip_prefix("xmlns:") == Some(prefix))
+        .map(|attr| attr.value())
+        .or_else(|| scope.resolve(prefix))
+}
+
 /// Attribute
 #[derive(Clone, PartialEq)]
 pub struct Attribute<'a> {
     pub name: Cow<'a, str>,
     pub value: Cow<'a, str>,
+    /// Quote character around the value in the source, only set by a reader in verbatim mode
+    pub quote: Option<char>,
 }
 
 impl<'a> Attribute<'a> {
@@ -95,9 +189,16 @@ impl<'a> Attribute<'a> {
         Self {
             name: name.into(),
             value: value.into(),
+            quote: None,
         }
     }
 
+    /// Set the quote character which was used in the source
+    pub fn with_quote(mut self, quote: char) -> Self {
+        self.quote = Some(quote);
+        self
+    }
+
     pub fn value(&self) -> &str {
         self.value.as_ref()
     }
@@ -114,10 +215,16 @@ impl<'a> Attribute<'a> {
         self.name.clone()
     }
 
+    /// Either `'"'` or `'\''` in verbatim mode, `None` otherwise and for defaulted attributes
+    pub fn quote(&self) -> Option<char> {
+        self.quote
+    }
+
     pub fn into_owned(self) -> Attribute<'static> {
         Attribute {
             name: self.name.into_owned().into(),
             value: self.value.into_owned().into(),
+            quote: self.quote,
         }
     }
 }
@@ -127,6 +234,7 @@ impl<'a> fmt::Debug for Attribute<'a> {
         f.debug_struct("Attribute")
             .field("name", &self.name)
             .field("value", &self.value)
+            .field("quote", &self.quote)
             .finish()
     }
 }
@@ -173,6 +281,37 @@ impl<'a> PI<'a> {
     }
 }
 
+/// Character reference like `&#65;` or `&#x41;`
+#[derive(Clone, Debug, PartialEq)]
+pub struct CharRef<'a> {
+    /// Referenced character
+    pub character: char,
+    /// Reference as written in the document including `&#` and `;`
+    pub reference: Cow<'a, str>,
+}
+
+impl<'a> CharRef<'a> {
+    pub fn character(&self) -> char {
+        self.character
+    }
+
+    pub fn reference(&self) -> &str {
+        &self.reference
+    }
+
+    /// Reference was written in hexadecimal like `&#x41;` instead of decimal
+    pub fn is_hex(&self) -> bool {
+        self.reference.starts_with("&#x")
+    }
+
+    pub fn into_owned(self) -> CharRef<'static> {
+        CharRef {
+            character: self.character,
+            reference: Cow::Owned(self.reference.into_owned()),
+        }
+    }
+}
+
 /// Event of Pull Parser
 #[derive(Clone, Debug, PartialEq)]
 pub enum XmlEvent<'a> {
@@ -180,7 +319,17 @@ pub enum XmlEvent<'a> {
     Dtd(Box<DocTypeDecl>),
     STag(STag<'a>),
     ETag(ETag<'a>),
+    /// Empty element tag like `<x/>`, only emitted when enabled instead of a `STag` and `ETag`
+    EmptyElement(STag<'a>),
     Characters(Cow<'a, str>),
+    /// Content of a CDATA section
+    CData(Cow<'a, str>),
+    /// Whitespace-only text between markup, only emitted when reported
+    Whitespace(Cow<'a, str>),
+    /// Recovered error, only emitted by a recoverable reader
+    Error(XmlError),
+    /// Character reference, only emitted when references are preserved
+    CharRef(CharRef<'a>),
     PI(PI<'a>),
     Comment(Cow<'a, str>),
 }
@@ -199,6 +348,7 @@ impl<'a> XmlEvent<'a> {
             name: name.into(),
             empty,
             attrs: vec![],
+            raw_attrs: None,
         })
     }
 
@@ -211,6 +361,7 @@ impl<'a> XmlEvent<'a> {
             name: name.into(),
             empty,
             attrs: attrs.into(),
+            raw_attrs: None,
         })
     }
 
@@ -218,6 +369,17 @@ impl<'a> XmlEvent<'a> {
         XmlEvent::Characters(chars.into())
     }
 
+    pub fn cdata(cdata: impl Into<Cow<'a, str>>) -> Self {
+        XmlEvent::CData(cdata.into())
+    }
+
+    pub fn char_ref(character: char, reference: impl Into<Cow<'a, str>>) -> Self {
+        XmlEvent::CharRef(CharRef {
+            character,
+            reference: reference.into(),
+        })
+    }
+
     pub fn etag(name: impl Into<Cow<'a, str>>) -> Self {
         XmlEvent::ETag(ETag { name: name.into() })
     }
@@ -239,14 +401,19 @@ impl<'a> XmlEvent<'a> {
             XmlEvent::Dtd(v) => XmlEvent::Dtd(v),
             XmlEvent::STag(v) => XmlEvent::STag(v.into_owned()),
             XmlEvent::ETag(v) => XmlEvent::ETag(v.into_owned()),
+            XmlEvent::EmptyElement(v) => XmlEvent::EmptyElement(v.into_owned()),
             XmlEvent::Characters(v) => XmlEvent::Characters(v.into_owned().into()),
+            XmlEvent::CData(v) => XmlEvent::CData(v.into_owned().into()),
+            XmlEvent::Whitespace(v) => XmlEvent::Whitespace(v.into_owned().into()),
+            XmlEvent::CharRef(v) => XmlEvent::CharRef(v.into_owned()),
+            XmlEvent::Error(v) => XmlEvent::Error(v),
             XmlEvent::PI(v) => XmlEvent::PI(v.into_owned()),
             XmlEvent::Comment(v) => XmlEvent::Comment(v.into_owned().into()),
         }
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum XmlErrorAtom {
     XmlDecl,
     CData,
@@ -258,7 +425,7 @@ pub enum XmlErrorAtom {
 }
 
 /// Fatal parsing error
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum XmlError {
     IllegalNameStartChar(char),
     IllegalChar(char),
@@ -273,6 +440,11 @@ pub enum XmlError {
         expected_name: String,
     },
     UnexpectedEof,
+    /// Input ended inside a token, so more input could complete it
+    ///
+    /// Only returned by token parsers. The contained error is the one for input known to be
+    /// complete, [`Reader::next`] reports it instead.
+    NeedMore(Box<XmlError>),
     IllegalCDataSectionEnd,
     UnexpectedDtdEntry,
     ETagAfterRootElement,
@@ -280,6 +452,15 @@ pub enum XmlError {
     NonUniqueAttribute {
         attribute: String,
     },
+    /// Attribute declared as `#REQUIRED` in the DTD is missing
+    MissingRequiredAttribute {
+        attribute: String,
+    },
+    /// Attribute value differs from the `#FIXED` value declared in the DTD
+    FixedAttributeMismatch {
+        attribute: String,
+        expected: String,
+    },
     IllegalName {
         name: String,
     },
@@ -287,9 +468,20 @@ pub enum XmlError {
     InvalidCharacter(char),
     IllegalReference,
     UnknownEntity(String),
+    /// Entity is referenced in its own replacement text
+    RecursiveEntity(String),
+    /// Reference to an entity in a standalone document which requires external markup
+    /// declarations
+    StandaloneViolation(String),
     ExpectToken(&'static str),
     IllegalAttributeValue(&'static str),
     UnsupportedEncoding(String),
+    /// Encoding declared in the XML declaration does not match the byte order mark or the
+    /// bytes of the declaration
+    EncodingMismatch {
+        detected: String,
+        declared: String,
+    },
     DtdError(XmlDtdError),
     /// Processing Instruction target should not be `xml` (case-insensitive)
     InvalidPITarget,
@@ -297,11 +489,40 @@ pub enum XmlError {
     CommentColonColon,
     UnknownNamespacePrefix(String),
     IllegalNamespaceUri(String),
+    /// Namespace prefix is declared twice on one element, `None` for the default namespace
+    DuplicateNamespaceDeclaration(Option<String>),
+    /// `xml` prefix is bound to another namespace or the `xmlns` prefix is declared
+    ReservedPrefixRebinding(String),
     Io(String),
     Decoding(String),
     UnsupportedVersion(String),
+    /// Elements are nested deeper than the configured maximum depth
+    DepthLimitExceeded(usize),
+    /// Replacement text of expanded entities exceeds the configured limit in bytes
+    ExpansionLimitExceeded(usize),
+}
+
+impl XmlError {
+    /// Input ended inside a token which would be complete with more input
+    pub(crate) fn need_more() -> Self {
+        XmlError::NeedMore(Box::new(XmlError::UnexpectedEof))
+    }
+
+    /// Whether more input could resolve the error
+    pub fn is_need_more(&self) -> bool {
+        matches!(self, XmlError::NeedMore(_))
+    }
+
+    /// Error for input known to be complete
+    pub fn into_final(self) -> Self {
+        match self {
+            XmlError::NeedMore(err) => *err,
+            err => err,
+        }
+    }
 }
 
+#[cfg(feature = "std")]
 impl From<io::Error> for XmlError {
     fn from(value: io::Error) -> Self {
         XmlError::Io(value.to_string())
@@ -314,11 +535,189 @@ impl Display for XmlError {
     }
 }
 
+#[cfg(feature = "std")]
 impl Error for XmlError {}
 
 /// Fatal DTD parsing error
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum XmlDtdError {
     SyntaxError,
     Unsupported,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clone_errors() {
+        let errors = vec![
+            XmlError::IllegalNameStartChar('1'),
+            XmlError::IllegalChar('\0'),
+            XmlError::ExpectedElementStart,
+            XmlError::ExpectedElementEnd,
+            XmlError::ExpectedAttrName,
+            XmlError::ExpectedEquals,
+            XmlError::ExpectedDocumentEnd,
+            XmlError::Expected(Box::new([XmlErrorAtom::XmlDecl, XmlErrorAtom::Element])),
+            XmlError::ExpectedWhitespace,
+            XmlError::WrongETagName {
+                expected_name: "a".to_string(),
+            },
+            XmlError::UnexpectedEof,
+            XmlError::need_more(),
+            XmlError::NeedMore(Box::new(XmlError::ExpectToken("-->"))),
+            XmlError::IllegalCDataSectionEnd,
+            XmlError::UnexpectedDtdEntry,
+            XmlError::ETagAfterRootElement,
+            XmlError::OpenElementAtEof,
+            XmlError::NonUniqueAttribute {
+                attribute: "a".to_string(),
+            },
+            XmlError::MissingRequiredAttribute {
+                attribute: "a".to_string(),
+            },
+            XmlError::FixedAttributeMismatch {
+                attribute: "a".to_string(),
+                expected: "1".to_string(),
+            },
+            XmlError::IllegalName {
+                name: "1a".to_string(),
+            },
+            XmlError::InvalidCharacterReference("#0".to_string()),
+            XmlError::InvalidCharacter('\0'),
+            XmlError::IllegalReference,
+            XmlError::UnknownEntity("e".to_string()),
+            XmlError::RecursiveEntity("e".to_string()),
+            XmlError::StandaloneViolation("e".to_string()),
+            XmlError::ExpectToken("?>"),
+            XmlError::IllegalAttributeValue("<"),
+            XmlError::UnsupportedEncoding("EBCDIC".to_string()),
+            XmlError::EncodingMismatch {
+                detected: "UTF-16LE".to_string(),
+                declared: "UTF-8".to_string(),
+            },
+            XmlError::DtdError(XmlDtdError::SyntaxError),
+            XmlError::DtdError(XmlDtdError::Unsupported),
+            XmlError::InvalidPITarget,
+            XmlError::UnexpectedCharacter('<'),
+            XmlError::CommentColonColon,
+            XmlError::UnknownNamespacePrefix("a".to_string()),
+            XmlError::IllegalNamespaceUri("".to_string()),
+            XmlError::DuplicateNamespaceDeclaration(Some("a".to_string())),
+            XmlError::ReservedPrefixRebinding("xml".to_string()),
+            XmlError::Io("broken pipe".to_string()),
+            XmlError::Decoding("invalid UTF-8".to_string()),
+            XmlError::UnsupportedVersion("2.0".to_string()),
+            XmlError::DepthLimitExceeded(10),
+            XmlError::ExpansionLimitExceeded(100),
+        ];
+
+        for error in &errors {
+            assert_eq!(error, &error.clone());
+        }
+        assert_eq!(errors, errors.clone());
+        assert_ne!(XmlError::UnexpectedEof, XmlError::need_more());
+        assert_eq!(
+            XmlError::UnexpectedEof,
+            XmlError::need_more().clone().into_final()
+        );
+    }
+
+    mod resolved_attributes {
+        use super::*;
+
+        fn stag(attrs: &[(&'static str, &'static str)]) -> STag<'static> {
+            STag {
+                name: "e".into(),
+                empty: true,
+                attrs: attrs
+                    .iter()
+                    .map(|(name, value)| Attribute::new(*name, *value))
+                    .collect(),
+                raw_attrs: None,
+            }
+        }
+
+        fn resolve<'s>(
+            stag: &'s STag,
+            scope: &'s NamespaceStack,
+        ) -> Result<Vec<ResolvedAttribute<'s>>, XmlError> {
+            stag.resolved_attributes(scope).collect()
+        }
+
+        #[test]
+        fn unprefixed_without_namespace() {
+            let stag = stag(&[("xmlns", "urn:default"), ("id", "1")]);
+            let mut scope = NamespaceStack::new();
+            let mut builder = scope.build_scope();
+            builder.add_prefix(None, "urn:outer".to_string()).unwrap();
+            builder.finish();
+            assert_eq!(Ok(vec![(None, "id", "1")]), resolve(&stag, &scope));
+        }
+
+        #[test]
+        fn prefixed() {
+            let stag = stag(&[("xmlns:a", "urn:a"), ("a:x", "1"), ("b:y", "2")]);
+            let mut scope = NamespaceStack::new();
+            let mut builder = scope.build_scope();
+            builder
+                .add_prefix(Some("b".to_string()), "urn:b".to_string())
+                .unwrap();
+            builder.finish();
+            assert_eq!(
+                Ok(vec![(Some("urn:a"), "x", "1"), (Some("urn:b"), "y", "2")]),
+                resolve(&stag, &scope)
+            );
+        }
+
+        #[test]
+        fn own_declaration_first() {
+            let stag = stag(&[("a:x", "1"), ("xmlns:a", "urn:inner")]);
+            let mut scope = NamespaceStack::new();
+            let mut builder = scope.build_scope();
+            builder
+                .add_prefix(Some("a".to_string()), "urn:outer".to_string())
+                .unwrap();
+            builder.finish();
+            assert_eq!(
+                Ok(vec![(Some("urn:inner"), "x", "1")]),
+                resolve(&stag, &scope)
+            );
+        }
+
+        #[test]
+        fn xml_prefix() {
+            let stag = stag(&[("xml:lang", "en")]);
+            assert_eq!(
+                Ok(vec![(Some(XML_URI), "lang", "en")]),
+                resolve(&stag, &NamespaceStack::new())
+            );
+        }
+
+        #[test]
+        fn undeclared_prefix() {
+            let stag = stag(&[("a:x", "1")]);
+            assert_eq!(
+                Err(XmlError::UnknownNamespacePrefix("a".to_string())),
+                resolve(&stag, &NamespaceStack::new())
+            );
+        }
+
+        #[test]
+        fn reader() {
+            let mut reader = Reader::new("<a:e xmlns:a='urn:a' a:x='1' y='2' xml:lang='en'/>");
+            assert_eq!(Ok(Some(XmlEvent::stag("a:e", true))), reader.next());
+            let scope = NamespaceStack::new();
+            let attrs: Result<Vec<_>, _> = reader.resolved_attributes(&scope).collect();
+            assert_eq!(
+                Ok(vec![
+                    (Some("urn:a"), "x", "1"),
+                    (None, "y", "2"),
+                    (Some(XML_URI), "lang", "en")
+                ]),
+                attrs
+            );
+        }
+    }
+}
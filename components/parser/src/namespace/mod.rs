@@ -1,14 +1,21 @@
-use std::borrow::Cow;
-use std::fmt;
-use std::fmt::{Display, Formatter};
-use std::str::{from_utf8, FromStr, ParseBoolError};
-use std::sync::Arc;
+use alloc::borrow::Cow;
+use alloc::sync::Arc;
+use core::fmt;
+use core::fmt::{Display, Formatter};
+use core::str::{from_utf8, FromStr, ParseBoolError};
 
-use crate::{DocTypeDecl, XmlDecl, XmlError, PI};
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use crate::{CharRef, DocTypeDecl, XmlDecl, XmlError, PI};
 
 pub mod parser;
 pub mod stack;
 
+/// Namespace bound to the `xml` prefix
+pub const XML_URI: &str = "http://www.w3.org/XML/1998/namespace";
+/// Namespace of namespace declarations, must not be declared
+pub const XMLNS_URI: &str = "http://www.w3.org/2000/xmlns/";
+
 /// Qualified Name
 ///
 /// Name with namespace prefix and local part
@@ -186,6 +193,10 @@ pub enum XmlNsEvent<'a> {
     STag(NsSTag<'a>),
     ETag(NsETag<'a>),
     Characters(Cow<'a, str>),
+    CData(Cow<'a, str>),
+    Whitespace(Cow<'a, str>),
+    Error(XmlError),
+    CharRef(CharRef<'a>),
     PI(PI<'a>),
     Comment(Cow<'a, str>),
 }
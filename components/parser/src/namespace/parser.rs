@@ -1,64 +1,57 @@
-use std::rc::Rc;
+use alloc::borrow::Cow;
+use alloc::rc::Rc;
 
 use crate::namespace::stack::NamespaceStack;
 use crate::namespace::{NsAttribute, NsETag, NsSTag, QName, XmlNsEvent};
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
 use crate::reader::Reader;
-use crate::{Attribute, ETag, STag, XmlError, XmlEvent};
+use crate::{Attribute, ETag, ResolvedAttribute, STag, XmlError, XmlEvent, XML_URI};
 
 pub struct NsReader<'a> {
     reader: Reader<'a>,
     namespaces: NamespaceStack,
     attributes: Vec<NsAttribute<'a>>,
+    /// End tag to return for the last `EmptyElement` event of the reader
+    empty_etag: Option<Cow<'a, str>>,
 }
 
 impl<'a> NsReader<'a> {
     pub fn new(input: &'a str) -> Self {
+        Self::from_reader(Reader::new(input))
+    }
+
+    /// Resolve namespaces of the events of a configured reader
+    pub fn from_reader(reader: Reader<'a>) -> Self {
         Self {
-            reader: Reader::new(input),
+            reader,
             namespaces: NamespaceStack::default(),
             attributes: Vec::with_capacity(4),
+            empty_etag: None,
         }
     }
 
     pub fn next(&mut self) -> Result<Option<XmlNsEvent<'a>>, XmlError> {
         self.attributes.clear();
+        if let Some(name) = self.empty_etag.take() {
+            return self.end_element(name);
+        }
 
-        let evt = self.reader.next()?;
+        let evt = self.reader.next().map_err(namespace_error)?;
         match evt {
             None => Ok(None),
-            Some(XmlEvent::STag(stag)) => {
-                self.attributes.reserve(stag.attributes().len());
-                let mut scope = self.namespaces.build_scope();
-                for attr in stag.attrs {
-                    let qname = QName::from_cow(attr.name)?;
-                    if let Some(prefix) = &qname.prefix {
-                        if *prefix == "xmlns" {
-                            scope.add_prefix(
-                                Some(qname.local_part.to_string()),
-                                attr.value.to_string(),
-                            )
-                        }
-                    } else if qname.local_part == "xmlns" {
-                        scope.add_prefix(None, attr.value.to_string())
-                    }
-
-                    self.attributes.push(NsAttribute::new(qname, attr.value));
-                }
-                scope.finish();
-
-                Ok(Some(XmlNsEvent::STag(NsSTag {
-                    qname: QName::from_cow(stag.name)?,
-                    empty: stag.empty,
-                })))
-            }
-            Some(XmlEvent::ETag(etag)) => {
-                self.namespaces.pop_scope();
-                Ok(Some(XmlNsEvent::ETag(NsETag {
-                    // TODO: use qname stack
-                    qname: QName::from_cow(etag.name)?,
-                })))
+            // namespace events keep the start and end tag representation
+            Some(XmlEvent::EmptyElement(stag)) => {
+                self.empty_etag = Some(stag.name.clone());
+                self.start_element(stag)
             }
+            Some(XmlEvent::STag(stag)) => self.start_element(stag),
+            Some(XmlEvent::ETag(etag)) => self.end_element(etag.name),
             Some(XmlEvent::Characters(chars)) => Ok(Some(XmlNsEvent::Characters(chars))),
+            Some(XmlEvent::CData(cdata)) => Ok(Some(XmlNsEvent::CData(cdata))),
+            Some(XmlEvent::Error(err)) => Ok(Some(XmlNsEvent::Error(namespace_error(err)))),
+            Some(XmlEvent::Whitespace(chars)) => Ok(Some(XmlNsEvent::Whitespace(chars))),
+            Some(XmlEvent::CharRef(char_ref)) => Ok(Some(XmlNsEvent::CharRef(char_ref))),
             Some(XmlEvent::XmlDecl(decl)) => Ok(Some(XmlNsEvent::XmlDecl(decl))),
             Some(XmlEvent::Dtd(dtd)) => Ok(Some(XmlNsEvent::Dtd(dtd))),
             Some(XmlEvent::PI(pi)) => Ok(Some(XmlNsEvent::PI(pi))),
@@ -66,11 +59,101 @@ impl<'a> NsReader<'a> {
         }
     }
 
+    fn start_element(&mut self, stag: STag<'a>) -> Result<Option<XmlNsEvent<'a>>, XmlError> {
+        let attributes = self.reader.drain_attributes();
+        self.attributes.reserve(attributes.len());
+        let mut scope = self.namespaces.build_scope();
+        for attr in attributes {
+            let qname = QName::from_cow(attr.name)?;
+            if let Some(prefix) = &qname.prefix {
+                if *prefix == "xmlns" {
+                    scope.add_prefix(Some(qname.local_part.to_string()), attr.value.to_string())?
+                }
+            } else if qname.local_part == "xmlns" {
+                scope.add_prefix(None, attr.value.to_string())?
+            }
+
+            self.attributes.push(NsAttribute::new(qname, attr.value));
+        }
+        scope.finish();
+
+        Ok(Some(XmlNsEvent::STag(NsSTag {
+            qname: QName::from_cow(stag.name)?,
+            empty: stag.empty,
+        })))
+    }
+
+    fn end_element(&mut self, name: Cow<'a, str>) -> Result<Option<XmlNsEvent<'a>>, XmlError> {
+        self.namespaces.pop_scope();
+        Ok(Some(XmlNsEvent::ETag(NsETag {
+            // TODO: use qname stack
+            qname: QName::from_cow(name)?,
+        })))
+    }
+
+    /// Consume events until a start tag with the given namespace and local name is found
+    ///
+    /// `"*"` as namespace or local name matches any value. A `None` namespace matches only
+    /// elements without namespace. Returns `None` when the end of the document is reached.
+    pub fn advance_to_element(
+        &mut self,
+        namespace: Option<&str>,
+        local_name: &str,
+    ) -> Result<Option<NsSTag<'a>>, XmlError> {
+        while let Some(evt) = self.next()? {
+            if let XmlNsEvent::STag(stag) = evt {
+                if local_name != "*" && stag.qname.local_part != local_name {
+                    continue;
+                }
+
+                let found = match namespace {
+                    Some("*") => true,
+                    namespace => self.resolve_element_namespace(&stag.qname)? == namespace,
+                };
+                if found {
+                    return Ok(Some(stag));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Owned copy of a name, see [`Reader::intern`]
+    pub fn intern(&mut self, name: &str) -> Rc<str> {
+        self.reader.intern(name)
+    }
+
     #[inline]
     pub fn attributes(&self) -> &[NsAttribute<'a>] {
         &self.attributes
     }
 
+    /// Attributes of the last start tag as namespace URI, local name and value
+    ///
+    /// The `xmlns` and `xmlns:*` declarations are skipped and unprefixed attributes have no
+    /// namespace, see [`STag::resolved_attributes`].
+    pub fn resolved_attributes(
+        &self,
+    ) -> impl Iterator<Item = Result<ResolvedAttribute<'_>, XmlError>> + '_ {
+        self.attributes
+            .iter()
+            .filter(|attr| match attr.qname.prefix.as_deref() {
+                Some(prefix) => prefix != "xmlns",
+                None => attr.qname.local_part != "xmlns",
+            })
+            .map(move |attr| {
+                let namespace = match attr.qname.prefix.as_deref() {
+                    Some("xml") => Some(XML_URI),
+                    _ => self.resolve_attribute_namespace(&attr.qname)?,
+                };
+                Ok((
+                    namespace,
+                    &attr.qname.local_part as &str,
+                    &attr.value as &str,
+                ))
+            })
+    }
+
     #[inline]
     pub fn resolve_namespace(&self, prefix: Option<&str>) -> Option<&str> {
         if let Some(prefix) = prefix {
@@ -106,3 +189,256 @@ impl<'a> NsReader<'a> {
         }
     }
 }
+
+/// Report a repeated namespace declaration as such instead of as a repeated attribute
+fn namespace_error(err: XmlError) -> XmlError {
+    match err {
+        XmlError::NonUniqueAttribute { attribute } if attribute == "xmlns" => {
+            XmlError::DuplicateNamespaceDeclaration(None)
+        }
+        XmlError::NonUniqueAttribute { attribute } if attribute.starts_with("xmlns:") => {
+            XmlError::DuplicateNamespaceDeclaration(Some(attribute["xmlns:".len()..].to_string()))
+        }
+        err => err,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::namespace::parser::NsReader;
+    use crate::namespace::{NsAttribute, NsSTag, QName, XmlNsEvent};
+    use crate::XmlError;
+
+    const DOC: &str = "<root xmlns:a='urn:a' xmlns='urn:default'>\
+        <a:item id='1'/>\
+        <item id='2'/>\
+        <other xmlns='urn:b'><item id='3'/></other>\
+        </root>";
+
+    fn qname(prefix: Option<&'static str>, local_part: &'static str) -> QName<'static> {
+        QName {
+            prefix: prefix.map(Into::into),
+            local_part: local_part.into(),
+        }
+    }
+
+    #[test]
+    fn uri_and_local_name() {
+        let mut reader = NsReader::new(DOC);
+        assert_eq!(
+            Ok(Some(NsSTag {
+                qname: qname(Some("a"), "item"),
+                empty: true
+            })),
+            reader.advance_to_element(Some("urn:a"), "item")
+        );
+        assert_eq!(
+            &[NsAttribute::new(qname(None, "id"), "1")],
+            reader.attributes()
+        );
+
+        let mut reader = NsReader::new(DOC);
+        assert_eq!(
+            Ok(Some(NsSTag {
+                qname: qname(None, "item"),
+                empty: true
+            })),
+            reader.advance_to_element(Some("urn:default"), "item")
+        );
+        assert_eq!(
+            &[NsAttribute::new(qname(None, "id"), "2")],
+            reader.attributes()
+        );
+    }
+
+    #[test]
+    fn empty_element_events() {
+        let mut reader = crate::ReaderBuilder::new()
+            .empty_element_events(true)
+            .build_ns("<root xmlns:a='urn:a'><a:item/><item/></root>");
+        assert!(matches!(reader.next(), Ok(Some(XmlNsEvent::STag(_)))));
+        assert_eq!(
+            Ok(Some(XmlNsEvent::STag(NsSTag {
+                qname: qname(Some("a"), "item"),
+                empty: true
+            }))),
+            reader.next()
+        );
+        assert_eq!(Some("urn:a"), reader.resolve_namespace(Some("a")));
+        assert!(matches!(reader.next(), Ok(Some(XmlNsEvent::ETag(_)))));
+        assert_eq!(
+            Ok(Some(XmlNsEvent::STag(NsSTag {
+                qname: qname(None, "item"),
+                empty: true
+            }))),
+            reader.next()
+        );
+        assert!(matches!(reader.next(), Ok(Some(XmlNsEvent::ETag(_)))));
+        assert!(matches!(reader.next(), Ok(Some(XmlNsEvent::ETag(_)))));
+        assert_eq!(Ok(None), reader.next());
+    }
+
+    #[test]
+    fn any_namespace() {
+        let mut reader = NsReader::new(DOC);
+        for id in &["1", "2", "3"] {
+            assert!(reader
+                .advance_to_element(Some("*"), "item")
+                .unwrap()
+                .is_some());
+            assert_eq!(
+                &[NsAttribute::new(qname(None, "id"), *id)],
+                reader.attributes()
+            );
+        }
+        assert_eq!(Ok(None), reader.advance_to_element(Some("*"), "item"));
+    }
+
+    #[test]
+    fn any_local_name() {
+        let mut reader = NsReader::new(DOC);
+        assert_eq!(
+            Ok(Some(NsSTag {
+                qname: qname(None, "other"),
+                empty: false
+            })),
+            reader.advance_to_element(Some("urn:b"), "*")
+        );
+    }
+
+    #[test]
+    fn without_namespace() {
+        let mut reader = NsReader::new("<root xmlns:a='urn:a'><a:item/><item/></root>");
+        assert_eq!(
+            Ok(Some(NsSTag {
+                qname: qname(None, "item"),
+                empty: true
+            })),
+            reader.advance_to_element(None, "item")
+        );
+    }
+
+    #[test]
+    fn resolved_attributes() {
+        let mut reader = NsReader::new(
+            "<r xmlns:a='urn:a' xmlns='urn:d'><e a:x='1' y='2' xmlns:b='urn:b' b:z='3'/></r>",
+        );
+        reader.next().unwrap();
+        reader.next().unwrap();
+        let attrs: Result<Vec<_>, _> = reader.resolved_attributes().collect();
+        assert_eq!(
+            Ok(vec![
+                (Some("urn:a"), "x", "1"),
+                (None, "y", "2"),
+                (Some("urn:b"), "z", "3")
+            ]),
+            attrs
+        );
+    }
+
+    #[test]
+    fn resolved_attributes_undeclared_prefix() {
+        let mut reader = NsReader::new("<e a:x='1'/>");
+        reader.next().unwrap();
+        let attrs: Result<Vec<_>, _> = reader.resolved_attributes().collect();
+        assert_eq!(
+            Err(XmlError::UnknownNamespacePrefix("a".to_string())),
+            attrs
+        );
+    }
+
+    mod declarations {
+        use crate::namespace::parser::NsReader;
+        use crate::{XmlError, XML_URI};
+
+        fn first_error(input: &str) -> XmlError {
+            let mut reader = NsReader::new(input);
+            loop {
+                match reader.next() {
+                    Ok(Some(_)) => {}
+                    Ok(None) => panic!("no error in {}", input),
+                    Err(err) => return err,
+                }
+            }
+        }
+
+        #[test]
+        fn duplicate_prefix() {
+            assert_eq!(
+                XmlError::DuplicateNamespaceDeclaration(Some("a".to_string())),
+                first_error("<e xmlns:a='urn:a' xmlns:a='urn:b'/>")
+            );
+        }
+
+        #[test]
+        fn duplicate_default() {
+            assert_eq!(
+                XmlError::DuplicateNamespaceDeclaration(None),
+                first_error("<e xmlns='urn:a' xmlns='urn:b'/>")
+            );
+        }
+
+        #[test]
+        fn duplicate_recoverable() {
+            let mut reader = NsReader::from_reader(
+                crate::Reader::new("<e xmlns:a='urn:a' xmlns:a='urn:b'/>").recoverable(),
+            );
+            assert!(matches!(
+                reader.next(),
+                Ok(Some(crate::XmlNsEvent::Error(
+                    XmlError::DuplicateNamespaceDeclaration(Some(_))
+                )))
+            ));
+        }
+
+        #[test]
+        fn redeclare_in_child() {
+            let mut reader = NsReader::new("<e xmlns:a='urn:a'><f xmlns:a='urn:b'/></e>");
+            while reader.next().unwrap().is_some() {}
+        }
+
+        #[test]
+        fn xml_prefix() {
+            assert_eq!(
+                XmlError::ReservedPrefixRebinding("xml".to_string()),
+                first_error("<e xmlns:xml='wrong'/>")
+            );
+
+            let input = format!("<e xmlns:xml='{}'/>", XML_URI);
+            let mut reader = NsReader::new(&input);
+            while reader.next().unwrap().is_some() {}
+        }
+
+        #[test]
+        fn xmlns_prefix() {
+            assert_eq!(
+                XmlError::ReservedPrefixRebinding("xmlns".to_string()),
+                first_error("<e xmlns:xmlns='http://www.w3.org/2000/xmlns/'/>")
+            );
+        }
+
+        #[test]
+        fn reserved_namespace() {
+            assert_eq!(
+                XmlError::IllegalNamespaceUri(XML_URI.to_string()),
+                first_error(&format!("<e xmlns:a='{}'/>", XML_URI))
+            );
+            assert_eq!(
+                XmlError::IllegalNamespaceUri(XML_URI.to_string()),
+                first_error(&format!("<e xmlns='{}'/>", XML_URI))
+            );
+        }
+
+        #[test]
+        fn empty_uri() {
+            assert_eq!(
+                XmlError::IllegalNamespaceUri(String::new()),
+                first_error("<e xmlns:a=''/>")
+            );
+
+            // undeclares the default namespace
+            let mut reader = NsReader::new("<e xmlns='urn:a'><f xmlns=''/></e>");
+            while reader.next().unwrap().is_some() {}
+        }
+    }
+}
@@ -1,4 +1,7 @@
-use crate::namespace::NamespaceDecl;
+use crate::namespace::{NamespaceDecl, XMLNS_URI, XML_URI};
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use crate::XmlError;
 
 pub struct NamespaceStack {
     namespaces: Vec<NamespaceDecl>,
@@ -30,7 +33,7 @@ impl NamespaceStack {
         self.namespaces
             .iter()
             .rev()
-            .find(|ns| matches!(ns.prefix.as_ref(), Some(prefix)))
+            .find(|ns| ns.prefix.as_deref() == Some(prefix))
             .map(|ns| &ns.uri as &str)
     }
 
@@ -54,8 +57,29 @@ impl<'a> NamespaceStackScopeBuilder<'a> {
         self.size += 1;
     }
 
-    pub fn add_prefix(&mut self, prefix: Option<String>, uri: String) {
+    /// Declare a prefix, or the default namespace with `None`, for the scope
+    ///
+    /// Fails when the prefix is already declared in the scope, when a reserved prefix or
+    /// namespace is misused or when a prefix is bound to an empty URI.
+    pub fn add_prefix(&mut self, prefix: Option<String>, uri: String) -> Result<(), XmlError> {
+        let scope = &self.stack.namespaces[self.stack.namespaces.len() - self.size..];
+        if scope.iter().any(|ns| ns.prefix == prefix) {
+            return Err(XmlError::DuplicateNamespaceDeclaration(prefix));
+        }
+        match prefix.as_deref() {
+            Some("xml") if uri == XML_URI => {}
+            Some(reserved @ "xml") | Some(reserved @ "xmlns") => {
+                return Err(XmlError::ReservedPrefixRebinding(reserved.to_string()))
+            }
+            Some(_) if uri.is_empty() => return Err(XmlError::IllegalNamespaceUri(uri)),
+            _ if uri == XML_URI || uri == XMLNS_URI => {
+                return Err(XmlError::IllegalNamespaceUri(uri))
+            }
+            _ => {}
+        }
+
         self.add(NamespaceDecl::new(prefix, uri));
+        Ok(())
     }
 
     pub fn finish(self) -> &'a mut NamespaceStack {
@@ -1,6 +1,8 @@
-use std::marker::PhantomData;
+use core::marker::PhantomData;
 
 use crate::parser::Parser;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
 use crate::Cursor;
 
 #[inline]
@@ -12,6 +12,29 @@ impl<'a> Cursor<'a> {
         }
     }
 
+    /// Create a cursor over `input` which starts at `offset` of a larger input
+    pub(crate) fn with_offset(input: &'a str, offset: usize) -> Self {
+        Self {
+            rest: input,
+            offset,
+        }
+    }
+
+    /// Create a cursor over the longest valid UTF-8 prefix of `input`
+    ///
+    /// Returns the offset of the first invalid UTF-8 sequence in `input` as well.
+    pub fn from_bytes(input: &'a [u8]) -> (Self, Option<usize>) {
+        match core::str::from_utf8(input) {
+            Ok(input) => (Self::new(input), None),
+            Err(err) => {
+                let valid = &input[..err.valid_up_to()];
+                // SAFETY: `from_utf8` validated the bytes up to `valid_up_to`
+                let valid = unsafe { core::str::from_utf8_unchecked(valid) };
+                (Self::new(valid), Some(err.valid_up_to()))
+            }
+        }
+    }
+
     pub fn next_char(&self) -> Option<char> {
         self.rest.chars().next()
     }
@@ -56,7 +79,18 @@ impl<'a> Cursor<'a> {
         self.rest.is_empty()
     }
 
+    /// Advance over the next character
+    pub fn advance_char(&self) -> Option<(char, Self)> {
+        let c = self.next_char()?;
+        Some((c, self.advance(c.len_utf8())))
+    }
+
     pub fn advance(&self, bytes: usize) -> Self {
+        debug_assert!(
+            self.rest.is_char_boundary(bytes),
+            "advance by {} bytes is not at a char boundary",
+            bytes
+        );
         let (_ignore, rest) = self.rest.split_at(bytes);
         #[cfg(test)]
         println!("ADVANCE {}: {:?}", bytes, _ignore);
@@ -66,7 +100,15 @@ impl<'a> Cursor<'a> {
         }
     }
 
+    /// # Safety
+    ///
+    /// `bytes` must not exceed the rest and has to be at a char boundary
     pub unsafe fn advance_unchecked(&self, bytes: usize) -> Self {
+        debug_assert!(
+            self.rest.is_char_boundary(bytes),
+            "advance by {} bytes is not at a char boundary",
+            bytes
+        );
         let (_ignore, rest) = unsafe {
             (
                 self.rest.get_unchecked(0..bytes),
@@ -82,6 +124,11 @@ impl<'a> Cursor<'a> {
     }
 
     pub fn advance2(&self, bytes: usize) -> (&'a str, Self) {
+        debug_assert!(
+            self.rest.is_char_boundary(bytes),
+            "advance by {} bytes is not at a char boundary",
+            bytes
+        );
         let (diff, rest) = self.rest.split_at(bytes);
         #[cfg(test)]
         println!("ADVANCE {}: {:?}", bytes, diff);
@@ -1,6 +1,6 @@
 use crate::parser::Parser;
 use crate::Cursor;
-use std::marker::PhantomData;
+use core::marker::PhantomData;
 
 // struct MapAttr<T, U, F: Fn(T::Attribute) -> U>(T, F);
 //
@@ -1,6 +1,11 @@
 //! Parser framework
 
-use crate::Cursor;
+use alloc::borrow::Cow;
+
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use crate::reader::{is_reserved_pi_target, name_len, CharRefToken, EntityRefToken};
+use crate::{Cursor, Entities, Entity, XmlError};
 
 pub mod core;
 pub mod cursor;
@@ -13,3 +18,338 @@ pub trait Parser<'a> {
 
     fn parse(&self, cursor: Cursor<'a>) -> Result<(Self::Attribute, Cursor<'a>), Self::Error>;
 }
+
+/// Whether `s` is a `Name` as accepted by the parser
+///
+/// `Name ::= NameStartChar (NameChar)*`
+pub fn is_valid_name(s: &str) -> bool {
+    name_len(s) == Ok(s.len())
+}
+
+/// Whether `s` is a name without colon as required for prefixes and local names in
+/// namespace-aware documents
+///
+/// `NCName ::= Name - (Char* ':' Char*)`
+pub fn is_valid_ncname(s: &str) -> bool {
+    is_valid_name(s) && !s.contains(':')
+}
+
+/// Whether `s` is a target of a processing instruction as accepted by the parser
+///
+/// `PITarget ::= Name - (('X' | 'x') ('M' | 'm') ('L' | 'l'))`
+pub fn is_valid_pi_target(s: &str) -> bool {
+    is_valid_name(s) && !is_reserved_pi_target(s)
+}
+
+/// Normalize an attribute value as written between the quotes
+///
+/// Implements section 3.3.3: character references are replaced by their character, entity
+/// references by their normalized replacement text from `entities` and white space
+/// characters and line breaks by a space. Values of attributes not declared as `CDATA`
+/// additionally lose leading and trailing spaces and have sequences of spaces collapsed, but
+/// characters from character references like `&#xD;` are kept.
+pub fn normalize_att_value<'a>(
+    raw: &'a str,
+    is_cdata_type: bool,
+    entities: &Entities,
+) -> Result<Cow<'a, str>, XmlError> {
+    let mut expanded = 0;
+    let entities = AttValueEntities {
+        entities,
+        standalone: false,
+        unread_markup_decls: false,
+        expanded: &mut expanded,
+        expansion_limit: None,
+    };
+    normalize_att_value_at(Cursor::new(raw), raw.len(), is_cdata_type, entities)
+}
+
+/// Entities for references in attribute values with the checks of the reader
+pub(crate) struct AttValueEntities<'e> {
+    pub entities: &'e Entities,
+    /// Entities declared outside of the document entity must not be referenced
+    pub standalone: bool,
+    /// Undeclared entities could be declared in markup declarations which are not read
+    pub unread_markup_decls: bool,
+    /// Bytes of replacement text expanded so far
+    pub expanded: &'e mut usize,
+    pub expansion_limit: Option<usize>,
+}
+
+impl<'e> AttValueEntities<'e> {
+    /// Entity to expand for the reference `&name;`
+    fn expand(&mut self, name: &str) -> Result<&'e Entity, XmlError> {
+        match self.entities.get_ref(name) {
+            Some(entity) if self.standalone && entity.is_external() => {
+                Err(XmlError::StandaloneViolation(name.to_string()))
+            }
+            Some(entity) => {
+                *self.expanded += entity.text().len();
+                if matches!(self.expansion_limit, Some(limit) if *self.expanded > limit) {
+                    return Err(XmlError::ExpansionLimitExceeded(*self.expanded));
+                }
+                Ok(entity)
+            }
+            // only an external declaration can define the entity
+            None if self.standalone && self.unread_markup_decls => {
+                Err(XmlError::StandaloneViolation(name.to_string()))
+            }
+            None => Err(XmlError::UnknownEntity(name.to_string())),
+        }
+    }
+}
+
+/// Normalize the attribute value of `len` bytes at the cursor
+///
+/// References are parsed from the cursor, so a reference cut off by the closing quote is
+/// reported like by the other token parsers.
+pub(crate) fn normalize_att_value_at<'a>(
+    cursor: Cursor<'a>,
+    len: usize,
+    is_cdata_type: bool,
+    mut entities: AttValueEntities,
+) -> Result<Cow<'a, str>, XmlError> {
+    let raw = &cursor.rest()[..len];
+    let mut value = if raw.contains(is_att_value_special) {
+        let mut value = String::with_capacity(len);
+        push_att_value(&mut value, cursor, len, &mut entities, &mut vec![])?;
+        Cow::Owned(value)
+    } else {
+        Cow::Borrowed(raw)
+    };
+
+    if !is_cdata_type && (value.starts_with(' ') || value.ends_with(' ') || value.contains("  ")) {
+        let mut collapsed = String::with_capacity(value.len());
+        for token in value.split(' ').filter(|token| !token.is_empty()) {
+            if !collapsed.is_empty() {
+                collapsed.push(' ');
+            }
+            collapsed.push_str(token);
+        }
+        value = Cow::Owned(collapsed);
+    }
+    Ok(value)
+}
+
+fn is_att_value_special(c: char) -> bool {
+    matches!(c, '&' | '<' | '\t' | '\n' | '\r')
+}
+
+/// Append the normalized value of `len` bytes at the cursor, `open` are the entities
+/// expanded at the moment
+fn push_att_value<'e>(
+    value: &mut String,
+    mut cursor: Cursor,
+    len: usize,
+    entities: &mut AttValueEntities<'e>,
+    open: &mut Vec<&'e str>,
+) -> Result<(), XmlError> {
+    let end = cursor.offset() + len;
+    loop {
+        let rest = &cursor.rest()[..end - cursor.offset()];
+        let pos = match rest.find(is_att_value_special) {
+            Some(pos) => pos,
+            None => {
+                value.push_str(rest);
+                return Ok(());
+            }
+        };
+        value.push_str(&rest[..pos]);
+        let special = cursor.advance(pos);
+        cursor = match special.next_byte(0) {
+            Some(b'<') => {
+                return Err(XmlError::IllegalAttributeValue(
+                    "< not allowed in attribute value",
+                ))
+            }
+            Some(b'&') => match special.next_byte(1) {
+                Some(b'#') => {
+                    let (character, cursor) =
+                        CharRefToken.parse(special).map_err(XmlError::into_final)?;
+                    value.push(character);
+                    cursor
+                }
+                Some(_) => {
+                    let (name, cursor) = EntityRefToken
+                        .parse(special)
+                        .map_err(XmlError::into_final)?;
+                    if open.contains(&name) {
+                        return Err(XmlError::RecursiveEntity(name.to_string()));
+                    }
+                    let entity = entities.expand(name)?;
+                    open.push(entity.name());
+                    let text = entity.text();
+                    push_att_value(value, Cursor::new(text), text.len(), entities, open)?;
+                    open.pop();
+                    cursor
+                }
+                None => return Err(XmlError::IllegalReference),
+            },
+            // a line break `\r\n` is one space
+            Some(b'\r') => {
+                value.push(' ');
+                let cursor = special.advance(1);
+                if cursor.offset() < end && cursor.next_byte(0) == Some(b'\n') {
+                    cursor.advance(1)
+                } else {
+                    cursor
+                }
+            }
+            _ => {
+                value.push(' ');
+                special.advance(1)
+            }
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn name() {
+        assert!(is_valid_name("a"));
+        assert!(is_valid_name("_a-b.c9"));
+        assert!(is_valid_name("äöü"));
+        assert!(is_valid_name(":a"));
+        assert!(is_valid_name("a:"));
+        assert!(is_valid_name("a:b:c"));
+        assert!(!is_valid_name(""));
+        assert!(!is_valid_name("1a"));
+        assert!(!is_valid_name("-a"));
+        assert!(!is_valid_name("a b"));
+        assert!(!is_valid_name("a>"));
+    }
+
+    #[test]
+    fn ncname() {
+        assert!(is_valid_ncname("a"));
+        assert!(is_valid_ncname("a-1"));
+        assert!(!is_valid_ncname(""));
+        assert!(!is_valid_ncname("1a"));
+        assert!(!is_valid_ncname(":a"));
+        assert!(!is_valid_ncname("a:"));
+        assert!(!is_valid_ncname("a:b"));
+    }
+
+    #[test]
+    fn pi_target() {
+        assert!(is_valid_pi_target("target"));
+        assert!(is_valid_pi_target("xml-stylesheet"));
+        assert!(is_valid_pi_target("xmlx"));
+        assert!(is_valid_pi_target("xm"));
+        assert!(!is_valid_pi_target(""));
+        assert!(!is_valid_pi_target("1pi"));
+        assert!(!is_valid_pi_target("xml"));
+        assert!(!is_valid_pi_target("XML"));
+        assert!(!is_valid_pi_target("xMl"));
+    }
+
+    #[test]
+    fn att_value_char_refs() {
+        let entities = Entities::default();
+        assert_eq!(
+            "a\rb\n\t",
+            normalize_att_value("a&#xD;b&#xA;&#x9;", true, &entities).unwrap()
+        );
+        assert_eq!(
+            "\r \r",
+            normalize_att_value(" &#xD;  &#13; ", false, &entities).unwrap()
+        );
+    }
+
+    #[test]
+    fn att_value_whitespace() {
+        let entities = Entities::default();
+        assert_eq!(
+            " a  b  c ",
+            normalize_att_value("\ta\t\nb\r\n\rc\n", true, &entities).unwrap()
+        );
+        assert_eq!(
+            "a b c",
+            normalize_att_value("\ta\t\nb\r\n\rc\n", false, &entities).unwrap()
+        );
+        assert!(matches!(
+            normalize_att_value("a b", false, &entities).unwrap(),
+            Cow::Borrowed("a b")
+        ));
+    }
+
+    #[test]
+    fn att_value_collapse() {
+        let entities = Entities::default();
+        assert_eq!(
+            "  a   b  ",
+            normalize_att_value("  a   b  ", true, &entities).unwrap()
+        );
+        assert_eq!(
+            "a b",
+            normalize_att_value("  a   b  ", false, &entities).unwrap()
+        );
+        assert_eq!("", normalize_att_value("   ", false, &entities).unwrap());
+        assert_eq!(
+            "a b",
+            normalize_att_value("a&#x20;&#32; b", false, &entities).unwrap()
+        );
+    }
+
+    #[test]
+    fn att_value_entities() {
+        let mut entities = Entities::default();
+        entities.register("ws", "x\ty");
+        entities.register("nested", " &ws;&lt; ");
+        entities.register("tag", "<a>");
+        entities.register("loop", "&loop;");
+        assert_eq!(
+            "1  x y<  2",
+            normalize_att_value("1 &nested; 2", true, &entities).unwrap()
+        );
+        assert_eq!(
+            "1 x y< 2",
+            normalize_att_value("1 &nested; 2", false, &entities).unwrap()
+        );
+        assert_eq!(
+            Err(XmlError::UnknownEntity("nbsp".to_string())),
+            normalize_att_value("&nbsp;", true, &entities)
+        );
+        assert!(matches!(
+            normalize_att_value("&tag;", true, &entities),
+            Err(XmlError::IllegalAttributeValue(_))
+        ));
+        assert_eq!(
+            Err(XmlError::RecursiveEntity("loop".to_string())),
+            normalize_att_value("&loop;", true, &entities)
+        );
+        assert_eq!(
+            Err(XmlError::IllegalReference),
+            normalize_att_value("a&", true, &entities)
+        );
+    }
+
+    /// The checkers agree with the parser
+    #[test]
+    fn parser_agreement() {
+        use crate::Reader;
+
+        for name in &[
+            "a", "1a", ":a", "a:b", "-a", "\u{B7}a", "a\u{B7}", "xml", "XmL", "x1",
+        ] {
+            let element = format!("<{}/>", name);
+            assert_eq!(
+                is_valid_name(name),
+                Reader::new(&element).next().is_ok(),
+                "element {}",
+                name
+            );
+
+            let pi = format!("<?{}?><a/>", name);
+            assert_eq!(
+                is_valid_pi_target(name),
+                Reader::new(&pi).next().is_ok(),
+                "PI {}",
+                name
+            );
+        }
+    }
+}
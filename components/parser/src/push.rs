@@ -0,0 +1,351 @@
+//! Parser for input which arrives in chunks
+
+use alloc::collections::VecDeque;
+use core::str::from_utf8;
+
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use crate::reader::{is_complete_token_error, SuspendedReader};
+use crate::{encoding, Reader, XmlError, XmlEvent};
+
+/// Result of [`StreamingParser::next`]
+#[derive(Clone, Debug, PartialEq)]
+pub enum Progress {
+    /// Next complete event
+    Event(XmlEvent<'static>),
+    /// More input is needed for the next event
+    Pending,
+    /// End of the document
+    End,
+}
+
+/// Parser which is fed with chunks of UTF-8 encoded input
+///
+/// An event is only returned when the input following it is known, so a token cut off at
+/// the end of the fed input is suspended until the next [`feed`](Self::feed) call. Errors are
+/// reported after [`finish`](Self::finish) was called, because more input might complete the
+/// markup in question. Only invalid UTF-8 is reported immediately.
+///
+/// Parsed input is dropped from the buffer and parsing continues behind the last complete
+/// event when new input arrives.
+///
+/// ```
+/// use xrs_parser::push::{Progress, StreamingParser};
+/// use xrs_parser::XmlEvent;
+///
+/// let mut parser = StreamingParser::new();
+/// parser.feed(b"<a>te");
+/// assert_eq!(Ok(Progress::Event(XmlEvent::stag("a", false))), parser.next());
+/// assert_eq!(Ok(Progress::Pending), parser.next());
+/// parser.feed(b"xt</a>");
+/// parser.finish();
+/// assert_eq!(Ok(Progress::Event(XmlEvent::characters("text"))), parser.next());
+/// assert_eq!(Ok(Progress::Event(XmlEvent::etag("a"))), parser.next());
+/// assert_eq!(Ok(Progress::End), parser.next());
+/// ```
+#[derive(Default)]
+pub struct StreamingParser {
+    /// Input not parsed yet, starting at `offset` of the whole input
+    buffer: Vec<u8>,
+    offset: usize,
+    /// State of the reader at `offset`, `None` while no event was read
+    state: Option<SuspendedReader>,
+    events: VecDeque<XmlEvent<'static>>,
+    /// Complete events at the end of the input, returned when more input follows them
+    held: Vec<XmlEvent<'static>>,
+    /// Error which more input can't resolve
+    error: Option<XmlError>,
+    /// Input arrived since the buffer was parsed last
+    fed: bool,
+    finished: bool,
+    end: bool,
+}
+
+impl StreamingParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a chunk of input
+    pub fn feed(&mut self, chunk: &[u8]) {
+        debug_assert!(!self.finished, "feed after finish");
+        self.buffer.extend_from_slice(chunk);
+        self.fed = true;
+    }
+
+    /// Signal that the input is complete
+    pub fn finish(&mut self) {
+        self.finished = true;
+        self.fed = true;
+    }
+
+    /// Next event or whether more input is needed
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Result<Progress, XmlError> {
+        if let Some(evt) = self.events.pop_front() {
+            return Ok(Progress::Event(evt));
+        }
+        if self.end {
+            return Ok(Progress::End);
+        }
+        if !self.fed {
+            return Ok(Progress::Pending);
+        }
+        self.fed = false;
+
+        let result = self.parse();
+        match self.events.pop_front() {
+            Some(evt) => {
+                // report the error after the queued events
+                self.fed = result.is_err();
+                Ok(Progress::Event(evt))
+            }
+            None => result,
+        }
+    }
+
+    /// Parse the buffer behind the last complete event and queue the complete events
+    fn parse(&mut self) -> Result<Progress, XmlError> {
+        if !self.buffer.is_empty() || self.finished {
+            self.events.extend(self.held.drain(..));
+        }
+        if self.error.is_some() {
+            return self.error_progress();
+        }
+
+        let input = match from_utf8(&self.buffer) {
+            Ok(_) => &self.buffer[..],
+            // incomplete UTF-8 sequence at the end
+            Err(err) if err.error_len().is_none() && !self.finished => {
+                &self.buffer[..err.valid_up_to()]
+            }
+            Err(_) => &self.buffer[..],
+        };
+        // offsets of a resumed reader count from the start of the whole input
+        let resumed = self.state.is_some();
+        let (mut reader, shift) = match self.state.take() {
+            Some(state) => (Reader::resume(input, state), 0),
+            None => (Reader::from_bytes(input), encoding::detect(input).bom_len()),
+        };
+        let input_end = self.offset + self.buffer.len();
+
+        let mut read = false;
+        let suspend_at = loop {
+            let before = shift + reader.consumed_len();
+            match reader.next() {
+                Ok(Some(evt)) => {
+                    read = true;
+                    let evt = match evt {
+                        XmlEvent::STag(stag) => XmlEvent::stag_with_attrs(
+                            stag.name.into_owned(),
+                            stag.empty,
+                            reader
+                                .attributes()
+                                .iter()
+                                .map(|attr| attr.clone().into_owned())
+                                .collect::<Vec<_>>(),
+                        ),
+                        evt => evt.into_owned(),
+                    };
+                    // the event is only known to be complete when input follows, which can
+                    // be the start of an incomplete UTF-8 sequence as well
+                    if self.finished || shift + reader.consumed_len() < input_end {
+                        self.events.push_back(evt);
+                    } else if is_character_data(&evt) && reader.entity_offset().is_none() {
+                        // more input can continue the character data
+                        break before;
+                    } else {
+                        self.held.push(evt);
+                    }
+                }
+                Ok(None) if self.finished => {
+                    self.events.extend(self.held.drain(..));
+                    self.end = true;
+                    return Ok(Progress::End);
+                }
+                Ok(None) => break shift + reader.consumed_len(),
+                Err(err)
+                    if self.finished
+                        || matches!(err, XmlError::Decoding(_))
+                        || is_complete_token_error(&err)
+                        || reader.entity_offset().is_some() =>
+                {
+                    // more input can't resolve the error
+                    self.error = Some(err);
+                    return self.error_progress();
+                }
+                // the cursor can be behind the start of the cut off markup
+                Err(_) => break before,
+            }
+        };
+
+        // a new reader detects the encoding again until it read an event
+        if resumed || read {
+            self.state = Some(reader.suspend(suspend_at));
+            self.buffer.drain(..suspend_at - self.offset);
+            self.offset = suspend_at;
+        }
+        Ok(Progress::Pending)
+    }
+
+    /// Result for the held error, which is reported after `finish` unless it's about invalid
+    /// UTF-8
+    fn error_progress(&self) -> Result<Progress, XmlError> {
+        match &self.error {
+            Some(err) if self.finished || matches!(err, XmlError::Decoding(_)) => Err(err.clone()),
+            _ => Ok(Progress::Pending),
+        }
+    }
+}
+
+fn is_character_data(evt: &XmlEvent) -> bool {
+    matches!(
+        evt,
+        XmlEvent::Characters(_) | XmlEvent::Whitespace(_) | XmlEvent::CharRef(_)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Events of the whole input with the attributes in the start tags
+    fn whole(input: &str) -> Result<Vec<XmlEvent<'static>>, XmlError> {
+        let mut reader = Reader::new(input);
+        let mut events = vec![];
+        while let Some(evt) = reader.next()? {
+            events.push(match evt {
+                XmlEvent::STag(stag) => XmlEvent::stag_with_attrs(
+                    stag.name.into_owned(),
+                    stag.empty,
+                    reader
+                        .attributes()
+                        .iter()
+                        .map(|attr| attr.clone().into_owned())
+                        .collect::<Vec<_>>(),
+                ),
+                evt => evt.into_owned(),
+            });
+        }
+        Ok(events)
+    }
+
+    fn byte_by_byte(input: &str) -> Result<Vec<XmlEvent<'static>>, XmlError> {
+        in_chunks(input, 1)
+    }
+
+    fn in_chunks(input: &str, size: usize) -> Result<Vec<XmlEvent<'static>>, XmlError> {
+        let mut parser = StreamingParser::new();
+        let mut events = vec![];
+        let mut chunks = input.as_bytes().chunks(size);
+        loop {
+            match chunks.next() {
+                Some(chunk) => parser.feed(chunk),
+                None => parser.finish(),
+            }
+            loop {
+                match parser.next()? {
+                    Progress::Event(evt) => events.push(evt),
+                    Progress::Pending => break,
+                    Progress::End => return Ok(events),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn one_byte_at_a_time() {
+        let input = "<?xml version='1.0'?>\r\n\
+            <!DOCTYPE r [<!ENTITY e 'entity <i>text</i>'>]>\
+            <r a='1' b=\"&lt;ä\">\r\n\
+              text &amp; &#x1F600; &e;\r\
+              <!-- comment --><![CDATA[ <cdata> ]]><?pi data?><empty/>中文\
+            </r>\n";
+        let expected = whole(input).unwrap();
+        assert_eq!(Ok(expected), byte_by_byte(input));
+    }
+
+    #[test]
+    fn chunk_sizes() {
+        let input = "<?xml version='1.0'?><!DOCTYPE r [<!ENTITY e 'a <i>b</i> c'>]>\
+            <r x='&e;'>text &e;&amp;<e/><!-- c -->&e;</r>";
+        let expected = whole(input);
+        for size in 1..input.len() {
+            assert_eq!(expected, in_chunks(input, size), "chunk size {}", size);
+        }
+    }
+
+    #[test]
+    fn drains_parsed_input() {
+        let mut parser = StreamingParser::new();
+        parser.feed(b"<r>");
+        for _ in 0..100 {
+            parser.feed(b"<e a='1'>text</e>");
+            while let Ok(Progress::Event(_)) = parser.next() {}
+        }
+        assert!(parser.buffer.len() < 20);
+    }
+
+    #[test]
+    fn error_in_entity() {
+        let input = "<!DOCTYPE r [<!ENTITY e '</x>'>]><r>&e;</r>";
+        assert_eq!(whole(input), byte_by_byte(input));
+        assert!(byte_by_byte(input).is_err());
+    }
+
+    #[test]
+    fn suspend_in_tag() {
+        let mut parser = StreamingParser::new();
+        parser.feed(b"<root attr='va");
+        assert_eq!(Ok(Progress::Pending), parser.next());
+        parser.feed(b"lue'>");
+        assert_eq!(Ok(Progress::Pending), parser.next());
+        parser.feed(b"<");
+        assert_eq!(
+            Ok(Progress::Event(XmlEvent::stag_with_attrs(
+                "root",
+                false,
+                vec![crate::Attribute::new("attr", "value")]
+            ))),
+            parser.next()
+        );
+        assert_eq!(Ok(Progress::Pending), parser.next());
+    }
+
+    #[test]
+    fn split_utf8_sequence() {
+        let input = "<a>😀</a>".as_bytes();
+        let mut parser = StreamingParser::new();
+        parser.feed(&input[..5]);
+        assert!(matches!(parser.next(), Ok(Progress::Event(_))));
+        assert_eq!(Ok(Progress::Pending), parser.next());
+        parser.feed(&input[5..]);
+        parser.finish();
+        assert_eq!(
+            Ok(Progress::Event(XmlEvent::characters("😀"))),
+            parser.next()
+        );
+        assert_eq!(Ok(Progress::Event(XmlEvent::etag("a"))), parser.next());
+        assert_eq!(Ok(Progress::End), parser.next());
+    }
+
+    #[test]
+    fn error_after_finish() {
+        let input = "<a></b>";
+        assert_eq!(whole(input), byte_by_byte(input));
+        assert!(byte_by_byte(input).is_err());
+    }
+
+    #[test]
+    fn utf8_bom() {
+        assert_eq!(whole("<a>x</a>"), byte_by_byte("\u{FEFF}<a>x</a>"));
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        let mut parser = StreamingParser::new();
+        parser.feed(b"<a>\xFF\xFF</a>");
+        assert!(matches!(parser.next(), Ok(Progress::Event(_))));
+        assert!(matches!(parser.next(), Err(XmlError::Decoding(_))));
+    }
+}
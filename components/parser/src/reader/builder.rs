@@ -0,0 +1,166 @@
+//! Configuration of a [`Reader`]
+
+use crate::reader::{Entities, InsignificantWhitespace, Reader, UnknownEntityPolicy};
+use crate::NsReader;
+
+/// Builder for a [`Reader`] with non-default options
+///
+/// ```
+/// use xrs_parser::ReaderBuilder;
+///
+/// let mut reader = ReaderBuilder::new()
+///     .coalesce(true)
+///     .max_depth(Some(64))
+///     .build("<e>text</e>");
+/// ```
+#[derive(Default)]
+pub struct ReaderBuilder {
+    coalesce: bool,
+    trim_whitespace: bool,
+    insignificant_whitespace: InsignificantWhitespace,
+    preserve_char_refs: bool,
+    verbatim: bool,
+    validate_attributes: bool,
+    recoverable: bool,
+    max_depth: Option<usize>,
+    expansion_limit: Option<usize>,
+    entities: Entities,
+    unknown_entity_policy: UnknownEntityPolicy,
+    empty_element_events: bool,
+    intern_names: bool,
+}
+
+impl ReaderBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Merge consecutive character data into one event, default is `false`
+    ///
+    /// See [`Reader::with_coalescing`].
+    pub fn coalesce(mut self, coalesce: bool) -> Self {
+        self.coalesce = coalesce;
+        self
+    }
+
+    /// Remove leading and trailing whitespace of text and skip whitespace-only text,
+    /// default is `false`
+    ///
+    /// Without coalescing, each run of text between references is trimmed on its own. CDATA
+    /// sections are never trimmed.
+    pub fn trim_whitespace(mut self, trim_whitespace: bool) -> Self {
+        self.trim_whitespace = trim_whitespace;
+        self
+    }
+
+    /// How whitespace-only text between markup is returned, default is
+    /// [`InsignificantWhitespace::Keep`]
+    pub fn insignificant_whitespace(mut self, policy: InsignificantWhitespace) -> Self {
+        self.insignificant_whitespace = policy;
+        self
+    }
+
+    /// Emit character references as `CharRef` events, default is `false`
+    ///
+    /// See [`Reader::with_preserved_char_refs`].
+    pub fn preserve_char_refs(mut self, preserve_char_refs: bool) -> Self {
+        self.preserve_char_refs = preserve_char_refs;
+        self
+    }
+
+    /// Keep quote characters and the raw attribute region of start tags, default is `false`
+    ///
+    /// See [`Reader::with_verbatim`].
+    pub fn verbatim(mut self, verbatim: bool) -> Self {
+        self.verbatim = verbatim;
+        self
+    }
+
+    /// Check `#REQUIRED` and `#FIXED` attribute declarations, default is `false`
+    ///
+    /// See [`Reader::with_attribute_validation`].
+    pub fn validate_attributes(mut self, validate_attributes: bool) -> Self {
+        self.validate_attributes = validate_attributes;
+        self
+    }
+
+    /// Report recoverable errors as events and continue reading, default is `false`
+    ///
+    /// See [`Reader::recoverable`].
+    pub fn recoverable(mut self, recoverable: bool) -> Self {
+        self.recoverable = recoverable;
+        self
+    }
+
+    /// Maximum nesting depth of elements, default is no limit
+    ///
+    /// A deeper element is reported as [`crate::XmlError::DepthLimitExceeded`].
+    pub fn max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Maximum number of bytes of replacement text of all expanded entities in content and
+    /// attribute values, default is no limit
+    ///
+    /// Exceeding it is reported as [`crate::XmlError::ExpansionLimitExceeded`].
+    pub fn expansion_limit(mut self, expansion_limit: Option<usize>) -> Self {
+        self.expansion_limit = expansion_limit;
+        self
+    }
+
+    /// Entities known in addition to the ones declared in the document, default are the
+    /// predefined entities
+    pub fn entities(mut self, entities: Entities) -> Self {
+        self.entities = entities;
+        self
+    }
+
+    /// How references to undeclared entities are handled, default is an error
+    ///
+    /// Any other policy than [`UnknownEntityPolicy::Error`] makes the reader non-conforming.
+    pub fn unknown_entity_policy(mut self, policy: UnknownEntityPolicy) -> Self {
+        self.unknown_entity_policy = policy;
+        self
+    }
+
+    /// Return empty element tags as single `EmptyElement` events, default is `false`
+    ///
+    /// See [`Reader::with_empty_element_events`].
+    pub fn empty_element_events(mut self, empty_element_events: bool) -> Self {
+        self.empty_element_events = empty_element_events;
+        self
+    }
+
+    /// Share allocations between equal names copied with [`Reader::intern`], default is
+    /// `false`
+    pub fn intern_names(mut self, intern_names: bool) -> Self {
+        self.intern_names = intern_names;
+        self
+    }
+
+    /// Create a reader for the input
+    pub fn build(self, input: &str) -> Reader<'_> {
+        let mut reader = Reader::with_entities(input, self.entities)
+            .with_unknown_entity_policy(self.unknown_entity_policy)
+            .with_insignificant_whitespace(self.insignificant_whitespace);
+        reader.coalesce = self.coalesce;
+        reader.trim_whitespace = self.trim_whitespace;
+        reader.ctx.preserve_char_refs = self.preserve_char_refs;
+        reader.ctx.verbatim = self.verbatim;
+        reader.ctx.validate_attributes = self.validate_attributes;
+        reader.ctx.recoverable = self.recoverable;
+        reader.max_depth = self.max_depth;
+        reader.ctx.expansion_limit = self.expansion_limit;
+        reader.empty_element_events = self.empty_element_events;
+        if self.intern_names {
+            reader = reader.with_interner();
+        }
+        reader
+    }
+
+    /// Create a reader for the input which resolves namespaces
+    pub fn build_ns(self, input: &str) -> NsReader<'_> {
+        NsReader::from_reader(self.build(input))
+    }
+}
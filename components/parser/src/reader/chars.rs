@@ -57,3 +57,29 @@ pub fn is_ascii_content_char(c: char) -> bool {
         c.is_xml_char()
     }
 }
+
+/// Check for an ASCII byte that can be part of character data without further processing
+#[inline]
+pub fn is_ascii_content_byte(c: u8) -> bool {
+    check_ascii(c, Category::ContentChar)
+}
+
+const ASCII_CHARS: [u8; 128] = {
+    let mut table = [0u8; 128];
+    let mut i = 0;
+    while i < 128 {
+        table[i] = i as u8;
+        i += 1;
+    }
+    table
+};
+
+/// Get a static string consisting of the single ASCII character `c`
+///
+/// Allows to return characters of references without allocation.
+#[inline]
+pub fn ascii_char_str(c: u8) -> &'static str {
+    let i = c as usize;
+    // SAFETY: every byte of `ASCII_CHARS` is ASCII and therefore valid UTF-8
+    unsafe { core::str::from_utf8_unchecked(&ASCII_CHARS[i..i + 1]) }
+}
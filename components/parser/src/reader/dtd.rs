@@ -1,15 +1,23 @@
+use alloc::borrow::Cow;
+
 use xrs_chars::{XmlAsciiChar, XmlChar};
 
 use crate::dtd::{
-    ContentParticle, ContentParticleEntry, ContentSpec, DocTypeDecl, Element, EntityDef,
-    ExternalId, GEDecl, IntSubset, MarkupDeclEntry, PEDecl, PEDef, Repetition,
+    AttDef, AttListDecl, AttType, ContentParticle, ContentParticleEntry, ContentSpec, DefaultDecl,
+    DocTypeDecl, Element, EntityDef, ExternalId, GEDecl, IntSubset, MarkupDeclEntry, NotationDecl,
+    NotationId, PEDecl, PEDef, Repetition,
 };
 use crate::parser::core::{kleene, optional, separated, Kleene, Optional, Separated};
 use crate::parser::helper::map_error;
 use crate::parser::string::lit;
 use crate::parser::Parser;
-use crate::reader::{xml_lit, xml_terminated, CharTerminated, NameToken, SToken, TerminatedChars};
-use crate::{Cursor, XmlDtdError, XmlError};
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use crate::reader::{
+    xml_lit, xml_terminated, AttValueToken, CharTerminated, CommentToken, NameToken, PIToken,
+    SToken, SimpleEntityStrValueResolver, TerminatedChars,
+};
+use crate::{Cursor, XmlDtdError, XmlError, PI};
 
 // 2.3 Common Syntactic Constructs
 // Literals
@@ -40,7 +48,7 @@ impl<'a> Parser<'a> for SystemLiteralToken {
             let (res, cursor) = cursor.advance2(pos);
             Ok((res, cursor.advance(1)))
         } else {
-            Err(XmlError::UnexpectedEof)
+            Err(XmlError::need_more())
         }
     }
 }
@@ -74,7 +82,7 @@ impl<'a> Parser<'a> for PubidLiteralToken {
             }
             Ok((res, cursor.advance(1)))
         } else {
-            Err(XmlError::UnexpectedEof)
+            Err(XmlError::need_more())
         }
     }
 }
@@ -106,7 +114,7 @@ impl<'a> Parser<'a> for EntityValueToken {
             let (res, cursor) = cursor.advance2(pos);
             Ok((res.to_string(), cursor.advance(1)))
         } else {
-            Err(XmlError::UnexpectedEof)
+            Err(XmlError::need_more())
         }
     }
 }
@@ -129,17 +137,19 @@ impl<'a> Parser<'a> for DocTypeDeclToken {
         let (external_id, cursor) = optional((SToken, ExternalIdToken)).parse(cursor)?;
         let external_id = external_id.map(|v| v.1);
         let (_, cursor) = optional(SToken).parse(cursor)?;
-        let (raw_internal, cursor) =
-            optional((xml_lit("["), IntSubsetToken, xml_lit("]"), optional(SToken)))
-                .parse(cursor)?;
+        let (internal, cursor) = match xml_lit("[").parse(cursor) {
+            Ok((_, cursor)) => {
+                let (subset, cursor) = IntSubsetToken.parse(cursor)?;
+                let (_, cursor) = xml_lit("]").parse(cursor)?;
+                let (_, cursor) = optional(SToken).parse(cursor)?;
+                (Some(subset), cursor)
+            }
+            Err(_) => (None, cursor),
+        };
         let (_, cursor) = xml_lit(">").parse(cursor)?;
 
         Ok((
-            DocTypeDecl::new(
-                name.to_string(),
-                external_id,
-                raw_internal.map(|subset| subset.1),
-            ),
+            DocTypeDecl::new(name.to_string(), external_id, internal),
             cursor,
         ))
     }
@@ -154,15 +164,19 @@ impl<'a> Parser<'a> for IntSubsetToken {
     type Attribute = IntSubset;
     type Error = XmlError;
 
-    fn parse(&self, cursor: Cursor<'a>) -> Result<(Self::Attribute, Cursor<'a>), Self::Error> {
-        kleene(MarkupDeclToken)
-            .parse(cursor)
-            .map(|(decls, cursor)| {
-                (
-                    IntSubset::new(decls.into_iter().filter_map(|decl| decl).collect()),
-                    cursor,
-                )
-            })
+    fn parse(&self, mut cursor: Cursor<'a>) -> Result<(Self::Attribute, Cursor<'a>), Self::Error> {
+        let mut decls = vec![];
+        loop {
+            match MarkupDeclToken.parse(cursor) {
+                Ok((decl, next)) => {
+                    decls.extend(decl);
+                    cursor = next;
+                }
+                // an illegal character can't be the end of the internal subset
+                Err(err @ XmlError::IllegalChar(_)) => return Err(err),
+                Err(_) => return Ok((IntSubset::new(decls), cursor)),
+            }
+        }
     }
 }
 
@@ -180,34 +194,37 @@ impl<'a> Parser<'a> for MarkupDeclToken {
     fn parse(&self, cursor: Cursor<'a>) -> Result<(Self::Attribute, Cursor<'a>), Self::Error> {
         if let Ok((_, cursor)) = SToken.parse(cursor) {
             Ok((None, cursor))
+        } else if let Ok((pe_ref, cursor)) = PEReferenceToken.parse(cursor) {
+            Ok((
+                Some(MarkupDeclEntry::PEReference(pe_ref.to_string())),
+                cursor,
+            ))
         } else if let Ok((element, cursor)) = ElementDeclToken.parse(cursor) {
             Ok((Some(MarkupDeclEntry::Element(element)), cursor))
+        } else if let Ok((att_list, cursor)) = AttListDeclToken.parse(cursor) {
+            Ok((Some(MarkupDeclEntry::AttList(att_list)), cursor))
         } else if let Ok((entity, cursor)) = GEDeclToken.parse(cursor) {
             Ok((Some(MarkupDeclEntry::GeneralEntity(entity)), cursor))
         } else if let Ok((entity, cursor)) = PEDeclToken.parse(cursor) {
             Ok((Some(MarkupDeclEntry::ParameterEntity(entity)), cursor))
+        } else if let Ok((notation, cursor)) = NotationDeclToken.parse(cursor) {
+            Ok((Some(MarkupDeclEntry::Notation(notation)), cursor))
+        } else if let Ok(((target, data), cursor)) = PIToken.parse(cursor) {
+            let pi = PI {
+                target: Cow::Owned(target.to_string()),
+                data: data.map(|data| Cow::Owned(data.to_string())),
+            };
+            Ok((Some(MarkupDeclEntry::PI(pi)), cursor))
         } else {
-            // TODO
-            Err(XmlError::UnexpectedDtdEntry)
-        }
-
-        // return if let Ok((pe_ref, cursor)) = PeReferenceToken.parse(cursor) {
-        //     Ok((MarkupDeclEntry::PEReference(pe_ref), cursor))
-        // } else if let Ok((element, cursor)) = ElementDeclToken.parse(cursor) {
-        //     Ok((MarkupDeclEntry::Element(element), cursor))
-        // } else if let Ok((att_list, cursor)) = AttListDeclToken.parse(cursor) {
-        //     Ok((MarkupDeclEntry::AttList(att_list), cursor))
-        // } else if let Ok((entity, cursor)) = EntityDeclToken.parse(cursor) {
-        //     Ok((MarkupDeclEntry::Entity(entity), cursor))
-        // } else if let Ok((notation, cursor)) = NotationDeclToken.parse(cursor) {
-        //     Ok((MarkupDeclEntry::Notation(notation), cursor))
-        // } else if let Ok((pi, cursor)) = PIToken.parse(cursor) {
-        //     Ok((MarkupDeclEntry::PI(pi), cursor))
-        // } else if let Ok((comment, cursor)) = CommentToken.parse(cursor) {
-        //     Ok((MarkupDeclEntry::Comment(comment), cursor))
-        // } else {
-        //     Err(XmlError::ExpectToken("markupdecl or DeclSep"))
-        // };
+            match CommentToken.parse(cursor) {
+                Ok((comment, cursor)) => {
+                    Ok((Some(MarkupDeclEntry::Comment(comment.to_string())), cursor))
+                }
+                // the comment is recognized, but contains a character not allowed in XML
+                Err(err @ XmlError::IllegalChar(_)) => Err(err),
+                Err(_) => Err(XmlError::UnexpectedDtdEntry),
+            }
+        }
     }
 }
 
@@ -396,7 +413,7 @@ impl<'a> Parser<'a> for MixedToken {
     fn parse(&self, cursor: Cursor<'a>) -> Result<(Self::Attribute, Cursor<'a>), Self::Error> {
         let (_, cursor) = xml_lit("(").parse(cursor)?;
         let (_, cursor) = optional(SToken).parse(cursor)?;
-        let (_, cursor) = optional(xml_lit("#PCDATA")).parse(cursor)?;
+        let (_, cursor) = xml_lit("#PCDATA").parse(cursor)?;
         let (names, cursor) =
             kleene((optional(SToken), xml_lit("|"), optional(SToken), NameToken)).parse(cursor)?;
         let (_, cursor) = xml_lit(")").parse(cursor)?;
@@ -416,9 +433,203 @@ impl<'a> Parser<'a> for MixedToken {
     }
 }
 
+// 3.3 Attribute-List Declarations
+
+/// Attribute-list Declaration
+///
+/// AttlistDecl ::= '<!ATTLIST' S Name AttDef* S? '>'
+struct AttListDeclToken;
+
+impl<'a> Parser<'a> for AttListDeclToken {
+    type Attribute = AttListDecl;
+    type Error = XmlError;
+
+    fn parse(&self, cursor: Cursor<'a>) -> Result<(Self::Attribute, Cursor<'a>), Self::Error> {
+        let (_, cursor) = xml_lit("<!ATTLIST").parse(cursor)?;
+        let (_, cursor) = SToken.parse(cursor)?;
+        let (name, cursor) = NameToken.parse(cursor)?;
+        let (defs, cursor) = kleene(AttDefToken).parse(cursor)?;
+        let (_, cursor) = optional(SToken).parse(cursor)?;
+        let (_, cursor) = xml_lit(">").parse(cursor)?;
+
+        Ok((
+            AttListDecl {
+                name: name.to_string(),
+                defs,
+            },
+            cursor,
+        ))
+    }
+}
+
+/// Attribute Definition
+///
+/// AttDef ::= S Name S AttType S DefaultDecl
+struct AttDefToken;
+
+impl<'a> Parser<'a> for AttDefToken {
+    type Attribute = AttDef;
+    type Error = XmlError;
+
+    fn parse(&self, cursor: Cursor<'a>) -> Result<(Self::Attribute, Cursor<'a>), Self::Error> {
+        let (_, cursor) = SToken.parse(cursor)?;
+        let (name, cursor) = NameToken.parse(cursor)?;
+        let (_, cursor) = SToken.parse(cursor)?;
+        let (att_type, cursor) = AttTypeToken.parse(cursor)?;
+        let (_, cursor) = SToken.parse(cursor)?;
+        let (default, cursor) = DefaultDeclToken.parse(cursor)?;
+
+        Ok((
+            AttDef {
+                name: name.to_string(),
+                att_type,
+                default,
+            },
+            cursor,
+        ))
+    }
+}
+
+// 3.3.1 Attribute Types
+
+/// Attribute Type
+///
+/// AttType        ::= StringType | TokenizedType | EnumeratedType
+/// StringType     ::= 'CDATA'
+/// TokenizedType  ::= 'ID' | 'IDREF' | 'IDREFS' | 'ENTITY' | 'ENTITIES' | 'NMTOKEN' | 'NMTOKENS'
+/// EnumeratedType ::= NotationType | Enumeration
+struct AttTypeToken;
+
+impl<'a> Parser<'a> for AttTypeToken {
+    type Attribute = AttType;
+    type Error = XmlError;
+
+    fn parse(&self, cursor: Cursor<'a>) -> Result<(Self::Attribute, Cursor<'a>), Self::Error> {
+        // longer keywords first, so that `ID` does not match the start of `IDREF`
+        const KEYWORDS: [(&str, AttType); 8] = [
+            ("CDATA", AttType::CData),
+            ("IDREFS", AttType::IdRefs),
+            ("IDREF", AttType::IdRef),
+            ("ID", AttType::Id),
+            ("ENTITIES", AttType::Entities),
+            ("ENTITY", AttType::Entity),
+            ("NMTOKENS", AttType::NmTokens),
+            ("NMTOKEN", AttType::NmToken),
+        ];
+
+        for (keyword, att_type) in KEYWORDS.iter() {
+            if let Ok((_, cursor)) = xml_lit(keyword).parse(cursor) {
+                return Ok((att_type.clone(), cursor));
+            }
+        }
+
+        if let Ok(((_, _, names), cursor)) =
+            (xml_lit("NOTATION"), SToken, EnumerationToken(NameToken)).parse(cursor)
+        {
+            Ok((AttType::Notation(names), cursor))
+        } else if let Ok((names, cursor)) = EnumerationToken(NmTokenToken).parse(cursor) {
+            Ok((AttType::Enumeration(names), cursor))
+        } else {
+            Err(XmlError::DtdError(XmlDtdError::SyntaxError))
+        }
+    }
+}
+
+/// Name token
+///
+/// Nmtoken ::= (NameChar)+
+#[derive(Clone, Copy)]
+struct NmTokenToken;
+
+impl<'a> Parser<'a> for NmTokenToken {
+    type Attribute = &'a str;
+    type Error = XmlError;
+
+    fn parse(&self, cursor: Cursor<'a>) -> Result<(Self::Attribute, Cursor<'a>), Self::Error> {
+        let size = cursor
+            .rest()
+            .char_indices()
+            .find(|(_, c)| !c.is_xml_name_char())
+            .map(|(i, _)| i)
+            .unwrap_or_else(|| cursor.rest_bytes().len());
+        if size > 0 {
+            Ok(cursor.advance2(size))
+        } else {
+            Err(XmlError::ExpectToken("Nmtoken"))
+        }
+    }
+}
+
+/// Enumerated values of `NotationType` or `Enumeration`
+///
+/// NotationType ::= 'NOTATION' S '(' S? Name (S? '|' S? Name)* S? ')'
+/// Enumeration  ::= '(' S? Nmtoken (S? '|' S? Nmtoken)* S? ')'
+struct EnumerationToken<T>(T);
+
+impl<'a, T> Parser<'a> for EnumerationToken<T>
+where
+    T: Parser<'a, Attribute = &'a str, Error = XmlError> + Copy,
+{
+    type Attribute = Vec<String>;
+    type Error = XmlError;
+
+    fn parse(&self, cursor: Cursor<'a>) -> Result<(Self::Attribute, Cursor<'a>), Self::Error> {
+        let (_, cursor) = xml_lit("(").parse(cursor)?;
+        let (_, cursor) = optional(SToken).parse(cursor)?;
+        let (values, cursor) =
+            separated(self.0, (optional(SToken), xml_lit("|"), optional(SToken))).parse(cursor)?;
+        let (_, cursor) = optional(SToken).parse(cursor)?;
+        let (_, cursor) = xml_lit(")").parse(cursor)?;
+
+        Ok((
+            values.into_iter().map(|value| value.to_string()).collect(),
+            cursor,
+        ))
+    }
+}
+
+// 3.3.2 Attribute Defaults
+
+/// Attribute Default
+///
+/// DefaultDecl ::= '#REQUIRED' | '#IMPLIED' | (('#FIXED' S)? AttValue)
+struct DefaultDeclToken;
+
+impl<'a> Parser<'a> for DefaultDeclToken {
+    type Attribute = DefaultDecl;
+    type Error = XmlError;
+
+    fn parse(&self, cursor: Cursor<'a>) -> Result<(Self::Attribute, Cursor<'a>), Self::Error> {
+        if let Ok((_, cursor)) = xml_lit("#REQUIRED").parse(cursor) {
+            Ok((DefaultDecl::Required, cursor))
+        } else if let Ok((_, cursor)) = xml_lit("#IMPLIED").parse(cursor) {
+            Ok((DefaultDecl::Implied, cursor))
+        } else if let Ok((_, cursor)) = (xml_lit("#FIXED"), SToken).parse(cursor) {
+            let (value, cursor) = AttValueToken::new(SimpleEntityStrValueResolver).parse(cursor)?;
+            Ok((DefaultDecl::Fixed(value.into_owned()), cursor))
+        } else {
+            let (value, cursor) = AttValueToken::new(SimpleEntityStrValueResolver).parse(cursor)?;
+            Ok((DefaultDecl::Default(value.into_owned()), cursor))
+        }
+    }
+}
+
 // 4.1 Character and Entity References
 
-// PEReference	   ::=   	'%' Name ';'
+/// `PEReference ::= '%' Name ';'`
+struct PEReferenceToken;
+
+impl<'a> Parser<'a> for PEReferenceToken {
+    type Attribute = &'a str;
+    type Error = XmlError;
+
+    fn parse(&self, cursor: Cursor<'a>) -> Result<(Self::Attribute, Cursor<'a>), Self::Error> {
+        let (_, cursor) = xml_lit("%").parse(cursor)?;
+        let (name, cursor) = NameToken.parse(cursor)?;
+        let (_, cursor) = xml_lit(";").parse(cursor)?;
+        Ok((name, cursor))
+    }
+}
 
 // 4.2 Entity Declarations
 
@@ -484,6 +695,7 @@ impl<'a> Parser<'a> for PEDeclToken {
         let (_, cursor) = SToken.parse(cursor)?;
         let (def, cursor) = PEDefToken.parse(cursor)?;
         let (_, cursor) = optional(SToken).parse(cursor)?;
+        let (_, cursor) = xml_lit(">").parse(cursor)?;
 
         Ok((
             PEDecl {
@@ -571,6 +783,41 @@ impl<'a> Parser<'a> for NDataDeclToken {
     }
 }
 
+// 4.7 Notation Declarations
+
+/// NotationDecl ::= '<!NOTATION' S Name S (ExternalID | PublicID) S? '>'
+/// PublicID     ::= 'PUBLIC' S PubidLiteral
+struct NotationDeclToken;
+
+impl<'a> Parser<'a> for NotationDeclToken {
+    type Attribute = NotationDecl;
+    type Error = XmlError;
+
+    fn parse(&self, cursor: Cursor<'a>) -> Result<(Self::Attribute, Cursor<'a>), Self::Error> {
+        let (_, cursor) = xml_lit("<!NOTATION").parse(cursor)?;
+        let (_, cursor) = SToken.parse(cursor)?;
+        let (name, cursor) = NameToken.parse(cursor)?;
+        let (_, cursor) = SToken.parse(cursor)?;
+        let (id, cursor) = if let Ok((external_id, cursor)) = ExternalIdToken.parse(cursor) {
+            (NotationId::External(external_id), cursor)
+        } else {
+            let ((_, _, pub_id), cursor) =
+                (xml_lit("PUBLIC"), SToken, PubidLiteralToken).parse(cursor)?;
+            (NotationId::Public(pub_id.to_string()), cursor)
+        };
+        let (_, cursor) = optional(SToken).parse(cursor)?;
+        let (_, cursor) = xml_lit(">").parse(cursor)?;
+
+        Ok((
+            NotationDecl {
+                name: name.to_string(),
+                id,
+            },
+            cursor,
+        ))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -876,6 +1123,50 @@ mod tests {
             );
         }
 
+        #[test]
+        fn element_content_choice() {
+            let (dtd, cursor) = DocTypeDeclToken
+                .parse(Cursor::new(
+                    "<!DOCTYPE e [ <!ELEMENT list (item | (head, item))+> ]>",
+                ))
+                .unwrap();
+            assert!(cursor.is_at_end());
+            assert_eq!(
+                &Some(IntSubset::new(vec![MarkupDeclEntry::new_element(
+                    "list".to_string(),
+                    ContentSpec::Children(ContentParticle {
+                        entry: ContentParticleEntry::Choice(vec![
+                            ContentParticle {
+                                entry: ContentParticleEntry::Name("item".to_string()),
+                                repetition: Repetition::One
+                            },
+                            ContentParticle {
+                                entry: ContentParticleEntry::Seq(vec![
+                                    ContentParticle {
+                                        entry: ContentParticleEntry::Name("head".to_string()),
+                                        repetition: Repetition::One
+                                    },
+                                    ContentParticle {
+                                        entry: ContentParticleEntry::Name("item".to_string()),
+                                        repetition: Repetition::One
+                                    }
+                                ]),
+                                repetition: Repetition::One
+                            }
+                        ]),
+                        repetition: Repetition::OneOrMore
+                    })
+                )])),
+                dtd.internal_subset()
+            );
+        }
+
+        #[test]
+        fn fail_mixed_without_pcdata() {
+            let result = DocTypeDeclToken.parse(Cursor::new("<!DOCTYPE e [ <!ELEMENT p (|a)*> ]>"));
+            assert!(result.is_err());
+        }
+
         #[test]
         #[ignore]
         fn element_content_3() {
@@ -986,4 +1277,189 @@ mod tests {
             );
         }
     }
+
+    /// 3.3 Attribute-List Declarations
+    mod att_list {
+        use crate::dtd::{AttDef, AttType, DefaultDecl, IntSubset, MarkupDeclEntry};
+        use crate::parser::Parser;
+        use crate::reader::dtd::DocTypeDeclToken;
+        use crate::Cursor;
+
+        fn att_def(name: &str, att_type: AttType, default: DefaultDecl) -> AttDef {
+            AttDef {
+                name: name.to_string(),
+                att_type,
+                default,
+            }
+        }
+
+        #[test]
+        fn empty() {
+            let (dtd, cursor) = DocTypeDeclToken
+                .parse(Cursor::new("<!DOCTYPE e [ <!ATTLIST e > ]>"))
+                .unwrap();
+            assert!(cursor.is_at_end());
+            assert_eq!(
+                &Some(IntSubset::new(vec![MarkupDeclEntry::new_att_list(
+                    "e".to_string(),
+                    vec![]
+                )])),
+                dtd.internal_subset()
+            );
+        }
+
+        #[test]
+        fn types() {
+            let (dtd, cursor) = DocTypeDeclToken
+                .parse(Cursor::new(
+                    "<!DOCTYPE e [ <!ATTLIST e a CDATA #IMPLIED b ID #REQUIRED c IDREF #IMPLIED \
+                     d IDREFS #IMPLIED f ENTITY #IMPLIED g ENTITIES #IMPLIED h NMTOKEN #IMPLIED \
+                     i NMTOKENS #IMPLIED> ]>",
+                ))
+                .unwrap();
+            assert!(cursor.is_at_end());
+            assert_eq!(
+                &Some(IntSubset::new(vec![MarkupDeclEntry::new_att_list(
+                    "e".to_string(),
+                    vec![
+                        att_def("a", AttType::CData, DefaultDecl::Implied),
+                        att_def("b", AttType::Id, DefaultDecl::Required),
+                        att_def("c", AttType::IdRef, DefaultDecl::Implied),
+                        att_def("d", AttType::IdRefs, DefaultDecl::Implied),
+                        att_def("f", AttType::Entity, DefaultDecl::Implied),
+                        att_def("g", AttType::Entities, DefaultDecl::Implied),
+                        att_def("h", AttType::NmToken, DefaultDecl::Implied),
+                        att_def("i", AttType::NmTokens, DefaultDecl::Implied),
+                    ]
+                )])),
+                dtd.internal_subset()
+            );
+        }
+
+        #[test]
+        fn enumerated() {
+            let (dtd, cursor) = DocTypeDeclToken
+                .parse(Cursor::new(
+                    "<!DOCTYPE e [ <!ATTLIST list type (bullets | ordered|1st) \"ordered\" \
+                     format NOTATION ( png|gif ) #FIXED 'png'> ]>",
+                ))
+                .unwrap();
+            assert!(cursor.is_at_end());
+            assert_eq!(
+                &Some(IntSubset::new(vec![MarkupDeclEntry::new_att_list(
+                    "list".to_string(),
+                    vec![
+                        att_def(
+                            "type",
+                            AttType::Enumeration(vec![
+                                "bullets".to_string(),
+                                "ordered".to_string(),
+                                "1st".to_string()
+                            ]),
+                            DefaultDecl::Default("ordered".to_string())
+                        ),
+                        att_def(
+                            "format",
+                            AttType::Notation(vec!["png".to_string(), "gif".to_string()]),
+                            DefaultDecl::Fixed("png".to_string())
+                        ),
+                    ]
+                )])),
+                dtd.internal_subset()
+            );
+        }
+
+        #[test]
+        fn fail_missing_default() {
+            let result =
+                DocTypeDeclToken.parse(Cursor::new("<!DOCTYPE e [ <!ATTLIST e a CDATA> ]>"));
+            assert!(result.is_err());
+        }
+    }
+
+    mod misc {
+        use std::borrow::Cow;
+
+        use crate::dtd::{
+            ContentSpec, ExternalId, IntSubset, MarkupDeclEntry, NotationDecl, NotationId, PEDecl,
+            PEDef,
+        };
+        use crate::parser::Parser;
+        use crate::reader::dtd::DocTypeDeclToken;
+        use crate::{Cursor, XmlError, PI};
+
+        #[test]
+        fn comment_and_pi() {
+            let (dtd, cursor) = DocTypeDeclToken
+                .parse(Cursor::new(
+                    "<!DOCTYPE e [ <!-- comment --><?target data?> <!ELEMENT e EMPTY> ]>",
+                ))
+                .unwrap();
+            assert!(cursor.is_at_end());
+            assert_eq!(
+                &Some(IntSubset::new(vec![
+                    MarkupDeclEntry::Comment(" comment ".to_string()),
+                    MarkupDeclEntry::PI(PI {
+                        target: Cow::Borrowed("target"),
+                        data: Some(Cow::Borrowed("data"))
+                    }),
+                    MarkupDeclEntry::new_element("e".to_string(), ContentSpec::Empty),
+                ])),
+                dtd.internal_subset()
+            );
+        }
+
+        #[test]
+        fn illegal_char_in_comment() {
+            let err = DocTypeDeclToken
+                .parse(Cursor::new("<!DOCTYPE e [ <!-- a\u{0}b --> ]>"))
+                .unwrap_err();
+            assert_eq!(XmlError::IllegalChar('\u{0}'), err);
+        }
+
+        #[test]
+        fn parameter_entity() {
+            let (dtd, cursor) = DocTypeDeclToken
+                .parse(Cursor::new(
+                    "<!DOCTYPE e [ <!ENTITY % common \"a | b\"> %common; ]>",
+                ))
+                .unwrap();
+            assert!(cursor.is_at_end());
+            assert_eq!(
+                &Some(IntSubset::new(vec![
+                    MarkupDeclEntry::ParameterEntity(PEDecl {
+                        name: "common".to_string(),
+                        def: PEDef::Internal("a | b".to_string())
+                    }),
+                    MarkupDeclEntry::PEReference("common".to_string()),
+                ])),
+                dtd.internal_subset()
+            );
+        }
+
+        #[test]
+        fn notation() {
+            let (dtd, cursor) = DocTypeDeclToken
+                .parse(Cursor::new(
+                    "<!DOCTYPE e [ <!NOTATION gif SYSTEM \"image/gif\"> <!NOTATION png PUBLIC 'png'> ]>",
+                ))
+                .unwrap();
+            assert!(cursor.is_at_end());
+            assert_eq!(
+                &Some(IntSubset::new(vec![
+                    MarkupDeclEntry::Notation(NotationDecl {
+                        name: "gif".to_string(),
+                        id: NotationId::External(ExternalId::System {
+                            system: "image/gif".to_string()
+                        })
+                    }),
+                    MarkupDeclEntry::Notation(NotationDecl {
+                        name: "png".to_string(),
+                        id: NotationId::Public("png".to_string())
+                    }),
+                ])),
+                dtd.internal_subset()
+            );
+        }
+    }
 }
@@ -1,29 +1,43 @@
 //! XML Pull Reader
 
-use std::borrow::Cow;
-use std::cell::RefCell;
-use std::collections::HashMap;
-use std::convert::{TryFrom, TryInto};
-use std::marker::PhantomData;
-use std::rc::Rc;
-use std::str::FromStr;
+use alloc::borrow::Cow;
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::rc::Rc;
+use core::cell::RefCell;
+use core::convert::{TryFrom, TryInto};
+use core::marker::PhantomData;
+use core::str::FromStr;
 
 use xrs_chars::{XmlAsciiChar, XmlChar};
 
 use crate::cow::CowStrBuilder;
+use crate::dtd::{AttDef, AttType, DefaultDecl, EntityDef, GEDecl, MarkupDeclEntry};
+use crate::encoding::{self, DetectedEncoding};
+use crate::intern::Interner;
 use crate::parser::core::{kleene, optional, plus, raw, Plus};
 use crate::parser::helper::map_error;
 use crate::parser::string::{bytes, chars, lit};
-use crate::parser::Parser;
-use crate::reader::chars::is_ascii_content_char;
+use crate::parser::{normalize_att_value_at, AttValueEntities, Parser};
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use crate::reader::chars::{ascii_char_str, is_ascii_content_char};
 use crate::reader::dtd::DocTypeDeclToken;
 use crate::simple::StrVisitor;
+use crate::stack::NamespaceStack;
 use crate::XmlError::{UnexpectedCharacter, UnexpectedEof};
 use crate::XmlEvent::Characters;
-use crate::{Attribute, Cursor, ETag, XmlDecl, XmlError, XmlEvent, PI};
+use crate::{
+    resolve_attributes, Attribute, CharRef, Cursor, ETag, ResolvedAttribute, STag, XmlDecl,
+    XmlError, XmlEvent, PI,
+};
 
+pub use builder::ReaderBuilder;
+
+mod builder;
 pub mod chars;
 pub mod dtd;
+mod html5;
+mod scan;
 
 // Common
 
@@ -46,7 +60,7 @@ impl<'a> Parser<'a> for TerminatedChars {
             }
             Ok(res)
         } else {
-            Err(XmlError::UnexpectedEof)
+            Err(XmlError::need_more())
         }
     }
 }
@@ -80,7 +94,7 @@ impl<'a, T: Fn(char) -> bool> Parser<'a> for CharTerminated<T> {
             }
             Ok(res)
         } else {
-            Err(XmlError::UnexpectedEof)
+            Err(XmlError::need_more())
         }
     }
 }
@@ -100,11 +114,7 @@ impl<'a> Parser<'a> for SToken {
     type Error = XmlError;
 
     fn parse(&self, cursor: Cursor<'a>) -> Result<(Self::Attribute, Cursor<'a>), Self::Error> {
-        let size = cursor
-            .rest_bytes()
-            .iter()
-            .take_while(|c| c.is_xml_whitespace())
-            .count();
+        let size = scan::skip_whitespace(cursor.rest_bytes());
         if size > 0 {
             Ok(((), cursor.advance(size)))
         } else {
@@ -113,6 +123,7 @@ impl<'a> Parser<'a> for SToken {
     }
 }
 
+#[derive(Clone, Copy)]
 pub(crate) struct NameToken;
 
 impl<'a> Parser<'a> for NameToken {
@@ -120,20 +131,54 @@ impl<'a> Parser<'a> for NameToken {
     type Error = XmlError;
 
     fn parse(&self, cursor: Cursor<'a>) -> Result<(Self::Attribute, Cursor<'a>), Self::Error> {
-        let mut chars = cursor.rest().char_indices();
-
-        match chars.next() {
-            Some((_, c)) if c.is_xml_name_start_char() => {}
-            Some((_, c)) => return Err(XmlError::IllegalNameStartChar(c)),
-            None => return Err(XmlError::UnexpectedEof),
+        let len = name_len(cursor.rest())?;
+        if len < cursor.rest().len() {
+            Ok(cursor.advance2(len))
+        } else {
+            Err(XmlError::need_more())
         }
+    }
+}
 
-        if let Some((i, _)) = chars.find(|(_, c)| !c.is_xml_name_char()) {
-            Ok(cursor.advance2(i))
-        } else {
-            Err(XmlError::UnexpectedEof)
+/// Length of the name at the start of `s`
+pub(crate) fn name_len(s: &str) -> Result<usize, XmlError> {
+    let mut chars = s.char_indices();
+
+    match chars.next() {
+        Some((_, c)) if c.is_xml_name_start_char() => {}
+        Some((_, c)) => return Err(XmlError::IllegalNameStartChar(c)),
+        None => return Err(XmlError::UnexpectedEof),
+    }
+
+    Ok(chars
+        .find(|(_, c)| !c.is_xml_name_char())
+        .map_or(s.len(), |(i, _)| i))
+}
+
+/// Name of an end tag, which is compared byte-wise with the expected name first
+///
+/// `NameToken` is only used when the name differs from the expected name.
+pub(crate) fn etag_name<'a>(
+    cursor: Cursor<'a>,
+    expected: Option<&str>,
+) -> Result<(&'a str, Cursor<'a>), XmlError> {
+    if let Some(expected) = expected {
+        if cursor.has_next_str(expected) {
+            // the name could continue with further name characters
+            match cursor.next_byte(expected.len()) {
+                Some(c) if c == b'>' || c.is_xml_whitespace() => {
+                    return Ok(cursor.advance2(expected.len()))
+                }
+                _ => {}
+            }
         }
     }
+    NameToken.parse(cursor)
+}
+
+/// Whether the PI target is reserved for standardization (`xml` in any case)
+pub(crate) fn is_reserved_pi_target(target: &str) -> bool {
+    target.eq_ignore_ascii_case("xml")
 }
 
 /// Entity resolver for entity references within attribute values
@@ -228,7 +273,39 @@ impl<'a, R: EntityStrValueResolver<'a>> AttValueToken<R> {
             }
         }
 
-        return Err(XmlError::UnexpectedEof);
+        return Err(XmlError::need_more());
+    }
+}
+
+/// Attribute value as written between the quotes
+///
+/// `AttValue ::= '"' ([^<&"] | Reference)* '"' | "'" ([^<&'] | Reference)* "'"`
+pub(crate) struct AttLiteralToken;
+
+impl<'a> Parser<'a> for AttLiteralToken {
+    type Attribute = &'a str;
+    type Error = XmlError;
+
+    fn parse(&self, cursor: Cursor<'a>) -> Result<(Self::Attribute, Cursor<'a>), Self::Error> {
+        let quote = match cursor.next_byte(0) {
+            Some(c @ (b'"' | b'\'')) => c,
+            _ => return Err(XmlError::ExpectToken("quote or single quote")),
+        };
+        let cursor = cursor.advance(1);
+        match cursor
+            .rest_bytes()
+            .iter()
+            .position(|&c| c == quote || c == b'<')
+        {
+            Some(end) if cursor.rest_bytes()[end] == quote => {
+                let (literal, cursor) = cursor.advance2(end);
+                Ok((literal, cursor.advance(1)))
+            }
+            Some(_) => Err(XmlError::IllegalAttributeValue(
+                "< not allowed in attribute value",
+            )),
+            None => Err(XmlError::need_more()),
+        }
     }
 }
 
@@ -279,13 +356,23 @@ impl<'a> Parser<'a> for PIToken {
     fn parse(&self, cursor: Cursor<'a>) -> Result<(Self::Attribute, Cursor<'a>), XmlError> {
         let (_, cursor) = xml_lit("<?").parse(cursor)?;
         let (target, cursor) = NameToken.parse(cursor)?;
-        if target.eq_ignore_ascii_case("xml") {
+        if is_reserved_pi_target(target) {
             return Err(XmlError::InvalidPITarget);
         }
-        let (maybe_data, cursor) = optional((SToken, TerminatedChars("?>"))).parse(cursor)?;
+        let (space, cursor) = optional(SToken).parse(cursor)?;
+        let (maybe_data, cursor) = if space.is_some() {
+            let (data, cursor) = map_error(TerminatedChars("?>"), |err| match err {
+                XmlError::NeedMore(_) => XmlError::NeedMore(Box::new(XmlError::ExpectToken("?>"))),
+                err => err,
+            })
+            .parse(cursor)?;
+            (Some(data), cursor)
+        } else {
+            (None, cursor)
+        };
         let (_, cursor) = xml_lit("?>").parse(cursor)?;
 
-        Ok(((target, maybe_data.map(|data| data.1)), cursor))
+        Ok(((target, maybe_data), cursor))
     }
 }
 
@@ -315,6 +402,31 @@ impl<'a> Parser<'a> for CDataToken {
 
 pub(crate) struct XmlDeclToken;
 
+/// Whether the error is found in a token after reading it completely, so input after the token
+/// can't cause it
+pub(crate) fn is_complete_token_error(err: &XmlError) -> bool {
+    matches!(
+        err,
+        XmlError::WrongETagName { .. }
+            | XmlError::NonUniqueAttribute { .. }
+            | XmlError::MissingRequiredAttribute { .. }
+            | XmlError::FixedAttributeMismatch { .. }
+            | XmlError::UnknownEntity(_)
+            | XmlError::RecursiveEntity(_)
+            | XmlError::StandaloneViolation(_)
+            | XmlError::ETagAfterRootElement
+    )
+}
+
+/// Whether the cursor is at `<?xml` which is not the start of a PI target like `xml-stylesheet`
+fn is_decl_start(cursor: Cursor) -> bool {
+    cursor.has_next_str("<?xml")
+        && cursor
+            .next_byte(5)
+            .map(|c| c.is_xml_whitespace() || c == b'?')
+            .unwrap_or(false)
+}
+
 impl<'a> Parser<'a> for XmlDeclToken {
     type Attribute = XmlDecl;
     type Error = XmlError;
@@ -458,7 +570,7 @@ fn take_till_ascii_char(
     {
         Ok(cursor.advance2(i))
     } else {
-        Err(XmlError::UnexpectedEof)
+        Err(XmlError::need_more())
     }
 }
 
@@ -568,6 +680,76 @@ fn expect_byte(cursor: Cursor, c: u8, err: fn() -> XmlError) -> Result<Cursor, X
     }
 }
 
+/// Find the end tag of the element `name` whose remaining content starts at `cursor`
+///
+/// Comments, CDATA sections, PIs and quoted attribute values are skipped without looking at
+/// their content, everything else is not interpreted apart from counting the depth of tags.
+/// Returns the length of the content and the cursor after the end tag.
+fn scan_inner_raw<'a>(cursor: Cursor<'a>, name: &str) -> Result<(usize, Cursor<'a>), XmlError> {
+    let input = cursor.rest();
+    let skip_past = |pos: usize, pat: &'static str| {
+        input[pos..]
+            .find(pat)
+            .map(|i| pos + i + pat.len())
+            .ok_or(XmlError::UnexpectedEof)
+    };
+
+    let mut depth = 0usize;
+    let mut pos = 0;
+    while let Some(i) = input[pos..].find('<') {
+        let start = pos + i;
+        let markup = &input[start..];
+        pos = if markup.starts_with("<!--") {
+            skip_past(start, "-->")?
+        } else if markup.starts_with("<![CDATA[") {
+            skip_past(start, "]]>")?
+        } else if markup.starts_with("<?") {
+            skip_past(start, "?>")?
+        } else if markup.starts_with("</") {
+            if depth == 0 {
+                let tag = cursor.advance(start + 2);
+                let (etag, tag) = etag_name(tag, Some(name))?;
+                if etag != name {
+                    return Err(XmlError::WrongETagName {
+                        expected_name: name.to_string(),
+                    });
+                }
+                let (_, tag) = optional(SToken).parse(tag)?;
+                let tag = expect_byte(tag, b'>', || XmlError::ExpectedElementEnd)?;
+                return Ok((start, tag));
+            }
+            depth -= 1;
+            skip_past(start, ">")?
+        } else {
+            // start tag, `>` is allowed in attribute values
+            let mut quote = None;
+            let end = markup
+                .bytes()
+                .enumerate()
+                .skip(1)
+                .find(|&(_, c)| match quote {
+                    Some(q) if c == q => {
+                        quote = None;
+                        false
+                    }
+                    Some(_) => false,
+                    None if c == b'"' || c == b'\'' => {
+                        quote = Some(c);
+                        false
+                    }
+                    None => c == b'>',
+                })
+                .map(|(i, _)| start + i)
+                .ok_or(XmlError::UnexpectedEof)?;
+            if input.as_bytes()[end - 1] != b'/' {
+                depth += 1;
+            }
+            end + 1
+        };
+    }
+    Err(XmlError::OpenElementAtEof)
+}
+
 pub struct Entity {
     name: String,
     external: bool,
@@ -582,16 +764,38 @@ impl Entity {
             text: text.into(),
         }
     }
+
+    /// Entity declared outside of the document entity, e.g. in the external subset
+    pub fn new_external(name: impl ToString, text: impl Into<Rc<str>>) -> Self {
+        Self {
+            external: true,
+            ..Self::new(name, text)
+        }
+    }
+
+    pub fn is_external(&self) -> bool {
+        self.external
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Replacement text
+    pub fn text(&self) -> &str {
+        &self.text
+    }
 }
 
+/// Table of general entities known to the reader
 pub struct Entities {
-    defined: HashMap<String, Rc<Entity>>,
+    defined: BTreeMap<String, Rc<Entity>>,
 }
 
 impl Default for Entities {
     fn default() -> Self {
         let mut result = Entities {
-            defined: HashMap::with_capacity(5),
+            defined: BTreeMap::new(),
         };
         result.register("lt", "&#60;");
         result.register("gt", "&#62;");
@@ -608,6 +812,21 @@ impl Entities {
             .insert(name.to_string(), Rc::new(Entity::new(name, value)));
     }
 
+    /// Register an entity declared outside of the document entity
+    ///
+    /// Referencing the entity in a standalone document is an error.
+    pub fn register_external(&mut self, name: impl ToString, value: impl Into<Rc<str>>) {
+        self.defined
+            .insert(name.to_string(), Rc::new(Entity::new_external(name, value)));
+    }
+
+    /// Add entity from a declaration of the document, the first declaration is binding
+    fn declare(&mut self, name: &str, value: &str) {
+        if !self.defined.contains_key(name) {
+            self.register(name, value);
+        }
+    }
+
     pub fn get_ref(&self, name: &str) -> Option<&Entity> {
         self.defined.get(name).map(|rc| rc.as_ref())
     }
@@ -615,6 +834,37 @@ impl Entities {
     pub fn get_rc(&self, name: &str) -> Option<Rc<Entity>> {
         self.defined.get(name).cloned()
     }
+
+    /// Register the named character references of HTML5 like `&nbsp;`
+    pub fn register_html5(&mut self) {
+        for (name, value) in html5::HTML5_ENTITIES {
+            self.register(name, *value);
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Entity> {
+        self.defined.values().map(|rc| rc.as_ref())
+    }
+}
+
+/// Start tag event, which carries the attribute region from `start` to `end` in verbatim mode
+fn stag_event<'a>(
+    name: &'a str,
+    empty: bool,
+    start: Cursor<'a>,
+    end: Cursor<'a>,
+    ctx: &DocumentContext,
+) -> XmlEvent<'a> {
+    XmlEvent::STag(STag {
+        name: name.into(),
+        empty,
+        attrs: vec![],
+        raw_attrs: if ctx.verbatim {
+            Some(start.rest()[..end.offset() - start.offset()].into())
+        } else {
+            None
+        },
+    })
 }
 
 struct SimpleEntityStrValueResolver;
@@ -623,27 +873,32 @@ impl<'i> EntityStrValueResolver<'i> for SimpleEntityStrValueResolver {}
 
 trait InternalXmlParser<'a> {
     fn stack_push(&mut self, tag: &'a str);
-    fn attributes_push(&mut self, name: &'a str, value: Cow<'a, str>);
+    fn attributes_push(&mut self, attr: Attribute<'a>);
     fn stack_pop(&mut self) -> Option<Cow<'a, str>>;
+    fn stack_top(&self) -> Option<&str>;
     fn set_version(&mut self, version: String);
     fn set_empty(&mut self, v: bool);
     fn set_seen_root(&mut self);
     fn set_cursor(&mut self, cur: Cursor<'a>);
 
     fn exists_attribute_name(&self, name: &'a str) -> bool;
+    fn attribute_value(&self, name: &str) -> Option<&str>;
     fn get_version(&self) -> Option<&str>;
     fn is_empty(&self) -> bool;
     fn is_after_root(&self) -> bool;
     fn cursor(&self) -> Cursor<'a>;
+    /// Character data may extend to the end of the input
+    fn is_characters_until_end(&self) -> bool;
 
     // Parser functions
 
-    fn parse_stag(&mut self) -> Result<Option<XmlEvent<'a>>, XmlError> {
+    fn parse_stag(&mut self, ctx: &mut DocumentContext) -> Result<Option<XmlEvent<'a>>, XmlError> {
         if self.is_after_root() {
             return Err(XmlError::ExpectedDocumentEnd);
         }
 
         let (name, mut cursor) = NameToken.parse(self.cursor())?;
+        let attrs_start = cursor;
         let mut got_whitespace = if let Ok((_, cur)) = SToken.parse(cursor) {
             cursor = cur;
             true
@@ -655,11 +910,12 @@ trait InternalXmlParser<'a> {
             // /> empty end
             if c == b'/' {
                 return if Some(b'>') == cursor.next_byte(1) {
+                    self.apply_attribute_defaults(name, ctx)?;
                     self.set_cursor(cursor.advance(2));
                     self.set_empty(true);
                     self.set_seen_root();
                     self.stack_push(name);
-                    Ok(Some(XmlEvent::stag(name, true)))
+                    Ok(Some(stag_event(name, true, attrs_start, cursor, ctx)))
                 } else {
                     Err(XmlError::ExpectedElementEnd)
                 };
@@ -667,11 +923,12 @@ trait InternalXmlParser<'a> {
 
             // normal end
             if c == b'>' {
+                self.apply_attribute_defaults(name, ctx)?;
                 self.set_cursor(cursor.advance(1));
                 self.set_empty(false);
                 self.set_seen_root();
                 self.stack_push(name);
-                return Ok(Some(XmlEvent::stag(name, false)));
+                return Ok(Some(stag_event(name, false, attrs_start, cursor, ctx)));
             }
 
             // attribute
@@ -681,7 +938,16 @@ trait InternalXmlParser<'a> {
 
             let (attr_name, cur) = NameToken.parse(cursor)?;
             let (_, cur) = EqToken.parse(cur)?;
-            let (value, cur) = AttValueToken(SimpleEntityStrValueResolver).parse(cur)?;
+            let quote = cur.next_byte(0);
+            let (literal, after_value) = AttLiteralToken.parse(cur)?;
+            // the literal starts behind the quote
+            let value = normalize_att_value_at(
+                cur.advance(1),
+                literal.len(),
+                ctx.is_cdata_attribute(name, attr_name),
+                ctx.att_value_entities(),
+            )?;
+            let cur = after_value;
             if let Ok((_, cur)) = SToken.parse(cur) {
                 cursor = cur;
                 got_whitespace = true;
@@ -691,34 +957,93 @@ trait InternalXmlParser<'a> {
             }
 
             if self.exists_attribute_name(attr_name) {
-                return Err(XmlError::NonUniqueAttribute {
+                let err = XmlError::NonUniqueAttribute {
                     attribute: attr_name.to_string(),
-                });
+                };
+                if !ctx.recoverable {
+                    return Err(err);
+                }
+                // keep the first value
+                ctx.recovered.push_back(err);
+                continue;
             }
 
-            self.attributes_push(attr_name, value);
+            let attr = Attribute::new(attr_name, value);
+            self.attributes_push(match quote {
+                Some(quote) if ctx.verbatim => attr.with_quote(quote as char),
+                _ => attr,
+            });
         }
 
         Err(XmlError::ExpectedElementEnd)
     }
 
-    fn parse_etag(&mut self) -> Result<Option<XmlEvent<'a>>, XmlError> {
-        // TODO: xml_lit(self.stack.pop()) should be faster
-        let (name, cursor) = NameToken.parse(self.cursor())?;
+    /// Add default values of attributes declared in the DTD and check `#REQUIRED` and `#FIXED`
+    fn apply_attribute_defaults(
+        &mut self,
+        name: &str,
+        ctx: &DocumentContext,
+    ) -> Result<(), XmlError> {
+        let defs = match ctx.attribute_defs.get(name) {
+            Some(defs) => defs,
+            None => return Ok(()),
+        };
+
+        for def in defs {
+            match self.attribute_value(&def.name) {
+                Some(value) => {
+                    if let DefaultDecl::Fixed(expected) = &def.default {
+                        if ctx.validate_attributes && value != expected {
+                            return Err(XmlError::FixedAttributeMismatch {
+                                attribute: def.name.clone(),
+                                expected: expected.clone(),
+                            });
+                        }
+                    }
+                }
+                None => match &def.default {
+                    DefaultDecl::Required if ctx.validate_attributes => {
+                        return Err(XmlError::MissingRequiredAttribute {
+                            attribute: def.name.clone(),
+                        })
+                    }
+                    DefaultDecl::Required => {}
+                    DefaultDecl::Default(default) | DefaultDecl::Fixed(default) => {
+                        self.attributes_push(Attribute::new(def.name.clone(), default.clone()))
+                    }
+                    DefaultDecl::Implied => {}
+                },
+            }
+        }
+
+        Ok(())
+    }
+
+    fn parse_etag(&mut self, ctx: &mut DocumentContext) -> Result<Option<XmlEvent<'a>>, XmlError> {
+        let (name, cursor) = etag_name(self.cursor(), self.stack_top())?;
         let (_, cursor) = optional(SToken).parse(cursor)?;
         let cursor = expect_byte(cursor, b'>', || XmlError::ExpectedElementEnd)?;
         self.set_cursor(cursor);
 
-        if let Some(expected_name) = self.stack_pop() {
-            if expected_name == name {
+        match self.stack_top() {
+            Some(expected_name) if expected_name == name => {
+                self.stack_pop();
                 Ok(Some(XmlEvent::etag(name)))
-            } else {
-                Err(XmlError::WrongETagName {
+            }
+            Some(expected_name) => {
+                let err = XmlError::WrongETagName {
                     expected_name: expected_name.to_string(),
-                })
+                };
+                if ctx.recoverable {
+                    // skip the end tag, the element stays open
+                    ctx.recovered.push_back(err);
+                    Ok(None)
+                } else {
+                    self.stack_pop();
+                    Err(err)
+                }
             }
-        } else {
-            Err(XmlError::ETagAfterRootElement)
+            None => Err(XmlError::ETagAfterRootElement),
         }
     }
 
@@ -739,10 +1064,40 @@ trait InternalXmlParser<'a> {
         Ok(Some(XmlEvent::XmlDecl(decl)))
     }
 
-    fn parse_doctypedecl(&mut self) -> Result<Option<XmlEvent<'a>>, XmlError> {
+    fn parse_doctypedecl(
+        &mut self,
+        ctx: &mut DocumentContext,
+    ) -> Result<Option<XmlEvent<'a>>, XmlError> {
         let (decl, cursor) = DocTypeDeclToken.parse(self.cursor())?;
         self.set_cursor(cursor);
-        // TODO: add new entities
+
+        if decl.external_id().is_some() {
+            ctx.unread_markup_decls = true;
+        }
+        if let Some(int_subset) = decl.internal_subset() {
+            for entry in int_subset.decls() {
+                match entry {
+                    MarkupDeclEntry::GeneralEntity(GEDecl {
+                        name,
+                        def: EntityDef::Internal(value),
+                    }) => ctx.entities.declare(name, value),
+                    MarkupDeclEntry::AttList(att_list) => {
+                        let defs = ctx.attribute_defs.entry(att_list.name.clone()).or_default();
+                        for def in &att_list.defs {
+                            // first declaration of an attribute is binding
+                            if defs.iter().all(|other| other.name != def.name) {
+                                defs.push(def.clone());
+                            }
+                        }
+                    }
+                    MarkupDeclEntry::PEReference(_) => ctx.unread_markup_decls = true,
+                    // external entities aren't read, so references to them are handled like
+                    // references to unknown entities, see `UnknownEntityPolicy`
+                    _ => {}
+                }
+            }
+        }
+
         Ok(Some(XmlEvent::Dtd(Box::new(decl))))
     }
 
@@ -764,14 +1119,15 @@ trait InternalXmlParser<'a> {
     fn parse_cdata(&mut self) -> Result<Option<XmlEvent<'a>>, XmlError> {
         let (cdata, cursor) = CDataToken.parse(self.cursor())?;
         self.set_cursor(cursor);
-        Ok(Some(XmlEvent::Characters(cdata.into())))
+        Ok(Some(XmlEvent::CData(cdata.into())))
     }
 
     fn parse_characters(&mut self) -> Result<Option<XmlEvent<'a>>, XmlError> {
-        if let Some((i, c)) = self
-            .cursor()
-            .rest()
+        let rest = self.cursor().rest();
+        let start = scan::skip_ascii_content(rest.as_bytes());
+        if let Some((i, c)) = rest[start..]
             .char_indices()
+            .map(|(i, c)| (start + i, c))
             .find(|(_, c)| !is_ascii_content_char(*c))
         {
             if c.is_xml_char() {
@@ -783,6 +1139,10 @@ trait InternalXmlParser<'a> {
             } else {
                 Err(XmlError::InvalidCharacter(c))
             }
+        } else if self.is_characters_until_end() && !rest.is_empty() {
+            let (chars, cursor) = self.cursor().advance2(rest.len());
+            self.set_cursor(cursor);
+            Ok(Some(Characters(chars.into())))
         } else {
             Err(UnexpectedEof)
         }
@@ -797,15 +1157,55 @@ trait InternalXmlParser<'a> {
             if c == b'#' {
                 let (character, cursor) = CharRefToken.parse(cur)?;
                 self.set_cursor(cursor);
-                Ok(Some(XmlEvent::Characters(character.to_string().into())))
+                if ctx.preserve_char_refs {
+                    let (reference, _) = cur.advance2(cursor.offset() - cur.offset());
+                    return Ok(Some(XmlEvent::CharRef(CharRef {
+                        character,
+                        reference: Cow::Borrowed(reference),
+                    })));
+                }
+                let chars = if character.is_ascii() {
+                    Cow::Borrowed(ascii_char_str(character as u8))
+                } else {
+                    Cow::Owned(character.to_string())
+                };
+                Ok(Some(XmlEvent::Characters(chars)))
             } else {
                 let (entity_ref, cursor) = EntityRefToken.parse(cur)?;
+                let standalone = ctx.standalone == Some(true);
                 if let Some(entity) = ctx.entities.get_rc(entity_ref) {
+                    if standalone && entity.external {
+                        return Err(XmlError::StandaloneViolation(entity_ref.to_string()));
+                    }
                     self.set_cursor(cursor);
                     ctx.next_entity = Some(entity);
                     Ok(None)
+                } else if standalone && ctx.unread_markup_decls {
+                    // only an external declaration can define the entity
+                    Err(XmlError::StandaloneViolation(entity_ref.to_string()))
                 } else {
-                    Err(XmlError::UnknownEntity(entity_ref.to_string()))
+                    match ctx.unknown_entity_policy {
+                        UnknownEntityPolicy::Error if ctx.recoverable => {
+                            self.set_cursor(cursor);
+                            ctx.recovered
+                                .push_back(XmlError::UnknownEntity(entity_ref.to_string()));
+                            Ok(None)
+                        }
+                        UnknownEntityPolicy::Error => {
+                            Err(XmlError::UnknownEntity(entity_ref.to_string()))
+                        }
+                        UnknownEntityPolicy::Passthrough => {
+                            let (reference, cursor) = cur.advance2(cursor.offset() - cur.offset());
+                            self.set_cursor(cursor);
+                            Ok(Some(Characters(Cow::Borrowed(reference))))
+                        }
+                        UnknownEntityPolicy::Skip => {
+                            self.set_cursor(cursor);
+                            ctx.warnings
+                                .push(XmlError::UnknownEntity(entity_ref.to_string()));
+                            Ok(None)
+                        }
+                    }
                 }
             }
         } else {
@@ -830,21 +1230,25 @@ struct DocumentParser<'a> {
     attributes: Vec<Attribute<'a>>,
     empty: bool,
     seen_root: bool,
-    stack: Vec<&'a str>,
+    stack: Vec<Cow<'a, str>>,
     version: Option<String>,
 }
 
 impl<'a> InternalXmlParser<'a> for DocumentParser<'a> {
     fn stack_push(&mut self, tag: &'a str) {
-        self.stack.push(tag);
+        self.stack.push(Cow::Borrowed(tag));
     }
 
-    fn attributes_push(&mut self, name: &'a str, value: Cow<'a, str>) {
-        self.attributes.push(Attribute::new(name, value));
+    fn attributes_push(&mut self, attr: Attribute<'a>) {
+        self.attributes.push(attr);
     }
 
     fn stack_pop(&mut self) -> Option<Cow<'a, str>> {
-        self.stack.pop().map(|tag| tag.into())
+        self.stack.pop()
+    }
+
+    fn stack_top(&self) -> Option<&str> {
+        self.stack.last().map(|tag| tag as &str)
     }
 
     fn set_version(&mut self, version: String) {
@@ -867,6 +1271,13 @@ impl<'a> InternalXmlParser<'a> for DocumentParser<'a> {
         self.attributes.iter().any(|attr| attr.name == name)
     }
 
+    fn attribute_value(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|attr| attr.name == name)
+            .map(|attr| attr.value())
+    }
+
     fn get_version(&self) -> Option<&str> {
         self.version.as_ref().map(|v| v as &str)
     }
@@ -879,6 +1290,10 @@ impl<'a> InternalXmlParser<'a> for DocumentParser<'a> {
         self.stack.is_empty() && self.seen_root
     }
 
+    fn is_characters_until_end(&self) -> bool {
+        false
+    }
+
     fn cursor(&self) -> Cursor<'a> {
         self.cursor
     }
@@ -890,10 +1305,8 @@ impl<'a> DocumentParser<'a> {
         self.attributes.clear();
         if self.empty {
             self.empty = false;
-            if let Some(name) = self.stack.pop() {
-                return Ok(Some(XmlEvent::etag(name)));
-            }
-            unreachable!()
+            let name = self.stack.pop().ok_or(XmlError::ExpectedElementStart)?;
+            return Ok(Some(XmlEvent::etag(name)));
         }
 
         while let Some(c) = self.cursor.next_byte(0) {
@@ -906,15 +1319,16 @@ impl<'a> DocumentParser<'a> {
                             if self.is_after_root() {
                                 return Err(XmlError::ExpectedDocumentEnd);
                             }
-                            self.parse_etag()
+                            match self.parse_etag(ctx) {
+                                // skipped end tag
+                                Ok(None) => continue,
+                                evt => evt,
+                            }
                         } else if c == b'?' {
-                            if self.is_prolog() && self.version.is_none() {
-                                // TODO: not correct
-                                if self.cursor.has_next_str("<?xml") {
-                                    self.parse_decl(ctx)
-                                } else {
-                                    self.parse_pi()
-                                }
+                            // the declaration is only allowed at the very start of the
+                            // document, elsewhere `<?xml` is a PI with a reserved target
+                            if self.cursor.offset() == 0 && is_decl_start(self.cursor) {
+                                self.parse_decl(ctx)
                             } else {
                                 self.parse_pi()
                             }
@@ -922,7 +1336,7 @@ impl<'a> DocumentParser<'a> {
                             if self.cursor.has_next_str("<!--") {
                                 self.parse_comment()
                             } else if self.cursor.has_next_str("<!DOCTYPE") {
-                                self.parse_doctypedecl()
+                                self.parse_doctypedecl(ctx)
                             } else if self.cursor.has_next_str("<![CDATA[") {
                                 self.parse_cdata()
                             } else {
@@ -930,13 +1344,17 @@ impl<'a> DocumentParser<'a> {
                             }
                         } else {
                             self.cursor = self.cursor.advance(1);
-                            self.parse_stag()
+                            self.parse_stag(ctx)
                         }
                     } else {
                         Err(XmlError::ExpectedElementStart)
                     }
                 }
-                b'&' => self.parse_reference(ctx),
+                b'&' => match self.parse_reference(ctx) {
+                    // skipped reference
+                    Ok(None) if ctx.next_entity.is_none() => continue,
+                    evt => evt,
+                },
                 b'\r' => {
                     if let Some(evt) = self.parse_carriage_return() {
                         Ok(Some(evt))
@@ -952,7 +1370,10 @@ impl<'a> DocumentParser<'a> {
                             self.cursor = cur;
                             continue;
                         } else {
-                            Err(UnexpectedCharacter(self.cursor.next_char().unwrap()))
+                            Err(self
+                                .cursor
+                                .next_char()
+                                .map_or(XmlError::UnexpectedEof, UnexpectedCharacter))
                         }
                     } else {
                         self.parse_characters()
@@ -1051,16 +1472,18 @@ impl<'a> InternalXmlParser<'a> for EntityParser<'a> {
         self.state.stack.push(tag.to_string())
     }
 
-    fn attributes_push(&mut self, name: &'a str, value: Cow<'a, str>) {
-        self.state
-            .attributes
-            .push(Attribute::new(name.to_string(), value.into_owned()))
+    fn attributes_push(&mut self, attr: Attribute<'a>) {
+        self.state.attributes.push(attr.into_owned())
     }
 
     fn stack_pop(&mut self) -> Option<Cow<'a, str>> {
         self.state.stack.pop().map(|tag| tag.into())
     }
 
+    fn stack_top(&self) -> Option<&str> {
+        self.state.stack.last().map(|tag| tag.as_str())
+    }
+
     fn set_version(&mut self, version: String) {
         self.state.version = Some(version);
     }
@@ -1082,6 +1505,14 @@ impl<'a> InternalXmlParser<'a> for EntityParser<'a> {
         self.state.attributes.iter().any(|attr| attr.name == name)
     }
 
+    fn attribute_value(&self, name: &str) -> Option<&str> {
+        self.state
+            .attributes
+            .iter()
+            .find(|attr| attr.name == name)
+            .map(|attr| attr.value())
+    }
+
     fn get_version(&self) -> Option<&str> {
         self.state.version.as_ref().map(|s| s as &str)
     }
@@ -1094,6 +1525,10 @@ impl<'a> InternalXmlParser<'a> for EntityParser<'a> {
         self.state.stack.is_empty() && self.state.seen_root
     }
 
+    fn is_characters_until_end(&self) -> bool {
+        true
+    }
+
     fn cursor(&self) -> Cursor<'a> {
         self.cursor
     }
@@ -1105,28 +1540,31 @@ impl<'a> EntityParser<'a> {
         self.state.attributes.clear();
         if self.state.empty {
             self.state.empty = false;
-            if let Some(name) = self.state.stack.pop() {
-                return Ok(Some(XmlEvent::etag(name)));
-            }
-            unreachable!()
+            let name = self
+                .state
+                .stack
+                .pop()
+                .ok_or(XmlError::ExpectedElementStart)?;
+            return Ok(Some(XmlEvent::etag(name)));
         }
 
-        let cursor = self.cursor();
-        while let Some(c) = cursor.next_byte(0) {
+        while let Some(c) = self.cursor().next_byte(0) {
+            let cursor = self.cursor();
             return match c {
                 b'<' => {
                     if let Some(c) = cursor.next_byte(1) {
                         if c == b'/' {
                             self.set_cursor(cursor.advance(2));
-                            self.parse_etag()
+                            match self.parse_etag(ctx) {
+                                // skipped end tag
+                                Ok(None) => continue,
+                                evt => evt,
+                            }
                         } else if c == b'?' {
-                            if self.is_prolog() && self.state.version.is_none() {
-                                // TODO: not correct
-                                if cursor.has_next_str("<?xml") {
-                                    self.parse_decl(ctx)
-                                } else {
-                                    self.parse_pi()
-                                }
+                            // the text declaration is only allowed at the very start of
+                            // the replacement text
+                            if cursor.offset() == 0 && is_decl_start(cursor) {
+                                self.parse_decl(ctx)
                             } else {
                                 self.parse_pi()
                             }
@@ -1134,7 +1572,7 @@ impl<'a> EntityParser<'a> {
                             if cursor.has_next_str("<!--") {
                                 self.parse_comment()
                             } else if cursor.has_next_str("<!DOCTYPE") {
-                                self.parse_doctypedecl()
+                                self.parse_doctypedecl(ctx)
                             } else if cursor.has_next_str("<![CDATA[") {
                                 self.parse_cdata()
                             } else {
@@ -1142,13 +1580,17 @@ impl<'a> EntityParser<'a> {
                             }
                         } else {
                             self.set_cursor(cursor.advance(1));
-                            self.parse_stag()
+                            self.parse_stag(ctx)
                         }
                     } else {
                         Err(XmlError::ExpectedElementStart)
                     }
                 }
-                b'&' => self.parse_reference(ctx),
+                b'&' => match self.parse_reference(ctx) {
+                    // skipped reference
+                    Ok(None) if ctx.next_entity.is_none() => continue,
+                    evt => evt,
+                },
                 b'\r' => {
                     if let Some(evt) = self.parse_carriage_return() {
                         Ok(Some(evt))
@@ -1156,20 +1598,8 @@ impl<'a> EntityParser<'a> {
                         continue;
                     }
                 }
-                _ => {
-                    if self.state.stack.is_empty() {
-                        // only white space allowed
-                        if c.is_xml_whitespace() {
-                            let (_, cur) = SToken.parse(cursor)?;
-                            self.set_cursor(cur);
-                            continue;
-                        } else {
-                            Err(UnexpectedCharacter(cursor.next_char().unwrap()))
-                        }
-                    } else {
-                        self.parse_characters()
-                    }
-                }
+                // replacement text is content, so characters are allowed on top level
+                _ => self.parse_characters(),
             };
         }
 
@@ -1186,22 +1616,233 @@ impl<'a> EntityParser<'a> {
     }
 }
 
+/// Handling of references to undeclared entities in content
+///
+/// Only [`UnknownEntityPolicy::Error`] conforms to the XML specification, which requires a
+/// well-formedness error. The other policies accept documents which are not well-formed.
+/// References in attribute values always fail.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UnknownEntityPolicy {
+    /// Fail with [`XmlError::UnknownEntity`]
+    #[default]
+    Error,
+    /// Return the reference `&name;` literally as characters
+    ///
+    /// Not conforming: meant for pre-processing like templates, where the reference is
+    /// resolved later. The text is returned as a separate `Characters` event unless
+    /// character data is coalesced.
+    Passthrough,
+    /// Ignore the reference and record a warning
+    Skip,
+}
+
+/// Handling of whitespace-only text between markup
+///
+/// Whitespace is only insignificant when it is not adjacent to other character data, so
+/// whitespace in mixed content is always kept.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum InsignificantWhitespace {
+    /// Return it as `Characters`
+    #[default]
+    Keep,
+    /// Return it as `Whitespace`
+    Report,
+    /// Drop it
+    Skip,
+}
+
+/// Whitespace handling requested by `xml:space`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum XmlSpace {
+    /// Application's default whitespace handling
+    #[default]
+    Default,
+    /// Whitespace has to be preserved
+    Preserve,
+}
+
+/// Effective `xml:space` and `xml:lang` of an element which declares one of them
+struct XmlScope<'a> {
+    depth: usize,
+    space: XmlSpace,
+    lang: Option<Cow<'a, str>>,
+}
+
+/// State of a [`Reader`] between two events of the document entity, see [`Reader::suspend`]
+pub(crate) struct SuspendedReader {
+    offset: usize,
+    stack: Vec<Cow<'static, str>>,
+    seen_root: bool,
+    version: Option<String>,
+    ctx: DocumentContext,
+    depth: usize,
+    xml_scopes: Vec<XmlScope<'static>>,
+}
+
 struct DocumentContext {
     standalone: Option<bool>,
     version: Option<String>,
     entities: Entities,
     next_entity: Option<Rc<Entity>>,
+    /// Document references markup declarations which are not read
+    unread_markup_decls: bool,
+    unknown_entity_policy: UnknownEntityPolicy,
+    /// Emit character references of the document entity as `CharRef` events
+    preserve_char_refs: bool,
+    warnings: Vec<XmlError>,
+    /// Report recoverable errors as events instead of failing
+    recoverable: bool,
+    /// Recovered errors which are not yet returned
+    recovered: VecDeque<XmlError>,
+    /// Attribute definitions of the internal subset by element name
+    attribute_defs: BTreeMap<String, Vec<AttDef>>,
+    /// Keep the quote characters and the raw attribute region of start tags
+    verbatim: bool,
+    /// Check `#REQUIRED` and `#FIXED` attribute declarations
+    validate_attributes: bool,
+    /// Maximum number of bytes of replacement text of all expanded entities
+    expansion_limit: Option<usize>,
+    expanded: usize,
+}
+
+impl DocumentContext {
+    /// Whether the attribute is undeclared or declared as `CDATA`, which keeps its spaces
+    fn is_cdata_attribute(&self, element: &str, attribute: &str) -> bool {
+        self.attribute_defs
+            .get(element)
+            .and_then(|defs| defs.iter().find(|def| def.name == attribute))
+            .is_none_or(|def| def.att_type == AttType::CData)
+    }
+
+    /// Entities for references in attribute values, counted against the expansion limit
+    fn att_value_entities(&mut self) -> AttValueEntities<'_> {
+        AttValueEntities {
+            entities: &self.entities,
+            standalone: self.standalone == Some(true),
+            unread_markup_decls: self.unread_markup_decls,
+            expanded: &mut self.expanded,
+            expansion_limit: self.expansion_limit,
+        }
+    }
+}
+
+fn is_whitespace(s: &str) -> bool {
+    s.bytes().all(|c| c.is_xml_whitespace())
+}
+
+fn trim_xml_whitespace(s: &str) -> &str {
+    s.trim_matches(|c: char| c.is_xml_whitespace())
 }
 
 /// XML Pull Parser
 pub struct Reader<'a> {
     root_parser: DocumentParser<'a>,
     sub_parsers: Vec<EntityParserState>,
+    /// Offset of the entity reference in the input whose expansion `sub_parsers` parse
+    entity_reference: usize,
     ctx: DocumentContext,
+    coalesce: bool,
+    peeked: Option<XmlEvent<'a>>,
+    /// Offset of the first invalid UTF-8 sequence of the input
+    invalid_utf8: Option<usize>,
+    /// Encoding of the input detected by [`Reader::from_bytes`] which the parser can't read
+    unsupported_encoding: Option<&'static str>,
+    trim_whitespace: bool,
+    insignificant_whitespace: InsignificantWhitespace,
+    /// Events read ahead to decide whether whitespace is insignificant
+    buffered: VecDeque<XmlEvent<'a>>,
+    /// Last returned event was character data
+    in_text: bool,
+    /// Result read while recovering from an error, returned after the error
+    held: Option<Result<Option<XmlEvent<'a>>, XmlError>>,
+    max_depth: Option<usize>,
+    depth: usize,
+    empty_element_events: bool,
+    /// End tag of the last returned `EmptyElement` still has to be skipped
+    skip_empty_etag: bool,
+    interner: Option<Interner>,
+    /// Scopes of elements declaring `xml:space` or `xml:lang`, innermost last
+    xml_scopes: Vec<XmlScope<'a>>,
 }
 
 impl<'a> Reader<'a> {
     pub fn new(input: &'a str) -> Self {
+        Self::with_entities(input, Entities::default())
+    }
+
+    /// Create a reader for UTF-8 encoded bytes
+    ///
+    /// A UTF-8 byte order mark is skipped. Invalid UTF-8 is reported as [`XmlError::Decoding`]
+    /// first when the parser reaches it, so all events before the invalid sequence are still
+    /// emitted. Input detected as another encoding by [`encoding::detect`] is reported as
+    /// [`XmlError::UnsupportedEncoding`] and must be decoded first.
+    pub fn from_bytes(input: &'a [u8]) -> Self {
+        let detected = encoding::detect(input);
+        if !matches!(detected, DetectedEncoding::Utf8 { .. }) {
+            let mut reader = Self::new("");
+            reader.unsupported_encoding = Some(detected.name());
+            return reader;
+        }
+
+        let bom_len = detected.bom_len();
+        let (cursor, invalid_utf8) = Cursor::from_bytes(&input[bom_len..]);
+        let mut reader = Self::new(cursor.rest());
+        reader.invalid_utf8 = invalid_utf8.map(|offset| offset + bom_len);
+        reader
+    }
+
+    /// Continue a suspended reader in `input`, which starts at the offset it was suspended at
+    ///
+    /// Offsets of the reader and of invalid UTF-8 count from the start of the original input.
+    pub(crate) fn resume(input: &'a [u8], state: SuspendedReader) -> Self {
+        let (cursor, invalid_utf8) = Cursor::from_bytes(input);
+        let mut reader = Self::new("");
+        reader.root_parser.cursor = Cursor::with_offset(cursor.rest(), state.offset);
+        reader.invalid_utf8 = invalid_utf8.map(|offset| offset + state.offset);
+        reader.root_parser.seen_root = state.seen_root;
+        reader.root_parser.version = state.version;
+        reader.root_parser.stack = state.stack;
+        reader.ctx = state.ctx;
+        reader.depth = state.depth;
+        reader.xml_scopes = state.xml_scopes;
+        reader
+    }
+
+    /// Suspend the reader to continue at `offset` with [`Reader::resume`]
+    ///
+    /// Only valid between events of the document entity when no events are read ahead. The
+    /// events between `offset` and the cursor must be character data, which doesn't change
+    /// the state.
+    pub(crate) fn suspend(self, offset: usize) -> SuspendedReader {
+        debug_assert!(self.sub_parsers.is_empty() && !self.root_parser.empty);
+        debug_assert!(self.peeked.is_none() && self.buffered.is_empty() && self.held.is_none());
+        SuspendedReader {
+            offset,
+            stack: self
+                .root_parser
+                .stack
+                .into_iter()
+                .map(|name| Cow::Owned(name.into_owned()))
+                .collect(),
+            seen_root: self.root_parser.seen_root,
+            version: self.root_parser.version,
+            ctx: self.ctx,
+            depth: self.depth,
+            xml_scopes: self
+                .xml_scopes
+                .into_iter()
+                .map(|scope| XmlScope {
+                    depth: scope.depth,
+                    space: scope.space,
+                    lang: scope.lang.map(|lang| Cow::Owned(lang.into_owned())),
+                })
+                .collect(),
+        }
+    }
+
+    /// Create a reader which knows the given entities in addition to the ones declared in
+    /// the document
+    pub fn with_entities(input: &'a str, entities: Entities) -> Self {
         Self {
             root_parser: DocumentParser {
                 cursor: Cursor::new(input),
@@ -1212,15 +1853,215 @@ impl<'a> Reader<'a> {
                 stack: vec![],
             },
             sub_parsers: vec![],
+            entity_reference: 0,
             ctx: DocumentContext {
                 standalone: None,
                 version: None,
-                entities: Default::default(),
+                entities,
                 next_entity: None,
+                unread_markup_decls: false,
+                unknown_entity_policy: UnknownEntityPolicy::default(),
+                preserve_char_refs: false,
+                warnings: vec![],
+                recoverable: false,
+                recovered: VecDeque::new(),
+                attribute_defs: BTreeMap::new(),
+                verbatim: false,
+                validate_attributes: false,
+                expansion_limit: None,
+                expanded: 0,
+            },
+            coalesce: false,
+            peeked: None,
+            invalid_utf8: None,
+            unsupported_encoding: None,
+            trim_whitespace: false,
+            insignificant_whitespace: InsignificantWhitespace::default(),
+            buffered: VecDeque::new(),
+            in_text: false,
+            held: None,
+            max_depth: None,
+            depth: 0,
+            empty_element_events: false,
+            skip_empty_etag: false,
+            interner: None,
+            xml_scopes: vec![],
+        }
+    }
+
+    /// Merge consecutive character data into a single `Characters` event
+    ///
+    /// Text, CDATA sections, character references and expanded entities following each other
+    /// are returned as one event. The merged text stays borrowed when it consists of a single
+    /// borrowed run. Because the reader has to look ahead, [`Reader::top_name`],
+    /// [`Reader::unparsed`] and [`Reader::cursor_offset`] can already reflect the following
+    /// event while a merged `Characters` event is returned. A CDATA section without adjacent
+    /// character data is still returned as `CData`. An error after character data is returned
+    /// by the call following the merged text.
+    pub fn with_coalescing(mut self) -> Self {
+        self.coalesce = true;
+        self
+    }
+
+    /// Emit character references like `&#65;` as `CharRef` events instead of `Characters`
+    ///
+    /// References in the replacement text of entities are still expanded. A `CharRef`
+    /// interrupts the merging of character data.
+    pub fn with_preserved_char_refs(mut self) -> Self {
+        self.ctx.preserve_char_refs = true;
+        self
+    }
+
+    /// Keep the source form of attributes for lossless round-trips
+    ///
+    /// Every [`STag`] carries the source text between its name and the closing `>` or `/>`
+    /// in [`STag::raw_attrs`], and every attribute read from the source records its quote
+    /// character in [`Attribute::quote`]. The normalized names and values are still
+    /// available as usual.
+    pub fn with_verbatim(mut self) -> Self {
+        self.ctx.verbatim = true;
+        self
+    }
+
+    /// Check attributes against the `#REQUIRED` and `#FIXED` declarations of the internal
+    /// subset
+    ///
+    /// These are validity constraints, so by default only the declared defaults are added.
+    /// A missing required attribute fails with [`XmlError::MissingRequiredAttribute`] and a
+    /// value different from the fixed one with [`XmlError::FixedAttributeMismatch`].
+    pub fn with_attribute_validation(mut self) -> Self {
+        self.ctx.validate_attributes = true;
+        self
+    }
+
+    /// Report recoverable errors as [`XmlEvent::Error`] events and continue reading
+    ///
+    /// Recoverable errors are:
+    ///
+    /// * [`XmlError::WrongETagName`]: the end tag is skipped and the element stays open
+    /// * [`XmlError::NonUniqueAttribute`]: the repeated attribute is skipped
+    /// * [`XmlError::UnknownEntity`] with [`UnknownEntityPolicy::Error`]: the reference is
+    ///   skipped
+    ///
+    /// An error is returned before the event in which it was found. All other errors still
+    /// stop the reader.
+    pub fn recoverable(mut self) -> Self {
+        self.ctx.recoverable = true;
+        self
+    }
+
+    /// Return an empty element tag like `<x/>` as a single [`XmlEvent::EmptyElement`] instead
+    /// of a `STag` followed by an `ETag`
+    ///
+    /// The attributes of the element are available through [`Reader::attributes`] until the
+    /// next event is read.
+    pub fn with_empty_element_events(mut self) -> Self {
+        self.empty_element_events = true;
+        self
+    }
+
+    /// Share one allocation between equal names returned by [`Reader::intern`]
+    pub fn with_interner(mut self) -> Self {
+        self.interner = Some(Interner::new());
+        self
+    }
+
+    /// Set how whitespace-only text between markup is returned, default is to keep it as
+    /// `Characters`
+    pub fn with_insignificant_whitespace(mut self, policy: InsignificantWhitespace) -> Self {
+        self.insignificant_whitespace = policy;
+        self
+    }
+
+    /// Set how references to undeclared entities are handled, default is an error
+    ///
+    /// Any other policy than [`UnknownEntityPolicy::Error`] makes the reader non-conforming.
+    pub fn with_unknown_entity_policy(mut self, policy: UnknownEntityPolicy) -> Self {
+        self.ctx.unknown_entity_policy = policy;
+        self
+    }
+
+    /// Owned copy of a name, which is shared with earlier copies when the reader has an
+    /// interner
+    ///
+    /// Without an interner every call allocates.
+    pub fn intern(&mut self, name: &str) -> Rc<str> {
+        match &mut self.interner {
+            Some(interner) => interner.intern(name),
+            None => Rc::from(name),
+        }
+    }
+
+    /// Effective `xml:space` of the current element, inherited from its ancestors
+    ///
+    /// Values other than `default` and `preserve` are ignored.
+    pub fn xml_space(&self) -> XmlSpace {
+        self.xml_scopes
+            .last()
+            .map_or(XmlSpace::Default, |scope| scope.space)
+    }
+
+    /// Effective `xml:lang` of the current element, inherited from its ancestors
+    ///
+    /// An empty `xml:lang` removes the language of the ancestors.
+    pub fn xml_lang(&self) -> Option<&str> {
+        self.xml_scopes
+            .last()
+            .and_then(|scope| scope.lang.as_deref())
+    }
+
+    fn push_xml_scope(&mut self) {
+        let mut space = None;
+        let mut lang = None;
+        for attr in self.attributes() {
+            match &attr.name as &str {
+                "xml:space" => match &attr.value as &str {
+                    "default" => space = Some(XmlSpace::Default),
+                    "preserve" => space = Some(XmlSpace::Preserve),
+                    _ => {}
+                },
+                "xml:lang" => lang = Some(attr.value.clone()),
+                _ => {}
+            }
+        }
+        if space.is_none() && lang.is_none() {
+            return;
+        }
+
+        let parent = self.xml_scopes.last();
+        let scope = XmlScope {
+            depth: self.depth,
+            space: space.unwrap_or_else(|| parent.map_or(XmlSpace::Default, |p| p.space)),
+            lang: match lang {
+                Some(lang) if lang.is_empty() => None,
+                Some(lang) => Some(lang),
+                None => parent.and_then(|p| p.lang.clone()),
             },
+        };
+        self.xml_scopes.push(scope);
+    }
+
+    fn pop_xml_scope(&mut self) {
+        if matches!(self.xml_scopes.last(), Some(scope) if scope.depth == self.depth) {
+            self.xml_scopes.pop();
         }
     }
 
+    /// Problems which were ignored while reading
+    pub fn warnings(&self) -> &[XmlError] {
+        &self.ctx.warnings
+    }
+
+    /// Define a general entity with `value` as replacement text
+    pub fn register_entity(&mut self, name: &str, value: &str) {
+        self.ctx.entities.register(name, value);
+    }
+
+    /// All general entities known to the reader
+    pub fn entities(&self) -> &Entities {
+        &self.ctx.entities
+    }
+
     pub fn top_name(&self) -> Option<&str> {
         if let Some(parser) = self.sub_parsers.last() {
             if let Some(e) = parser.state.stack.last() {
@@ -1228,7 +2069,7 @@ impl<'a> Reader<'a> {
             }
         }
 
-        self.root_parser.stack.last().copied()
+        self.root_parser.stack.last().map(|name| name as &str)
     }
 
     pub fn top_name_cow(&self) -> Option<Cow<'a, str>> {
@@ -1238,10 +2079,7 @@ impl<'a> Reader<'a> {
             }
         }
 
-        self.root_parser
-            .stack
-            .last()
-            .map(|name| Cow::Borrowed(*name))
+        self.root_parser.stack.last().cloned()
     }
 
     pub fn attributes(&self) -> &[Attribute<'a>] {
@@ -1252,6 +2090,17 @@ impl<'a> Reader<'a> {
         }
     }
 
+    /// Attributes of the last start tag as namespace URI, local name and value
+    ///
+    /// `scope` holds the namespace declarations of the ancestors, see
+    /// [`STag::resolved_attributes`].
+    pub fn resolved_attributes<'s>(
+        &'s self,
+        scope: &'s NamespaceStack,
+    ) -> impl Iterator<Item = Result<ResolvedAttribute<'s>, XmlError>> + 's {
+        resolve_attributes(self.attributes(), scope)
+    }
+
     pub fn drain_attributes(&mut self) -> Vec<Attribute<'a>> {
         if let Some(parser) = self.sub_parsers.last_mut() {
             parser.drain_attributes()
@@ -1268,34 +2117,257 @@ impl<'a> Reader<'a> {
         }
     }
 
+    /// Byte offset of the parser position in the input
+    ///
+    /// While an entity is expanded, this is the offset of the `&name;` reference in the input
+    /// which started the expansion, also for references in replacement texts. The position in
+    /// the replacement text is returned by [`Reader::entity_offset`].
     pub fn cursor_offset(&self) -> usize {
-        if let Some(parser) = self.sub_parsers.last() {
-            parser.offset()
-        } else {
+        if self.sub_parsers.is_empty() {
             self.root_parser.offset()
+        } else {
+            self.entity_reference
         }
     }
 
+    /// Byte offset of the parser position in the replacement text of the innermost expanded
+    /// entity, `None` while no entity is expanded
+    pub fn entity_offset(&self) -> Option<usize> {
+        self.sub_parsers.last().map(|parser| parser.offset())
+    }
+
+    /// Number of bytes of the input parsed so far
+    ///
+    /// Replacement texts of entities are not counted, so it only grows during parsing.
+    pub fn consumed_len(&self) -> usize {
+        self.root_parser.offset()
+    }
+
+    /// Number of bytes of the input not parsed yet
+    pub fn remaining_len(&self) -> usize {
+        self.root_parser.cursor.rest().len()
+    }
+
     #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> Result<Option<XmlEvent<'a>>, XmlError> {
-        let evt = if let Some(parser) = self.sub_parsers.last_mut() {
+        if !self.trim_whitespace {
+            return self.next_significant();
+        }
+
+        loop {
+            match self.next_significant()? {
+                Some(XmlEvent::Characters(chars)) => {
+                    let chars = match chars {
+                        Cow::Borrowed(chars) => Cow::Borrowed(trim_xml_whitespace(chars)),
+                        Cow::Owned(chars) if trim_xml_whitespace(&chars).len() == chars.len() => {
+                            Cow::Owned(chars)
+                        }
+                        Cow::Owned(chars) => Cow::Owned(trim_xml_whitespace(&chars).to_string()),
+                    };
+                    if !chars.is_empty() {
+                        return Ok(Some(XmlEvent::Characters(chars)));
+                    }
+                }
+                evt => return Ok(evt),
+            }
+        }
+    }
+
+    fn next_significant(&mut self) -> Result<Option<XmlEvent<'a>>, XmlError> {
+        let evt = match self.buffered.pop_front() {
+            Some(evt) => Some(evt),
+            None if self.insignificant_whitespace == InsignificantWhitespace::Keep => {
+                self.next_coalesced()?
+            }
+            None => match self.next_coalesced()? {
+                Some(XmlEvent::Characters(chars)) if !self.in_text && is_whitespace(&chars) => {
+                    self.read_whitespace(chars)?
+                }
+                evt => evt,
+            },
+        };
+        self.in_text = matches!(
+            evt,
+            Some(XmlEvent::Characters(_) | XmlEvent::CData(_) | XmlEvent::CharRef(_))
+        );
+        // events can be read ahead, so the depth and scope follow the returned events
+        match &evt {
+            Some(XmlEvent::STag(_) | XmlEvent::EmptyElement(_)) => {
+                self.depth += 1;
+                if matches!(self.max_depth, Some(max_depth) if self.depth > max_depth) {
+                    return Err(XmlError::DepthLimitExceeded(self.depth));
+                }
+                self.push_xml_scope();
+            }
+            Some(XmlEvent::ETag(_)) => {
+                self.pop_xml_scope();
+                self.depth -= 1;
+            }
+            _ => {}
+        }
+        Ok(evt)
+    }
+
+    /// Read the rest of a character data run starting with whitespace
+    ///
+    /// The run is returned as is when it contains other character data.
+    fn read_whitespace(&mut self, chars: Cow<'a, str>) -> Result<Option<XmlEvent<'a>>, XmlError> {
+        let mut run = vec![chars];
+        let next = loop {
+            match self.next_coalesced()? {
+                Some(XmlEvent::Characters(more)) if is_whitespace(&more) => run.push(more),
+                next => break next,
+            }
+        };
+
+        let significant = matches!(
+            next,
+            Some(XmlEvent::Characters(_) | XmlEvent::CData(_) | XmlEvent::CharRef(_))
+        );
+        if significant {
+            self.buffered
+                .extend(run.into_iter().map(XmlEvent::Characters));
+            self.buffered.extend(next);
+            return Ok(self.buffered.pop_front());
+        }
+
+        match self.insignificant_whitespace {
+            InsignificantWhitespace::Skip => Ok(next),
+            _ => {
+                self.buffered.extend(next);
+                let mut run = run.into_iter();
+                let mut whitespace = run.next().unwrap_or_default();
+                for more in run {
+                    whitespace.to_mut().push_str(&more);
+                }
+                Ok(Some(XmlEvent::Whitespace(whitespace)))
+            }
+        }
+    }
+
+    fn next_coalesced(&mut self) -> Result<Option<XmlEvent<'a>>, XmlError> {
+        if !self.coalesce {
+            return self.next_event();
+        }
+
+        let evt = match self.peeked.take() {
+            Some(evt) => Some(evt),
+            None => self.next_event()?,
+        };
+        let (mut chars, mut cdata) = match evt {
+            Some(XmlEvent::Characters(chars)) => (chars, false),
+            Some(XmlEvent::CData(chars)) => (chars, true),
+            evt => return Ok(evt),
+        };
+        loop {
+            let next = match self.next_event() {
+                Ok(next) => next,
+                Err(err) => {
+                    // the merged text is returned first and the error with the next call
+                    self.held = Some(Err(err));
+                    None
+                }
+            };
+            match next {
+                Some(XmlEvent::Characters(more)) | Some(XmlEvent::CData(more)) => {
+                    cdata = false;
+                    if chars.is_empty() {
+                        chars = more;
+                    } else if !more.is_empty() {
+                        chars.to_mut().push_str(&more);
+                    }
+                }
+                next => {
+                    self.peeked = next;
+                    return Ok(Some(if cdata {
+                        XmlEvent::CData(chars)
+                    } else {
+                        XmlEvent::Characters(chars)
+                    }));
+                }
+            }
+        }
+    }
+
+    fn next_event(&mut self) -> Result<Option<XmlEvent<'a>>, XmlError> {
+        if self.skip_empty_etag {
+            self.skip_empty_etag = false;
+            self.read_event()?;
+            self.pop_xml_scope();
+            self.depth -= 1;
+        }
+        if let Some(err) = self.ctx.recovered.pop_front() {
+            return Ok(Some(XmlEvent::Error(err)));
+        }
+        let evt = match self.held.take() {
+            Some(evt) => evt,
+            None => self.read_event(),
+        };
+        if let Some(err) = self.ctx.recovered.pop_front() {
+            // errors are reported before the event in which they were recovered
+            self.held = Some(evt);
+            return Ok(Some(XmlEvent::Error(err)));
+        }
+        match evt? {
+            Some(XmlEvent::STag(stag)) if stag.empty && self.empty_element_events => {
+                self.skip_empty_etag = true;
+                Ok(Some(XmlEvent::EmptyElement(stag)))
+            }
+            evt => Ok(evt),
+        }
+    }
+
+    fn read_event(&mut self) -> Result<Option<XmlEvent<'a>>, XmlError> {
+        let evt = if let Some(parser) = self.sub_parsers.last_mut() {
             let mut tmp_parser = EntityParser::new(&mut parser.state, &parser.entity);
             let result = tmp_parser.next(&mut self.ctx);
-            return if result == Ok(None) {
-                self.sub_parsers.pop();
-                self.next()
-            } else {
-                result.map(|evt| evt.map(|evt| evt.into_owned()))
-            };
+            match result {
+                // a reference in the replacement text is expanded below
+                Ok(None) if self.ctx.next_entity.is_some() => Ok(None),
+                Ok(None) => {
+                    self.sub_parsers.pop();
+                    return self.read_event();
+                }
+                // references in replacement text are part of the expanded content
+                Ok(Some(XmlEvent::CharRef(char_ref))) => Ok(Some(XmlEvent::Characters(
+                    Cow::Owned(char_ref.character.to_string()),
+                ))),
+                result => {
+                    return result
+                        .map(|evt| evt.map(|evt| evt.into_owned()))
+                        .map_err(XmlError::into_final)
+                }
+            }
+        } else if let Some(encoding) = self.unsupported_encoding {
+            Err(XmlError::UnsupportedEncoding(encoding.to_string()))
         } else {
-            self.root_parser.next(&mut self.ctx)
+            let unparsed = self.root_parser.cursor.rest();
+            let evt = self.root_parser.next(&mut self.ctx);
+            self.check_decoding(unparsed, evt.map_err(XmlError::into_final))
         };
 
         match evt {
             Ok(None) => {
                 if let Some(entity) = self.ctx.next_entity.take() {
+                    // WFC: No Recursion
+                    if self
+                        .sub_parsers
+                        .iter()
+                        .any(|parser| parser.entity.name() == entity.name())
+                    {
+                        return Err(XmlError::RecursiveEntity(entity.name().to_string()));
+                    }
+                    self.ctx.expanded += entity.text().len();
+                    if matches!(self.ctx.expansion_limit, Some(limit) if self.ctx.expanded > limit)
+                    {
+                        return Err(XmlError::ExpansionLimitExceeded(self.ctx.expanded));
+                    }
+                    if self.sub_parsers.is_empty() {
+                        // the root parser is behind the reference `&name;`
+                        self.entity_reference = self.root_parser.offset() - entity.name().len() - 2;
+                    }
                     self.sub_parsers.push(EntityParserState::new(entity));
-                    self.next()
+                    self.read_event()
                 } else {
                     Ok(None)
                 }
@@ -1303,6 +2375,224 @@ impl<'a> Reader<'a> {
             evt => evt,
         }
     }
+
+    /// Replace the result with a decoding error when the parser needed the invalid input
+    fn check_decoding(
+        &self,
+        unparsed: &str,
+        evt: Result<Option<XmlEvent<'a>>, XmlError>,
+    ) -> Result<Option<XmlEvent<'a>>, XmlError> {
+        let offset = match self.invalid_utf8 {
+            Some(offset) => offset,
+            None => return evt,
+        };
+
+        let reached_invalid = match &evt {
+            Ok(None) => self.ctx.next_entity.is_none(),
+            Ok(Some(_)) => false,
+            Err(XmlError::UnexpectedEof | XmlError::OpenElementAtEof) => true,
+            Err(err) if is_complete_token_error(err) => false,
+            // markup or a reference at the cursor is cut off by the invalid sequence
+            Err(_) => match unparsed.as_bytes().first() {
+                Some(b'&') => !unparsed.contains(';'),
+                _ => !unparsed.contains('>'),
+            },
+        };
+        if reached_invalid {
+            Err(XmlError::Decoding(format!(
+                "invalid UTF-8 sequence at offset {}",
+                offset
+            )))
+        } else {
+            evt
+        }
+    }
+
+    /// Consume events until a start tag with the given name is found at any depth
+    ///
+    /// The name is compared including a possible namespace prefix and `"*"` matches any
+    /// element. Use [`crate::NsReader::advance_to_element`] to match by namespace URI. The
+    /// attributes of the found element are available through [`Reader::attributes`].
+    /// Returns `None` when the end of the document is reached.
+    pub fn advance_to_element(&mut self, name: &str) -> Result<Option<STag<'a>>, XmlError> {
+        while let Some(evt) = self.next()? {
+            if let XmlEvent::STag(stag) | XmlEvent::EmptyElement(stag) = evt {
+                if name == "*" || stag.name == name {
+                    return Ok(Some(stag));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Consume the events of the current element up to and including its end tag
+    ///
+    /// Called after a `STag`, nested elements are skipped with their content. The `ETag`
+    /// following the `STag` of an empty element tag is consumed as well, after an
+    /// `EmptyElement` there is nothing to skip.
+    pub fn skip_element(&mut self) -> Result<(), XmlError> {
+        if self.skip_empty_etag {
+            return Ok(());
+        }
+
+        let mut depth = 0usize;
+        loop {
+            match self.next()? {
+                Some(XmlEvent::STag(_)) => depth += 1,
+                Some(XmlEvent::ETag(_)) if depth == 0 => return Ok(()),
+                Some(XmlEvent::ETag(_)) => depth -= 1,
+                Some(_) => {}
+                None => return Err(XmlError::OpenElementAtEof),
+            }
+        }
+    }
+
+    /// Source text of the content of the current element up to its end tag
+    ///
+    /// Called after a `STag`, the returned slice is the content between the start tag and the
+    /// matching end tag as written in the input, without expanding references or normalizing
+    /// line endings. Nested elements are only balanced, comments and CDATA sections are
+    /// skipped. The end tag is consumed, so no `ETag` is returned for the element. An empty
+    /// element tag has empty content.
+    ///
+    /// The content of elements in the replacement text of entities can't be borrowed from the
+    /// input and fails with [`XmlError::ExpectedElementStart`], as does a call without an
+    /// open element.
+    pub fn read_inner_raw(&mut self) -> Result<&'a str, XmlError> {
+        if self.skip_empty_etag {
+            return Ok("");
+        }
+        // events read ahead belong to the content after the current event
+        if !self.sub_parsers.is_empty()
+            || self.peeked.is_some()
+            || !self.buffered.is_empty()
+            || self.held.is_some()
+        {
+            return Err(XmlError::ExpectedElementStart);
+        }
+        if self.root_parser.empty {
+            // the end tag of the empty element tag is still pending
+            self.next_event()?;
+            self.pop_xml_scope();
+            self.depth -= 1;
+            return Ok("");
+        }
+        let name = match self.root_parser.stack.last() {
+            Some(name) => name.clone(),
+            None => return Err(XmlError::ExpectedElementStart),
+        };
+
+        let content = self.root_parser.cursor;
+        let (len, cursor) = scan_inner_raw(content, &name)?;
+        self.root_parser.cursor = cursor;
+        self.root_parser.stack.pop();
+        self.root_parser.attributes.clear();
+        self.pop_xml_scope();
+        self.depth -= 1;
+        self.in_text = false;
+        Ok(&content.rest()[..len])
+    }
+
+    /// Iterate over the remaining events
+    ///
+    /// The iterator ends at the end of the document or after the first error. The reader stays
+    /// usable after the iterator is dropped.
+    pub fn events(&mut self) -> Events<'_, 'a> {
+        Events {
+            reader: self,
+            failed: false,
+        }
+    }
+
+    /// Iterate over the remaining events as events not borrowing from the input
+    ///
+    /// The attributes of start tags are moved into the returned [`STag`], so the events can be
+    /// sent to another thread or stored after the input is gone. The iterator ends at the end
+    /// of the document or after the first error.
+    pub fn into_owned_events(self) -> OwnedEvents<'a> {
+        OwnedEvents {
+            reader: self,
+            failed: false,
+        }
+    }
+}
+
+/// Iterator over the events of a [`Reader`], see [`Reader::events`]
+pub struct Events<'r, 'a> {
+    reader: &'r mut Reader<'a>,
+    failed: bool,
+}
+
+impl<'r, 'a> Iterator for Events<'r, 'a> {
+    type Item = Result<XmlEvent<'a>, XmlError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        match self.reader.next() {
+            Ok(evt) => evt.map(Ok),
+            Err(err) => {
+                self.failed = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+/// Iterator over owned events of a [`Reader`], see [`Reader::into_owned_events`]
+pub struct OwnedEvents<'a> {
+    reader: Reader<'a>,
+    failed: bool,
+}
+
+impl<'a> Iterator for OwnedEvents<'a> {
+    type Item = Result<XmlEvent<'static>, XmlError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        match self.reader.next() {
+            Ok(Some(XmlEvent::STag(mut stag))) => {
+                stag.attrs = self.reader.drain_attributes();
+                Some(Ok(XmlEvent::STag(stag.into_owned())))
+            }
+            Ok(Some(XmlEvent::EmptyElement(mut stag))) => {
+                stag.attrs = self.reader.drain_attributes();
+                Some(Ok(XmlEvent::EmptyElement(stag.into_owned())))
+            }
+            Ok(evt) => evt.map(|evt| Ok(evt.into_owned())),
+            Err(err) => {
+                self.failed = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+/// Read the whole document and collect its events
+///
+/// Stops at the first error, so any input either returns all events or an error. The
+/// attributes of start tags are moved into the returned [`STag`].
+pub fn parse_all(input: &str) -> Result<Vec<XmlEvent<'_>>, XmlError> {
+    let mut reader = Reader::new(input);
+    let mut events = vec![];
+    while let Some(evt) = reader.next()? {
+        events.push(match evt {
+            XmlEvent::STag(mut stag) => {
+                stag.attrs = reader.drain_attributes();
+                XmlEvent::STag(stag)
+            }
+            evt => evt,
+        });
+    }
+    Ok(events)
+}
+
+/// Like [`parse_all`], but returns events not borrowing from the input
+pub fn parse_all_owned(input: &str) -> Result<Vec<XmlEvent<'static>>, XmlError> {
+    Reader::new(input).into_owned_events().collect()
 }
 
 #[cfg(test)]
@@ -1367,6 +2657,124 @@ mod tests {
         }
     }
 
+    mod empty_element_events {
+        use crate::reader::{Reader, ReaderBuilder};
+        use crate::{Attribute, STag, XmlEvent};
+
+        fn empty_element(name: &'static str) -> XmlEvent<'static> {
+            XmlEvent::EmptyElement(STag {
+                name: name.into(),
+                empty: true,
+                attrs: vec![],
+                raw_attrs: None,
+            })
+        }
+
+        #[test]
+        fn single_event() {
+            let mut reader =
+                Reader::new("<r><a x='1'/><b></b><c/></r>").with_empty_element_events();
+            assert_evt!(Ok(Some(XmlEvent::stag("r", false))), reader);
+            assert_evt!(Ok(Some(empty_element("a"))), reader);
+            assert_eq!(&[Attribute::new("x", "1")], reader.attributes());
+            assert_evt!(Ok(Some(XmlEvent::stag("b", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("b"))), reader);
+            assert_evt!(Ok(Some(empty_element("c"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("r"))), reader);
+            assert_evt!(Ok(None), reader);
+        }
+
+        #[test]
+        fn root_element() {
+            let mut reader = Reader::new("<r/>").with_empty_element_events();
+            assert_evt!(Ok(Some(empty_element("r"))), reader);
+            assert_evt!(Ok(None), reader);
+        }
+
+        #[test]
+        fn in_entity() {
+            let mut reader = Reader::new("<!DOCTYPE r [<!ENTITY e '<x/>'>]><r>&e;<y/></r>")
+                .with_empty_element_events();
+            assert!(matches!(reader.next(), Ok(Some(XmlEvent::Dtd(_)))));
+            assert_evt!(Ok(Some(XmlEvent::stag("r", false))), reader);
+            assert_evt!(Ok(Some(empty_element("x"))), reader);
+            assert_evt!(Ok(Some(empty_element("y"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("r"))), reader);
+            assert_evt!(Ok(None), reader);
+        }
+
+        #[test]
+        fn depth() {
+            let mut reader = ReaderBuilder::new()
+                .empty_element_events(true)
+                .max_depth(Some(2))
+                .build("<a><b/><b/><b/></a>");
+            assert_evt!(Ok(Some(XmlEvent::stag("a", false))), reader);
+            for _ in 0..3 {
+                assert_evt!(Ok(Some(empty_element("b"))), reader);
+            }
+            assert_evt!(Ok(Some(XmlEvent::etag("a"))), reader);
+            assert_evt!(Ok(None), reader);
+        }
+
+        #[test]
+        fn into_owned_keeps_empty() {
+            assert_eq!(
+                XmlEvent::stag("e", true),
+                XmlEvent::stag(String::from("e"), true).into_owned()
+            );
+            assert_eq!(
+                empty_element("e"),
+                XmlEvent::EmptyElement(STag {
+                    name: String::from("e").into(),
+                    empty: true,
+                    attrs: vec![],
+                    raw_attrs: None,
+                })
+                .into_owned()
+            );
+        }
+    }
+
+    mod interner {
+        use std::rc::Rc;
+
+        use crate::reader::{Reader, ReaderBuilder};
+        use crate::XmlEvent;
+
+        #[test]
+        fn shared_names() {
+            let mut reader = Reader::new("<r><a x='1'/><a x='2'/></r>").with_interner();
+            let mut names = vec![];
+            while let Some(evt) = reader.next().unwrap() {
+                if let XmlEvent::STag(stag) = evt {
+                    names.push(reader.intern(&stag.name));
+                    for i in 0..reader.attributes().len() {
+                        let name = reader.attributes()[i].name.clone();
+                        names.push(reader.intern(&name));
+                    }
+                }
+            }
+            assert_eq!(
+                vec!["r", "a", "x", "a", "x"],
+                names.iter().map(|n| &**n).collect::<Vec<_>>()
+            );
+            assert!(Rc::ptr_eq(&names[1], &names[3]));
+            assert!(Rc::ptr_eq(&names[2], &names[4]));
+        }
+
+        #[test]
+        fn without_interner() {
+            let mut reader = ReaderBuilder::new().build("<r/>");
+            let a = reader.intern("a");
+            assert!(!Rc::ptr_eq(&a, &reader.intern("a")));
+
+            let mut reader = ReaderBuilder::new().intern_names(true).build("<r/>");
+            let a = reader.intern("a");
+            assert!(Rc::ptr_eq(&a, &reader.intern("a")));
+        }
+    }
+
     mod attributes {
         use crate::reader::Reader;
         use crate::{Attribute, XmlError, XmlEvent};
@@ -1497,6 +2905,35 @@ mod tests {
             let mut reader = Reader::new("<e a='&' />");
             assert_evt_matches!(Err(XmlError::IllegalNameStartChar('\'')), reader);
         }
+
+        #[test]
+        fn normalized_whitespace() {
+            let mut reader = Reader::new("<e a='\ta\r\nb&#x9;c\n' />");
+            assert_evt!(Ok(Some(XmlEvent::stag("e", true))), reader);
+            assert_eq!(&[Attribute::new("a", " a b\tc ")], reader.attributes());
+        }
+
+        #[test]
+        fn normalized_declared_type() {
+            let mut reader = Reader::new(
+                "<!DOCTYPE e [<!ATTLIST e t NMTOKENS #IMPLIED c CDATA #IMPLIED>]>\
+                 <e t='  a\n  b ' c='  a\n  b ' />",
+            );
+            assert_evt_matches!(Ok(Some(XmlEvent::Dtd(_))), reader);
+            assert_evt!(Ok(Some(XmlEvent::stag("e", true))), reader);
+            assert_eq!(
+                &[Attribute::new("t", "a b"), Attribute::new("c", "  a   b ")],
+                reader.attributes()
+            );
+        }
+
+        #[test]
+        fn declared_entity() {
+            let mut reader = Reader::new("<!DOCTYPE e [<!ENTITY x 'a&lt;b'>]><e a='&x;' />");
+            assert_evt_matches!(Ok(Some(XmlEvent::Dtd(_))), reader);
+            assert_evt!(Ok(Some(XmlEvent::stag("e", true))), reader);
+            assert_eq!(&[Attribute::new("a", "a<b")], reader.attributes());
+        }
     }
 
     mod etag {
@@ -1629,6 +3066,25 @@ mod tests {
             assert_evt!(Ok(Some(XmlEvent::etag("e"))), reader);
             assert_evt!(Ok(None), reader);
         }
+
+        #[test]
+        fn decl_after_comment() {
+            let mut reader = Reader::new("<!-- c --><?xml version='1.0'?><e/>");
+            assert_evt!(Ok(Some(XmlEvent::comment(" c "))), reader);
+            assert_evt!(Err(XmlError::InvalidPITarget), reader);
+        }
+
+        #[test]
+        fn decl_after_whitespace() {
+            let mut reader = Reader::new(" \n<?xml version='1.0'?><e/>");
+            assert_evt!(Err(XmlError::InvalidPITarget), reader);
+        }
+
+        #[test]
+        fn decl_after_bom() {
+            let mut reader = Reader::from_bytes(b"\xEF\xBB\xBF<?xml version='1.0'?><e/>");
+            assert_evt!(Ok(Some(XmlEvent::decl("1.0", None, None))), reader);
+        }
     }
 
     mod characters {
@@ -1841,6 +3297,53 @@ mod tests {
             let mut reader = Reader::new("<!-- B+, B, or B--->");
             assert_evt!(Err(XmlError::CommentColonColon), reader);
         }
+
+        #[test]
+        fn illegal_char() {
+            let mut reader = Reader::new("<e><!-- a\u{0}b --></e>");
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Err(XmlError::IllegalChar('\u{0}')), reader);
+        }
+
+        #[test]
+        fn illegal_char_outside_root() {
+            let mut reader = Reader::new("<!-- a\u{0}b --><e/>");
+            assert_evt!(Err(XmlError::IllegalChar('\u{0}')), reader);
+
+            let mut reader = Reader::new("<e/><!-- \u{1F} -->");
+            assert_evt!(Ok(Some(XmlEvent::stag("e", true))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("e"))), reader);
+            assert_evt!(Err(XmlError::IllegalChar('\u{1F}')), reader);
+        }
+
+        #[test]
+        fn illegal_char_in_doctype() {
+            let mut reader = Reader::new("<!DOCTYPE e [<!-- \u{0} -->]><e/>");
+            assert_evt!(Err(XmlError::IllegalChar('\u{0}')), reader);
+        }
+
+        #[test]
+        fn illegal_char_in_entity() {
+            let mut reader = Reader::new("<e>&x;</e>");
+            reader.register_entity("x", "<!-- \u{0} -->");
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Err(XmlError::IllegalChar('\u{0}')), reader);
+        }
+
+        #[test]
+        fn supplementary_char() {
+            let mut reader = Reader::new("<e><!-- \u{1F600}\u{10FFFF} --></e>");
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::comment(" \u{1F600}\u{10FFFF} "))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("e"))), reader);
+        }
+
+        #[test]
+        fn double_hyphen_in_content() {
+            let mut reader = Reader::new("<e><!-- a--b --></e>");
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Err(XmlError::CommentColonColon), reader);
+        }
     }
 
     mod pi {
@@ -1867,7 +3370,13 @@ mod tests {
         }
 
         #[test]
-        #[ignore]
+        fn illegal_char_in_data() {
+            let mut reader = Reader::new("<e><?target a\u{0}b?></e>");
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Err(XmlError::IllegalChar('\u{0}')), reader);
+        }
+
+        #[test]
         fn parse_pi_starting_with_xml_1() {
             let mut reader = Reader::new("<?xml-abc?>");
             assert_evt!(Ok(Some(XmlEvent::pi("xml-abc", None))), reader);
@@ -1895,27 +3404,83 @@ mod tests {
         }
 
         #[test]
-        fn invalid_target_name_1() {
-            let mut reader = Reader::new("<?xml version='1.0'?><?xml?>");
+        fn prolog_order() {
+            let mut reader = Reader::new(
+                "<?xml version='1.0'?>\n\
+                 <?xml-stylesheet href='a.xsl'?>\n\
+                 <!-- c1 -->\n\
+                 <?xml-model href='a.rng'?>\n\
+                 <!-- c2 --><?app?>\n\
+                 <e/>",
+            );
             assert_evt_matches!(Ok(Some(XmlEvent::XmlDecl(_))), reader);
-            assert_evt!(Err(XmlError::InvalidPITarget), reader);
+            assert_evt!(
+                Ok(Some(XmlEvent::pi(
+                    "xml-stylesheet",
+                    Some(Cow::Borrowed("href='a.xsl'"))
+                ))),
+                reader
+            );
+            assert_evt!(Ok(Some(XmlEvent::comment(" c1 "))), reader);
+            assert_evt!(
+                Ok(Some(XmlEvent::pi(
+                    "xml-model",
+                    Some(Cow::Borrowed("href='a.rng'"))
+                ))),
+                reader
+            );
+            assert_evt!(Ok(Some(XmlEvent::comment(" c2 "))), reader);
+            assert_evt!(Ok(Some(XmlEvent::pi("app", None))), reader);
+            assert_evt_matches!(Ok(Some(XmlEvent::STag(_))), reader);
+            assert_evt_matches!(Ok(Some(XmlEvent::ETag(_))), reader);
+            assert_evt!(Ok(None), reader);
         }
 
         #[test]
-        fn invalid_target_name_2() {
-            let mut reader = Reader::new("<?xml version='1.0'?><?XmL?>");
-            assert_evt_matches!(Ok(Some(XmlEvent::XmlDecl(_))), reader);
-            assert_evt!(Err(XmlError::InvalidPITarget), reader);
+        fn prolog_order_without_decl() {
+            let mut reader = Reader::new("<?xml-stylesheet?><!--c--><?xml-model?><e/>");
+            assert_evt!(Ok(Some(XmlEvent::pi("xml-stylesheet", None))), reader);
+            assert_evt!(Ok(Some(XmlEvent::comment("c"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::pi("xml-model", None))), reader);
+            assert_evt_matches!(Ok(Some(XmlEvent::STag(_))), reader);
         }
 
         #[test]
-        fn missing_end() {
-            let mut reader = Reader::new("<?e abc=gdsfh");
-            assert_evt!(Err(XmlError::ExpectToken("?>")), reader);
-        }
-    }
-
-    mod cdata {
+        fn xml_prefixed_targets() {
+            let mut reader = Reader::new("<?xml2 d?><?xmlé d?><e/>");
+            assert_evt!(
+                Ok(Some(XmlEvent::pi("xml2", Some(Cow::Borrowed("d"))))),
+                reader
+            );
+            assert_evt!(
+                Ok(Some(XmlEvent::pi("xmlé", Some(Cow::Borrowed("d"))))),
+                reader
+            );
+            assert_evt_matches!(Ok(Some(XmlEvent::STag(_))), reader);
+        }
+
+        #[test]
+        fn invalid_target_name_1() {
+            let mut reader = Reader::new("<?xml version='1.0'?><?xml?>");
+            assert_evt_matches!(Ok(Some(XmlEvent::XmlDecl(_))), reader);
+            assert_evt!(Err(XmlError::InvalidPITarget), reader);
+        }
+
+        #[test]
+        fn invalid_target_name_2() {
+            let mut reader = Reader::new("<?xml version='1.0'?><?XmL?>");
+            assert_evt_matches!(Ok(Some(XmlEvent::XmlDecl(_))), reader);
+            assert_evt!(Err(XmlError::InvalidPITarget), reader);
+        }
+
+        #[test]
+        fn missing_end() {
+            let mut reader = Reader::new("<?e abc=gdsfh");
+            assert_evt!(Err(XmlError::ExpectToken("?>")), reader);
+        }
+    }
+
+    mod cdata {
         use crate::reader::Reader;
         use crate::{XmlDecl, XmlError, XmlEvent};
 
@@ -1924,9 +3489,7 @@ mod tests {
             let mut reader = Reader::new("<e><![CDATA[<greeting>Hello, world!</greeting>]]></e>");
             assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
             assert_evt!(
-                Ok(Some(XmlEvent::characters(
-                    "<greeting>Hello, world!</greeting>"
-                ))),
+                Ok(Some(XmlEvent::cdata("<greeting>Hello, world!</greeting>"))),
                 reader
             );
             assert_evt!(Ok(Some(XmlEvent::etag("e"))), reader);
@@ -1937,7 +3500,7 @@ mod tests {
         fn pass2() {
             let mut reader = Reader::new("<e><![CDATA[]]]]></e>");
             assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
-            assert_evt!(Ok(Some(XmlEvent::characters("]]"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::cdata("]]"))), reader);
             assert_evt!(Ok(Some(XmlEvent::etag("e"))), reader);
             assert_evt!(Ok(None), reader);
         }
@@ -1946,7 +3509,7 @@ mod tests {
         fn pass3() {
             let mut reader = Reader::new("<e><![CDATA[[]]]></e>");
             assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
-            assert_evt!(Ok(Some(XmlEvent::characters("[]"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::cdata("[]"))), reader);
             assert_evt!(Ok(Some(XmlEvent::etag("e"))), reader);
             assert_evt!(Ok(None), reader);
         }
@@ -1955,11 +3518,28 @@ mod tests {
         fn pass4() {
             let mut reader = Reader::new("<e><![CDATA[]]></e>");
             assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
-            assert_evt!(Ok(Some(XmlEvent::characters(""))), reader);
+            assert_evt!(Ok(Some(XmlEvent::cdata(""))), reader);
             assert_evt!(Ok(Some(XmlEvent::etag("e"))), reader);
             assert_evt!(Ok(None), reader);
         }
 
+        #[test]
+        fn distinct_from_references() {
+            let mut reader = Reader::new("<e><![CDATA[x]]>&lt;</e>");
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::CData("x".into()))), reader);
+            assert_evt!(Ok(Some(XmlEvent::Characters("<".into()))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("e"))), reader);
+            assert_evt!(Ok(None), reader);
+        }
+
+        #[test]
+        fn illegal_char() {
+            let mut reader = Reader::new("<e><![CDATA[a\u{0}b]]></e>");
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Err(XmlError::IllegalChar('\u{0}')), reader);
+        }
+
         #[test]
         fn fail1() {
             let mut reader = Reader::new("<e><![CDATA[]></e>");
@@ -1969,6 +3549,8 @@ mod tests {
     }
 
     mod char_ref {
+        use std::borrow::Cow;
+
         use crate::reader::Reader;
         use crate::{XmlDecl, XmlError, XmlEvent};
 
@@ -1981,6 +3563,16 @@ mod tests {
             assert_evt!(Ok(None), reader);
         }
 
+        #[test]
+        fn ascii_char_is_borrowed() {
+            let mut reader = Reader::new("<e>&#x41;&#xE4;</e>");
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt_matches!(Ok(Some(XmlEvent::Characters(Cow::Borrowed("A")))), reader);
+            assert_evt_matches!(Ok(Some(XmlEvent::Characters(Cow::Owned(_)))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("e"))), reader);
+            assert_evt!(Ok(None), reader);
+        }
+
         #[test]
         fn pass_decimal() {
             let mut reader = Reader::new("<e>&#32;</e>");
@@ -2061,153 +3653,1701 @@ mod tests {
         }
     }
 
-    mod entity_replacement {
+    mod recoverable {
         use crate::reader::Reader;
-        use crate::{XmlDecl, XmlError, XmlEvent};
+        use crate::{Attribute, XmlError, XmlEvent};
 
         #[test]
-        fn replace_lt() {
-            let mut reader = Reader::new("<e>&lt;</e>");
+        fn multiple_errors() {
+            let mut reader = Reader::new("<a x='1' x='2'><b>&unknown;</c></b></a>").recoverable();
+            assert_evt!(
+                Ok(Some(XmlEvent::Error(XmlError::NonUniqueAttribute {
+                    attribute: "x".to_string()
+                }))),
+                reader
+            );
+            assert_evt!(Ok(Some(XmlEvent::stag("a", false))), reader);
+            assert_eq!(&[Attribute::new("x", "1")], reader.attributes());
+            assert_evt!(Ok(Some(XmlEvent::stag("b", false))), reader);
+            assert_evt!(
+                Ok(Some(XmlEvent::Error(XmlError::UnknownEntity(
+                    "unknown".to_string()
+                )))),
+                reader
+            );
+            assert_evt!(
+                Ok(Some(XmlEvent::Error(XmlError::WrongETagName {
+                    expected_name: "b".to_string()
+                }))),
+                reader
+            );
+            assert_evt!(Ok(Some(XmlEvent::etag("b"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("a"))), reader);
+            assert_evt!(Ok(None), reader);
+        }
+
+        #[test]
+        fn unrecoverable() {
+            let mut reader = Reader::new("<a></b><c").recoverable();
+            assert_evt!(Ok(Some(XmlEvent::stag("a", false))), reader);
+            assert_evt_matches!(Ok(Some(XmlEvent::Error(_))), reader);
+            assert_evt!(Err(XmlError::UnexpectedEof), reader);
+        }
+
+        #[test]
+        fn not_recoverable_by_default() {
+            let mut reader = Reader::new("<a></b>");
+            assert_evt!(Ok(Some(XmlEvent::stag("a", false))), reader);
+            assert_evt!(
+                Err(XmlError::WrongETagName {
+                    expected_name: "a".to_string()
+                }),
+                reader
+            );
+        }
+    }
+
+    mod insignificant_whitespace {
+        use crate::reader::{InsignificantWhitespace, Reader};
+        use crate::XmlEvent;
+
+        #[test]
+        fn skip() {
+            let mut reader = Reader::new("<a>\n  <b> x </b>\r\n</a>")
+                .with_insignificant_whitespace(InsignificantWhitespace::Skip);
+            assert_evt!(Ok(Some(XmlEvent::stag("a", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::stag("b", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters(" x "))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("b"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("a"))), reader);
+            assert_evt!(Ok(None), reader);
+        }
+
+        #[test]
+        fn report() {
+            let mut reader = Reader::new("<a>\r\n  <b/></a>")
+                .with_insignificant_whitespace(InsignificantWhitespace::Report);
+            assert_evt!(Ok(Some(XmlEvent::stag("a", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::Whitespace("\n  ".into()))), reader);
+            assert_evt!(Ok(Some(XmlEvent::stag("b", true))), reader);
+        }
+
+        #[test]
+        fn mixed_content() {
+            let mut reader = Reader::new("<a>x\r\n<b/> &amp;</a>")
+                .with_insignificant_whitespace(InsignificantWhitespace::Skip);
+            assert_evt!(Ok(Some(XmlEvent::stag("a", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("x"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("\n"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::stag("b", true))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("b"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters(" "))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("&"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("a"))), reader);
+        }
+
+        #[test]
+        fn cdata_is_significant() {
+            let mut reader = Reader::new("<a> <![CDATA[ ]]></a>")
+                .with_insignificant_whitespace(InsignificantWhitespace::Skip);
+            assert_evt!(Ok(Some(XmlEvent::stag("a", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters(" "))), reader);
+            assert_evt!(Ok(Some(XmlEvent::cdata(" "))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("a"))), reader);
+        }
+    }
+
+    mod preserved_char_refs {
+        use crate::reader::Reader;
+        use crate::XmlEvent;
+
+        #[test]
+        fn distinct_from_literal() {
+            let mut reader = Reader::new("<e>A&#65;&#x41;</e>").with_preserved_char_refs();
             assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
-            assert_evt!(Ok(Some(XmlEvent::characters("<"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("A"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::char_ref('A', "&#65;"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::char_ref('A', "&#x41;"))), reader);
             assert_evt!(Ok(Some(XmlEvent::etag("e"))), reader);
             assert_evt!(Ok(None), reader);
         }
 
         #[test]
-        fn replace_gt() {
-            let mut reader = Reader::new("<e>&gt;</e>");
+        fn radix() {
+            let mut reader = Reader::new("<e>&#65;&#x41;</e>").with_preserved_char_refs();
             assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
-            assert_evt!(Ok(Some(XmlEvent::characters(">"))), reader);
+            for hex in [false, true] {
+                match reader.next() {
+                    Ok(Some(XmlEvent::CharRef(char_ref))) => assert_eq!(hex, char_ref.is_hex()),
+                    evt => panic!("unexpected event {:?}", evt),
+                }
+            }
+        }
+
+        #[test]
+        fn expanded_by_default() {
+            let mut reader = Reader::new("<e>&#65;</e>");
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("A"))), reader);
+        }
+
+        #[test]
+        fn interrupts_coalescing() {
+            let mut reader = Reader::new("<e>a&#65;b</e>")
+                .with_coalescing()
+                .with_preserved_char_refs();
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("a"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::char_ref('A', "&#65;"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("b"))), reader);
+        }
+
+        #[test]
+        fn expanded_in_entities() {
+            let mut reader = Reader::new("<e>&lt;</e>").with_preserved_char_refs();
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("<"))), reader);
+        }
+    }
+
+    mod entity_replacement {
+        use crate::reader::{Entities, Reader, UnknownEntityPolicy};
+        use crate::{XmlDecl, XmlError, XmlEvent};
+
+        #[test]
+        fn nested_entity() {
+            let mut reader =
+                Reader::new("<!DOCTYPE e [<!ENTITY a 'x'><!ENTITY b '&a;<i>&a;</i>y'>]><e>&b;</e>");
+            assert_evt_matches!(Ok(Some(XmlEvent::Dtd(_))), reader);
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("x"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::stag("i", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("x"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("i"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("y"))), reader);
             assert_evt!(Ok(Some(XmlEvent::etag("e"))), reader);
             assert_evt!(Ok(None), reader);
         }
 
         #[test]
-        fn replace_amp() {
-            let mut reader = Reader::new("<e>&amp;</e>");
+        fn external_entity() {
+            let mut reader = Reader::new("<!DOCTYPE e [<!ENTITY x SYSTEM 'x.xml'>]><e>&x;</e>");
+            assert_evt_matches!(Ok(Some(XmlEvent::Dtd(_))), reader);
             assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
-            assert_evt!(Ok(Some(XmlEvent::characters("&"))), reader);
+            assert_evt!(Err(XmlError::UnknownEntity("x".to_string())), reader);
+        }
+
+        #[test]
+        fn nested_registered_entity() {
+            let mut reader = Reader::new("<e>&b;</e>");
+            reader.register_entity("a", "x");
+            reader.register_entity("b", "&a;&a;");
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("x"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("x"))), reader);
             assert_evt!(Ok(Some(XmlEvent::etag("e"))), reader);
             assert_evt!(Ok(None), reader);
         }
 
         #[test]
-        fn replace_apos() {
-            let mut reader = Reader::new("<e>&apos;</e>");
+        fn recursive_entity() {
+            let mut reader = Reader::new("<!DOCTYPE e [<!ENTITY a '&a;'>]><e>&a;</e>");
+            assert_evt_matches!(Ok(Some(XmlEvent::Dtd(_))), reader);
             assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
-            assert_evt!(Ok(Some(XmlEvent::characters("'"))), reader);
+            assert_evt!(Err(XmlError::RecursiveEntity("a".to_string())), reader);
+
+            let mut reader = Reader::new("<!DOCTYPE e [<!ENTITY a 'x&a;'>]><e>&a;</e>");
+            assert_evt_matches!(Ok(Some(XmlEvent::Dtd(_))), reader);
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("x"))), reader);
+            assert_evt!(Err(XmlError::RecursiveEntity("a".to_string())), reader);
+        }
+
+        #[test]
+        fn mutually_recursive_entities() {
+            let mut reader =
+                Reader::new("<!DOCTYPE e [<!ENTITY a '&b;'><!ENTITY b '&a;'>]><e>&a;</e>");
+            assert_evt_matches!(Ok(Some(XmlEvent::Dtd(_))), reader);
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Err(XmlError::RecursiveEntity("a".to_string())), reader);
+        }
+
+        #[test]
+        fn registered_entity() {
+            let mut reader = Reader::new("<e>a&nbsp;b</e>");
+            reader.register_entity("nbsp", "\u{A0}");
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("a"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("\u{A0}"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("b"))), reader);
             assert_evt!(Ok(Some(XmlEvent::etag("e"))), reader);
             assert_evt!(Ok(None), reader);
         }
 
         #[test]
-        fn replace_quot() {
-            let mut reader = Reader::new("<e>&quot;</e>");
+        fn html5_entities() {
+            let mut entities = Entities::default();
+            entities.register_html5();
+            assert_eq!(
+                Some("\u{2026}"),
+                entities.get_ref("hellip").map(|e| e.text())
+            );
+            assert_eq!(Some("&#60;"), entities.get_ref("lt").map(|e| e.text()));
+
+            let mut reader =
+                Reader::with_entities("<e>&hellip;&LT;&copy;</e>", entities).with_coalescing();
             assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
-            assert_evt!(Ok(Some(XmlEvent::characters("\""))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("\u{2026}<\u{A9}"))), reader);
             assert_evt!(Ok(Some(XmlEvent::etag("e"))), reader);
             assert_evt!(Ok(None), reader);
         }
 
         #[test]
-        fn fail_on_open() {
-            let mut reader = Reader::new("<e>&quot</e>");
+        fn unregistered_entity() {
+            let mut reader = Reader::new("<e>&nbsp;</e>");
             assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
-            assert_evt!(Err(XmlError::ExpectToken(";")), reader);
+            assert_evt!(Err(XmlError::UnknownEntity("nbsp".to_string())), reader);
         }
 
         #[test]
-        fn fail_on_unknown_entity() {
-            let mut reader = Reader::new("<e>&nent;</e>");
+        fn internal_subset_entity() {
+            let mut reader = Reader::new(
+                "<?xml version='1.0' standalone='yes'?><!DOCTYPE e [<!ENTITY x 'y'>]><e>&x;</e>",
+            );
+            assert_evt_matches!(Ok(Some(XmlEvent::XmlDecl(_))), reader);
+            assert_evt_matches!(Ok(Some(XmlEvent::Dtd(_))), reader);
             assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
-            assert_evt!(Err(XmlError::UnknownEntity("nent".to_string())), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("y"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("e"))), reader);
+            assert_evt!(Ok(None), reader);
         }
 
         #[test]
-        fn fail_on_open2() {
-            let mut reader = Reader::new("<e>&lt&gt;</e>");
+        fn first_declaration_is_binding() {
+            let mut reader = Reader::new("<!DOCTYPE e [<!ENTITY x 'y'><!ENTITY x 'z'>]><e>&x;</e>");
+            assert_evt_matches!(Ok(Some(XmlEvent::Dtd(_))), reader);
             assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
-            assert_evt!(Err(XmlError::ExpectToken(";")), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("y"))), reader);
         }
-    }
-
-    /// 4.3.3 Character Encoding in Entities
-    mod encoding {
-        use crate::reader::Reader;
-        use crate::{XmlDecl, XmlError, XmlEvent};
 
         #[test]
-        fn utf8_lower() {
-            let mut reader = Reader::new("<?xml version='1.0' encoding='utf-8'?><e/>");
+        fn standalone_external_entity() {
+            let mut entities = Entities::default();
+            entities.register_external("ext", "x");
+            let mut reader = Reader::with_entities(
+                "<?xml version='1.0' standalone='yes'?><e>&ext;</e>",
+                entities,
+            );
+            assert_evt_matches!(Ok(Some(XmlEvent::XmlDecl(_))), reader);
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
             assert_evt!(
-                Ok(Some(XmlEvent::decl("1.0", Option::Some("utf-8"), None))),
+                Err(XmlError::StandaloneViolation("ext".to_string())),
                 reader
             );
         }
 
         #[test]
-        fn utf8_upper() {
-            let mut reader = Reader::new("<?xml version='1.0' encoding='UTF-8'?><e/>");
+        fn not_standalone_external_entity() {
+            let mut entities = Entities::default();
+            entities.register_external("ext", "x");
+            let mut reader = Reader::with_entities(
+                "<?xml version='1.0' standalone='no'?><e>&ext;</e>",
+                entities,
+            );
+            assert_evt_matches!(Ok(Some(XmlEvent::XmlDecl(_))), reader);
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("x"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("e"))), reader);
+        }
+
+        #[test]
+        fn standalone_external_entity_in_attribute_value() {
+            let mut entities = Entities::default();
+            entities.register_external("ext", "x");
+            let mut reader = Reader::with_entities(
+                "<?xml version='1.0' standalone='yes'?><e a='&ext;'/>",
+                entities,
+            );
+            assert_evt_matches!(Ok(Some(XmlEvent::XmlDecl(_))), reader);
             assert_evt!(
-                Ok(Some(XmlEvent::decl("1.0", Option::Some("UTF-8"), None))),
+                Err(XmlError::StandaloneViolation("ext".to_string())),
                 reader
             );
         }
-    }
 
-    /// 2.11 End-of-Line Handling
-    mod end_of_line_handling {
-        use crate::reader::Reader;
-        use crate::{XmlDecl, XmlError, XmlEvent};
+        #[test]
+        fn standalone_undeclared_in_attribute_value() {
+            let mut reader = Reader::new(
+                "<?xml version='1.0' standalone='yes'?><!DOCTYPE e SYSTEM 'e.dtd'><e a='&x;'/>",
+            );
+            assert_evt_matches!(Ok(Some(XmlEvent::XmlDecl(_))), reader);
+            assert_evt_matches!(Ok(Some(XmlEvent::Dtd(_))), reader);
+            assert_evt!(Err(XmlError::StandaloneViolation("x".to_string())), reader);
+        }
 
         #[test]
-        fn passthrough_line_feed() {
-            let mut reader = Reader::new("<e>a\nb</e>");
+        fn standalone_undeclared_with_external_subset() {
+            let mut reader = Reader::new(
+                "<?xml version='1.0' standalone='yes'?><!DOCTYPE e SYSTEM 'e.dtd'><e>&x;</e>",
+            );
+            assert_evt_matches!(Ok(Some(XmlEvent::XmlDecl(_))), reader);
+            assert_evt_matches!(Ok(Some(XmlEvent::Dtd(_))), reader);
             assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
-            assert_evt!(Ok(Some(XmlEvent::characters("a\nb"))), reader);
-            assert_evt!(Ok(Some(XmlEvent::etag("e"))), reader);
-            assert_evt!(Ok(None), reader);
+            assert_evt!(Err(XmlError::StandaloneViolation("x".to_string())), reader);
+
+            let mut reader = Reader::new("<!DOCTYPE e SYSTEM 'e.dtd'><e>&x;</e>");
+            assert_evt_matches!(Ok(Some(XmlEvent::Dtd(_))), reader);
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Err(XmlError::UnknownEntity("x".to_string())), reader);
         }
 
         #[test]
-        fn convert_carriage_return() {
-            let mut reader = Reader::new("<e>a\rb</e>");
+        fn unknown_entity_error() {
+            let mut reader =
+                Reader::new("<e>a&x;b</e>").with_unknown_entity_policy(UnknownEntityPolicy::Error);
             assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
             assert_evt!(Ok(Some(XmlEvent::characters("a"))), reader);
-            assert_evt!(Ok(Some(XmlEvent::characters("\n"))), reader);
+            assert_evt!(Err(XmlError::UnknownEntity("x".to_string())), reader);
+        }
+
+        #[test]
+        fn unknown_entity_passthrough() {
+            let mut reader = Reader::new("<e>a&x;b</e>")
+                .with_unknown_entity_policy(UnknownEntityPolicy::Passthrough);
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("a"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("&x;"))), reader);
             assert_evt!(Ok(Some(XmlEvent::characters("b"))), reader);
             assert_evt!(Ok(Some(XmlEvent::etag("e"))), reader);
             assert_evt!(Ok(None), reader);
+            assert!(reader.warnings().is_empty());
         }
 
         #[test]
-        fn ignore_carriage_return_before_line_feed() {
-            let mut reader = Reader::new("<e>a\r\nb</e>");
+        fn unknown_entity_default() {
+            let mut reader = Reader::new("<e>&foo;</e>");
             assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
-            assert_evt!(Ok(Some(XmlEvent::characters("a"))), reader);
-            assert_evt!(Ok(Some(XmlEvent::characters("\nb"))), reader);
+            assert_evt!(Err(XmlError::UnknownEntity("foo".to_string())), reader);
+        }
+
+        #[test]
+        fn unknown_entity_passthrough_coalesced() {
+            let mut reader = Reader::new("<e>a &foo; b</e>")
+                .with_unknown_entity_policy(UnknownEntityPolicy::Passthrough)
+                .with_coalescing();
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("a &foo; b"))), reader);
             assert_evt!(Ok(Some(XmlEvent::etag("e"))), reader);
-            assert_evt!(Ok(None), reader);
         }
 
         #[test]
-        fn ignore_carriage_return_before_line_feed2() {
-            let mut reader = Reader::new("<e>a\r\n</e>");
+        fn unknown_entity_passthrough_in_attribute() {
+            let mut reader = Reader::new("<e a='&foo;'/>")
+                .with_unknown_entity_policy(UnknownEntityPolicy::Passthrough);
+            assert_evt!(Err(XmlError::UnknownEntity("foo".to_string())), reader);
+        }
+
+        #[test]
+        fn unknown_entity_skip() {
+            let mut reader = Reader::new("<e>a&x;&y;b</e>")
+                .with_unknown_entity_policy(UnknownEntityPolicy::Skip);
             assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
             assert_evt!(Ok(Some(XmlEvent::characters("a"))), reader);
-            assert_evt!(Ok(Some(XmlEvent::characters("\n"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("b"))), reader);
             assert_evt!(Ok(Some(XmlEvent::etag("e"))), reader);
             assert_evt!(Ok(None), reader);
+            assert_eq!(
+                &[
+                    XmlError::UnknownEntity("x".to_string()),
+                    XmlError::UnknownEntity("y".to_string())
+                ],
+                reader.warnings()
+            );
         }
 
         #[test]
-        fn ignore_carriage_return_before_line_feed3() {
-            let mut reader = Reader::new("<e>\r\n</e>");
+        fn replace_lt() {
+            let mut reader = Reader::new("<e>&lt;</e>");
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("<"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("e"))), reader);
+            assert_evt!(Ok(None), reader);
+        }
+
+        #[test]
+        fn replace_gt() {
+            let mut reader = Reader::new("<e>&gt;</e>");
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters(">"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("e"))), reader);
+            assert_evt!(Ok(None), reader);
+        }
+
+        #[test]
+        fn replace_amp() {
+            let mut reader = Reader::new("<e>&amp;</e>");
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("&"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("e"))), reader);
+            assert_evt!(Ok(None), reader);
+        }
+
+        #[test]
+        fn replace_apos() {
+            let mut reader = Reader::new("<e>&apos;</e>");
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("'"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("e"))), reader);
+            assert_evt!(Ok(None), reader);
+        }
+
+        #[test]
+        fn replace_quot() {
+            let mut reader = Reader::new("<e>&quot;</e>");
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("\""))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("e"))), reader);
+            assert_evt!(Ok(None), reader);
+        }
+
+        #[test]
+        fn fail_on_open() {
+            let mut reader = Reader::new("<e>&quot</e>");
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Err(XmlError::ExpectToken(";")), reader);
+        }
+
+        #[test]
+        fn fail_on_unknown_entity() {
+            let mut reader = Reader::new("<e>&nent;</e>");
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Err(XmlError::UnknownEntity("nent".to_string())), reader);
+        }
+
+        #[test]
+        fn fail_on_open2() {
+            let mut reader = Reader::new("<e>&lt&gt;</e>");
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Err(XmlError::ExpectToken(";")), reader);
+        }
+    }
+
+    /// 4.3.3 Character Encoding in Entities
+    mod encoding {
+        use crate::reader::Reader;
+        use crate::{XmlDecl, XmlError, XmlEvent};
+
+        #[test]
+        fn utf8_lower() {
+            let mut reader = Reader::new("<?xml version='1.0' encoding='utf-8'?><e/>");
+            assert_evt!(
+                Ok(Some(XmlEvent::decl("1.0", Option::Some("utf-8"), None))),
+                reader
+            );
+        }
+
+        #[test]
+        fn utf8_upper() {
+            let mut reader = Reader::new("<?xml version='1.0' encoding='UTF-8'?><e/>");
+            assert_evt!(
+                Ok(Some(XmlEvent::decl("1.0", Option::Some("UTF-8"), None))),
+                reader
+            );
+        }
+    }
+
+    /// 2.11 End-of-Line Handling
+    mod end_of_line_handling {
+        use crate::reader::Reader;
+        use crate::{XmlDecl, XmlError, XmlEvent};
+
+        #[test]
+        fn passthrough_line_feed() {
+            let mut reader = Reader::new("<e>a\nb</e>");
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("a\nb"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("e"))), reader);
+            assert_evt!(Ok(None), reader);
+        }
+
+        #[test]
+        fn convert_carriage_return() {
+            let mut reader = Reader::new("<e>a\rb</e>");
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("a"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("\n"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("b"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("e"))), reader);
+            assert_evt!(Ok(None), reader);
+        }
+
+        #[test]
+        fn ignore_carriage_return_before_line_feed() {
+            let mut reader = Reader::new("<e>a\r\nb</e>");
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("a"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("\nb"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("e"))), reader);
+            assert_evt!(Ok(None), reader);
+        }
+
+        #[test]
+        fn ignore_carriage_return_before_line_feed2() {
+            let mut reader = Reader::new("<e>a\r\n</e>");
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("a"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("\n"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("e"))), reader);
+            assert_evt!(Ok(None), reader);
+        }
+
+        #[test]
+        fn ignore_carriage_return_before_line_feed3() {
+            let mut reader = Reader::new("<e>\r\n</e>");
             assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
             assert_evt!(Ok(Some(XmlEvent::characters("\n"))), reader);
             assert_evt!(Ok(Some(XmlEvent::etag("e"))), reader);
             assert_evt!(Ok(None), reader);
         }
     }
+    mod advance_to_element {
+        use crate::reader::Reader;
+        use crate::{Attribute, XmlError, XmlEvent};
+
+        fn advance<'a>(
+            reader: &mut Reader<'a>,
+            name: &str,
+        ) -> Result<Option<XmlEvent<'a>>, XmlError> {
+            reader
+                .advance_to_element(name)
+                .map(|stag| stag.map(XmlEvent::STag))
+        }
+
+        #[test]
+        fn find_nested() {
+            let mut reader =
+                Reader::new("<root><a>text</a><b><target x='1'>content</target></b></root>");
+            assert_eq!(
+                Ok(Some(XmlEvent::stag("target", false))),
+                advance(&mut reader, "target")
+            );
+            assert_eq!(&[Attribute::new("x", "1")], reader.attributes());
+            assert_evt!(Ok(Some(XmlEvent::characters("content"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("target"))), reader);
+        }
+
+        #[test]
+        fn find_first() {
+            let mut reader = Reader::new("<root><target/><other/><target>2</target></root>");
+            assert_eq!(
+                Ok(Some(XmlEvent::stag("target", true))),
+                advance(&mut reader, "target")
+            );
+            assert_evt!(Ok(Some(XmlEvent::etag("target"))), reader);
+            assert_eq!(
+                Ok(Some(XmlEvent::stag("target", false))),
+                advance(&mut reader, "target")
+            );
+            assert_evt!(Ok(Some(XmlEvent::characters("2"))), reader);
+        }
+
+        #[test]
+        fn find_root() {
+            let mut reader = Reader::new("<?xml version='1.0'?><!--c--><target/>");
+            assert_eq!(
+                Ok(Some(XmlEvent::stag("target", true))),
+                advance(&mut reader, "target")
+            );
+        }
+
+        #[test]
+        fn prefixed_name() {
+            let mut reader = Reader::new("<root xmlns:ns='urn:x'><target/><ns:target/></root>");
+            assert_eq!(
+                Ok(Some(XmlEvent::stag("ns:target", true))),
+                advance(&mut reader, "ns:target")
+            );
+        }
+
+        #[test]
+        fn wildcard() {
+            let mut reader = Reader::new("<root><a/></root>");
+            assert_eq!(
+                Ok(Some(XmlEvent::stag("root", false))),
+                advance(&mut reader, "*")
+            );
+            assert_eq!(
+                Ok(Some(XmlEvent::stag("a", true))),
+                advance(&mut reader, "*")
+            );
+            assert_eq!(Ok(None), advance(&mut reader, "*"));
+        }
+
+        #[test]
+        fn not_found() {
+            let mut reader = Reader::new("<root><a/><b/></root>");
+            assert_eq!(Ok(None), advance(&mut reader, "target"));
+            assert_evt!(Ok(None), reader);
+        }
+    }
+    mod coalescing {
+        use std::borrow::Cow;
+
+        use crate::reader::Reader;
+        use crate::{XmlError, XmlEvent};
+
+        #[test]
+        fn char_refs() {
+            let mut reader = Reader::new("<e>test&#x20;seq</e>").with_coalescing();
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("test seq"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("e"))), reader);
+            assert_evt!(Ok(None), reader);
+        }
+
+        #[test]
+        fn cdata() {
+            let mut reader = Reader::new("<e>a<![CDATA[<b>]]>c<x/>d</e>").with_coalescing();
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("a<b>c"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::stag("x", true))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("x"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("d"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("e"))), reader);
+            assert_evt!(Ok(None), reader);
+        }
+
+        #[test]
+        fn lone_cdata() {
+            let mut reader = Reader::new("<e><![CDATA[<b>]]><x/></e>").with_coalescing();
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::cdata("<b>"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::stag("x", true))), reader);
+        }
+
+        #[test]
+        fn entities() {
+            let mut reader = Reader::new("<e>a&lt;&amp;b</e>").with_coalescing();
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("a<&b"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("e"))), reader);
+            assert_evt!(Ok(None), reader);
+        }
+
+        #[test]
+        fn line_endings() {
+            let mut reader = Reader::new("<e>a\r\nb\rc</e>").with_coalescing();
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("a\nb\nc"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("e"))), reader);
+            assert_evt!(Ok(None), reader);
+        }
+
+        #[test]
+        fn text_before_error() {
+            let mut reader = Reader::new("<e>a&#x20;&unknown;b</e>").with_coalescing();
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("a "))), reader);
+            assert_evt!(Err(XmlError::UnknownEntity("unknown".to_string())), reader);
+        }
+
+        #[test]
+        fn single_run_stays_borrowed() {
+            let mut reader = Reader::new("<e>text<![CDATA[]]></e>").with_coalescing();
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt_matches!(
+                Ok(Some(XmlEvent::Characters(Cow::Borrowed("text")))),
+                reader
+            );
+            assert_evt!(Ok(Some(XmlEvent::etag("e"))), reader);
+            assert_evt!(Ok(None), reader);
+        }
+
+        #[test]
+        fn attributes_of_following_element() {
+            let mut reader = Reader::new("<e>a<x k='v'/></e>").with_coalescing();
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("a"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::stag("x", true))), reader);
+            assert_eq!(&[crate::Attribute::new("k", "v")], reader.attributes());
+        }
+    }
+
+    mod multibyte {
+        use super::*;
+        use crate::parser::Parser;
+        use crate::reader::{CDataToken, CommentToken, NameToken, PIToken};
+        use crate::Cursor;
+
+        const TEXTS: [&str; 4] = ["😀", "中文", "é😀中", "a\u{EFFFF}b"];
+
+        #[test]
+        fn advance_char() {
+            let cursor = Cursor::new("😀a");
+            let (c, cursor) = cursor.advance_char().unwrap();
+            assert_eq!('😀', c);
+            assert_eq!(4, cursor.offset());
+            let (c, cursor) = cursor.advance_char().unwrap();
+            assert_eq!('a', c);
+            assert!(cursor.is_at_end());
+            assert_eq!(None, Cursor::new("").advance_char());
+        }
+
+        #[test]
+        fn tokens() {
+            for text in TEXTS {
+                let input = format!("{}>", text);
+                let (name, cursor) = NameToken.parse(Cursor::new(&input)).unwrap();
+                assert_eq!(text, name);
+                assert_eq!(">", cursor.rest());
+
+                let input = format!("<!--{}-->", text);
+                assert_eq!(text, CommentToken.parse(Cursor::new(&input)).unwrap().0);
+
+                let input = format!("<![CDATA[{}]]>", text);
+                assert_eq!(text, CDataToken.parse(Cursor::new(&input)).unwrap().0);
+
+                let input = format!("<?{} {}?>", text, text);
+                assert_eq!(
+                    (text, Some(text)),
+                    PIToken.parse(Cursor::new(&input)).unwrap().0
+                );
+            }
+        }
+
+        #[test]
+        fn document() {
+            for text in TEXTS {
+                let input = format!(
+                    "<{0} {0}='{0}'><!--{0}--><![CDATA[{0}]]><?{0} {0}?>{0}&#x1F600;{0}</{0}>",
+                    text
+                );
+                let mut reader = Reader::new(&input);
+                assert_evt!(Ok(Some(XmlEvent::stag(text, false))), reader);
+                assert_eq!(&[Attribute::new(text, text)], reader.attributes());
+                assert_evt!(Ok(Some(XmlEvent::comment(text))), reader);
+                assert_evt!(Ok(Some(XmlEvent::cdata(text))), reader);
+                assert_evt!(Ok(Some(XmlEvent::pi(text, Some(text.into())))), reader);
+                assert_evt!(Ok(Some(XmlEvent::characters(text))), reader);
+                assert_evt!(Ok(Some(XmlEvent::characters("😀"))), reader);
+                assert_evt!(Ok(Some(XmlEvent::characters(text))), reader);
+                assert_evt!(Ok(Some(XmlEvent::etag(text))), reader);
+                assert_evt!(Ok(None), reader);
+            }
+        }
+    }
+
+    mod parse_all {
+        use super::*;
+        use crate::reader::{parse_all, parse_all_owned};
+
+        #[test]
+        fn document() {
+            assert_eq!(
+                Ok(vec![
+                    XmlEvent::stag("a", false),
+                    XmlEvent::characters("t"),
+                    XmlEvent::etag("a"),
+                ]),
+                parse_all("<a>t</a>")
+            );
+        }
+
+        #[test]
+        fn owned() {
+            let input = String::from("<a/>");
+            let events = parse_all_owned(&input);
+            drop(input);
+            assert_eq!(
+                Ok(vec![XmlEvent::stag("a", true), XmlEvent::etag("a")]),
+                events
+            );
+        }
+
+        #[test]
+        fn attributes() {
+            let expected = vec![
+                XmlEvent::stag_with_attrs("a", false, [Attribute::new("x", "1")]),
+                XmlEvent::stag_with_attrs("b", true, [Attribute::new("y", "2")]),
+                XmlEvent::etag("b"),
+                XmlEvent::etag("a"),
+            ];
+            assert_eq!(Ok(expected.clone()), parse_all("<a x='1'><b y='2'/></a>"));
+            assert_eq!(Ok(expected), parse_all_owned("<a x='1'><b y='2'/></a>"));
+        }
+
+        #[test]
+        fn malformed() {
+            for input in [
+                "<",
+                "<a",
+                "<a></b>",
+                "<a>&#0;</a>",
+                "<?xml?>",
+                "<a/><b/>",
+                "]]>",
+            ] {
+                assert!(parse_all(input).is_err(), "{:?} should fail", input);
+                assert!(parse_all_owned(input).is_err(), "{:?} should fail", input);
+            }
+        }
+
+        #[test]
+        fn truncated_and_garbled() {
+            let document = "<?xml version='1.0'?>\
+                <!DOCTYPE r [<!ENTITY e '<b a=\"&#x1F600;\">中</b>'><!ATTLIST r x CDATA 'y'>]>\
+                <r a='é' b=\"&lt;\"><![CDATA[😀]]><!--中--><?pi 𝄞?>&e;&#233;\r\ntext</r>";
+            for (i, _) in document.char_indices() {
+                let _ = parse_all(&document[..i]);
+                for garbage in ["<", "&", "]]>", "😀", "\r", "'", "\u{0}"] {
+                    let _ = parse_all(&format!("{}{}{}", &document[..i], garbage, &document[i..]));
+                }
+            }
+        }
+    }
+
+    mod events {
+        use crate::reader::Reader;
+        use crate::{XmlError, XmlEvent};
+
+        #[test]
+        fn collect() {
+            let mut reader = Reader::new("<e>text</e>");
+            let events: Result<Vec<_>, _> = reader.events().collect();
+            assert_eq!(
+                Ok(vec![
+                    XmlEvent::stag("e", false),
+                    XmlEvent::characters("text"),
+                    XmlEvent::etag("e"),
+                ]),
+                events
+            );
+        }
+
+        #[test]
+        fn stops_after_error() {
+            let mut reader = Reader::new("<e></f>");
+            let events: Vec<_> = reader.events().collect();
+            assert_eq!(
+                vec![
+                    Ok(XmlEvent::stag("e", false)),
+                    Err(XmlError::WrongETagName {
+                        expected_name: "e".to_string()
+                    })
+                ],
+                events
+            );
+        }
+
+        #[test]
+        fn reader_stays_usable() {
+            let mut reader = Reader::new("<e><x/></e>");
+            assert_eq!(Some(Ok(XmlEvent::stag("e", false))), reader.events().next());
+            assert_evt!(Ok(Some(XmlEvent::stag("x", true))), reader);
+        }
+
+        #[test]
+        fn events_outlive_reader() {
+            let input = String::from("<e/>");
+            let events: Vec<_> = {
+                let mut reader = Reader::new(&input);
+                reader.events().collect()
+            };
+            assert_eq!(Ok(XmlEvent::stag("e", true)), events[0]);
+        }
+    }
+
+    mod builder {
+        use crate::reader::ReaderBuilder;
+        use crate::{XmlError, XmlEvent};
+
+        #[test]
+        fn defaults() {
+            let mut reader = ReaderBuilder::new().build("<e> a&amp;b </e>");
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters(" a"))), reader);
+        }
+
+        #[test]
+        fn trim_whitespace() {
+            let mut reader = ReaderBuilder::new()
+                .coalesce(true)
+                .trim_whitespace(true)
+                .build("<e>\n  <x> a&amp;b </x>\n</e>");
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::stag("x", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("a&b"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("x"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("e"))), reader);
+            assert_evt!(Ok(None), reader);
+        }
+
+        #[test]
+        fn max_depth() {
+            let mut reader = ReaderBuilder::new()
+                .max_depth(Some(2))
+                .build("<a><b><c/></b></a>");
+            assert_evt!(Ok(Some(XmlEvent::stag("a", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::stag("b", false))), reader);
+            assert_evt!(Err(XmlError::DepthLimitExceeded(3)), reader);
+        }
+
+        #[test]
+        fn max_depth_of_siblings() {
+            let mut reader = ReaderBuilder::new()
+                .max_depth(Some(2))
+                .build("<a><b/><b/></a>");
+            let events: Result<Vec<_>, _> = reader.events().collect();
+            assert!(events.is_ok());
+        }
+
+        #[test]
+        fn expansion_limit() {
+            let mut reader = ReaderBuilder::new()
+                .expansion_limit(Some(16))
+                .build("<!DOCTYPE e [<!ENTITY x '0123456789'>]><e>&x;&x;</e>");
+            assert_evt_matches!(Ok(Some(XmlEvent::Dtd(_))), reader);
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("0123456789"))), reader);
+            assert_evt!(Err(XmlError::ExpansionLimitExceeded(20)), reader);
+        }
+
+        #[test]
+        fn expansion_limit_in_attribute_value() {
+            let mut reader = ReaderBuilder::new().expansion_limit(Some(32)).build(
+                "<!DOCTYPE e [<!ENTITY x '0123456789'><!ENTITY y '&x;&x;'>]><e a='&y;&y;'/>",
+            );
+            assert_evt_matches!(Ok(Some(XmlEvent::Dtd(_))), reader);
+            assert_evt!(Err(XmlError::ExpansionLimitExceeded(42)), reader);
+        }
+    }
+
+    mod attribute_defaults {
+        use crate::reader::Reader;
+        use crate::{Attribute, XmlError, XmlEvent};
+
+        #[test]
+        fn default_value() {
+            let mut reader = Reader::new("<!DOCTYPE e [ <!ATTLIST e x CDATA \"def\"> ]><e/>");
+            assert_evt_matches!(Ok(Some(XmlEvent::Dtd(_))), reader);
+            assert_evt!(Ok(Some(XmlEvent::stag("e", true))), reader);
+            assert_eq!(&[Attribute::new("x", "def")], reader.attributes());
+        }
+
+        #[test]
+        fn explicit_value_wins() {
+            let mut reader =
+                Reader::new("<!DOCTYPE e [ <!ATTLIST e x CDATA 'def'> ]><e x='v'></e>");
+            assert_evt_matches!(Ok(Some(XmlEvent::Dtd(_))), reader);
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_eq!(&[Attribute::new("x", "v")], reader.attributes());
+        }
+
+        #[test]
+        fn implied() {
+            let mut reader = Reader::new("<!DOCTYPE e [ <!ATTLIST e x CDATA #IMPLIED> ]><e/>");
+            assert_evt_matches!(Ok(Some(XmlEvent::Dtd(_))), reader);
+            assert_evt!(Ok(Some(XmlEvent::stag("e", true))), reader);
+            assert!(reader.attributes().is_empty());
+        }
+
+        #[test]
+        fn required() {
+            let mut reader =
+                Reader::new("<!DOCTYPE e [ <!ATTLIST e x CDATA #REQUIRED> ]><e x='1'><e/></e>")
+                    .with_attribute_validation();
+            assert_evt_matches!(Ok(Some(XmlEvent::Dtd(_))), reader);
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(
+                Err(XmlError::MissingRequiredAttribute {
+                    attribute: "x".to_string()
+                }),
+                reader
+            );
+        }
+
+        #[test]
+        fn fixed() {
+            let mut reader = Reader::new(
+                "<!DOCTYPE e [ <!ATTLIST e x CDATA #FIXED '1'> ]><e><e x='1'/><e x='2'/></e>",
+            )
+            .with_attribute_validation();
+            assert_evt_matches!(Ok(Some(XmlEvent::Dtd(_))), reader);
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_eq!(&[Attribute::new("x", "1")], reader.attributes());
+            assert_evt!(Ok(Some(XmlEvent::stag("e", true))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("e"))), reader);
+            assert_evt!(
+                Err(XmlError::FixedAttributeMismatch {
+                    attribute: "x".to_string(),
+                    expected: "1".to_string()
+                }),
+                reader
+            );
+        }
+
+        #[test]
+        fn not_validated_by_default() {
+            let mut reader = Reader::new(
+                "<!DOCTYPE e [ <!ATTLIST e x CDATA #FIXED '1' y CDATA #REQUIRED> ]><e x='2'><e/></e>",
+            );
+            assert_evt_matches!(Ok(Some(XmlEvent::Dtd(_))), reader);
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_eq!(&[Attribute::new("x", "2")], reader.attributes());
+            assert_evt!(Ok(Some(XmlEvent::stag("e", true))), reader);
+            assert_eq!(&[Attribute::new("x", "1")], reader.attributes());
+        }
+
+        #[test]
+        fn first_declaration_is_binding() {
+            let mut reader = Reader::new(
+                "<!DOCTYPE e [ <!ATTLIST e x CDATA 'a'> <!ATTLIST e x CDATA 'b' y CDATA 'c'> ]><e/>",
+            );
+            assert_evt_matches!(Ok(Some(XmlEvent::Dtd(_))), reader);
+            assert_evt!(Ok(Some(XmlEvent::stag("e", true))), reader);
+            assert_eq!(
+                &[Attribute::new("x", "a"), Attribute::new("y", "c")],
+                reader.attributes()
+            );
+        }
+    }
+
+    mod from_bytes {
+        use std::borrow::Cow;
+
+        use crate::reader::Reader;
+        use crate::{XmlError, XmlEvent};
+
+        fn decoding_error(offset: usize) -> XmlError {
+            XmlError::Decoding(format!("invalid UTF-8 sequence at offset {}", offset))
+        }
+
+        #[test]
+        fn valid() {
+            let mut reader = Reader::from_bytes("<e>ä</e>".as_bytes());
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("ä"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("e"))), reader);
+            assert_evt!(Ok(None), reader);
+        }
+
+        #[test]
+        fn invalid_in_text() {
+            let mut reader = Reader::from_bytes(b"<e><a/>text\xff</e>");
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::stag("a", true))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("a"))), reader);
+            assert_evt!(Err(decoding_error(11)), reader);
+        }
+
+        #[test]
+        fn invalid_in_attribute() {
+            let mut reader = Reader::from_bytes(b"<e><a k='\xc3\x28'/></e>");
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Err(decoding_error(9)), reader);
+        }
+
+        #[test]
+        fn invalid_in_name() {
+            let mut reader = Reader::from_bytes(b"<e\xe2\x82/>");
+            assert_evt!(Err(decoding_error(2)), reader);
+        }
+
+        #[test]
+        fn utf8_bom() {
+            let mut reader = Reader::from_bytes(b"\xEF\xBB\xBF<e>\xff</e>");
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Err(decoding_error(6)), reader);
+        }
+
+        #[test]
+        fn utf16() {
+            let mut reader = Reader::from_bytes(b"\xFF\xFE<\0e\0/\0>\0");
+            assert_evt!(
+                Err(XmlError::UnsupportedEncoding("UTF-16LE".to_string())),
+                reader
+            );
+
+            let mut reader = Reader::from_bytes(b"\0<\0e\0/\0>");
+            assert_evt!(
+                Err(XmlError::UnsupportedEncoding("UTF-16BE".to_string())),
+                reader
+            );
+        }
+
+        #[test]
+        fn invalid_in_comment() {
+            let mut reader = Reader::from_bytes(b"<e><!-- a > b \x80 --></e>");
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Err(decoding_error(14)), reader);
+        }
+
+        #[test]
+        fn invalid_after_root() {
+            let mut reader = Reader::from_bytes(b"<e/>\n\xf0");
+            assert_evt!(Ok(Some(XmlEvent::stag("e", true))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("e"))), reader);
+            assert_evt!(Err(decoding_error(5)), reader);
+        }
+
+        #[test]
+        fn earlier_error_wins() {
+            let mut reader = Reader::from_bytes(b"<e></a>\xff");
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt_matches!(Err(XmlError::WrongETagName { .. }), reader);
+        }
+
+        #[test]
+        fn unknown_entity_before_invalid() {
+            let mut reader = Reader::from_bytes(b"<e>&bogus;\xff</e>");
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Err(XmlError::UnknownEntity("bogus".to_string())), reader);
+        }
+
+        #[test]
+        fn invalid_in_reference() {
+            let mut reader = Reader::from_bytes(b"<e>&bo\xffgus;</e>");
+            assert_evt!(Ok(Some(XmlEvent::stag("e", false))), reader);
+            assert_evt!(Err(decoding_error(6)), reader);
+        }
+    }
+
+    mod lengths {
+        use crate::reader::Reader;
+        use crate::XmlEvent;
+
+        #[test]
+        fn monotonic() {
+            let input = "<!DOCTYPE r [<!ENTITY e 'x <a>text</a> y'>]>\
+                         <r x='1'>&e;&lt;<!-- c --><?pi?><![CDATA[d]]></r>";
+            let mut reader = Reader::new(input);
+            assert_eq!(0, reader.consumed_len());
+            assert_eq!(input.len(), reader.remaining_len());
+
+            let mut consumed = 0;
+            while reader.next().unwrap().is_some() {
+                assert!(reader.consumed_len() >= consumed);
+                assert_eq!(input.len(), reader.consumed_len() + reader.remaining_len());
+                consumed = reader.consumed_len();
+            }
+            assert_eq!(input.len(), reader.consumed_len());
+            assert_eq!(0, reader.remaining_len());
+        }
+
+        #[test]
+        fn offsets_in_entity() {
+            let input = "<!DOCTYPE r [<!ENTITY e 'x <a>text</a> y'>]><r>&e;z</r>";
+            let reference = input.find("&e;").unwrap();
+            let mut reader = Reader::new(input);
+            while reader.next().unwrap() != Some(XmlEvent::stag("r", false)) {}
+            assert_eq!(None, reader.entity_offset());
+
+            assert_eq!(Some(XmlEvent::characters("x ")), reader.next().unwrap());
+            assert_eq!(reference, reader.cursor_offset());
+            assert_eq!(Some(2), reader.entity_offset());
+            assert_eq!(Some(XmlEvent::stag("a", false)), reader.next().unwrap());
+            assert_eq!(reference, reader.cursor_offset());
+            assert_eq!(Some(5), reader.entity_offset());
+            while reader.next().unwrap() != Some(XmlEvent::characters("z")) {
+                assert!(reader.cursor_offset() < input.len());
+            }
+            assert_eq!(None, reader.entity_offset());
+            assert_eq!(input.find("</r>").unwrap(), reader.cursor_offset());
+        }
+    }
+
+    mod truncated {
+        use super::*;
+        use crate::parser::Parser;
+        use crate::reader::{CDataToken, CommentToken, PIToken};
+        use crate::Cursor;
+
+        #[test]
+        fn tokens_need_more() {
+            for input in ["<!-- c", "<!-- c -"] {
+                let err = CommentToken.parse(Cursor::new(input)).unwrap_err();
+                assert_eq!(XmlError::need_more(), err);
+            }
+            let err = CDataToken.parse(Cursor::new("<![CDATA[x]]")).unwrap_err();
+            assert_eq!(XmlError::need_more(), err);
+            let err = PIToken.parse(Cursor::new("<?pi data?")).unwrap_err();
+            assert!(err.is_need_more());
+            assert_eq!(XmlError::ExpectToken("?>"), err.into_final());
+        }
+
+        #[test]
+        fn complete_token_is_final() {
+            let err = CommentToken
+                .parse(Cursor::new("<!-- a -- b -->"))
+                .unwrap_err();
+            assert_eq!(XmlError::CommentColonColon, err);
+            assert!(!err.is_need_more());
+        }
+
+        #[test]
+        fn comment() {
+            let mut reader = Reader::new("<a><!-- c");
+            assert_evt!(Ok(Some(XmlEvent::stag("a", false))), reader);
+            assert_evt!(Err(XmlError::UnexpectedEof), reader);
+        }
+
+        #[test]
+        fn cdata() {
+            let mut reader = Reader::new("<a><![CDATA[x]");
+            assert_evt!(Ok(Some(XmlEvent::stag("a", false))), reader);
+            assert_evt!(Err(XmlError::UnexpectedEof), reader);
+        }
+
+        #[test]
+        fn pi() {
+            let mut reader = Reader::new("<a><?pi data");
+            assert_evt!(Ok(Some(XmlEvent::stag("a", false))), reader);
+            assert_evt!(Err(XmlError::ExpectToken("?>")), reader);
+        }
+
+        #[test]
+        fn in_entity() {
+            let mut reader = Reader::new("<!DOCTYPE a [<!ENTITY e '<!-- c'>]><a>&e;</a>");
+            assert_evt_matches!(Ok(Some(XmlEvent::Dtd(_))), reader);
+            assert_evt!(Ok(Some(XmlEvent::stag("a", false))), reader);
+            assert_evt!(Err(XmlError::UnexpectedEof), reader);
+        }
+
+        #[test]
+        fn clean_end() {
+            let mut reader = Reader::new("<a/><!-- c -->");
+            assert_evt!(Ok(Some(XmlEvent::stag("a", true))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("a"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::comment(" c "))), reader);
+            assert_evt!(Ok(None), reader);
+        }
+    }
+
+    mod xml_scope {
+        use super::*;
+        use crate::reader::{InsignificantWhitespace, XmlSpace};
+        use crate::ReaderBuilder;
+
+        #[test]
+        fn inheritance() {
+            let mut reader = Reader::new("<a xml:space='preserve' xml:lang='en'><b><c/></b></a>");
+            assert_eq!(XmlSpace::Default, reader.xml_space());
+            assert_eq!(None, reader.xml_lang());
+            assert_evt!(Ok(Some(XmlEvent::stag("a", false))), reader);
+            assert_eq!(XmlSpace::Preserve, reader.xml_space());
+            assert_eq!(Some("en"), reader.xml_lang());
+            assert_evt!(Ok(Some(XmlEvent::stag("b", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::stag("c", true))), reader);
+            assert_eq!(XmlSpace::Preserve, reader.xml_space());
+            assert_eq!(Some("en"), reader.xml_lang());
+        }
+
+        #[test]
+        fn override_and_restore() {
+            let mut reader = Reader::new(
+                "<a xml:space='preserve' xml:lang='en'>\
+                 <b xml:space='default' xml:lang='de'/>\
+                 <c xml:lang=''/></a>",
+            );
+            assert_evt!(Ok(Some(XmlEvent::stag("a", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::stag("b", true))), reader);
+            assert_eq!(XmlSpace::Default, reader.xml_space());
+            assert_eq!(Some("de"), reader.xml_lang());
+            assert_evt!(Ok(Some(XmlEvent::etag("b"))), reader);
+            assert_eq!(XmlSpace::Preserve, reader.xml_space());
+            assert_eq!(Some("en"), reader.xml_lang());
+            assert_evt!(Ok(Some(XmlEvent::stag("c", true))), reader);
+            assert_eq!(XmlSpace::Preserve, reader.xml_space());
+            assert_eq!(None, reader.xml_lang());
+            assert_evt!(Ok(Some(XmlEvent::etag("c"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("a"))), reader);
+            assert_eq!(XmlSpace::Default, reader.xml_space());
+            assert_eq!(None, reader.xml_lang());
+        }
+
+        #[test]
+        fn invalid_space_is_inherited() {
+            let mut reader = Reader::new("<a xml:space='preserve'><b xml:space='keep'/></a>");
+            assert_evt!(Ok(Some(XmlEvent::stag("a", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::stag("b", true))), reader);
+            assert_eq!(XmlSpace::Preserve, reader.xml_space());
+        }
+
+        #[test]
+        fn empty_element_events() {
+            let mut reader =
+                Reader::new("<a><b xml:lang='en'/><c/></a>").with_empty_element_events();
+            assert_evt!(Ok(Some(XmlEvent::stag("a", false))), reader);
+            assert_evt_matches!(Ok(Some(XmlEvent::EmptyElement(_))), reader);
+            assert_eq!(Some("en"), reader.xml_lang());
+            assert_evt_matches!(Ok(Some(XmlEvent::EmptyElement(_))), reader);
+            assert_eq!(None, reader.xml_lang());
+        }
+
+        #[test]
+        fn coalesced_text_before_end_tag() {
+            let mut reader = ReaderBuilder::new()
+                .coalesce(true)
+                .build("<a xml:space='preserve'>x<![CDATA[y]]></a>");
+            assert_evt!(Ok(Some(XmlEvent::stag("a", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::characters("xy"))), reader);
+            assert_eq!(XmlSpace::Preserve, reader.xml_space());
+            assert_evt!(Ok(Some(XmlEvent::etag("a"))), reader);
+            assert_eq!(XmlSpace::Default, reader.xml_space());
+        }
+
+        #[test]
+        fn reported_whitespace_before_start_tag() {
+            let mut reader = ReaderBuilder::new()
+                .insignificant_whitespace(InsignificantWhitespace::Report)
+                .build("<a> <b xml:lang='en'> </b></a>");
+            assert_evt!(Ok(Some(XmlEvent::stag("a", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::Whitespace(" ".into()))), reader);
+            assert_eq!(None, reader.xml_lang());
+            assert_evt!(Ok(Some(XmlEvent::stag("b", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::Whitespace(" ".into()))), reader);
+            assert_eq!(Some("en"), reader.xml_lang());
+            assert_evt!(Ok(Some(XmlEvent::etag("b"))), reader);
+            assert_eq!(None, reader.xml_lang());
+        }
+    }
+
+    mod verbatim {
+        use super::*;
+        use crate::ReaderBuilder;
+
+        /// Write the events of a verbatim reader back as XML
+        fn round_trip(input: &str) -> String {
+            let mut reader = Reader::new(input).with_verbatim();
+            let mut output = String::new();
+            let mut empty = false;
+            while let Some(evt) = reader.next().unwrap() {
+                match evt {
+                    XmlEvent::STag(stag) => {
+                        output.push('<');
+                        output.push_str(stag.name());
+                        output.push_str(stag.raw_attributes().unwrap());
+                        output.push_str(if stag.empty() { "/>" } else { ">" });
+                        empty = stag.empty();
+                    }
+                    XmlEvent::ETag(_) if empty => empty = false,
+                    XmlEvent::ETag(etag) => {
+                        output.push_str("</");
+                        output.push_str(etag.name());
+                        output.push('>');
+                    }
+                    XmlEvent::Characters(text) | XmlEvent::Whitespace(text) => {
+                        output.push_str(&text)
+                    }
+                    XmlEvent::Comment(comment) => {
+                        output.push_str("<!--");
+                        output.push_str(&comment);
+                        output.push_str("-->");
+                    }
+                    evt => panic!("unexpected event {:?}", evt),
+                }
+            }
+            output
+        }
+
+        #[test]
+        fn round_trip_is_lossless() {
+            let input = "<root  a='1'\tb=\"two words\"\n  c = '\"' >\n\
+                <!-- c --><e x=\"1\"   /><f/><g y='&apos;'></g>text</root>";
+            assert_eq!(input, round_trip(input));
+        }
+
+        #[test]
+        fn quotes() {
+            let mut reader = Reader::new("<e a='1' b=\"2\"/>").with_verbatim();
+            assert_evt_matches!(Ok(Some(XmlEvent::STag(_))), reader);
+            let quotes: Vec<_> = reader
+                .attributes()
+                .iter()
+                .map(|attr| attr.quote())
+                .collect();
+            assert_eq!(vec![Some('\''), Some('"')], quotes);
+            assert_eq!("1", reader.attributes()[0].value());
+        }
+
+        #[test]
+        fn raw_region() {
+            let mut reader = Reader::new("<e a = 'x&amp;y' ><f/></e>").with_verbatim();
+            match reader.next() {
+                Ok(Some(XmlEvent::STag(stag))) => {
+                    assert_eq!(Some(" a = 'x&amp;y' "), stag.raw_attributes())
+                }
+                evt => panic!("unexpected event {:?}", evt),
+            }
+            assert_eq!("x&y", reader.attributes()[0].value());
+            match reader.next() {
+                Ok(Some(XmlEvent::STag(stag))) => assert_eq!(Some(""), stag.raw_attributes()),
+                evt => panic!("unexpected event {:?}", evt),
+            }
+        }
+
+        #[test]
+        fn off_by_default() {
+            let mut reader = Reader::new("<e a='1'/>");
+            match reader.next() {
+                Ok(Some(XmlEvent::STag(stag))) => assert_eq!(None, stag.raw_attributes()),
+                evt => panic!("unexpected event {:?}", evt),
+            }
+            assert_eq!(None, reader.attributes()[0].quote());
+        }
+
+        #[test]
+        fn defaulted_attribute_has_no_quote() {
+            let mut reader = ReaderBuilder::new()
+                .verbatim(true)
+                .build("<!DOCTYPE e [<!ATTLIST e d CDATA 'x'>]><e a='1'/>");
+            assert_evt_matches!(Ok(Some(XmlEvent::Dtd(_))), reader);
+            assert_evt_matches!(Ok(Some(XmlEvent::STag(_))), reader);
+            let quotes: Vec<_> = reader
+                .attributes()
+                .iter()
+                .map(|attr| attr.quote())
+                .collect();
+            assert_eq!(vec![Some('\''), None], quotes);
+        }
+    }
+
+    mod owned_events {
+        use super::*;
+        use std::sync::mpsc;
+        use std::thread;
+
+        fn assert_send<T: Send>() {}
+
+        #[test]
+        fn events_are_send() {
+            assert_send::<XmlEvent<'static>>();
+            assert_send::<crate::STag<'static>>();
+            assert_send::<XmlError>();
+        }
+
+        #[test]
+        fn attributes() {
+            let input = String::from("<a x='1'><b y='&amp;'/></a>");
+            let events: Result<Vec<_>, _> = Reader::new(&input).into_owned_events().collect();
+            drop(input);
+            assert_eq!(
+                Ok(vec![
+                    XmlEvent::stag_with_attrs("a", false, vec![Attribute::new("x", "1")]),
+                    XmlEvent::stag_with_attrs("b", true, vec![Attribute::new("y", "&")]),
+                    XmlEvent::etag("b"),
+                    XmlEvent::etag("a"),
+                ]),
+                events
+            );
+        }
+
+        #[test]
+        fn empty_element_events() {
+            let events: Result<Vec<_>, _> = Reader::new("<a x='1'/>")
+                .with_empty_element_events()
+                .into_owned_events()
+                .collect();
+            assert_eq!(
+                Ok(vec![XmlEvent::EmptyElement(crate::STag {
+                    name: "a".into(),
+                    empty: true,
+                    attrs: vec![Attribute::new("x", "1")],
+                    raw_attrs: None,
+                })]),
+                events
+            );
+        }
+
+        #[test]
+        fn send_to_consumer() {
+            let (sender, receiver) = mpsc::channel();
+            let producer = thread::spawn(move || {
+                let input = String::from("<a>text</a>");
+                for evt in Reader::new(&input).into_owned_events() {
+                    sender.send(evt).unwrap();
+                }
+            });
+            let events: Result<Vec<_>, _> = receiver.iter().collect();
+            producer.join().unwrap();
+            assert_eq!(
+                Ok(vec![
+                    XmlEvent::stag("a", false),
+                    XmlEvent::characters("text"),
+                    XmlEvent::etag("a"),
+                ]),
+                events
+            );
+        }
+
+        #[test]
+        fn stops_after_error() {
+            let mut events = Reader::new("<a></b>").into_owned_events();
+            assert_eq!(Some(Ok(XmlEvent::stag("a", false))), events.next());
+            assert!(matches!(events.next(), Some(Err(_))));
+            assert_eq!(None, events.next());
+        }
+    }
+
+    mod inner_raw {
+        use super::*;
+
+        #[test]
+        fn nested() {
+            let mut reader = Reader::new("<r><a x='1'>t<a>&amp;<b/></a><a/></a><c/></r>");
+            assert_evt!(Ok(Some(XmlEvent::stag("r", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::stag("a", false))), reader);
+            assert_eq!(Ok("t<a>&amp;<b/></a><a/>"), reader.read_inner_raw());
+            assert_evt!(Ok(Some(XmlEvent::stag("c", true))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("c"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("r"))), reader);
+            assert_evt!(Ok(None), reader);
+        }
+
+        #[test]
+        fn cdata_and_comments() {
+            let mut reader =
+                Reader::new("<a><![CDATA[</a><a>]]><!-- </a> --><?pi </a>?><b t='>'></b ></a >");
+            assert_evt!(Ok(Some(XmlEvent::stag("a", false))), reader);
+            assert_eq!(
+                Ok("<![CDATA[</a><a>]]><!-- </a> --><?pi </a>?><b t='>'></b >"),
+                reader.read_inner_raw()
+            );
+            assert_evt!(Ok(None), reader);
+        }
+
+        #[test]
+        fn empty_element() {
+            let mut reader = Reader::new("<r><a/><b/></r>");
+            assert_evt!(Ok(Some(XmlEvent::stag("r", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::stag("a", true))), reader);
+            assert_eq!(Ok(""), reader.read_inner_raw());
+            assert_evt!(Ok(Some(XmlEvent::stag("b", true))), reader);
+
+            let mut reader = Reader::new("<r><a/><b/></r>").with_empty_element_events();
+            assert_evt!(Ok(Some(XmlEvent::stag("r", false))), reader);
+            assert_evt_matches!(Ok(Some(XmlEvent::EmptyElement(_))), reader);
+            assert_eq!(Ok(""), reader.read_inner_raw());
+            assert_evt_matches!(Ok(Some(XmlEvent::EmptyElement(_))), reader);
+        }
+
+        #[test]
+        fn scope_and_depth() {
+            let mut reader = Reader::new("<r xml:lang='en'><a xml:lang='de'><a/></a><b/></r>");
+            assert_evt!(Ok(Some(XmlEvent::stag("r", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::stag("a", false))), reader);
+            assert_eq!(Some("de"), reader.xml_lang());
+            assert_eq!(Ok("<a/>"), reader.read_inner_raw());
+            assert_eq!(Some("en"), reader.xml_lang());
+            assert_eq!(Some("r"), reader.top_name());
+        }
+
+        #[test]
+        fn unclosed() {
+            let mut reader = Reader::new("<a><b></b>");
+            assert_evt!(Ok(Some(XmlEvent::stag("a", false))), reader);
+            assert_eq!(Err(XmlError::OpenElementAtEof), reader.read_inner_raw());
+
+            let mut reader = Reader::new("<a><!-- </a>");
+            assert_evt!(Ok(Some(XmlEvent::stag("a", false))), reader);
+            assert_eq!(Err(XmlError::UnexpectedEof), reader.read_inner_raw());
+        }
+
+        #[test]
+        fn wrong_end_tag() {
+            let mut reader = Reader::new("<a><b></b></c>");
+            assert_evt!(Ok(Some(XmlEvent::stag("a", false))), reader);
+            assert_eq!(
+                Err(XmlError::WrongETagName {
+                    expected_name: "a".to_string()
+                }),
+                reader.read_inner_raw()
+            );
+        }
+
+        #[test]
+        fn without_element() {
+            let mut reader = Reader::new("<a/>");
+            assert_eq!(Err(XmlError::ExpectedElementStart), reader.read_inner_raw());
+        }
+
+        #[test]
+        fn after_coalesced_text() {
+            let mut reader = Reader::new("<a>x<b/></a>").with_coalescing();
+            assert_evt!(Ok(Some(XmlEvent::stag("a", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::Characters("x".into()))), reader);
+            assert_eq!(Err(XmlError::ExpectedElementStart), reader.read_inner_raw());
+            assert_evt!(Ok(Some(XmlEvent::stag("b", true))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("b"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("a"))), reader);
+            assert_evt!(Ok(None), reader);
+        }
+
+        #[test]
+        fn after_recovered_error() {
+            let mut reader = Reader::new("<a>&e;<b/></a>").recoverable();
+            assert_evt!(Ok(Some(XmlEvent::stag("a", false))), reader);
+            assert_evt!(
+                Ok(Some(XmlEvent::Error(XmlError::UnknownEntity(
+                    "e".to_string()
+                )))),
+                reader
+            );
+            assert_eq!(Err(XmlError::ExpectedElementStart), reader.read_inner_raw());
+            assert_evt!(Ok(Some(XmlEvent::stag("b", true))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("b"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("a"))), reader);
+            assert_evt!(Ok(None), reader);
+        }
+    }
+
+    mod skip_element {
+        use super::*;
+
+        #[test]
+        fn nested_same_name() {
+            let mut reader = Reader::new("<r><a><a>text<a/></a><!--c--><b/></a><c/></r>");
+            assert_evt!(Ok(Some(XmlEvent::stag("r", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::stag("a", false))), reader);
+            assert_eq!(Ok(()), reader.skip_element());
+            assert_evt!(Ok(Some(XmlEvent::stag("c", true))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("c"))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("r"))), reader);
+            assert_evt!(Ok(None), reader);
+        }
+
+        #[test]
+        fn empty_element() {
+            let mut reader = Reader::new("<r><a/><b/></r>");
+            assert_evt!(Ok(Some(XmlEvent::stag("r", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::stag("a", true))), reader);
+            assert_eq!(Ok(()), reader.skip_element());
+            assert_evt!(Ok(Some(XmlEvent::stag("b", true))), reader);
+        }
+
+        #[test]
+        fn empty_element_events() {
+            let mut reader = Reader::new("<r><a/><b><c/></b><d/></r>").with_empty_element_events();
+            assert_evt!(Ok(Some(XmlEvent::stag("r", false))), reader);
+            assert_evt_matches!(Ok(Some(XmlEvent::EmptyElement(_))), reader);
+            assert_eq!(Ok(()), reader.skip_element());
+            assert_evt!(Ok(Some(XmlEvent::stag("b", false))), reader);
+            assert_eq!(Ok(()), reader.skip_element());
+            assert_evt_matches!(Ok(Some(XmlEvent::EmptyElement(_))), reader);
+            assert_evt!(Ok(Some(XmlEvent::etag("r"))), reader);
+        }
+
+        #[test]
+        fn error_in_skipped_content() {
+            let mut reader = Reader::new("<r><a><b></a></r>");
+            assert_evt!(Ok(Some(XmlEvent::stag("r", false))), reader);
+            assert_evt!(Ok(Some(XmlEvent::stag("a", false))), reader);
+            assert_eq!(
+                Err(XmlError::WrongETagName {
+                    expected_name: "b".to_string()
+                }),
+                reader.skip_element()
+            );
+        }
+    }
 }
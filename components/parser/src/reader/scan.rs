@@ -0,0 +1,210 @@
+//! Byte scanners for the hot loops of the reader
+//!
+//! With the `simd` feature enabled the scanners process 16 bytes at once on x86_64 using SSE2.
+//! The tail of the input and all other targets use the scalar implementation.
+
+use xrs_chars::XmlAsciiChar;
+
+use crate::reader::chars::is_ascii_content_byte;
+
+/// Count leading XML whitespace bytes
+#[inline]
+pub fn skip_whitespace(bytes: &[u8]) -> usize {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    {
+        sse2::skip_whitespace(bytes)
+    }
+    #[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+    {
+        scalar_skip_whitespace(bytes)
+    }
+}
+
+/// Count leading ASCII bytes which are character data without further processing
+///
+/// The scan stops at `<`, `&`, `\r`, control characters and non-ASCII bytes. The returned
+/// position is always on a char boundary.
+#[inline]
+pub fn skip_ascii_content(bytes: &[u8]) -> usize {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    {
+        sse2::skip_ascii_content(bytes)
+    }
+    #[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+    {
+        scalar_skip_ascii_content(bytes)
+    }
+}
+
+#[inline]
+fn scalar_skip_whitespace(bytes: &[u8]) -> usize {
+    bytes.iter().take_while(|c| c.is_xml_whitespace()).count()
+}
+
+#[inline]
+fn scalar_skip_ascii_content(bytes: &[u8]) -> usize {
+    bytes
+        .iter()
+        .take_while(|&&c| is_ascii_content_byte(c))
+        .count()
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod sse2 {
+    use core::arch::x86_64::{
+        __m128i, _mm_andnot_si128, _mm_cmpeq_epi8, _mm_cmpgt_epi8, _mm_loadu_si128,
+        _mm_movemask_epi8, _mm_or_si128, _mm_set1_epi8,
+    };
+
+    use super::{scalar_skip_ascii_content, scalar_skip_whitespace};
+
+    const BLOCK_SIZE: usize = 16;
+
+    /// Apply `stop_mask` to every full block and return the position of the first stop byte
+    #[inline(always)]
+    fn scan(
+        bytes: &[u8],
+        stop_mask: unsafe fn(__m128i) -> u32,
+        scalar: fn(&[u8]) -> usize,
+    ) -> usize {
+        let mut offset = 0;
+        while offset + BLOCK_SIZE <= bytes.len() {
+            // SAFETY: SSE2 is part of the x86_64 baseline and the load reads 16 bytes that are
+            // in bounds.
+            let mask =
+                unsafe { stop_mask(_mm_loadu_si128(bytes.as_ptr().add(offset) as *const __m128i)) };
+            if mask != 0 {
+                return offset + mask.trailing_zeros() as usize;
+            }
+            offset += BLOCK_SIZE;
+        }
+        offset + scalar(&bytes[offset..])
+    }
+
+    /// Bit mask of all bytes which are not `#x20 | #x9 | #xD | #xA`
+    #[inline(always)]
+    unsafe fn whitespace_stop_mask(v: __m128i) -> u32 {
+        let ws = _mm_or_si128(
+            _mm_or_si128(
+                _mm_cmpeq_epi8(v, _mm_set1_epi8(b' ' as i8)),
+                _mm_cmpeq_epi8(v, _mm_set1_epi8(b'\t' as i8)),
+            ),
+            _mm_or_si128(
+                _mm_cmpeq_epi8(v, _mm_set1_epi8(b'\n' as i8)),
+                _mm_cmpeq_epi8(v, _mm_set1_epi8(b'\r' as i8)),
+            ),
+        );
+        !(_mm_movemask_epi8(ws) as u32) & 0xFFFF
+    }
+
+    /// Bit mask of all bytes which are not `(#x9 | #xA | [#x20-#x7F]) - ('&' | '<')`
+    #[inline(always)]
+    unsafe fn content_stop_mask(v: __m128i) -> u32 {
+        // signed compare: bytes >= 0x80 are negative and therefore excluded
+        let printable = _mm_cmpgt_epi8(v, _mm_set1_epi8(0x1F));
+        let allowed = _mm_or_si128(
+            printable,
+            _mm_or_si128(
+                _mm_cmpeq_epi8(v, _mm_set1_epi8(b'\t' as i8)),
+                _mm_cmpeq_epi8(v, _mm_set1_epi8(b'\n' as i8)),
+            ),
+        );
+        let markup = _mm_or_si128(
+            _mm_cmpeq_epi8(v, _mm_set1_epi8(b'<' as i8)),
+            _mm_cmpeq_epi8(v, _mm_set1_epi8(b'&' as i8)),
+        );
+        let content = _mm_andnot_si128(markup, allowed);
+        !(_mm_movemask_epi8(content) as u32) & 0xFFFF
+    }
+
+    #[inline]
+    pub fn skip_whitespace(bytes: &[u8]) -> usize {
+        scan(bytes, whitespace_stop_mask, scalar_skip_whitespace)
+    }
+
+    #[inline]
+    pub fn skip_ascii_content(bytes: &[u8]) -> usize {
+        scan(bytes, content_stop_mask, scalar_skip_ascii_content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whitespace() {
+        assert_eq!(skip_whitespace(b""), 0);
+        assert_eq!(skip_whitespace(b"a"), 0);
+        assert_eq!(skip_whitespace(b" \t\r\n<"), 4);
+        assert_eq!(skip_whitespace(&[b' '; 40]), 40);
+    }
+
+    #[test]
+    fn ascii_content() {
+        assert_eq!(skip_ascii_content(b""), 0);
+        assert_eq!(skip_ascii_content(b"<a>"), 0);
+        assert_eq!(skip_ascii_content(b"abc&amp;"), 3);
+        assert_eq!(skip_ascii_content(b"0123456789abcdefghi\r\n"), 19);
+        assert_eq!(skip_ascii_content("0123456789abcdefä".as_bytes()), 16);
+    }
+
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    mod simd {
+        use super::super::*;
+
+        /// xorshift64, good enough to generate test input
+        struct Rng(u64);
+
+        impl Rng {
+            fn next(&mut self) -> u64 {
+                self.0 ^= self.0 << 13;
+                self.0 ^= self.0 >> 7;
+                self.0 ^= self.0 << 17;
+                self.0
+            }
+
+            fn bytes(&mut self, alphabet: &[u8]) -> Vec<u8> {
+                let len = (self.next() % 80) as usize;
+                (0..len)
+                    .map(|_| {
+                        // mostly boring bytes so that long runs appear
+                        if self.next() % 8 == 0 {
+                            self.next() as u8
+                        } else {
+                            alphabet[(self.next() % alphabet.len() as u64) as usize]
+                        }
+                    })
+                    .collect()
+            }
+        }
+
+        #[test]
+        fn whitespace_matches_scalar() {
+            let mut rng = Rng(0x2545_F491_4F6C_DD1D);
+            for _ in 0..20_000 {
+                let input = rng.bytes(b" \t\n\r");
+                assert_eq!(
+                    sse2::skip_whitespace(&input),
+                    scalar_skip_whitespace(&input),
+                    "input: {:?}",
+                    input
+                );
+            }
+        }
+
+        #[test]
+        fn ascii_content_matches_scalar() {
+            let mut rng = Rng(0x9E37_79B9_7F4A_7C15);
+            for _ in 0..20_000 {
+                let input = rng.bytes(b"abc xyz\t\n]>?!\x7F");
+                assert_eq!(
+                    sse2::skip_ascii_content(&input),
+                    scalar_skip_ascii_content(&input),
+                    "input: {:?}",
+                    input
+                );
+            }
+        }
+    }
+}
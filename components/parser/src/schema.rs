@@ -0,0 +1,545 @@
+//! Structural validation of element content without a DTD
+//!
+//! A [`Schema`] lists the allowed content of elements by name with the content models of
+//! element type declarations. [`validate_events`] checks the events of a [`Reader`] against
+//! it while they are read.
+//!
+//! ```
+//! use xrs_parser::dtd::{ContentParticle, ContentParticleEntry, ContentSpec, Repetition};
+//! use xrs_parser::schema::{validate_events, ElementRule, Schema};
+//! use xrs_parser::Reader;
+//!
+//! let name = |name: &str, repetition| ContentParticle {
+//!     entry: ContentParticleEntry::Name(name.to_string()),
+//!     repetition,
+//! };
+//! let schema = Schema::new(vec![ElementRule::new(
+//!     "book",
+//!     ContentSpec::Children(ContentParticle {
+//!         entry: ContentParticleEntry::Seq(vec![
+//!             name("title", Repetition::One),
+//!             name("author", Repetition::OneOrMore),
+//!         ]),
+//!         repetition: Repetition::One,
+//!     }),
+//! )]);
+//!
+//! let mut reader = Reader::new("<book><title/><author/><author/></book>");
+//! assert_eq!(Ok(()), validate_events(&mut reader, &schema));
+//! ```
+
+use alloc::collections::{BTreeMap, BTreeSet};
+
+use xrs_chars::XmlAsciiChar;
+
+use crate::dtd::{ContentParticle, ContentParticleEntry, ContentSpec, Repetition};
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use crate::{Reader, XmlError, XmlEvent};
+
+/// Allowed content of an element, same as in an element type declaration
+pub type ContentModel = ContentSpec;
+
+/// Content model of the elements with a name
+#[derive(Clone, Debug, PartialEq)]
+pub struct ElementRule {
+    pub name: String,
+    pub content: ContentModel,
+}
+
+impl ElementRule {
+    pub fn new(name: impl Into<String>, content: ContentModel) -> Self {
+        Self {
+            name: name.into(),
+            content,
+        }
+    }
+}
+
+/// Rules for the content of elements
+///
+/// The content of elements without a rule is not checked.
+#[derive(Clone, Debug, Default)]
+pub struct Schema {
+    rules: BTreeMap<String, (ContentModel, Option<Automaton>)>,
+}
+
+impl Schema {
+    pub fn new(rules: impl IntoIterator<Item = ElementRule>) -> Self {
+        let mut schema = Self::default();
+        for rule in rules {
+            schema.add_rule(rule);
+        }
+        schema
+    }
+
+    /// Add a rule, replacing an earlier rule for the same element
+    pub fn add_rule(&mut self, rule: ElementRule) {
+        let automaton = match &rule.content {
+            ContentSpec::Children(particle) => Some(Automaton::new(particle)),
+            _ => None,
+        };
+        self.rules.insert(rule.name, (rule.content, automaton));
+    }
+
+    pub fn rule(&self, name: &str) -> Option<&ContentModel> {
+        self.rules.get(name).map(|(content, _)| content)
+    }
+
+    fn automaton(&self, name: &str) -> Option<&Automaton> {
+        self.rules
+            .get(name)
+            .and_then(|(_, automaton)| automaton.as_ref())
+    }
+}
+
+/// Nondeterministic automaton accepting the child element names of element content
+///
+/// The states of a content particle are built like in Thompson's construction, so every
+/// state can still reach the accepting state.
+#[derive(Clone, Debug)]
+struct Automaton {
+    states: Vec<AutomatonState>,
+    accept: usize,
+}
+
+#[derive(Clone, Debug, Default)]
+struct AutomatonState {
+    /// Transition for a child element with the name
+    name: Option<(String, usize)>,
+    /// Transitions without a child element
+    epsilon: Vec<usize>,
+}
+
+impl Automaton {
+    /// The start state is state 0
+    fn new(particle: &ContentParticle) -> Self {
+        let mut automaton = Self {
+            states: vec![],
+            accept: 0,
+        };
+        let (_, accept) = automaton.add_particle(particle);
+        automaton.accept = accept;
+        automaton
+    }
+
+    fn add_state(&mut self) -> usize {
+        self.states.push(AutomatonState::default());
+        self.states.len() - 1
+    }
+
+    /// Add the states of a particle and return its start and end state
+    fn add_particle(&mut self, particle: &ContentParticle) -> (usize, usize) {
+        let start = self.add_state();
+        let (entry_start, entry_end) = self.add_entry(&particle.entry);
+        let end = self.add_state();
+        self.states[start].epsilon.push(entry_start);
+        self.states[entry_end].epsilon.push(end);
+        match particle.repetition {
+            Repetition::One => {}
+            Repetition::ZeroOrOne => self.states[start].epsilon.push(end),
+            Repetition::OneOrMore => self.states[entry_end].epsilon.push(entry_start),
+            Repetition::ZeroOrMore => {
+                self.states[start].epsilon.push(end);
+                self.states[entry_end].epsilon.push(entry_start);
+            }
+        }
+        (start, end)
+    }
+
+    fn add_entry(&mut self, entry: &ContentParticleEntry) -> (usize, usize) {
+        match entry {
+            ContentParticleEntry::Name(name) => {
+                let start = self.add_state();
+                let end = self.add_state();
+                self.states[start].name = Some((name.clone(), end));
+                (start, end)
+            }
+            ContentParticleEntry::Choice(particles) => {
+                let start = self.add_state();
+                let end = self.add_state();
+                for particle in particles {
+                    let (particle_start, particle_end) = self.add_particle(particle);
+                    self.states[start].epsilon.push(particle_start);
+                    self.states[particle_end].epsilon.push(end);
+                }
+                (start, end)
+            }
+            ContentParticleEntry::Seq(particles) => {
+                let start = self.add_state();
+                let mut end = start;
+                for particle in particles {
+                    let (particle_start, particle_end) = self.add_particle(particle);
+                    self.states[end].epsilon.push(particle_start);
+                    end = particle_end;
+                }
+                (start, end)
+            }
+        }
+    }
+
+    /// States reachable before the first child element
+    fn start(&self) -> BTreeSet<usize> {
+        self.closure(core::iter::once(0))
+    }
+
+    /// States reachable from `states` with a child element named `name`
+    fn advance(&self, states: &BTreeSet<usize>, name: &str) -> BTreeSet<usize> {
+        self.closure(
+            states
+                .iter()
+                .filter_map(|&state| match &self.states[state].name {
+                    Some((allowed, next)) if allowed == name => Some(*next),
+                    _ => None,
+                }),
+        )
+    }
+
+    fn closure(&self, states: impl Iterator<Item = usize>) -> BTreeSet<usize> {
+        let mut reachable = BTreeSet::new();
+        let mut todo: Vec<usize> = states.collect();
+        while let Some(state) = todo.pop() {
+            if reachable.insert(state) {
+                todo.extend(self.states[state].epsilon.iter().copied());
+            }
+        }
+        reachable
+    }
+}
+
+/// Violation of a [`Schema`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SchemaViolation {
+    /// Child element is not allowed at its position
+    UnexpectedElement { parent: String, name: String },
+    /// Element ends before its content is complete
+    MissingContent { name: String },
+    /// Character data in an element which does not allow it
+    UnexpectedText { name: String },
+}
+
+/// Error of [`validate_events`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SchemaError {
+    /// Document is not well-formed
+    Xml(XmlError),
+    /// Document violates the schema at the byte offset of the input
+    Invalid {
+        violation: SchemaViolation,
+        offset: usize,
+    },
+}
+
+impl From<XmlError> for SchemaError {
+    fn from(err: XmlError) -> Self {
+        SchemaError::Xml(err)
+    }
+}
+
+struct OpenElement<'s> {
+    name: String,
+    content: Option<&'s ContentModel>,
+    automaton: Option<&'s Automaton>,
+    /// Automaton states reachable with the previous children
+    states: BTreeSet<usize>,
+}
+
+impl<'s> OpenElement<'s> {
+    fn new(name: String, schema: &'s Schema) -> Self {
+        let automaton = schema.automaton(&name);
+        Self {
+            content: schema.rule(&name),
+            states: automaton.map(Automaton::start).unwrap_or_default(),
+            automaton,
+            name,
+        }
+    }
+
+    /// Check whether the element can contain `name` after the previous children
+    fn add_child(&mut self, name: &str) -> Result<(), SchemaViolation> {
+        let allowed = match self.content {
+            None | Some(ContentSpec::Any) => true,
+            Some(ContentSpec::Empty) | Some(ContentSpec::PCData) => false,
+            Some(ContentSpec::Mixed(names)) => names.iter().any(|allowed| allowed == name),
+            Some(ContentSpec::Children(_)) => match self.automaton {
+                Some(automaton) => {
+                    self.states = automaton.advance(&self.states, name);
+                    !self.states.is_empty()
+                }
+                None => true,
+            },
+        };
+        if allowed {
+            Ok(())
+        } else {
+            Err(SchemaViolation::UnexpectedElement {
+                parent: self.name.clone(),
+                name: name.to_string(),
+            })
+        }
+    }
+
+    fn add_text(&self, text: &str) -> Result<(), SchemaViolation> {
+        let allowed = match self.content {
+            Some(ContentSpec::Empty) => false,
+            // only white space is allowed between child elements
+            Some(ContentSpec::Children(_)) => text.bytes().all(|c| c.is_xml_whitespace()),
+            _ => true,
+        };
+        if allowed {
+            Ok(())
+        } else {
+            Err(SchemaViolation::UnexpectedText {
+                name: self.name.clone(),
+            })
+        }
+    }
+
+    fn end(&self) -> Result<(), SchemaViolation> {
+        let complete = match self.automaton {
+            Some(automaton) => self.states.contains(&automaton.accept),
+            None => true,
+        };
+        if complete {
+            Ok(())
+        } else {
+            Err(SchemaViolation::MissingContent {
+                name: self.name.clone(),
+            })
+        }
+    }
+}
+
+/// Read all events and check the content of the elements against the schema
+///
+/// Stops at the first violation and reports it with the offset of the event in which it was
+/// found: the start tag of an unexpected child, the end tag of an incomplete element or the
+/// unexpected character data.
+pub fn validate_events(reader: &mut Reader<'_>, schema: &Schema) -> Result<(), SchemaError> {
+    let mut stack: Vec<OpenElement<'_>> = vec![];
+    loop {
+        let offset = reader.consumed_len();
+        let evt = match reader.next()? {
+            Some(evt) => evt,
+            None => return Ok(()),
+        };
+        let invalid = |violation| SchemaError::Invalid { violation, offset };
+        // without an end tag event the element ends with its start tag
+        let is_empty_element = matches!(evt, XmlEvent::EmptyElement(_));
+
+        match evt {
+            XmlEvent::STag(stag) | XmlEvent::EmptyElement(stag) => {
+                if let Some(parent) = stack.last_mut() {
+                    parent.add_child(&stag.name).map_err(invalid)?;
+                }
+                let element = OpenElement::new(stag.name.into_owned(), schema);
+                if is_empty_element {
+                    element.end().map_err(invalid)?;
+                } else {
+                    stack.push(element);
+                }
+            }
+            XmlEvent::ETag(_) => {
+                if let Some(element) = stack.pop() {
+                    element.end().map_err(invalid)?;
+                }
+            }
+            XmlEvent::Characters(text) | XmlEvent::CData(text) => {
+                if let Some(element) = stack.last() {
+                    element.add_text(&text).map_err(invalid)?;
+                }
+            }
+            XmlEvent::CharRef(_) => {
+                // a reference is character data even when it references white space
+                if let Some(element) = stack.last() {
+                    element.add_text("&").map_err(invalid)?;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn name(name: &str, repetition: Repetition) -> ContentParticle {
+        ContentParticle {
+            entry: ContentParticleEntry::Name(name.to_string()),
+            repetition,
+        }
+    }
+
+    /// `book` contains `title` then `author+`, `author` and `title` only text
+    fn book_schema() -> Schema {
+        Schema::new(vec![
+            ElementRule::new(
+                "book",
+                ContentSpec::Children(ContentParticle {
+                    entry: ContentParticleEntry::Seq(vec![
+                        name("title", Repetition::One),
+                        name("author", Repetition::OneOrMore),
+                    ]),
+                    repetition: Repetition::One,
+                }),
+            ),
+            ElementRule::new("title", ContentSpec::PCData),
+            ElementRule::new("author", ContentSpec::PCData),
+        ])
+    }
+
+    fn validate(input: &str) -> Result<(), SchemaError> {
+        validate_events(&mut Reader::new(input), &book_schema())
+    }
+
+    #[test]
+    fn satisfied() {
+        assert_eq!(
+            Ok(()),
+            validate(
+                "<book>\n  <title>XML</title>\n  <author>A</author>\n  \
+                 <author>B</author>\n</book>"
+            )
+        );
+    }
+
+    #[test]
+    fn missing_child() {
+        let input = "<book><title>XML</title></book>";
+        assert_eq!(
+            Err(SchemaError::Invalid {
+                violation: SchemaViolation::MissingContent {
+                    name: "book".to_string()
+                },
+                offset: input.find("</book>").unwrap(),
+            }),
+            validate(input)
+        );
+    }
+
+    #[test]
+    fn extra_child() {
+        let input = "<book><title>XML</title><author>A</author><price/></book>";
+        assert_eq!(
+            Err(SchemaError::Invalid {
+                violation: SchemaViolation::UnexpectedElement {
+                    parent: "book".to_string(),
+                    name: "price".to_string(),
+                },
+                offset: input.find("<price/>").unwrap(),
+            }),
+            validate(input)
+        );
+    }
+
+    #[test]
+    fn wrong_order() {
+        let input = "<book><author>A</author><title>XML</title></book>";
+        assert_eq!(
+            Err(SchemaError::Invalid {
+                violation: SchemaViolation::UnexpectedElement {
+                    parent: "book".to_string(),
+                    name: "author".to_string(),
+                },
+                offset: input.find("<author>").unwrap(),
+            }),
+            validate(input)
+        );
+    }
+
+    #[test]
+    fn text_in_element_content() {
+        let input = "<book>text<title/><author/></book>";
+        assert_eq!(
+            Err(SchemaError::Invalid {
+                violation: SchemaViolation::UnexpectedText {
+                    name: "book".to_string()
+                },
+                offset: input.find("text").unwrap(),
+            }),
+            validate(input)
+        );
+    }
+
+    #[test]
+    fn element_in_text_content() {
+        let input = "<book><title>XML <b>1</b></title><author/></book>";
+        assert_eq!(
+            Err(SchemaError::Invalid {
+                violation: SchemaViolation::UnexpectedElement {
+                    parent: "title".to_string(),
+                    name: "b".to_string(),
+                },
+                offset: input.find("<b>").unwrap(),
+            }),
+            validate(input)
+        );
+    }
+
+    #[test]
+    fn empty_element_events() {
+        let mut reader = Reader::new("<book><title/></book>").with_empty_element_events();
+        assert!(matches!(
+            validate_events(&mut reader, &book_schema()),
+            Err(SchemaError::Invalid {
+                violation: SchemaViolation::MissingContent { .. },
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn repeated_choice() {
+        // (a | b)*, c
+        let schema = Schema::new(vec![ElementRule::new(
+            "r",
+            ContentSpec::Children(ContentParticle {
+                entry: ContentParticleEntry::Seq(vec![
+                    ContentParticle {
+                        entry: ContentParticleEntry::Choice(vec![
+                            name("a", Repetition::One),
+                            name("b", Repetition::One),
+                        ]),
+                        repetition: Repetition::ZeroOrMore,
+                    },
+                    name("c", Repetition::One),
+                ]),
+                repetition: Repetition::One,
+            }),
+        )]);
+        let validate = |input: &str| validate_events(&mut Reader::new(input), &schema);
+
+        let mut input = "<r>".to_string();
+        for _ in 0..1000 {
+            input.push_str("<a/><b/>");
+        }
+        input.push_str("<c/></r>");
+        assert_eq!(Ok(()), validate(&input));
+        assert_eq!(Ok(()), validate("<r><c/></r>"));
+        assert!(matches!(
+            validate("<r><a/><c/><a/></r>"),
+            Err(SchemaError::Invalid {
+                violation: SchemaViolation::UnexpectedElement { .. },
+                offset: 11,
+            })
+        ));
+        assert!(matches!(
+            validate("<r><a/></r>"),
+            Err(SchemaError::Invalid {
+                violation: SchemaViolation::MissingContent { .. },
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn not_well_formed() {
+        assert_eq!(
+            Err(SchemaError::Xml(XmlError::WrongETagName {
+                expected_name: "title".to_string()
+            })),
+            validate("<book><title></book>")
+        );
+    }
+}
@@ -1,10 +1,10 @@
 use crate::XmlError;
-use std::arch::x86_64::{
+use core::arch::x86_64::{
     __m128i, _mm_and_si128, _mm_cmpeq_epi8, _mm_load_si128, _mm_loadu_si128, _mm_movemask_epi8,
     _mm_set1_epi8, _mm_setr_epi8, _mm_shuffle_epi8, _mm_srli_epi32,
 };
-use std::mem::transmute;
-use std::ptr;
+use core::mem::transmute;
+use core::ptr;
 use xrs_chars::XmlAsciiChar;
 
 #[allow(non_camel_case_types)]
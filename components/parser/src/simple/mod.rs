@@ -1,13 +1,17 @@
-use std::borrow::Cow;
+use alloc::borrow::Cow;
+use alloc::collections::BTreeMap;
 
 use xrs_chars::{XmlAsciiChar, XmlChar};
 
+use crate::cow::CowStrBuilder;
 use crate::parser::core::optional;
 use crate::parser::Parser;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
 use crate::reader::chars::is_ascii_content_char;
 use crate::reader::{
-    AttValueToken, CDataToken, CharRefToken, CommentToken, EntityRefToken, EntityStrValueResolver,
-    EqToken, NameToken, PIToken, SToken, XmlDeclToken,
+    etag_name, AttValueToken, CDataToken, CharRefToken, CommentToken, EntityRefToken,
+    EntityStrValueResolver, EqToken, NameToken, PIToken, SToken, XmlDeclToken,
 };
 use crate::XmlError::{UnexpectedCharacter, UnexpectedEof};
 use crate::{Cursor, XmlDecl, XmlDtdError, XmlError, XmlErrorAtom};
@@ -95,6 +99,62 @@ impl<'i> StrVisitor<'i> for StringVisitor {
     }
 }
 
+/// Event returned by [`SimpleXmlParser::next`]
+#[derive(Clone, Debug, PartialEq)]
+pub enum SimpleEvent<'i> {
+    Decl(XmlDecl),
+    PI(&'i str, Option<&'i str>),
+    Comment(&'i str),
+    /// Start tag with the attributes in document order, an empty element tag is followed by
+    /// an `End` event
+    Start(&'i str, Vec<(Cow<'i, str>, Cow<'i, str>)>),
+    End(&'i str),
+    Chars(Cow<'i, str>),
+}
+
+/// Visitor creating [`SimpleEvent`]s
+struct SimpleEventVisitor;
+
+impl<'i> SimpleXmlVisitor<'i> for SimpleEventVisitor {
+    type Value = SimpleEvent<'i>;
+
+    fn visit_start_element<A: AttributeAccess<'i>>(
+        self,
+        tag: &'i str,
+        mut attrs: A,
+    ) -> Result<Self::Value, XmlError> {
+        let mut attrs_vec = vec![];
+        while let Some(entry) = attrs.next_entry(CowVisitor, CowVisitor)? {
+            attrs_vec.push(entry);
+        }
+        Ok(SimpleEvent::Start(tag, attrs_vec))
+    }
+
+    fn visit_end_element(self, tag: &'i str) -> Result<Self::Value, XmlError> {
+        Ok(SimpleEvent::End(tag))
+    }
+
+    fn visit_declaration(self, decl: XmlDecl) -> Result<Self::Value, XmlError> {
+        Ok(SimpleEvent::Decl(decl))
+    }
+
+    fn visit_characters(self, characters: &'i str) -> Result<Self::Value, XmlError> {
+        Ok(SimpleEvent::Chars(Cow::Borrowed(characters)))
+    }
+
+    fn visit_borrowed_characters(self, characters: &str) -> Result<Self::Value, XmlError> {
+        Ok(SimpleEvent::Chars(Cow::Owned(characters.to_string())))
+    }
+
+    fn visit_pi(self, target: &'i str, data: Option<&'i str>) -> Result<Self::Value, XmlError> {
+        Ok(SimpleEvent::PI(target, data))
+    }
+
+    fn visit_comment(self, comment: &'i str) -> Result<Self::Value, XmlError> {
+        Ok(SimpleEvent::Comment(comment))
+    }
+}
+
 /// Simple XML parser
 ///
 /// Does not support DTDs and only UTF-8 strings.
@@ -107,8 +167,36 @@ pub struct SimpleXmlParser<'i> {
     attribute_names: Vec<&'i str>,
     stack: Vec<&'i str>,
     version: Option<String>,
+    /// Replacement texts of the entities besides the predefined ones
+    entities: BTreeMap<String, String>,
+    /// An error was returned by the iterator
+    failed: bool,
 }
 
+/// Allowed constructs at the start of the document
+const XML_DECL_ATOMS: &[XmlErrorAtom] = &[
+    XmlErrorAtom::XmlDecl,
+    XmlErrorAtom::Comment,
+    XmlErrorAtom::PI,
+    XmlErrorAtom::Element,
+    XmlErrorAtom::Whitespace,
+];
+
+/// Allowed constructs before the root element, a document type declaration is not supported
+const PROLOGUE_ATOMS: &[XmlErrorAtom] = &[
+    XmlErrorAtom::Comment,
+    XmlErrorAtom::PI,
+    XmlErrorAtom::Element,
+    XmlErrorAtom::Whitespace,
+];
+
+/// Allowed constructs after the root element
+const EPILOGUE_ATOMS: &[XmlErrorAtom] = &[
+    XmlErrorAtom::Comment,
+    XmlErrorAtom::PI,
+    XmlErrorAtom::Whitespace,
+];
+
 pub enum ParserState {
     XmlDecl,
     Prologue,
@@ -125,13 +213,36 @@ impl<'i> SimpleXmlParser<'i> {
             attribute_names: vec![],
             stack: vec![],
             version: None,
+            entities: BTreeMap::new(),
+            failed: false,
         }
     }
 
+    /// Resolve the entities `entities` additionally to the predefined ones
+    ///
+    /// The replacement texts are inserted as character data and not parsed as markup, so they
+    /// must only contain legal XML characters. Otherwise the reference is rejected with
+    /// [`XmlError::InvalidCharacter`].
+    pub fn with_entities(mut self, entities: impl IntoIterator<Item = (String, String)>) -> Self {
+        self.entities = entities.into_iter().collect();
+        self
+    }
+
+    /// Byte offset of the parser position relative to the start of the input
     pub fn cursor_offset(&self) -> usize {
         self.cursor.offset()
     }
 
+    /// Number of bytes of the input parsed so far
+    pub fn consumed_len(&self) -> usize {
+        self.cursor.offset()
+    }
+
+    /// Number of bytes of the input not parsed yet
+    pub fn remaining_len(&self) -> usize {
+        self.cursor.rest().len()
+    }
+
     pub fn unparsed(&self) -> &'i str {
         self.cursor.rest()
     }
@@ -140,60 +251,39 @@ impl<'i> SimpleXmlParser<'i> {
         &mut self,
         visitor: V,
     ) -> Result<Option<V::Value>, XmlError> {
-        Ok(Some(match self.state {
-            ParserState::XmlDecl => self.parse_xml_decl(visitor),
-            ParserState::Prologue => self.parse_prologue(visitor),
-            ParserState::Main => self.parse_root_element(visitor),
-            ParserState::Epilogue => return self.parse_epilogue(visitor),
-        }?))
+        let value = match self.state {
+            ParserState::XmlDecl => self.parse_xml_decl(visitor).map(Some),
+            ParserState::Prologue => self.parse_prologue(visitor).map(Some),
+            ParserState::Main => self.parse_root_element(visitor).map(Some),
+            ParserState::Epilogue => self.parse_epilogue(visitor),
+        };
+        value.map_err(XmlError::into_final)
+    }
+
+    /// Parse the next event without a visitor
+    ///
+    /// Returns `None` at the end of the document.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Result<Option<SimpleEvent<'i>>, XmlError> {
+        self.parse_next(SimpleEventVisitor)
     }
 
     fn parse_xml_decl<V: SimpleXmlVisitor<'i>>(
         &mut self,
         visitor: V,
     ) -> Result<V::Value, XmlError> {
-        while let Some(c) = self.cursor.next_byte(0) {
-            return match c {
-                b'<' => {
-                    if let Some(c) = self.cursor.next_byte(1) {
-                        if c == b'?' {
-                            self.state = ParserState::Prologue;
-                            if self.is_decl_start() {
-                                self.parse_decl(visitor)
-                            } else {
-                                self.parse_pi(visitor)
-                            }
-                        } else if c == b'!' {
-                            if self.cursor.has_next_str("<!--") {
-                                self.parse_comment(visitor)
-                            } else if self.cursor.has_next_str("<!DOCTYPE") {
-                                Err(XmlError::DtdError(XmlDtdError::Unsupported))
-                            } else {
-                                break;
-                            }
-                        } else {
-                            self.cursor = self.cursor.advance(1);
-                            self.state = ParserState::Main;
-                            self.parse_stag(visitor)
-                        }
-                    } else {
-                        break;
-                    }
-                }
-                _ => {
-                    self.consume_whitespace(c)?;
-                    continue;
-                }
-            };
-        }
-
-        Err(XmlError::Expected(Box::new([
-            XmlErrorAtom::XmlDecl,
-            XmlErrorAtom::Comment,
-            XmlErrorAtom::PI,
-            XmlErrorAtom::Element,
-            XmlErrorAtom::Whitespace,
-        ])))
+        // the declaration is only allowed at the very start
+        self.state = ParserState::Prologue;
+        if self.is_decl_start() {
+            return self.parse_decl(visitor);
+        }
+        let start = self.cursor.offset();
+        self.parse_prologue(visitor).map_err(|err| match err {
+            XmlError::Expected(_) if self.cursor.offset() == start => {
+                XmlError::Expected(XML_DECL_ATOMS.into())
+            }
+            err => err,
+        })
     }
 
     fn is_decl_start(&self) -> bool {
@@ -201,7 +291,7 @@ impl<'i> SimpleXmlParser<'i> {
             && self
                 .cursor
                 .next_byte(5)
-                .map(|c| c.is_xml_whitespace() || !c.is_ascii_alphabetic() && !b":_-.".contains(&c))
+                .map(|c| c.is_xml_whitespace() || c == b'?')
                 .unwrap_or(false)
     }
 
@@ -239,12 +329,7 @@ impl<'i> SimpleXmlParser<'i> {
             };
         }
 
-        Err(XmlError::Expected(Box::new([
-            XmlErrorAtom::Comment,
-            XmlErrorAtom::PI,
-            XmlErrorAtom::Element,
-            XmlErrorAtom::Whitespace,
-        ])))
+        Err(XmlError::Expected(PROLOGUE_ATOMS.into()))
     }
 
     fn parse_root_element<V: SimpleXmlVisitor<'i>>(
@@ -253,13 +338,11 @@ impl<'i> SimpleXmlParser<'i> {
     ) -> Result<V::Value, XmlError> {
         if self.empty {
             self.empty = false;
-            if let Some(name) = self.stack.pop() {
-                if self.stack.is_empty() {
-                    self.state = ParserState::Epilogue;
-                }
-                return Ok(visitor.visit_end_element(name)?);
+            let name = self.stack.pop().ok_or(XmlError::ExpectedElementStart)?;
+            if self.stack.is_empty() {
+                self.state = ParserState::Epilogue;
             }
-            unreachable!()
+            return visitor.visit_end_element(name);
         }
 
         while let Some(c) = self.cursor.next_byte(0) {
@@ -274,8 +357,6 @@ impl<'i> SimpleXmlParser<'i> {
                                 self.parse_comment(visitor)
                             } else if self.cursor.has_next_str("<![CDATA[") {
                                 self.parse_cdata(visitor)
-                            } else if self.cursor.has_next_str("<!DOCTYPE") {
-                                Err(XmlError::DtdError(XmlDtdError::Unsupported))
                             } else {
                                 Err(XmlError::ExpectedElementStart)
                             }
@@ -321,10 +402,7 @@ impl<'i> SimpleXmlParser<'i> {
                         }
                     }
 
-                    return Err(XmlError::Expected(Box::new([
-                        XmlErrorAtom::Comment,
-                        XmlErrorAtom::PI,
-                    ])));
+                    return Err(XmlError::Expected(EPILOGUE_ATOMS.into()));
                 }
                 _ => {
                     self.consume_whitespace(c)?;
@@ -342,7 +420,10 @@ impl<'i> SimpleXmlParser<'i> {
             self.cursor = cur;
             Ok(())
         } else {
-            Err(UnexpectedCharacter(self.cursor.next_char().unwrap()))
+            Err(self
+                .cursor
+                .next_char()
+                .map_or(XmlError::UnexpectedEof, UnexpectedCharacter))
         }
     }
 
@@ -369,8 +450,7 @@ impl<'i> SimpleXmlParser<'i> {
     }
 
     fn parse_etag<V: SimpleXmlVisitor<'i>>(&mut self, visitor: V) -> Result<V::Value, XmlError> {
-        // TODO: xml_lit(self.stack.pop()) should be faster
-        let (name, cursor) = NameToken.parse(self.cursor)?;
+        let (name, cursor) = etag_name(self.cursor, self.stack.last().copied())?;
         let (_, cursor) = optional(SToken).parse(cursor)?;
         let cursor = expect_byte(cursor, b'>', || XmlError::ExpectedElementEnd)?;
         self.commit(cursor);
@@ -387,7 +467,7 @@ impl<'i> SimpleXmlParser<'i> {
                 })
             }
         } else {
-            unreachable!()
+            Err(XmlError::ETagAfterRootElement)
         }
     }
 
@@ -466,20 +546,35 @@ impl<'i> SimpleXmlParser<'i> {
             } else {
                 let (entity_ref, cursor) = EntityRefToken.parse(cur)?;
                 self.commit(cursor);
-                visitor.visit_characters(match entity_ref {
+                let predefined = match entity_ref {
                     "apos" => "\'",
                     "quot" => "\"",
                     "lt" => "<",
                     "gt" => ">",
                     "amp" => "&",
-                    _ => return Err(XmlError::UnknownEntity(entity_ref.to_string())),
-                })
+                    _ => {
+                        return visitor.visit_borrowed_characters(self.resolve_entity(entity_ref)?)
+                    }
+                };
+                visitor.visit_characters(predefined)
             }
         } else {
             Err(XmlError::IllegalReference)
         }
     }
 
+    /// Replacement text of an entity which is not predefined
+    fn resolve_entity(&self, entity_ref: &str) -> Result<&str, XmlError> {
+        let value = self
+            .entities
+            .get(entity_ref)
+            .ok_or_else(|| XmlError::UnknownEntity(entity_ref.to_string()))?;
+        if let Some(c) = value.chars().find(|c| !c.is_xml_char()) {
+            return Err(XmlError::InvalidCharacter(c));
+        }
+        Ok(value)
+    }
+
     fn consume(&mut self, n: usize) {
         self.cursor = self.cursor.advance(n);
     }
@@ -493,9 +588,20 @@ impl<'i> SimpleXmlParser<'i> {
     }
 }
 
-struct SimpleEntityStrValueResolver;
-
-impl<'i> EntityStrValueResolver<'i> for SimpleEntityStrValueResolver {}
+struct SimpleEntityStrValueResolver<'a, 'i>(&'a SimpleXmlParser<'i>);
+
+impl<'a, 'i> EntityStrValueResolver<'i> for SimpleEntityStrValueResolver<'a, 'i> {
+    fn resolve_unknown_entity(
+        &self,
+        entity_ref: &str,
+        str_builder: &mut CowStrBuilder<'i>,
+    ) -> Result<(), XmlError> {
+        // whitespace is normalized like in the attribute value itself
+        let value = self.0.resolve_entity(entity_ref)?;
+        str_builder.push_str(&value.replace(|c: char| c.is_xml_whitespace(), " "));
+        Ok(())
+    }
+}
 
 struct SimpleAttributeAccess<'a, 'i> {
     parser: &'a mut SimpleXmlParser<'i>,
@@ -533,7 +639,8 @@ impl<'a, 'i> AttributeAccess<'i> for SimpleAttributeAccess<'a, 'i> {
 
             let (attr_name, cur) = NameToken.parse(self.parser.cursor)?;
             let (_, cur) = EqToken.parse(cur)?;
-            let (value, cur) = AttValueToken::new(SimpleEntityStrValueResolver).parse(cur)?;
+            let (value, cur) =
+                AttValueToken::new(SimpleEntityStrValueResolver(self.parser)).parse(cur)?;
             if let Ok((_, cur)) = SToken.parse(cur) {
                 self.parser.commit(cur);
                 self.got_whitespace = true;
@@ -558,6 +665,24 @@ impl<'a, 'i> AttributeAccess<'i> for SimpleAttributeAccess<'a, 'i> {
     }
 }
 
+/// Iterates over the events until the end of the document or the first error
+impl<'i> Iterator for SimpleXmlParser<'i> {
+    type Item = Result<SimpleEvent<'i>, XmlError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        match SimpleXmlParser::next(self) {
+            Ok(evt) => evt.map(Ok),
+            Err(err) => {
+                self.failed = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
 fn expect_byte(cursor: Cursor, c: u8, err: impl Fn() -> XmlError) -> Result<Cursor, XmlError> {
     if cursor.next_byte(0) == Some(c) {
         Ok(cursor.advance(1))
@@ -811,7 +936,8 @@ mod tests {
             assert_evt!(
                 Err(XmlError::Expected(Box::new([
                     XmlErrorAtom::Comment,
-                    XmlErrorAtom::PI
+                    XmlErrorAtom::PI,
+                    XmlErrorAtom::Whitespace
                 ]))),
                 parser
             );
@@ -829,6 +955,35 @@ mod tests {
             );
         }
 
+        #[test]
+        fn fail_on_etag_with_expected_prefix() {
+            let mut parser = SimpleXmlParser::from_str("<e></ed>");
+            assert_evt!(Ok(Some(Event::Start("e", vec![]))), parser);
+            assert_evt!(
+                Err(XmlError::WrongETagName {
+                    expected_name: "e".to_string(),
+                }),
+                parser
+            );
+
+            let mut parser = SimpleXmlParser::from_str("<ed></e>");
+            assert_evt!(Ok(Some(Event::Start("ed", vec![]))), parser);
+            assert_evt!(
+                Err(XmlError::WrongETagName {
+                    expected_name: "ed".to_string(),
+                }),
+                parser
+            );
+        }
+
+        #[test]
+        fn etag_with_whitespace() {
+            let mut parser = SimpleXmlParser::from_str("<e></e\t>");
+            assert_evt!(Ok(Some(Event::Start("e", vec![]))), parser);
+            assert_evt!(Ok(Some(Event::End("e"))), parser);
+            assert_evt!(Ok(None), parser);
+        }
+
         #[test]
         fn fail_on_wrong_etag_in_depth_graph() {
             let mut parser = SimpleXmlParser::from_str("<a><e><e></e><e/></d></a>");
@@ -858,7 +1013,8 @@ mod tests {
             assert_evt!(
                 Err(XmlError::Expected(Box::new([
                     XmlErrorAtom::Comment,
-                    XmlErrorAtom::PI
+                    XmlErrorAtom::PI,
+                    XmlErrorAtom::Whitespace
                 ]))),
                 parser
             );
@@ -880,7 +1036,104 @@ mod tests {
             assert_evt!(
                 Err(XmlError::Expected(Box::new([
                     XmlErrorAtom::Comment,
-                    XmlErrorAtom::PI
+                    XmlErrorAtom::PI,
+                    XmlErrorAtom::Whitespace
+                ]))),
+                parser
+            );
+        }
+    }
+
+    mod prologue_and_epilogue {
+        use super::*;
+
+        #[test]
+        fn comment_in_prologue() {
+            let mut parser =
+                SimpleXmlParser::from_str("<?xml version='1.0'?><!-- c --> <?pi?><!--d--><e/>");
+            assert_evt!(Ok(Some(Event::decl("1.0", None, None))), parser);
+            assert_evt!(Ok(Some(Event::Comment(" c "))), parser);
+            assert_evt!(Ok(Some(Event::PI("pi", None))), parser);
+            assert_evt!(Ok(Some(Event::Comment("d"))), parser);
+            assert_evt!(Ok(Some(Event::Start("e", vec![]))), parser);
+        }
+
+        #[test]
+        fn comment_between_root_and_pi() {
+            let mut parser = SimpleXmlParser::from_str("<e/>\n<!-- c -->\n<?pi data?><!---->");
+            assert_evt!(Ok(Some(Event::Start("e", vec![]))), parser);
+            assert_evt!(Ok(Some(Event::End("e"))), parser);
+            assert_evt!(Ok(Some(Event::Comment(" c "))), parser);
+            assert_evt!(Ok(Some(Event::PI("pi", Some("data")))), parser);
+            assert_evt!(Ok(Some(Event::Comment(""))), parser);
+            assert_evt!(Ok(None), parser);
+        }
+
+        #[test]
+        fn fail_on_doctype_in_epilogue() {
+            let mut parser = SimpleXmlParser::from_str("<e/><!DOCTYPE e>");
+            assert_evt!(Ok(Some(Event::Start("e", vec![]))), parser);
+            assert_evt!(Ok(Some(Event::End("e"))), parser);
+            assert_evt!(
+                Err(XmlError::Expected(Box::new([
+                    XmlErrorAtom::Comment,
+                    XmlErrorAtom::PI,
+                    XmlErrorAtom::Whitespace
+                ]))),
+                parser
+            );
+        }
+
+        #[test]
+        fn fail_on_cdata_in_epilogue() {
+            let mut parser = SimpleXmlParser::from_str("<e/><![CDATA[x]]>");
+            assert_evt!(Ok(Some(Event::Start("e", vec![]))), parser);
+            assert_evt!(Ok(Some(Event::End("e"))), parser);
+            assert_evt!(
+                Err(XmlError::Expected(Box::new([
+                    XmlErrorAtom::Comment,
+                    XmlErrorAtom::PI,
+                    XmlErrorAtom::Whitespace
+                ]))),
+                parser
+            );
+        }
+
+        #[test]
+        fn fail_on_doctype_in_prologue() {
+            let mut parser = SimpleXmlParser::from_str("<!DOCTYPE e><e/>");
+            assert_evt!(Err(XmlError::DtdError(XmlDtdError::Unsupported)), parser);
+        }
+
+        #[test]
+        fn fail_on_decl_after_comment() {
+            let mut parser = SimpleXmlParser::from_str("<!-- c --><?xml version='1.0'?><e/>");
+            assert_evt!(Ok(Some(Event::Comment(" c "))), parser);
+            assert_evt!(Err(XmlError::InvalidPITarget), parser);
+        }
+
+        #[test]
+        fn expected_atoms_per_state() {
+            let mut parser = SimpleXmlParser::from_str("<!x");
+            assert_evt!(
+                Err(XmlError::Expected(Box::new([
+                    XmlErrorAtom::XmlDecl,
+                    XmlErrorAtom::Comment,
+                    XmlErrorAtom::PI,
+                    XmlErrorAtom::Element,
+                    XmlErrorAtom::Whitespace
+                ]))),
+                parser
+            );
+
+            let mut parser = SimpleXmlParser::from_str("<!-- c --><!x");
+            assert_evt!(Ok(Some(Event::Comment(" c "))), parser);
+            assert_evt!(
+                Err(XmlError::Expected(Box::new([
+                    XmlErrorAtom::Comment,
+                    XmlErrorAtom::PI,
+                    XmlErrorAtom::Element,
+                    XmlErrorAtom::Whitespace
                 ]))),
                 parser
             );
@@ -1146,6 +1399,20 @@ mod tests {
             let mut parser = SimpleXmlParser::from_str("<!-- B+, B, or B--->");
             assert_evt!(Err(XmlError::CommentColonColon), parser);
         }
+
+        #[test]
+        fn illegal_char() {
+            let mut parser = SimpleXmlParser::from_str("<e><!-- a\u{0}b --></e>");
+            assert_evt!(Ok(Some(Event::Start("e", vec![]))), parser);
+            assert_evt!(Err(XmlError::IllegalChar('\u{0}')), parser);
+        }
+
+        #[test]
+        fn supplementary_char() {
+            let mut parser = SimpleXmlParser::from_str("<e><!-- \u{1F600} --></e>");
+            assert_evt!(Ok(Some(Event::Start("e", vec![]))), parser);
+            assert_evt!(Ok(Some(Event::Comment(" \u{1F600} "))), parser);
+        }
     }
 
     mod pi {
@@ -1178,6 +1445,51 @@ mod tests {
             assert_evt!(Ok(Some(Event::PI("xml-abc", None))), parser);
         }
 
+        #[test]
+        fn prolog_order() {
+            let mut parser = SimpleXmlParser::from_str(
+                "<?xml version='1.0'?>\n\
+                 <?xml-stylesheet href='a.xsl'?>\n\
+                 <!-- c1 -->\n\
+                 <?xml-model href='a.rng'?>\n\
+                 <!-- c2 --><?app?>\n\
+                 <e/>",
+            );
+            assert_evt_matches!(Ok(Some(Event::Decl(_))), parser);
+            assert_evt!(
+                Ok(Some(Event::PI("xml-stylesheet", Some("href='a.xsl'")))),
+                parser
+            );
+            assert_evt!(Ok(Some(Event::Comment(" c1 "))), parser);
+            assert_evt!(
+                Ok(Some(Event::PI("xml-model", Some("href='a.rng'")))),
+                parser
+            );
+            assert_evt!(Ok(Some(Event::Comment(" c2 "))), parser);
+            assert_evt!(Ok(Some(Event::PI("app", None))), parser);
+            assert_evt!(Ok(Some(Event::Start("e", vec![]))), parser);
+            assert_evt!(Ok(Some(Event::End("e"))), parser);
+            assert_evt!(Ok(None), parser);
+        }
+
+        #[test]
+        fn prolog_order_without_decl() {
+            let mut parser =
+                SimpleXmlParser::from_str("<?xml-stylesheet?><!--c--><?xml-model?><e/>");
+            assert_evt!(Ok(Some(Event::PI("xml-stylesheet", None))), parser);
+            assert_evt!(Ok(Some(Event::Comment("c"))), parser);
+            assert_evt!(Ok(Some(Event::PI("xml-model", None))), parser);
+            assert_evt!(Ok(Some(Event::Start("e", vec![]))), parser);
+        }
+
+        #[test]
+        fn xml_prefixed_targets() {
+            let mut parser = SimpleXmlParser::from_str("<?xml2 d?><?xmlé d?><e/>");
+            assert_evt!(Ok(Some(Event::PI("xml2", Some("d")))), parser);
+            assert_evt!(Ok(Some(Event::PI("xmlé", Some("d")))), parser);
+            assert_evt!(Ok(Some(Event::Start("e", vec![]))), parser);
+        }
+
         #[test]
         fn invalid_1() {
             let mut parser = SimpleXmlParser::from_str("<?e/fsdg?>");
@@ -1351,6 +1663,8 @@ mod tests {
     }
 
     mod entity_replacement {
+        use std::collections::HashMap;
+
         use super::*;
 
         #[test]
@@ -1418,6 +1732,69 @@ mod tests {
             assert_evt!(Ok(Some(Event::Start("e", vec![]))), parser);
             assert_evt!(Err(XmlError::ExpectToken(";")), parser);
         }
+
+        fn nbsp_entities() -> HashMap<String, String> {
+            let mut entities = HashMap::new();
+            entities.insert("nbsp".to_string(), "\u{A0}".to_string());
+            entities.insert("br".to_string(), "\n".to_string());
+            entities.insert("nul".to_string(), "\0".to_string());
+            entities
+        }
+
+        #[test]
+        fn replace_custom_entity() {
+            let mut parser =
+                SimpleXmlParser::from_str("<e>a&nbsp;b&br;</e>").with_entities(nbsp_entities());
+            assert_evt!(Ok(Some(Event::Start("e", vec![]))), parser);
+            assert_evt!(Ok(Some(Event::Chars("a".into()))), parser);
+            assert_evt!(Ok(Some(Event::Chars("\u{A0}".into()))), parser);
+            assert_evt!(Ok(Some(Event::Chars("b".into()))), parser);
+            assert_evt!(Ok(Some(Event::Chars("\n".into()))), parser);
+            assert_evt!(Ok(Some(Event::End("e"))), parser);
+            assert_evt!(Ok(None), parser);
+        }
+
+        #[test]
+        fn replace_custom_entity_in_attribute() {
+            let mut parser =
+                SimpleXmlParser::from_str("<e a='x&nbsp;&br;y'/>").with_entities(nbsp_entities());
+            assert_evt!(
+                Ok(Some(Event::Start(
+                    "e",
+                    vec![("a".into(), "x\u{A0} y".into())]
+                ))),
+                parser
+            );
+        }
+
+        #[test]
+        fn fail_on_illegal_replacement() {
+            let mut parser =
+                SimpleXmlParser::from_str("<e>&nul;</e>").with_entities(nbsp_entities());
+            assert_evt!(Ok(Some(Event::Start("e", vec![]))), parser);
+            assert_evt!(Err(XmlError::InvalidCharacter('\0')), parser);
+        }
+    }
+
+    mod lengths {
+        use super::*;
+
+        #[test]
+        fn monotonic() {
+            let input = "<?xml version='1.0'?><r x='1'>a&lt;<!-- c --><?pi?><e/></r>";
+            let mut parser = SimpleXmlParser::from_str(input);
+            assert_eq!(0, parser.consumed_len());
+            assert_eq!(input.len(), parser.remaining_len());
+
+            let mut consumed = 0;
+            while parser.parse_next(EventVisitor).unwrap().is_some() {
+                assert!(parser.consumed_len() >= consumed);
+                assert_eq!(parser.cursor_offset(), parser.consumed_len());
+                assert_eq!(input.len(), parser.consumed_len() + parser.remaining_len());
+                consumed = parser.consumed_len();
+            }
+            assert_eq!(0, parser.remaining_len());
+        }
     }
 
     /// 4.3.3 Character Encoding in Entities
@@ -1508,4 +1885,81 @@ mod tests {
             assert_evt!(Ok(None), parser);
         }
     }
+
+    mod events {
+        use super::*;
+
+        #[test]
+        fn next_event() {
+            let mut parser = SimpleXmlParser::from_str("<elem attr=\"&lt;\">a&amp;b</elem>");
+            assert_eq!(
+                Ok(Some(SimpleEvent::Start(
+                    "elem",
+                    vec![("attr".into(), "<".into())]
+                ))),
+                parser.next()
+            );
+            assert_eq!(Ok(Some(SimpleEvent::Chars("a".into()))), parser.next());
+            assert_eq!(Ok(Some(SimpleEvent::Chars("&".into()))), parser.next());
+            assert_eq!(Ok(Some(SimpleEvent::Chars("b".into()))), parser.next());
+            assert_eq!(Ok(Some(SimpleEvent::End("elem"))), parser.next());
+            assert_eq!(Ok(None), parser.next());
+        }
+
+        #[test]
+        fn iterate_document() {
+            let parser = SimpleXmlParser::from_str(
+                "<?xml version=\"1.0\"?><!-- c --><?pi data?><e><x/></e><?pi?>",
+            );
+            let events: Result<Vec<_>, _> = parser.collect();
+            assert_eq!(
+                Ok(vec![
+                    SimpleEvent::Decl(XmlDecl {
+                        version: "1.0".to_string(),
+                        encoding: None,
+                        standalone: None,
+                    }),
+                    SimpleEvent::Comment(" c "),
+                    SimpleEvent::PI("pi", Some("data")),
+                    SimpleEvent::Start("e", vec![]),
+                    SimpleEvent::Start("x", vec![]),
+                    SimpleEvent::End("x"),
+                    SimpleEvent::End("e"),
+                    SimpleEvent::PI("pi", None),
+                ]),
+                events
+            );
+        }
+
+        #[test]
+        fn same_events_as_visitor() {
+            let input = "<r a='1'><![CDATA[<c>]]>&#x21;<!--x--></r>";
+            let mut visitor_parser = SimpleXmlParser::from_str(input);
+            for event in SimpleXmlParser::from_str(input) {
+                let expected = visitor_parser.parse_next(EventVisitor).unwrap().unwrap();
+                let event = match event.unwrap() {
+                    SimpleEvent::Decl(decl) => Event::Decl(decl),
+                    SimpleEvent::PI(target, data) => Event::PI(target, data),
+                    SimpleEvent::Comment(comment) => Event::Comment(comment),
+                    SimpleEvent::Start(tag, attrs) => Event::Start(tag, attrs),
+                    SimpleEvent::End(tag) => Event::End(tag),
+                    SimpleEvent::Chars(chars) => Event::Chars(chars),
+                };
+                assert_eq!(expected, event);
+            }
+            assert_evt!(Ok(None), visitor_parser);
+        }
+
+        #[test]
+        fn stop_after_error() {
+            let mut parser = SimpleXmlParser::from_str("<a></b><c/>");
+            assert_eq!(
+                Some(Ok(SimpleEvent::Start("a", vec![]))),
+                Iterator::next(&mut parser)
+            );
+            assert!(matches!(Iterator::next(&mut parser), Some(Err(_))));
+            assert_eq!(None, Iterator::next(&mut parser));
+            assert_eq!(None, Iterator::next(&mut parser));
+        }
+    }
 }
@@ -1,15 +1,15 @@
-use std::borrow::Cow;
-use std::fmt;
-use std::fmt::{Formatter, Write};
-use std::rc::Rc;
-use std::sync::Arc;
-
+use alloc::borrow::Cow;
+use alloc::rc::Rc;
+use alloc::sync::Arc;
+use core::fmt;
+use core::fmt::{Formatter, Write};
+
+use crate::namespace::{XMLNS_URI, XML_URI};
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
 use crate::simple::{AttributeAccess, CowVisitor, SimpleXmlParser, SimpleXmlVisitor, StrVisitor};
 use crate::{XmlDecl, XmlError};
 
-pub const XML_URI: &str = "http://www.w3.org/XML/1998/namespace";
-pub const XMLNS_URI: &str = "http://www.w3.org/2000/xmlns/";
-
 #[derive(PartialEq, Debug, Copy, Clone)]
 pub struct QName<'i> {
     prefix: Option<&'i str>,
@@ -167,10 +167,21 @@ impl<'i> SimpleNsXmlParser<'i> {
         }
     }
 
+    /// Byte offset of the parser position relative to the start of the input
     pub fn cursor_offset(&self) -> usize {
         self.parser.cursor_offset()
     }
 
+    /// Number of bytes of the input parsed so far
+    pub fn consumed_len(&self) -> usize {
+        self.parser.consumed_len()
+    }
+
+    /// Number of bytes of the input not parsed yet
+    pub fn remaining_len(&self) -> usize {
+        self.parser.remaining_len()
+    }
+
     pub fn unparsed(&self) -> &'i str {
         self.parser.unparsed()
     }
@@ -253,7 +264,7 @@ impl<'i, 'a, V> VisitorAdapter<'i, 'a, V> {
 
 struct AttrAccessAdapter<'i, 'a> {
     scopes: &'a mut NamespaceStack<'i>,
-    rest_attrs: std::vec::Drain<'a, (Option<Namespace>, QName<'i>, Cow<'i, str>)>,
+    rest_attrs: alloc::vec::Drain<'a, (Option<Namespace>, QName<'i>, Cow<'i, str>)>,
 }
 
 impl<'i, 'a> NsAttributeAccess<'i> for AttrAccessAdapter<'i, 'a> {
@@ -2,7 +2,7 @@ use std::fmt::Debug;
 use std::fmt::Write;
 
 use xrs_parser::{ETag, Reader, STag, XmlDecl, XmlError, XmlEvent, PI};
-use xrs_xts::TestableParser;
+use xrs_xts::{Support, TestableParser};
 
 pub struct ReaderIT;
 
@@ -39,14 +39,20 @@ impl ReaderIT {
                 XmlEvent::ETag(etag) => {
                     self.write_etag(&mut result, etag)?;
                 }
-                XmlEvent::Characters(chars) => {
+                XmlEvent::Characters(chars)
+                | XmlEvent::CData(chars)
+                | XmlEvent::Whitespace(chars) => {
                     result.push_str(&Self::process_cdata(chars.as_ref()));
                 }
+                XmlEvent::CharRef(char_ref) => {
+                    result.push_str(&Self::process_cdata(&char_ref.character.to_string()));
+                }
                 XmlEvent::PI(pi) => {
                     self.write_pi(&mut result, pi)?;
                 }
                 XmlEvent::Dtd(_) => {}
                 XmlEvent::Comment(_) => {}
+                XmlEvent::Error(err) => return Err(err),
             }
         }
 
@@ -90,10 +96,15 @@ impl ReaderIT {
 }
 
 impl TestableParser for ReaderIT {
-    fn check_well_formed(&self, input: &[u8], namespace: bool) -> Result<(), (String, usize)> {
+    fn check_well_formed(&self, input: &[u8], namespace: bool) -> Support {
         let input = match std::str::from_utf8(input) {
             Ok(input) => input,
-            Err(err) => return Err((format!("{}", err), err.valid_up_to())),
+            Err(err) => {
+                return Support::NotWellFormed {
+                    message: format!("{}", err),
+                    offset: err.valid_up_to(),
+                }
+            }
         };
 
         let mut reader = Reader::new(input);
@@ -101,10 +112,13 @@ impl TestableParser for ReaderIT {
             match reader.next() {
                 Ok(Some(_)) => {}
                 Ok(None) => {
-                    return Ok(());
+                    return Support::WellFormed;
                 }
                 Err(err) => {
-                    return Err((format!("{:?}", err), reader.cursor_offset()));
+                    return Support::NotWellFormed {
+                        message: format!("{:?}", err),
+                        offset: reader.cursor_offset(),
+                    };
                 }
             }
         }
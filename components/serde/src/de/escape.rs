@@ -2,19 +2,31 @@
 
 use std::borrow::Cow;
 
-use serde::de::{self, Visitor};
+use serde::de::{self, IntoDeserializer, Visitor};
 use serde::{self, forward_to_deserialize_any};
 
+use crate::de::BoolAttributes;
 use crate::{error::Reason, error::ResultExt, Error, Result};
 
 #[derive(Clone)]
 pub(crate) struct EscapedDeserializer<'de> {
     value: Cow<'de, str>,
+    /// Attribute name and mode when deserializing an attribute value
+    bool_attribute: Option<(Cow<'de, str>, BoolAttributes)>,
 }
 
 impl<'de> EscapedDeserializer<'de> {
     pub fn new(value: Cow<'de, str>) -> Self {
-        Self { value }
+        Self {
+            value,
+            bool_attribute: None,
+        }
+    }
+
+    /// Deserialize the value of the attribute `name` with the given `bool` handling
+    pub fn with_bool_attribute(mut self, name: Cow<'de, str>, mode: BoolAttributes) -> Self {
+        self.bool_attribute = Some((name, mode));
+        self
     }
 
     fn error(&self, reason: Reason) -> Error {
@@ -48,6 +60,14 @@ impl<'de> serde::Deserializer<'de> for EscapedDeserializer<'de> {
     where
         V: Visitor<'de>,
     {
+        match &self.bool_attribute {
+            Some((_, BoolAttributes::Presence)) => return visitor.visit_bool(true),
+            Some((name, BoolAttributes::Named)) if *name == self.value => {
+                return visitor.visit_bool(true)
+            }
+            _ => {}
+        }
+
         match &*self.value {
             "true" | "1" => visitor.visit_bool(true),
             "false" | "0" => visitor.visit_bool(false),
@@ -133,7 +153,7 @@ impl<'de> serde::Deserializer<'de> for EscapedDeserializer<'de> {
         _variants: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value> {
-        visitor.visit_enum(self)
+        visitor.visit_enum(self.value.into_deserializer())
     }
 
     deserialize_num!(deserialize_i64, i64, visit_i64);
@@ -151,37 +171,3 @@ impl<'de> serde::Deserializer<'de> for EscapedDeserializer<'de> {
         unit_struct seq tuple tuple_struct map struct identifier ignored_any
     }
 }
-
-impl<'de> de::EnumAccess<'de> for EscapedDeserializer<'de> {
-    type Error = Error;
-    type Variant = Self;
-
-    fn variant_seed<V: de::DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self)> {
-        let name = seed.deserialize(self.clone())?;
-        Ok((name, self))
-    }
-}
-
-impl<'de> de::VariantAccess<'de> for EscapedDeserializer<'de> {
-    type Error = Error;
-
-    fn unit_variant(self) -> Result<()> {
-        Ok(())
-    }
-
-    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
-        seed.deserialize(self)
-    }
-
-    fn tuple_variant<V: de::Visitor<'de>>(self, _len: usize, _visitor: V) -> Result<V::Value> {
-        unimplemented!()
-    }
-
-    fn struct_variant<V: de::Visitor<'de>>(
-        self,
-        _fields: &'static [&'static str],
-        _visitor: V,
-    ) -> Result<V::Value> {
-        unimplemented!()
-    }
-}
@@ -1,11 +1,14 @@
 //! Serde `Deserializer` module
 
 use std::borrow::Cow;
+use std::rc::Rc;
 
+use serde::de::value::SeqDeserializer;
 use serde::de::{self, DeserializeSeed, IntoDeserializer};
 
 use crate::{
-    de::{escape::EscapedDeserializer, Deserializer, INNER_VALUE},
+    de::{escape::EscapedDeserializer, Capture, Deserializer, COMMENTS, INNER_VALUE, PIS},
+    error::Reason,
     Error,
 };
 use xrs_parser::Attribute;
@@ -13,9 +16,15 @@ use xrs_parser::Attribute;
 #[derive(Debug)]
 enum MapValue<'de> {
     Empty,
-    Attribute { value: Cow<'de, str> },
-    Nested,
+    Attribute {
+        name: Cow<'de, str>,
+        value: Cow<'de, str>,
+    },
+    /// Child element with its name
+    Nested(Cow<'de, str>),
     InnerValue,
+    /// Collected comments or processing instructions
+    Captured(Vec<Cow<'de, str>>),
 }
 
 /// A deserializer for `Attributes`
@@ -25,28 +34,103 @@ pub(crate) struct MapAccess<'a, 'de> {
     value: MapValue<'de>,
     has_value_field: bool,
     read_value_field: bool,
+    skip_content: bool,
+    has_comments_field: bool,
+    has_pis_field: bool,
+    /// Use the local names of child elements as keys
+    local_names: bool,
+    /// Content of the element with the end tag was read, only set when comments or processing
+    /// instructions are captured
+    captured: Option<Capture<'de>>,
 }
 
 impl<'a, 'de> MapAccess<'a, 'de> {
-    fn create_attr_key(key: &str) -> String {
-        // TODO: optimize copies!
-        let mut result = String::with_capacity(key.len() + 1);
-        result.push('@');
-        result.push_str(key);
-        result
+    /// Key of an attribute, which shares its allocation with equal keys when the reader has
+    /// an interner
+    fn create_attr_key(&mut self, key: &str) -> Rc<str> {
+        let de = &mut *self.de;
+        de.attr_key.clear();
+        de.attr_key.push('@');
+        de.attr_key.push_str(key);
+        de.source.intern(&de.attr_key)
     }
 
-    /// Create a new MapAccess
-    pub fn new(de: &'a mut Deserializer<'de>, has_value_field: bool) -> Result<Self, Error> {
-        let attributes = de.reader.drain_attributes().into_iter();
+    /// Create a new MapAccess for a struct with the given fields
+    ///
+    /// The comments and processing instructions in the content are collected when the struct
+    /// has a `$comment` or `$pi` field.
+    pub fn new(de: &'a mut Deserializer<'de>, fields: &[&str]) -> Result<Self, Error> {
+        let attributes = de.drain_attributes().into_iter();
+        let has_comments_field = fields.contains(&COMMENTS);
+        let has_pis_field = fields.contains(&PIS);
+        if has_comments_field || has_pis_field {
+            de.start_capture();
+        }
         Ok(MapAccess {
             de,
             attributes,
             value: MapValue::Empty,
-            has_value_field,
+            has_value_field: fields.contains(&INNER_VALUE),
             read_value_field: false,
+            skip_content: false,
+            has_comments_field,
+            has_pis_field,
+            local_names: false,
+            captured: None,
         })
     }
+
+    /// Create a MapAccess which only visits the attributes and skips the element content
+    pub fn attributes_only(de: &'a mut Deserializer<'de>) -> Self {
+        let attributes = de.drain_attributes().into_iter();
+        MapAccess {
+            de,
+            attributes,
+            value: MapValue::Empty,
+            has_value_field: false,
+            read_value_field: false,
+            skip_content: true,
+            has_comments_field: false,
+            has_pis_field: false,
+            local_names: false,
+            captured: None,
+        }
+    }
+
+    /// Create a MapAccess for a map keyed by the local names of the child elements
+    pub fn map(de: &'a mut Deserializer<'de>) -> Self {
+        let mut attributes = de.drain_attributes();
+        if !de.map_attributes {
+            attributes.clear();
+        }
+        MapAccess {
+            de,
+            attributes: attributes.into_iter(),
+            value: MapValue::Empty,
+            has_value_field: false,
+            read_value_field: false,
+            skip_content: false,
+            has_comments_field: false,
+            has_pis_field: false,
+            local_names: true,
+            captured: None,
+        }
+    }
+}
+
+impl<'a, 'de> MapAccess<'a, 'de> {
+    /// Continue with the captured fields after the end tag was read
+    fn content_read<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        if self.has_comments_field || self.has_pis_field {
+            self.captured = Some(self.de.finish_capture());
+            de::MapAccess::next_key_seed(self, seed)
+        } else {
+            Ok(None)
+        }
+    }
 }
 
 impl<'a, 'de> de::MapAccess<'de> for MapAccess<'a, 'de> {
@@ -58,23 +142,46 @@ impl<'a, 'de> de::MapAccess<'de> for MapAccess<'a, 'de> {
     ) -> Result<Option<K::Value>, Self::Error> {
         if let Some(attr) = self.attributes.next() {
             // try getting map from attributes (key= "value")
-            self.value = MapValue::Attribute { value: attr.value };
-            seed.deserialize(Self::create_attr_key(&attr.name).into_deserializer())
-                .map(Some)
+            let key = self.create_attr_key(&attr.name);
+            self.value = MapValue::Attribute {
+                name: attr.name,
+                value: attr.value,
+            };
+            seed.deserialize(key.as_ref().into_deserializer()).map(Some)
+        } else if self.skip_content {
+            self.de.read_to_end()?;
+            Ok(None)
+        } else if let Some(captured) = &mut self.captured {
+            if self.has_comments_field {
+                self.has_comments_field = false;
+                self.value = MapValue::Captured(std::mem::take(&mut captured.comments));
+                seed.deserialize(COMMENTS.into_deserializer()).map(Some)
+            } else if self.has_pis_field {
+                self.has_pis_field = false;
+                self.value = MapValue::Captured(std::mem::take(&mut captured.pis));
+                seed.deserialize(PIS.into_deserializer()).map(Some)
+            } else {
+                Ok(None)
+            }
         } else if self.has_value_field {
             if self.read_value_field {
-                Ok(None)
+                self.content_read(seed)
             } else {
                 self.value = MapValue::InnerValue;
                 self.read_value_field = true;
                 seed.deserialize(INNER_VALUE.into_deserializer()).map(Some)
             }
         } else {
-            self.value = MapValue::Nested;
             if let Some(stag) = self.de.next_maybe_start()? {
-                seed.deserialize(stag.name.into_deserializer()).map(Some)
+                self.value = MapValue::Nested(stag.name.clone());
+                let key = if self.local_names {
+                    local_name(stag.name)
+                } else {
+                    stag.name
+                };
+                seed.deserialize(key.into_deserializer()).map(Some)
             } else {
-                Ok(None)
+                self.content_read(seed)
             }
         }
     }
@@ -84,9 +191,43 @@ impl<'a, 'de> de::MapAccess<'de> for MapAccess<'a, 'de> {
         seed: K,
     ) -> Result<K::Value, Self::Error> {
         match std::mem::replace(&mut self.value, MapValue::Empty) {
-            MapValue::Attribute { value } => seed.deserialize(EscapedDeserializer::new(value)),
-            MapValue::Nested | MapValue::InnerValue => seed.deserialize(&mut *self.de),
+            MapValue::Attribute { name, value } => {
+                let segment = format!("@{}", name);
+                seed.deserialize(
+                    EscapedDeserializer::new(value)
+                        .with_bool_attribute(name, self.de.bool_attributes),
+                )
+                .map_err(|err| err.in_path(&segment))
+            }
+            MapValue::Nested(name) => seed
+                .deserialize(&mut *self.de)
+                .map_err(|err| err.in_path(&name)),
+            MapValue::InnerValue => seed.deserialize(&mut *self.de).map_err(|err| {
+                if let Reason::NoMarkupExpected = err.reason() {
+                    let offset = err.offset();
+                    err.with_hint(
+                        offset,
+                        format!("child element next to {} text", INNER_VALUE),
+                    )
+                } else {
+                    err
+                }
+            }),
+            MapValue::Captured(values) => {
+                seed.deserialize(SeqDeserializer::new(values.into_iter()))
+            }
             MapValue::Empty => unreachable!(),
         }
     }
 }
+
+/// Name without namespace prefix
+fn local_name(name: Cow<str>) -> Cow<str> {
+    match name {
+        Cow::Borrowed(name) => Cow::Borrowed(name.rsplit(':').next().unwrap_or(name)),
+        Cow::Owned(name) => match name.rsplit_once(':') {
+            Some((_, local)) => Cow::Owned(local.to_string()),
+            None => Cow::Owned(name),
+        },
+    }
+}
@@ -106,17 +106,33 @@
 //!     Ok(html)
 //! }
 //! ```
+//!
+//! # Whitespace
+//!
+//! Text content deserialized into numbers, booleans and enums is trimmed of XML whitespace.
+//! Strings keep their whitespace unless disabled with
+//! [`RootDeserializer::preserve_whitespace`].
+//!
+//! # Sequences
+//!
+//! Sequences are read from repeated elements with the name of the field. A sequence of scalars
+//! like `Vec<String>` can also be wrapped in one element, in which case the text of each child
+//! element is one item: `<tags><tag>a</tag><tag>b</tag></tags>`. Only an element with child
+//! elements wraps the sequence, so `<tag/>` is an empty string item.
 
 use std::borrow::Cow;
+use std::collections::VecDeque;
 use std::io::BufRead;
+use std::ops::Range;
 
 use serde::de::{self, DeserializeOwned, IntoDeserializer, Visitor};
 use serde::{serde_if_integer128, Deserialize};
 
 use xrs_chars::XmlAsciiChar;
-use xrs_parser::{Reader, STag, XmlEvent};
+use xrs_parser::{Attribute, Reader, STag, XmlError, XmlEvent};
 
 use crate::de::cow::{CowStrExt, StrExt};
+use crate::de::source::EventSource;
 use crate::error::Reason;
 use crate::error::ResultExt;
 use crate::Error;
@@ -125,14 +141,70 @@ mod cow;
 mod escape;
 mod map;
 mod seq;
+mod source;
 mod var;
 
 const INNER_VALUE: &str = "$value";
+const COMMENTS: &str = "$comment";
+const PIS: &str = "$pi";
+
+/// How `bool` fields are deserialized from attributes
+///
+/// A missing attribute still needs `#[serde(default)]` on the field to become `false`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BoolAttributes {
+    /// Only `true`, `1`, `false` and `0` are accepted
+    Value,
+    /// The presence of the attribute means `true` regardless of its value
+    Presence,
+    /// An attribute whose value equals its name like `checked="checked"` means `true`,
+    /// other values are handled like [`BoolAttributes::Value`]
+    Named,
+}
+
+impl Default for BoolAttributes {
+    fn default() -> Self {
+        BoolAttributes::Value
+    }
+}
+
+/// Comments and processing instructions directly contained in an element
+struct Capture<'a> {
+    /// Depth of the element
+    depth: usize,
+    comments: Vec<Cow<'a, str>>,
+    pis: Vec<Cow<'a, str>>,
+}
 
 /// An xml deserializer
 pub struct Deserializer<'a> {
-    reader: Reader<'a>,
-    peek: Option<XmlEvent<'a>>,
+    source: EventSource<'a>,
+    /// Event read ahead by `peek` with its offset
+    peek: Option<(usize, XmlEvent<'a>)>,
+    /// Offset of the event last returned by `next` or `peek`
+    offset: usize,
+    /// Offset after the event last read from the source or replayed
+    end_offset: usize,
+    /// End tag of an `EmptyElement` event returned as start tag
+    empty_etag: Option<XmlEvent<'a>>,
+    /// Buffer for the key of an attribute
+    attr_key: String,
+    /// Names of the open elements returned by `next`
+    open: Vec<Cow<'a, str>>,
+    /// Attributes of the start tag last returned by `next`
+    attributes: Vec<Attribute<'a>>,
+    /// Events read ahead by `collect_siblings` with their start and end offsets, which are
+    /// returned before reading on
+    replay: VecDeque<(Range<usize>, XmlEvent<'a>)>,
+    /// Collected content of the elements deserialized into structs with `$comment` or `$pi`
+    /// fields
+    captures: Vec<Capture<'a>>,
+    bool_attributes: BoolAttributes,
+    /// Only accept the `xsd:boolean` values in element text
+    strict_booleans: bool,
+    map_attributes: bool,
+    /// Keep leading and trailing whitespace of text deserialized into strings
+    preserve_whitespace: bool,
 }
 
 /// Deserialize a xml string
@@ -141,6 +213,28 @@ pub fn from_str<'de, T: Deserialize<'de>>(s: &'de str) -> Result<T, Error> {
     T::deserialize(&mut de)
 }
 
+/// Deserialize a struct from the attributes of the root element of a xml string
+///
+/// The content of the root element is skipped, so only `@` fields are filled.
+pub fn from_attributes_str<'de, T: Deserialize<'de>>(s: &'de str) -> Result<T, Error> {
+    let mut de = Deserializer::new(Reader::new(s)).attributes_only();
+    T::deserialize(&mut de)
+}
+
+/// Deserialize from events, e.g. of a filtered [`Reader`]
+///
+/// Start tags need their attributes in `attrs`, which are only returned by
+/// [`Reader::drain_attributes`] for the events of a [`Reader`]. Errors have no offsets in the
+/// document.
+pub fn from_events<'de, I, T>(events: I) -> Result<T, Error>
+where
+    I: Iterator<Item = Result<XmlEvent<'de>, XmlError>> + 'de,
+    T: Deserialize<'de>,
+{
+    let mut de = Deserializer::from_events(events);
+    T::deserialize(&mut de)
+}
+
 /// Deserialize from a reader
 pub fn from_reader<R: BufRead, T: DeserializeOwned>(mut reader: R) -> Result<T, Error> {
     let mut buf = String::new();
@@ -151,8 +245,36 @@ pub fn from_reader<R: BufRead, T: DeserializeOwned>(mut reader: R) -> Result<T,
 impl<'a> Deserializer<'a> {
     /// Get a new deserializer
     pub fn new(reader: Reader<'a>) -> RootDeserializer<'a> {
+        Self::with_source(EventSource::Reader(reader))
+    }
+
+    /// Get a new deserializer from events with the attributes in the start tags
+    pub fn from_events<I>(events: I) -> RootDeserializer<'a>
+    where
+        I: Iterator<Item = Result<XmlEvent<'a>, XmlError>> + 'a,
+    {
+        Self::with_source(EventSource::events(Box::new(events)))
+    }
+
+    fn with_source(source: EventSource<'a>) -> RootDeserializer<'a> {
         RootDeserializer {
-            de: Self { reader, peek: None },
+            de: Self {
+                source,
+                peek: None,
+                offset: 0,
+                end_offset: 0,
+                empty_etag: None,
+                attr_key: String::new(),
+                open: vec![],
+                attributes: vec![],
+                replay: VecDeque::new(),
+                captures: vec![],
+                bool_attributes: BoolAttributes::default(),
+                strict_booleans: false,
+                map_attributes: false,
+                preserve_whitespace: true,
+            },
+            attributes_only: false,
         }
     }
 
@@ -163,38 +285,210 @@ impl<'a> Deserializer<'a> {
 
     fn peek(&mut self) -> Result<&XmlEvent<'a>, Error> {
         if self.peek.is_none() {
-            self.peek = Some(self.next()?);
+            let evt = self.next()?;
+            self.peek = Some((self.offset, evt));
         }
-        Ok(self.peek.as_ref().unwrap())
+        Ok(&self.peek.as_ref().unwrap().1)
     }
 
     fn next(&mut self) -> Result<XmlEvent<'a>, Error> {
-        if let Some(e) = self.peek.take() {
+        if let Some((offset, e)) = self.peek.take() {
+            self.offset = offset;
+            return Ok(e);
+        }
+        if let Some(e) = self.empty_etag.take() {
+            self.open.pop();
+            self.offset = self.end_offset;
             return Ok(e);
         }
 
         loop {
-            if let Some(evt) = self.reader.next()? {
-                match evt {
-                    e @ (XmlEvent::STag(_) | XmlEvent::ETag(_) | XmlEvent::Characters(_)) => {
-                        return Ok(e);
+            let evt = self.next_raw()?;
+            match &evt {
+                XmlEvent::STag(stag) | XmlEvent::EmptyElement(stag) => {
+                    self.open.push(stag.name.clone())
+                }
+                XmlEvent::ETag(_) => {
+                    self.open.pop();
+                }
+                _ => {}
+            }
+            match evt {
+                XmlEvent::STag(mut stag) => {
+                    self.attributes = std::mem::take(&mut stag.attrs);
+                    return Ok(XmlEvent::STag(stag));
+                }
+                e @ (XmlEvent::ETag(_) | XmlEvent::Characters(_)) => {
+                    return Ok(e);
+                }
+                // the deserializer works with start and end tags
+                XmlEvent::EmptyElement(mut stag) => {
+                    self.attributes = std::mem::take(&mut stag.attrs);
+                    self.empty_etag = Some(XmlEvent::etag(stag.name.clone()));
+                    return Ok(XmlEvent::STag(stag));
+                }
+                XmlEvent::Comment(comment) => {
+                    if let Some(capture) = self.capture() {
+                        capture.comments.push(comment);
+                    }
+                }
+                XmlEvent::PI(pi) => {
+                    if let Some(capture) = self.capture() {
+                        capture.pis.push(match pi.data {
+                            Some(data) => format!("{} {}", pi.target, data).into(),
+                            None => pi.target,
+                        });
                     }
-                    _ => (),
                 }
-            } else {
-                return if let Some(top) = self.reader.top_name() {
+                XmlEvent::CData(cdata) => return Ok(XmlEvent::Characters(cdata)),
+                XmlEvent::Error(err) => return Err(err.into()),
+                XmlEvent::CharRef(char_ref) => {
+                    return Ok(XmlEvent::Characters(char_ref.character.to_string().into()));
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Next event read ahead or from the source with the attributes in start tags
+    fn next_raw(&mut self) -> Result<XmlEvent<'a>, Error> {
+        if let Some((range, evt)) = self.replay.pop_front() {
+            self.offset = range.start;
+            self.end_offset = range.end;
+            return Ok(evt);
+        }
+
+        self.offset = self.source.cursor_offset();
+        let evt = self.source.next()?;
+        self.end_offset = self.source.cursor_offset();
+        match evt {
+            Some(evt) => Ok(evt),
+            None => {
+                if let Some(top) = self.source.top_name() {
                     Err(Error::new(Reason::Eof, 0)
                         .with_hint(0, format!("Expecting end of this element: {}", top)))
                 } else {
-                    unreachable!("Expecting an element at end of document")
-                };
+                    // only events can end without a root element
+                    Err(Error::new(Reason::Eof, 0))
+                }
             }
         }
     }
 
+    /// Take back the event returned by `peek` in the form `next_raw` returned it, with its
+    /// offsets
+    fn unread(&mut self) -> Option<(Range<usize>, XmlEvent<'a>)> {
+        let (offset, evt) = self.peek.take()?;
+        let evt = match evt {
+            XmlEvent::STag(mut stag) => {
+                self.open.pop();
+                stag.attrs = std::mem::take(&mut self.attributes);
+                if self.empty_etag.take().is_some() {
+                    XmlEvent::EmptyElement(stag)
+                } else {
+                    XmlEvent::STag(stag)
+                }
+            }
+            XmlEvent::ETag(etag) => {
+                self.open.push(etag.name.clone());
+                XmlEvent::ETag(etag)
+            }
+            evt => evt,
+        };
+        Some((offset..self.end_offset, evt))
+    }
+
+    /// Move the following sibling elements named `name` in front of the other siblings
+    ///
+    /// The content is read ahead up to the end tag of the parent element, so repeated
+    /// elements are found regardless of the elements in between. The order of the elements
+    /// with the same name and of the other content is kept.
+    fn collect_siblings(&mut self, name: &str) -> Result<(), Error> {
+        let mut matching = VecDeque::new();
+        let mut others = VecDeque::new();
+        let mut evt = self.unread();
+        loop {
+            let (range, next) = match evt.take() {
+                Some(evt) => evt,
+                None => {
+                    let next = self.next_raw()?;
+                    (self.offset..self.end_offset, next)
+                }
+            };
+            match next {
+                XmlEvent::STag(stag) => {
+                    let target = if stag.name == name {
+                        &mut matching
+                    } else {
+                        &mut others
+                    };
+                    target.push_back((range, XmlEvent::STag(stag)));
+                    self.read_raw_element(target)?;
+                }
+                XmlEvent::EmptyElement(stag) if stag.name == name => {
+                    matching.push_back((range, XmlEvent::EmptyElement(stag)))
+                }
+                end @ XmlEvent::ETag(_) => {
+                    others.push_back((range, end));
+                    break;
+                }
+                other => others.push_back((range, other)),
+            }
+        }
+
+        others.append(&mut self.replay);
+        matching.append(&mut others);
+        self.replay = matching;
+        Ok(())
+    }
+
+    /// Read the content and the end tag of an element with `next_raw`
+    fn read_raw_element(
+        &mut self,
+        events: &mut VecDeque<(Range<usize>, XmlEvent<'a>)>,
+    ) -> Result<(), Error> {
+        let mut depth = 1;
+        while depth > 0 {
+            let evt = self.next_raw()?;
+            match &evt {
+                XmlEvent::STag(_) => depth += 1,
+                XmlEvent::ETag(_) => depth -= 1,
+                _ => {}
+            }
+            events.push_back((self.offset..self.end_offset, evt));
+        }
+        Ok(())
+    }
+
+    /// Attributes of the start tag last returned by `next`
+    fn drain_attributes(&mut self) -> Vec<Attribute<'a>> {
+        std::mem::take(&mut self.attributes)
+    }
+
+    /// Capture of the element directly containing the current content
+    fn capture(&mut self) -> Option<&mut Capture<'a>> {
+        let depth = self.open.len();
+        self.captures
+            .last_mut()
+            .filter(|capture| capture.depth == depth)
+    }
+
+    /// Start collecting the comments and processing instructions of the current element
+    fn start_capture(&mut self) {
+        self.captures.push(Capture {
+            depth: self.open.len(),
+            comments: vec![],
+            pis: vec![],
+        });
+    }
+
+    fn finish_capture(&mut self) -> Capture<'a> {
+        self.captures.pop().expect("capture was started")
+    }
+
     fn withdraw(&mut self, evt: XmlEvent<'a>) {
         assert!(self.peek.is_none());
-        self.peek = Some(evt);
+        self.peek = Some((self.offset, evt));
     }
 
     fn next_maybe_start(&mut self) -> Result<Option<STag<'a>>, Error> {
@@ -225,19 +519,27 @@ impl<'a> Deserializer<'a> {
                     }
                 }
                 XmlEvent::ETag(_) => return Ok(result),
-                _ => return Err(Error::new(Reason::NoMarkupExpected, 0)),
+                _ => return Err(Error::new(Reason::NoMarkupExpected, self.offset)),
             }
         }
     }
 
-    fn next_trimmed_text(&mut self) -> Result<Cow<'a, str>, Error> {
+    /// Consumes Characters with terminating end tag and returns the offset of the trimmed text
+    fn next_trimmed_text(&mut self) -> Result<(usize, Cow<'a, str>), Error> {
+        self.peek()?;
+        let offset = self.offset;
         let mut text = self.next_text()?;
+        let leading = text.len()
+            - text
+                .trim_start_matches(|c: char| c.is_xml_whitespace())
+                .len();
         text.trim_matches_inplace(|c: char| c.is_xml_whitespace());
-        Ok(text)
+        Ok((offset + leading, text))
     }
 
+    /// Consumes the remaining content including the end tag of the current element
     fn read_to_end(&mut self) -> Result<(), Error> {
-        let mut depth = 1;
+        let mut depth = 0;
         while depth >= 0 {
             match self.next()? {
                 XmlEvent::STag(_) => depth += 1,
@@ -261,16 +563,21 @@ impl<'a> Deserializer<'a> {
     }
 
     pub(crate) fn error(&self, reason: Reason) -> Error {
-        Error::new(reason, self.reader.cursor_offset())
+        Error::new(reason, self.end_offset)
     }
 
+    /// Error at the start of the peeked event or else of the event last returned by `next`
     pub(crate) fn peek_error(&self, reason: Reason) -> Error {
-        Error::new(reason, self.reader.cursor_offset())
+        let offset = match &self.peek {
+            Some((offset, _)) => *offset,
+            None => self.offset,
+        };
+        Error::new(reason, offset)
     }
 
     pub(crate) fn fix_position(&self, err: Error) -> Error {
         if err.offset() == 0 {
-            err.with_position(self.reader.cursor_offset())
+            err.with_position(self.end_offset)
         } else {
             err
         }
@@ -280,7 +587,8 @@ impl<'a> Deserializer<'a> {
 macro_rules! deserialize_type {
     ($deserialize:ident => $ty:path, $visit:ident) => {
         fn $deserialize<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
-            let value = self.next_trimmed_text()?.parse::<$ty>().at_offset(0)?;
+            let (offset, text) = self.next_trimmed_text()?;
+            let value = text.parse::<$ty>().at_offset(offset)?;
             visitor.$visit(value)
         }
     };
@@ -293,7 +601,7 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
         self.skip_ignorable_and_whitespace()?;
 
         match self.peek()? {
-            XmlEvent::STag(_) => self.deserialize_map(visitor),
+            XmlEvent::STag(_) => self.deserialize_struct("", &[], visitor),
             XmlEvent::ETag(_) => self.deserialize_unit(visitor),
             _ => self.deserialize_string(visitor),
         }
@@ -316,10 +624,13 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     }
 
     fn deserialize_bool<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
-        match self.next_text()?.as_ref() {
-            "true" | "1" | "yes" | "on" => visitor.visit_bool(true),
-            "false" | "0" | "no" | "off" => visitor.visit_bool(false),
-            e => Err(self.error(Reason::InvalidBoolean(e.to_string()))),
+        let (offset, text) = self.next_trimmed_text()?;
+        match text.as_ref() {
+            "true" | "1" => visitor.visit_bool(true),
+            "false" | "0" => visitor.visit_bool(false),
+            "yes" | "on" if !self.strict_booleans => visitor.visit_bool(true),
+            "no" | "off" if !self.strict_booleans => visitor.visit_bool(false),
+            e => Err(Error::new(Reason::InvalidBoolean(e.to_string()), offset)),
         }
     }
 
@@ -332,7 +643,12 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     }
 
     fn deserialize_string<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
-        match self.next_text()? {
+        let text = if self.preserve_whitespace {
+            self.next_text()?
+        } else {
+            self.next_trimmed_text()?.1
+        };
+        match text {
             Cow::Borrowed(borrowed) => visitor.visit_str(borrowed),
             Cow::Owned(owned) => visitor.visit_string(owned),
         }
@@ -379,14 +695,9 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     }
 
     fn deserialize_seq<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
-        let result = visitor
+        visitor
             .visit_seq(seq::SeqAccess::new(self, None)?)
-            .map_err(|err| self.fix_position(err));
-        if let XmlEvent::ETag(_) = self.next()? {
-            result
-        } else {
-            Err(self.error(Reason::EndOfArrayExpected))
-        }
+            .map_err(|err| self.fix_position(err))
     }
 
     fn deserialize_tuple<V: de::Visitor<'de>>(
@@ -414,7 +725,8 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     }
 
     fn deserialize_map<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
-        self.deserialize_struct("", &[], visitor)
+        let map = map::MapAccess::map(self);
+        visitor.visit_map(map).map_err(|err| self.fix_position(err))
     }
 
     fn deserialize_struct<V: de::Visitor<'de>>(
@@ -423,7 +735,7 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
         fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value, Error> {
-        let map = map::MapAccess::new(self, fields.contains(&INNER_VALUE))?;
+        let map = map::MapAccess::new(self, fields)?;
         let value = visitor
             .visit_map(map)
             .map_err(|err| self.fix_position(err))?;
@@ -440,7 +752,7 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
 
         match self.peek()? {
             XmlEvent::Characters(_) => visitor
-                .visit_enum(self.next_trimmed_text()?.into_deserializer())
+                .visit_enum(self.next_trimmed_text()?.1.into_deserializer())
                 .map_err(|err| self.fix_position(err)),
             _ => visitor
                 .visit_enum(var::EnumAccess::new(self))
@@ -453,23 +765,58 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     }
 
     fn deserialize_ignored_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
-        let mut depth = 1;
-        while depth >= 0 {
-            match self.next()? {
-                XmlEvent::STag(_) => depth += 1,
-                XmlEvent::ETag(_) => depth -= 1,
-                _ => {}
-            }
-        }
+        self.read_to_end()?;
         visitor.visit_unit().map_err(|err| self.fix_position(err))
     }
 }
 
 pub struct RootDeserializer<'de> {
     de: Deserializer<'de>,
+    attributes_only: bool,
 }
 
 impl<'de> RootDeserializer<'de> {
+    /// Fill the root struct only from the attributes and skip the content of the root element
+    pub fn attributes_only(mut self) -> Self {
+        self.attributes_only = true;
+        self
+    }
+
+    /// Set how `bool` fields are deserialized from attributes, default is
+    /// [`BoolAttributes::Value`]
+    pub fn bool_attributes(mut self, bool_attributes: BoolAttributes) -> Self {
+        self.de.bool_attributes = bool_attributes;
+        self
+    }
+
+    /// Only accept `true`, `false`, `1` and `0` like `xsd:boolean` for `bool` values in element
+    /// text, default is `false`
+    ///
+    /// The lenient default also accepts `yes` and `on` as `true` and `no` and `off` as `false`.
+    pub fn strict_booleans(mut self, strict_booleans: bool) -> Self {
+        self.de.strict_booleans = strict_booleans;
+        self
+    }
+
+    /// Add the attributes as `@name` entries to maps, default is `false`
+    ///
+    /// Maps like `HashMap<String, T>` are filled with the child elements keyed by their local
+    /// names. Attributes are skipped unless enabled here.
+    pub fn map_attributes(mut self, map_attributes: bool) -> Self {
+        self.de.map_attributes = map_attributes;
+        self
+    }
+
+    /// Keep leading and trailing whitespace of element text deserialized into strings, default
+    /// is `true`
+    ///
+    /// Text deserialized into numbers, booleans and enums is always trimmed. Attribute values
+    /// are never trimmed.
+    pub fn preserve_whitespace(mut self, preserve_whitespace: bool) -> Self {
+        self.de.preserve_whitespace = preserve_whitespace;
+        self
+    }
+
     fn root_struct_error(&self) -> Error {
         self.de.error(Reason::RootStruct)
     }
@@ -506,10 +853,13 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut RootDeserializer<'de> {
         visitor: V,
     ) -> Result<V::Value, Error> {
         match self.de.next()? {
-            XmlEvent::STag(e) if &e.name == name => {
-                (&mut self.de).deserialize_unit_struct(name, visitor)
-            }
-            XmlEvent::STag(_) => Err(self.de.error(Reason::Tag(name))),
+            XmlEvent::STag(e) if &e.name == name => (&mut self.de)
+                .deserialize_unit_struct(name, visitor)
+                .map_err(|err| err.in_path(name)),
+            XmlEvent::STag(e) => Err(self.de.error(Reason::Tag {
+                expected: name,
+                found: e.name.into_owned(),
+            })),
             _ => Err(self.de.error(Reason::Start)),
         }
     }
@@ -520,10 +870,13 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut RootDeserializer<'de> {
         visitor: V,
     ) -> Result<V::Value, Error> {
         match self.de.next()? {
-            XmlEvent::STag(e) if &e.name == name => {
-                (&mut self.de).deserialize_newtype_struct(name, visitor)
-            }
-            XmlEvent::STag(_) => Err(self.de.error(Reason::Tag(name))),
+            XmlEvent::STag(e) if &e.name == name => (&mut self.de)
+                .deserialize_newtype_struct(name, visitor)
+                .map_err(|err| err.in_path(name)),
+            XmlEvent::STag(e) => Err(self.de.error(Reason::Tag {
+                expected: name,
+                found: e.name.into_owned(),
+            })),
             _ => Err(self.de.error(Reason::Start)),
         }
     }
@@ -547,11 +900,16 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut RootDeserializer<'de> {
         Err(self.root_struct_error())
     }
 
-    fn deserialize_map<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        Err(self.root_struct_error())
+        match self.de.next()? {
+            XmlEvent::STag(e) => (&mut self.de)
+                .deserialize_map(visitor)
+                .map_err(|err| err.in_path(&e.name)),
+            _ => Err(self.de.error(Reason::Start)),
+        }
     }
 
     fn deserialize_struct<V: de::Visitor<'de>>(
@@ -561,10 +919,19 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut RootDeserializer<'de> {
         visitor: V,
     ) -> Result<V::Value, Error> {
         match self.de.next()? {
-            XmlEvent::STag(e) if &e.name == name => {
-                (&mut self.de).deserialize_struct(name, fields, visitor)
-            }
-            XmlEvent::STag(_) => Err(self.de.error(Reason::Tag(name))),
+            XmlEvent::STag(e) if &e.name == name && self.attributes_only => {
+                let map = map::MapAccess::attributes_only(&mut self.de);
+                visitor
+                    .visit_map(map)
+                    .map_err(|err| self.de.fix_position(err).in_path(name))
+            }
+            XmlEvent::STag(e) if &e.name == name => (&mut self.de)
+                .deserialize_struct(name, fields, visitor)
+                .map_err(|err| err.in_path(name)),
+            XmlEvent::STag(e) => Err(self.de.error(Reason::Tag {
+                expected: name,
+                found: e.name.into_owned(),
+            })),
             _ => Err(self.de.error(Reason::Start)),
         }
     }
@@ -576,10 +943,13 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut RootDeserializer<'de> {
         visitor: V,
     ) -> Result<V::Value, Error> {
         match self.de.next()? {
-            XmlEvent::STag(e) if &e.name == name => {
-                (&mut self.de).deserialize_enum(name, variants, visitor)
-            }
-            XmlEvent::STag(_) => Err(self.de.error(Reason::Tag(name))),
+            XmlEvent::STag(e) if &e.name == name => (&mut self.de)
+                .deserialize_enum(name, variants, visitor)
+                .map_err(|err| err.in_path(name)),
+            XmlEvent::STag(e) => Err(self.de.error(Reason::Tag {
+                expected: name,
+                found: e.name.into_owned(),
+            })),
             _ => Err(self.de.error(Reason::Start)),
         }
     }
@@ -649,6 +1019,495 @@ mod tests {
         );
     }
 
+    #[test]
+    fn struct_from_attributes_only() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(rename = "rec")]
+        struct Rec {
+            #[serde(rename = "@a")]
+            a: u32,
+            #[serde(rename = "@b")]
+            b: String,
+        }
+
+        let rec: Rec =
+            from_attributes_str(r#"<rec a="1" b="2"><ignored/>text<x><y/></x></rec>"#).unwrap();
+        assert_eq!(
+            rec,
+            Rec {
+                a: 1,
+                b: "2".to_string()
+            }
+        );
+
+        let rec: Rec = from_attributes_str(r#"<rec a="1" b="2"/>"#).unwrap();
+        assert_eq!(
+            rec,
+            Rec {
+                a: 1,
+                b: "2".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn empty_element_events() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(rename = "list")]
+        struct List {
+            #[serde(rename = "item")]
+            items: Vec<Item>,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Item {
+            #[serde(rename = "@id")]
+            id: u32,
+        }
+
+        let input = r#"<list><item id="1"/><item id="2"></item><item id="3"/></list>"#;
+        let mut de = Deserializer::new(Reader::new(input).with_empty_element_events());
+        let list = List::deserialize(&mut de).unwrap();
+        assert_eq!(
+            list,
+            List {
+                items: vec![Item { id: 1 }, Item { id: 2 }, Item { id: 3 }]
+            }
+        );
+        assert_eq!(list, parse(input));
+    }
+
+    #[test]
+    fn comments_and_pis() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(rename = "doc")]
+        struct Doc {
+            #[serde(rename = "$comment")]
+            comments: Vec<String>,
+            #[serde(rename = "$pi")]
+            pis: Vec<String>,
+            section: Section,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(rename = "section")]
+        struct Section {
+            #[serde(rename = "$comment")]
+            comments: Vec<String>,
+            title: Title,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Title {
+            #[serde(rename = "$value")]
+            text: String,
+            #[serde(rename = "$comment")]
+            comments: Vec<String>,
+        }
+
+        let doc: Doc = parse(
+            r#"<!-- prolog --><doc>
+                <!-- first -->
+                <?generator xrs?>
+                <section><!-- nested --><title>Intro<!-- in text -->duction</title></section>
+                <?end?>
+                <!-- last -->
+            </doc>"#,
+        );
+        assert_eq!(
+            doc,
+            Doc {
+                comments: vec![" first ".to_string(), " last ".to_string()],
+                pis: vec!["generator xrs".to_string(), "end".to_string()],
+                section: Section {
+                    comments: vec![" nested ".to_string()],
+                    title: Title {
+                        text: "Introduction".to_string(),
+                        comments: vec![" in text ".to_string()],
+                    },
+                },
+            }
+        );
+
+        let doc: Section = parse("<section><title>x</title></section>");
+        assert!(doc.comments.is_empty());
+        let doc: Section = parse("<section><title/></section>");
+        assert!(doc.title.comments.is_empty());
+    }
+
+    #[test]
+    fn comments_ignored_without_field() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(rename = "doc")]
+        struct Doc {
+            name: String,
+        }
+
+        let doc: Doc = parse("<doc><!-- c --><name>a<!-- d -->b</name><?pi?></doc>");
+        assert_eq!(
+            doc,
+            Doc {
+                name: "ab".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn error_path() {
+        #[derive(Debug, Deserialize)]
+        #[serde(rename = "root")]
+        struct Root {
+            #[allow(dead_code)]
+            items: Items,
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct Items {
+            #[allow(dead_code)]
+            item: Vec<Item>,
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct Item {
+            #[serde(rename = "@price")]
+            #[allow(dead_code)]
+            price: u32,
+        }
+
+        let err = from_str::<Root>(
+            r#"<root><items><item price="1"/><item price="2"/><item price="3"/><item price="abc"/></items></root>"#,
+        )
+        .unwrap_err();
+        assert_eq!(["root", "items", "item[3]", "@price"], err.path());
+        assert!(
+            err.to_string()
+                .starts_with("root > items > item[3] > @price: Invalid integer"),
+            "{}",
+            err
+        );
+
+        let err = from_str::<Root>("<root><items><item/></items></root>").unwrap_err();
+        assert_eq!(["root", "items", "item[0]"], err.path());
+    }
+
+    #[test]
+    fn root_name_mismatch() {
+        #[derive(Debug, Deserialize)]
+        #[serde(rename = "foo")]
+        struct Foo {
+            #[serde(rename = "@id")]
+            #[allow(dead_code)]
+            id: Option<String>,
+        }
+
+        let err = from_str::<Foo>("<bar/>").unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("Expected root element 'foo', found 'bar'"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn error_offset() {
+        #[derive(Debug, Deserialize)]
+        #[serde(rename = "person")]
+        struct Person {
+            #[allow(dead_code)]
+            name: String,
+            #[allow(dead_code)]
+            age: u32,
+        }
+
+        let input = "<person><name>Max</name><age> abc </age></person>";
+        let err = from_str::<Person>(input).unwrap_err();
+        assert_eq!(input.find("abc").unwrap(), err.offset());
+        assert!(err.to_string().starts_with("person > age: Invalid integer"));
+
+        let input = "<person><name>Max</name><age>1.5</age></person>";
+        let err = from_str::<Person>(input).unwrap_err();
+        assert_eq!(input.find("1.5").unwrap(), err.offset());
+    }
+
+    #[test]
+    fn replayed_error_offset() {
+        #[derive(Debug, Deserialize)]
+        #[serde(rename = "c")]
+        struct C {
+            #[allow(dead_code)]
+            item: Vec<u32>,
+            #[allow(dead_code)]
+            tag: String,
+            #[allow(dead_code)]
+            age: u32,
+        }
+
+        // `tag` and `age` are read ahead to collect the `item` elements
+        let input = "<c><item>1</item><tag>a</tag><age>xyz</age><item>2</item></c>";
+        let err = from_str::<C>(input).unwrap_err();
+        assert_eq!(34, err.offset());
+    }
+
+    #[test]
+    fn peeked_error_offset() {
+        #[derive(Debug, Deserialize)]
+        enum Kind {
+            A,
+        }
+
+        #[derive(Debug, Deserialize)]
+        #[serde(rename = "item")]
+        struct Item {
+            #[allow(dead_code)]
+            kind: Kind,
+        }
+
+        let input = "<item><kind> </kind><next/></item>";
+        let err = from_str::<Item>(input).unwrap_err();
+        assert_eq!(input.find("</kind>").unwrap(), err.offset());
+    }
+
+    #[test]
+    fn map_by_element_names() {
+        use std::collections::HashMap;
+
+        let config: HashMap<String, u32> =
+            parse("<config><timeout>5</timeout><retries>3</retries></config>");
+        assert_eq!(
+            config,
+            HashMap::from([("timeout".to_string(), 5), ("retries".to_string(), 3)])
+        );
+
+        let config: HashMap<String, String> =
+            parse(r#"<config mode="fast"><x:name xmlns:x="urn:x">a</x:name></config>"#);
+        assert_eq!(
+            config,
+            HashMap::from([("name".to_string(), "a".to_string())])
+        );
+    }
+
+    #[test]
+    fn map_enum_keys() {
+        use std::collections::HashMap;
+
+        #[derive(Debug, Deserialize, PartialEq, Eq, Hash)]
+        #[serde(rename_all = "lowercase")]
+        enum Limit {
+            Cpu,
+            Memory,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(rename = "pod")]
+        struct Pod {
+            limits: HashMap<Limit, u32>,
+        }
+
+        let pod: Pod = parse("<pod><limits><cpu>2</cpu><memory>512</memory></limits></pod>");
+        assert_eq!(
+            pod.limits,
+            HashMap::from([(Limit::Cpu, 2), (Limit::Memory, 512)])
+        );
+        assert!(from_str::<Pod>("<pod><limits><disk>1</disk></limits></pod>").is_err());
+    }
+
+    #[test]
+    fn map_attributes() {
+        use std::collections::HashMap;
+
+        let input = r#"<config mode="fast"><timeout>5</timeout></config>"#;
+        let mut de = Deserializer::from_str(input).map_attributes(true);
+        let config = HashMap::<String, String>::deserialize(&mut de).unwrap();
+        assert_eq!(
+            config,
+            HashMap::from([
+                ("@mode".to_string(), "fast".to_string()),
+                ("timeout".to_string(), "5".to_string())
+            ])
+        );
+    }
+
+    #[test]
+    fn string_whitespace() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(rename = "doc")]
+        struct Doc {
+            name: String,
+            count: u32,
+            flag: bool,
+        }
+
+        let input = "<doc><name>  a b\n</name><count> 3 </count><flag>\ttrue </flag></doc>";
+
+        let doc: Doc = parse(input);
+        assert_eq!(
+            doc,
+            Doc {
+                name: "  a b\n".to_string(),
+                count: 3,
+                flag: true
+            }
+        );
+
+        let mut de = Deserializer::from_str(input).preserve_whitespace(false);
+        let doc = Doc::deserialize(&mut de).unwrap();
+        assert_eq!(
+            doc,
+            Doc {
+                name: "a b".to_string(),
+                count: 3,
+                flag: true
+            }
+        );
+    }
+
+    #[test]
+    fn lenient_booleans() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(rename = "flags")]
+        struct Flags {
+            a: bool,
+            b: bool,
+            c: bool,
+            d: bool,
+        }
+
+        assert_eq!(
+            parse::<Flags>("<flags><a>yes</a><b>off</b><c> on </c><d>no</d></flags>"),
+            Flags {
+                a: true,
+                b: false,
+                c: true,
+                d: false
+            }
+        );
+
+        let err = from_str::<Flags>("<flags><a>y</a><b>0</b><c>1</c><d>0</d></flags>").unwrap_err();
+        assert!(
+            err.to_string().contains("Invalid boolean value 'y'"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn strict_booleans() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(rename = "flags")]
+        struct Flags {
+            a: bool,
+            b: bool,
+        }
+
+        let mut de =
+            Deserializer::from_str("<flags><a>true</a><b>0</b></flags>").strict_booleans(true);
+        assert_eq!(
+            Flags::deserialize(&mut de).unwrap(),
+            Flags { a: true, b: false }
+        );
+
+        for value in ["yes", "on", "no", "off"] {
+            let xml = format!("<flags><a>{}</a><b>1</b></flags>", value);
+            let mut de = Deserializer::from_str(&xml).strict_booleans(true);
+            let err = Flags::deserialize(&mut de).unwrap_err();
+            assert!(
+                err.to_string()
+                    .contains(&format!("Invalid boolean value '{}'", value)),
+                "{}",
+                err
+            );
+            assert_eq!(xml.find(value).unwrap(), err.offset());
+        }
+    }
+
+    #[test]
+    fn bool_attribute_presence() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(rename = "input")]
+        struct Input {
+            #[serde(rename = "@disabled", default)]
+            disabled: bool,
+            #[serde(rename = "@checked", default)]
+            checked: bool,
+        }
+
+        let mut de = Deserializer::from_str(r#"<input disabled=""/>"#)
+            .bool_attributes(BoolAttributes::Presence);
+        let input = Input::deserialize(&mut de).unwrap();
+        assert_eq!(
+            input,
+            Input {
+                disabled: true,
+                checked: false
+            }
+        );
+
+        let mut de = Deserializer::from_str(r#"<input disabled="disabled" checked="0"/>"#)
+            .bool_attributes(BoolAttributes::Named);
+        let input = Input::deserialize(&mut de).unwrap();
+        assert_eq!(
+            input,
+            Input {
+                disabled: true,
+                checked: false
+            }
+        );
+
+        let res: Result<Input, Error> = from_str(r#"<input disabled="disabled"/>"#);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn enum_attribute() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        enum Kind {
+            A,
+            B,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(rename = "item")]
+        struct Item {
+            #[serde(rename = "@kind")]
+            kind: Kind,
+        }
+
+        let item: Item = parse(r#"<item kind="B"/>"#);
+        assert_eq!(item, Item { kind: Kind::B });
+
+        let res: Result<Item, Error> = from_str(r#"<item kind="C"/>"#);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn enum_attribute_rename_all() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(rename_all = "kebab-case")]
+        enum Kind {
+            PlainText,
+            Html,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(rename = "item")]
+        struct Item {
+            #[serde(rename = "@kind")]
+            kind: Kind,
+            #[serde(rename = "@alt")]
+            alt: Option<Kind>,
+        }
+
+        let item: Item = parse(r#"<item kind="plain-text" alt="html"/>"#);
+        assert_eq!(
+            item,
+            Item {
+                kind: Kind::PlainText,
+                alt: Some(Kind::Html)
+            }
+        );
+    }
+
     #[test]
     fn simple_struct_from_attribute_and_child() {
         #[derive(Debug, Deserialize, PartialEq)]
@@ -703,6 +1562,75 @@ mod tests {
         );
     }
 
+    #[test]
+    fn missing_optional_elements() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(rename = "item")]
+        struct Item {
+            name: String,
+            comment: Option<String>,
+            #[serde(default)]
+            count: u32,
+        }
+
+        assert_eq!(
+            parse::<Item>("<item><name>a</name></item>"),
+            Item {
+                name: "a".to_string(),
+                comment: None,
+                count: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn empty_elements() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(rename = "item")]
+        struct Item {
+            name: String,
+            comment: Option<String>,
+            source: Option<String>,
+        }
+
+        assert_eq!(
+            parse::<Item>("<item><name/><comment></comment><source/></item>"),
+            Item {
+                name: "".to_string(),
+                comment: Some("".to_string()),
+                source: Some("".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn elements_out_of_order() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(rename = "item")]
+        struct Item {
+            name: String,
+            comment: Option<String>,
+            count: u32,
+        }
+
+        assert_eq!(
+            parse::<Item>("<item><count>3</count><comment>c</comment><name>a</name></item>"),
+            Item {
+                name: "a".to_string(),
+                comment: Some("c".to_string()),
+                count: 3,
+            }
+        );
+        assert_eq!(
+            parse::<Item>("<item><count>3</count><name>a</name></item>"),
+            Item {
+                name: "a".to_string(),
+                comment: None,
+                count: 3,
+            }
+        );
+    }
+
     #[test]
     fn collection() {
         #[derive(Debug, Deserialize, PartialEq)]
@@ -757,6 +1685,113 @@ mod tests {
         );
     }
 
+    #[test]
+    fn shuffled_elements() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Item;
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(rename = "s")]
+        struct S {
+            a: Item,
+            b: Item,
+        }
+
+        assert_eq!(parse::<S>("<s><b/><a/></s>"), S { a: Item, b: Item });
+    }
+
+    #[test]
+    fn interleaved_collection() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Pre {
+            #[serde(rename = "@id")]
+            id: String,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(rename = "collection")]
+        struct Collection {
+            pre: Pre,
+            #[serde(rename = "item", default)]
+            items: Vec<u32>,
+            #[serde(rename = "tag", default)]
+            tags: Vec<String>,
+        }
+
+        let s = r##"
+            <collection>
+                <item>1</item>
+                <tag>a</tag>
+                <pre id="x"/>
+                <unknown><item>4</item></unknown>
+                <item>2</item>
+                <tag>b</tag>
+                <item>3</item>
+            </collection>
+        "##;
+
+        assert_eq!(
+            parse::<Collection>(s),
+            Collection {
+                pre: Pre {
+                    id: "x".to_string()
+                },
+                items: vec![1, 2, 3],
+                tags: vec!["a".to_string(), "b".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn wrapped_scalar_collection() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(rename = "post")]
+        struct Post {
+            tags: Vec<String>,
+            counts: Vec<u32>,
+        }
+
+        let s = r##"
+            <post>
+                <tags><tag>a</tag><tag>b</tag></tags>
+                <counts>
+                    <n>1</n>
+                    <n>2</n>
+                </counts>
+            </post>
+        "##;
+
+        assert_eq!(
+            parse::<Post>(s),
+            Post {
+                tags: vec!["a".to_string(), "b".to_string()],
+                counts: vec![1, 2],
+            }
+        );
+    }
+
+    #[test]
+    fn empty_scalar_item() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(rename = "r")]
+        struct R {
+            tag: Vec<String>,
+        }
+
+        assert_eq!(
+            parse::<R>("<r><tag/><tag>b</tag></r>"),
+            R {
+                tag: vec!["".to_string(), "b".to_string()],
+            }
+        );
+        assert_eq!(
+            parse::<R>("<r><tag/></r>"),
+            R {
+                tag: vec!["".to_string()],
+            }
+        );
+    }
+
     #[test]
     fn nested_collection() {
         #[derive(Debug, Deserialize, PartialEq)]
@@ -893,6 +1928,124 @@ mod tests {
         );
     }
 
+    #[test]
+    fn events() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(rename = "price")]
+        struct Price {
+            #[serde(rename = "@currency")]
+            currency: String,
+            #[serde(rename = "$value")]
+            amount: f64,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(rename = "order")]
+        struct Order {
+            id: u32,
+            price: Price,
+        }
+
+        let events = vec![
+            XmlEvent::stag("order", false),
+            XmlEvent::stag("id", false),
+            XmlEvent::characters("7"),
+            XmlEvent::etag("id"),
+            XmlEvent::stag_with_attrs("price", false, vec![Attribute::new("currency", "EUR")]),
+            XmlEvent::characters("1.5"),
+            XmlEvent::etag("price"),
+            XmlEvent::etag("order"),
+        ];
+        let order: Order = from_events(events.into_iter().map(Ok)).unwrap();
+        assert_eq!(
+            order,
+            Order {
+                id: 7,
+                price: Price {
+                    currency: "EUR".into(),
+                    amount: 1.5
+                }
+            }
+        );
+
+        let err = from_events::<_, Order>(
+            vec![XmlEvent::stag("order", false), XmlEvent::stag("id", false)]
+                .into_iter()
+                .map(Ok),
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string()
+                .ends_with("Unexpected end of file (Expecting end of this element: id)"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn attributes_and_value() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(rename = "price")]
+        struct Price {
+            #[serde(rename = "@currency")]
+            currency: String,
+            #[serde(rename = "$value")]
+            amount: f64,
+        }
+
+        let price: Price = parse(r#"<price currency="USD">9.99</price>"#);
+        assert_eq!(
+            price,
+            Price {
+                currency: "USD".into(),
+                amount: 9.99
+            }
+        );
+    }
+
+    #[test]
+    fn value_with_child_elements() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(rename = "price")]
+        struct Price {
+            #[serde(rename = "@currency")]
+            currency: String,
+            #[serde(rename = "$value")]
+            amount: f64,
+        }
+
+        let input = r#"<price currency="USD">9.99<tax>0.5</tax></price>"#;
+        let err = from_str::<Price>(input).unwrap_err();
+        assert_eq!(input.find("<tax>").unwrap(), err.offset());
+        assert!(
+            err.to_string().ends_with(
+                "Expecting only characters at offset 26 (child element next to $value text)"
+            ),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn cdata_value() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        #[serde(rename = "root")]
+        struct Item {
+            #[serde(rename = "$value")]
+            content: String,
+        }
+
+        let s = r#"<root><![CDATA[<content>]]></root>"#;
+        let item: Item = parse(s);
+
+        assert_eq!(
+            item,
+            Item {
+                content: "<content>".into()
+            }
+        );
+    }
+
     #[test]
     fn without_value() {
         #[derive(Debug, Deserialize, PartialEq)]
@@ -2,6 +2,7 @@ use std::borrow::Cow;
 
 use crate::de::cow::StrExt;
 use serde::de;
+use serde::serde_if_integer128;
 use xrs_parser::XmlEvent;
 
 use crate::de::Deserializer;
@@ -13,6 +14,12 @@ pub struct SeqAccess<'a, 'de> {
     de: &'a mut Deserializer<'de>,
     max_size: Option<usize>,
     name: Option<Cow<'a, str>>,
+    /// Index of the next element
+    index: usize,
+    /// Elements with the name were moved in front of their siblings
+    collected: bool,
+    /// Scalar elements are children of the element the sequence was started in
+    wrapped: bool,
 }
 
 impl<'a, 'de> SeqAccess<'a, 'de> {
@@ -22,10 +29,41 @@ impl<'a, 'de> SeqAccess<'a, 'de> {
             de,
             max_size,
             name: None,
+            index: 0,
+            collected: false,
+            wrapped: false,
         })
     }
 }
 
+impl<'a, 'de> SeqAccess<'a, 'de> {
+    fn deserialize_element<T: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<T::Value, Error> {
+        let index = self.index;
+        self.index += 1;
+        seed.deserialize(&mut *self.de)
+            .map_err(|err| err.at_index(index))
+    }
+
+    /// Prepare to read a scalar as first element
+    ///
+    /// An element with child elements wraps the sequence and the text of each child is one
+    /// element, like in `<tags><tag>a</tag><tag>b</tag></tags>`. The text of any other element
+    /// is the first element itself, so an element without content is an empty string.
+    fn start_scalar(&mut self) -> Result<(), Error> {
+        self.de.skip_ignorable_and_whitespace()?;
+        if let XmlEvent::STag(_) = self.de.peek()? {
+            if let XmlEvent::STag(tag) = self.de.next()? {
+                self.name = Some(tag.name);
+                self.wrapped = true;
+            }
+        }
+        Ok(())
+    }
+}
+
 impl<'de, 'a> de::SeqAccess<'de> for SeqAccess<'a, 'de> {
     type Error = Error;
 
@@ -42,16 +80,19 @@ impl<'de, 'a> de::SeqAccess<'de> for SeqAccess<'a, 'de> {
         if let Some(name) = &self.name {
             loop {
                 match self.de.next()? {
-                    XmlEvent::STag(tag) => {
-                        return if &tag.name == name {
-                            seed.deserialize(&mut *self.de).map(Some)
-                        } else {
-                            self.de.withdraw(XmlEvent::STag(tag));
-                            Ok(None)
-                        }
+                    XmlEvent::STag(tag) if &tag.name == name => {
+                        return self.deserialize_element(seed).map(Some);
                     }
-                    XmlEvent::ETag(tag) => {
-                        self.de.withdraw(XmlEvent::ETag(tag));
+                    // look for more elements after the siblings with other names
+                    XmlEvent::STag(tag) if !self.collected => {
+                        self.collected = true;
+                        self.de.withdraw(XmlEvent::STag(tag));
+                        self.de.collect_siblings(name)?;
+                    }
+                    // end of the wrapping element
+                    XmlEvent::ETag(_) if self.wrapped => return Ok(None),
+                    evt @ (XmlEvent::STag(_) | XmlEvent::ETag(_)) => {
+                        self.de.withdraw(evt);
                         return Ok(None);
                     }
                     XmlEvent::Characters(c) if !c.as_ref().is_xml_whitespace() => {
@@ -61,8 +102,11 @@ impl<'de, 'a> de::SeqAccess<'de> for SeqAccess<'a, 'de> {
                 }
             }
         } else {
-            self.name = Some(self.de.reader.top_name_cow().unwrap());
-            seed.deserialize(&mut *self.de).map(Some)
+            self.name = self.de.open.last().cloned();
+            self.index += 1;
+            seed.deserialize(FirstElement { seq: self })
+                .map(Some)
+                .map_err(|err| err.at_index(0))
         }
     }
 
@@ -70,3 +114,76 @@ impl<'de, 'a> de::SeqAccess<'de> for SeqAccess<'a, 'de> {
         self.max_size
     }
 }
+
+/// Deserializer for the first element of a sequence, which finds out whether scalars are
+/// wrapped
+struct FirstElement<'s, 'a, 'de> {
+    seq: &'s mut SeqAccess<'a, 'de>,
+}
+
+macro_rules! forward_scalar {
+    ($($deserialize:ident)*) => {
+        $(
+            fn $deserialize<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                self.seq.start_scalar()?;
+                (&mut *self.seq.de).$deserialize(visitor)
+            }
+        )*
+    };
+}
+
+macro_rules! forward {
+    ($($deserialize:ident($($arg:ident: $ty:ty),*))*) => {
+        $(
+            fn $deserialize<V: de::Visitor<'de>>(
+                self,
+                $($arg: $ty,)*
+                visitor: V,
+            ) -> Result<V::Value, Error> {
+                (&mut *self.seq.de).$deserialize($($arg,)* visitor)
+            }
+        )*
+    };
+}
+
+impl<'de, 's, 'a> de::Deserializer<'de> for FirstElement<'s, 'a, 'de> {
+    type Error = Error;
+
+    forward_scalar!(
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_f32
+        deserialize_f64 deserialize_char deserialize_str deserialize_string
+    );
+
+    serde_if_integer128! {
+        forward_scalar!(deserialize_i128 deserialize_u128);
+    }
+
+    forward!(
+        deserialize_any()
+        deserialize_bytes()
+        deserialize_byte_buf()
+        deserialize_unit()
+        deserialize_unit_struct(name: &'static str)
+        deserialize_seq()
+        deserialize_tuple(len: usize)
+        deserialize_tuple_struct(name: &'static str, len: usize)
+        deserialize_map()
+        deserialize_struct(name: &'static str, fields: &'static [&'static str])
+        deserialize_enum(name: &'static str, variants: &'static [&'static str])
+        deserialize_identifier()
+        deserialize_ignored_any()
+    );
+
+    fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+}
@@ -0,0 +1,81 @@
+//! Sources of the events of a `Deserializer`
+
+use std::borrow::Cow;
+use std::rc::Rc;
+
+use xrs_parser::{Reader, XmlError, XmlEvent};
+
+/// Iterator of events with the attributes in the start tags
+pub(crate) type Events<'a> = Box<dyn Iterator<Item = Result<XmlEvent<'a>, XmlError>> + 'a>;
+
+/// Reader or iterator the events of a `Deserializer` come from
+pub(crate) enum EventSource<'a> {
+    Reader(Reader<'a>),
+    /// Events without offsets in a document
+    Events {
+        events: Events<'a>,
+        /// Names of the open elements
+        open: Vec<Cow<'a, str>>,
+    },
+}
+
+impl<'a> EventSource<'a> {
+    pub fn events(events: Events<'a>) -> Self {
+        EventSource::Events {
+            events,
+            open: vec![],
+        }
+    }
+
+    /// Next event with the attributes in start tags
+    pub fn next(&mut self) -> Result<Option<XmlEvent<'a>>, XmlError> {
+        match self {
+            EventSource::Reader(reader) => Ok(match reader.next()? {
+                Some(XmlEvent::STag(mut stag)) => {
+                    stag.attrs = reader.drain_attributes();
+                    Some(XmlEvent::STag(stag))
+                }
+                Some(XmlEvent::EmptyElement(mut stag)) => {
+                    stag.attrs = reader.drain_attributes();
+                    Some(XmlEvent::EmptyElement(stag))
+                }
+                evt => evt,
+            }),
+            EventSource::Events { events, open } => {
+                let evt = events.next().transpose()?;
+                match &evt {
+                    Some(XmlEvent::STag(stag)) => open.push(stag.name.clone()),
+                    Some(XmlEvent::ETag(_)) => {
+                        open.pop();
+                    }
+                    _ => {}
+                }
+                Ok(evt)
+            }
+        }
+    }
+
+    /// Offset of the next event in the document, `0` for events without offsets
+    pub fn cursor_offset(&self) -> usize {
+        match self {
+            EventSource::Reader(reader) => reader.cursor_offset(),
+            EventSource::Events { .. } => 0,
+        }
+    }
+
+    /// Name of the innermost open element
+    pub fn top_name(&self) -> Option<&str> {
+        match self {
+            EventSource::Reader(reader) => reader.top_name(),
+            EventSource::Events { open, .. } => open.last().map(|name| name.as_ref()),
+        }
+    }
+
+    /// Shared allocation of a name, see [`Reader::intern`]
+    pub fn intern(&mut self, name: &str) -> Rc<str> {
+        match self {
+            EventSource::Reader(reader) => reader.intern(name),
+            EventSource::Events { .. } => Rc::from(name),
+        }
+    }
+}
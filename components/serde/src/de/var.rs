@@ -30,8 +30,7 @@ impl<'de, 'a> de::EnumAccess<'de> for EnumAccess<'a, 'de> {
             let ident = seed.deserialize(de)?;
             Ok((ident, VariantAccess { de: self.de }))
         } else {
-            dbg!("var");
-            return Err(self.de.peek_error(Reason::Start));
+            Err(self.de.peek_error(Reason::Start))
         }
     }
 }
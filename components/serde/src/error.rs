@@ -16,9 +16,31 @@ impl Error {
             offset,
             reason,
             hints: vec![],
+            path: vec![],
+            index: None,
         }))
     }
 
+    /// Prepend an enclosing element or an attribute (`@name`) to the path
+    ///
+    /// A pending sequence index is added to the segment.
+    pub(crate) fn in_path(mut self, segment: &str) -> Self {
+        let segment = match self.0.index.take() {
+            Some(index) => format!("{}[{}]", segment, index),
+            None => segment.to_string(),
+        };
+        self.0.path.insert(0, segment);
+        self
+    }
+
+    /// Set the index of the element in a sequence, which is added to the next path segment
+    pub(crate) fn at_index(mut self, index: usize) -> Self {
+        if let Some(inner) = self.0.index.replace(index) {
+            self.0.path.insert(0, format!("[{}]", inner));
+        }
+        self
+    }
+
     pub(crate) fn with_position(mut self, offset: usize) -> Self {
         self.0.offset = offset;
         self
@@ -29,9 +51,21 @@ impl Error {
         self
     }
 
+    pub(crate) fn reason(&self) -> &Reason {
+        &self.0.reason
+    }
+
     pub fn offset(&self) -> usize {
         self.0.offset
     }
+
+    /// Enclosing elements of the error location from the root element on
+    ///
+    /// Elements of sequences have their index starting at 0 appended like `item[3]` and
+    /// attributes are prefixed with `@`.
+    pub fn path(&self) -> &[String] {
+        &self.0.path
+    }
 }
 
 /// struct to reduce size of `Error`
@@ -39,6 +73,9 @@ struct ErrorImpl {
     offset: usize,
     reason: Reason,
     hints: Vec<(usize, String)>,
+    path: Vec<String>,
+    /// Index of the element in a sequence not added to the path yet
+    index: Option<usize>,
 }
 
 pub(crate) enum Reason {
@@ -68,8 +105,11 @@ pub(crate) enum Reason {
     End,
     /// Expecting struct as root object
     RootStruct,
-    /// Expecting tag name
-    Tag(&'static str),
+    /// Root element with another name than expected
+    Tag {
+        expected: &'static str,
+        found: String,
+    },
 }
 
 impl fmt::Display for Reason {
@@ -93,7 +133,9 @@ impl fmt::Display for Reason {
             Reason::NoMarkupExpected => write!(f, "Expecting only characters"),
             Reason::MarkupExpected => write!(f, "Expecting only markup"),
             Reason::RootStruct => write!(f, "Can only deserialize struct on root level"),
-            Reason::Tag(tag) => write!(f, "Expecting start tag <{} ...>", tag),
+            Reason::Tag { expected, found } => {
+                write!(f, "Expected root element '{}', found '{}'", expected, found)
+            }
             Reason::EndOfArrayExpected => write!(f, "Expected end of element array"),
         }
     }
@@ -101,6 +143,9 @@ impl fmt::Display for Reason {
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !self.0.path.is_empty() {
+            write!(f, "{}: ", self.0.path.join(" > "))?;
+        }
         if self.0.offset == 0 {
             write!(f, "{}", self.0.reason)?;
         } else {
@@ -126,6 +171,7 @@ impl fmt::Debug for Error {
             .field("message", &self.0.reason.to_string())
             .field("offset", &self.0.offset)
             .field("hints", &self.0.hints)
+            .field("path", &self.0.path)
             .finish()
     }
 }
@@ -0,0 +1,47 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use xrs_writer::escape::{DefaultEscaper, Escape, MinimalEscaper};
+
+const NO_HIT: &str = include_str!("../../parser/benches/atom.xml");
+const ATTR_VALUE: &str = "urn:uuid:60a76c80-d399-11d9-b93C-0003939e0af6";
+const MIXED: &str = "Tom & \"Jerry\" <cat> café ]]> 'x' and some more text without escapes";
+
+fn bench_escaper<E: Escape>(c: &mut Criterion, name: &str, escaper: E) {
+    c.bench_function(&format!("{}_content_no_hit", name), |b| {
+        b.iter(|| {
+            let mut buf = String::with_capacity(NO_HIT.len());
+            escaper
+                .escape_content(black_box(NO_HIT), &mut &mut buf)
+                .unwrap();
+            black_box(buf)
+        })
+    });
+    c.bench_function(&format!("{}_attr_values", name), |b| {
+        b.iter(|| {
+            let mut buf = String::with_capacity(ATTR_VALUE.len() * 100);
+            for _ in 0..100 {
+                escaper
+                    .escape_attr_value_quot(black_box(ATTR_VALUE), &mut &mut buf)
+                    .unwrap();
+            }
+            black_box(buf)
+        })
+    });
+    c.bench_function(&format!("{}_content_mixed", name), |b| {
+        b.iter(|| {
+            let mut buf = String::with_capacity(MIXED.len() * 2);
+            escaper
+                .escape_content(black_box(MIXED), &mut &mut buf)
+                .unwrap();
+            black_box(buf)
+        })
+    });
+}
+
+pub fn escaping(c: &mut Criterion) {
+    bench_escaper(c, "default", DefaultEscaper);
+    bench_escaper(c, "minimal", MinimalEscaper);
+}
+
+criterion_group!(benches, escaping);
+criterion_main!(benches);
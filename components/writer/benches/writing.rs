@@ -0,0 +1,81 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use xrs_writer::{CompactXmlWrite, XmlWriter};
+
+/// Allocator counting the allocations to show that writing does not allocate per element
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+const ELEMENTS: usize = 1_000_000;
+const DEPTH: usize = 1_000;
+
+/// Write `ELEMENTS` elements in chains of `DEPTH` nested elements below the root, with the
+/// names formatted into a reused buffer
+fn write_elements(out: &mut String) {
+    let mut writer = XmlWriter::without_decl(CompactXmlWrite::new(out));
+    let mut name = String::new();
+    writer.element("root").unwrap().finish().unwrap();
+    for chain in 0..ELEMENTS / DEPTH {
+        for level in 0..DEPTH {
+            name.clear();
+            write!(name, "e{}-{}", chain % 10, level).unwrap();
+            writer.element(&name).unwrap().finish().unwrap();
+        }
+        for _ in 0..DEPTH {
+            writer.end_element().unwrap();
+        }
+    }
+    writer.end_element().unwrap();
+    writer.finish().unwrap();
+}
+
+pub fn writing(c: &mut Criterion) {
+    let mut out = String::new();
+    write_elements(&mut out);
+    let capacity = out.len();
+
+    // the output buffer is allocated up front, so only the writer allocates
+    let mut out = String::with_capacity(capacity);
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    write_elements(&mut out);
+    eprintln!(
+        "{} allocations for {} elements",
+        ALLOCATIONS.load(Ordering::Relaxed) - before,
+        ELEMENTS
+    );
+
+    c.bench_function("write_1m_elements", |b| {
+        b.iter(|| {
+            let mut out = String::with_capacity(capacity);
+            write_elements(&mut out);
+            out
+        })
+    });
+}
+
+criterion_group!(benches, writing);
+criterion_main!(benches);
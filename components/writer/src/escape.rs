@@ -14,95 +14,84 @@ pub trait Escape {
         -> io::Result<()>;
 }
 
-pub struct MinimalEscaper;
-
-impl Escape for MinimalEscaper {
-    fn escape_content<W: UnicodeWrite>(&self, mut input: &str, write: &mut W) -> io::Result<()> {
-        let mut p = 0;
-        for (i, r) in input.match_indices(|c: char| c == '&' || c == '<' || c == '>') {
+/// Write `input` with the replacements of `replace` for ASCII bytes
+///
+/// `replace` gets the byte and the bytes before it. Runs without replaced bytes are written as
+/// whole slices, so input without any escapable character is written with one `write_all`.
+#[inline]
+fn escape_with<W: UnicodeWrite>(
+    input: &str,
+    write: &mut W,
+    replace: impl Fn(u8, &[u8]) -> Option<&'static str>,
+) -> io::Result<()> {
+    let mut p = 0;
+    let bytes = input.as_bytes();
+    for (i, &b) in bytes.iter().enumerate() {
+        if let Some(replacement) = replace(b, &bytes[..i]) {
             write.write_all(&input[p..i])?;
-            if r == "&" {
-                write.write_all("&amp;")?;
-            } else if r == "<" {
-                write.write_all("&lt;")?;
-            } else if input[..i].ends_with("]]") {
-                write.write_all("&gt;")?;
-            } else {
-                write.write_all(r)?;
-            }
+            write.write_all(replacement)?;
             p = i + 1;
         }
-        write.write_all(if p == 0 { input } else { &input[p..] })
+    }
+    write.write_all(&input[p..])
+}
+
+pub struct MinimalEscaper;
+
+impl Escape for MinimalEscaper {
+    fn escape_content<W: UnicodeWrite>(&self, input: &str, write: &mut W) -> io::Result<()> {
+        escape_with(input, write, |b, before| match b {
+            b'&' => Some("&amp;"),
+            b'<' => Some("&lt;"),
+            b'>' if before.ends_with(b"]]") => Some("&gt;"),
+            _ => None,
+        })
     }
 
     fn escape_attr_value_apos<W: UnicodeWrite>(
         &self,
-        mut input: &str,
+        input: &str,
         write: &mut W,
     ) -> io::Result<()> {
-        let mut p = 0;
-        for (i, r) in input.match_indices(|c: char| c == '&' || c == '<' || c == '\'') {
-            write.write_all(&input[p..i])?;
-            if r == "&" {
-                write.write_all("&amp;")?;
-            } else if r == "<" {
-                write.write_all("&lt;")?;
-            } else {
-                write.write_all("&apos;")?;
-            }
-            p = i + 1;
-        }
-        write.write_all(if p == 0 { input } else { &input[p..] })
+        escape_with(input, write, |b, _| match b {
+            b'&' => Some("&amp;"),
+            b'<' => Some("&lt;"),
+            b'\'' => Some("&apos;"),
+            _ => None,
+        })
     }
 
     fn escape_attr_value_quot<W: UnicodeWrite>(
         &self,
-        mut input: &str,
+        input: &str,
         write: &mut W,
     ) -> io::Result<()> {
-        let mut p = 0;
-        for (i, r) in input.match_indices(|c: char| c == '&' || c == '<' || c == '\"') {
-            write.write_all(&input[p..i])?;
-            if r == "&" {
-                write.write_all("&amp;")?;
-            } else if r == "<" {
-                write.write_all("&lt;")?;
-            } else {
-                write.write_all("&#34;")?;
-            }
-            p = i + 1;
-        }
-        write.write_all(if p == 0 { input } else { &input[p..] })
+        escape_with(input, write, |b, _| match b {
+            b'&' => Some("&amp;"),
+            b'<' => Some("&lt;"),
+            b'"' => Some("&#34;"),
+            _ => None,
+        })
     }
 }
 
 pub struct DefaultEscaper;
 
 impl DefaultEscaper {
-    fn escape<W: UnicodeWrite>(input: &str, write: &mut W) -> io::Result<()> {
-        let mut p = 0;
-        for (i, r) in input
-            .match_indices(|c: char| c == '>' || c == '<' || c == '&' || c == '\'' || c == '\"')
-        {
-            write.write_all(unsafe { input.get_unchecked(p..i) })?;
-            if r == ">" {
-                write.write_all("&gt;")?;
-            } else if r == "<" {
-                write.write_all("&lt;")?;
-            } else if r == "&" {
-                write.write_all("&amp;")?;
-            } else if r == "\'" {
-                write.write_all("&apos;")?;
-            } else {
-                write.write_all("&quot;")?;
-            }
-            p = i + 1;
+    #[inline]
+    fn replacement(b: u8) -> Option<&'static str> {
+        match b {
+            b'>' => Some("&gt;"),
+            b'<' => Some("&lt;"),
+            b'&' => Some("&amp;"),
+            b'\'' => Some("&apos;"),
+            b'"' => Some("&quot;"),
+            _ => None,
         }
-        write.write_all(if p == 0 {
-            input
-        } else {
-            unsafe { input.get_unchecked(p..) }
-        })
+    }
+
+    fn escape<W: UnicodeWrite>(input: &str, write: &mut W) -> io::Result<()> {
+        escape_with(input, write, |b, _| Self::replacement(b))
     }
 }
 
@@ -113,7 +102,7 @@ impl Escape for DefaultEscaper {
 
     fn escape_attr_value_apos<W: UnicodeWrite>(
         &self,
-        mut input: &str,
+        input: &str,
         write: &mut W,
     ) -> io::Result<()> {
         Self::escape(input, write)
@@ -121,13 +110,59 @@ impl Escape for DefaultEscaper {
 
     fn escape_attr_value_quot<W: UnicodeWrite>(
         &self,
-        mut input: &str,
+        input: &str,
         write: &mut W,
     ) -> io::Result<()> {
         Self::escape(input, write)
     }
 }
 
+/// Escaper writing the same characters as [`DefaultEscaper`] as decimal character references
+///
+/// For consumers which don't know the predefined entities like `&lt;`.
+pub struct NumericEscaper;
+
+impl NumericEscaper {
+    #[inline]
+    fn replacement(b: u8) -> Option<&'static str> {
+        match b {
+            b'>' => Some("&#62;"),
+            b'<' => Some("&#60;"),
+            b'&' => Some("&#38;"),
+            b'\'' => Some("&#39;"),
+            b'"' => Some("&#34;"),
+            _ => None,
+        }
+    }
+
+    fn escape<W: UnicodeWrite>(input: &str, write: &mut W) -> io::Result<()> {
+        escape_with(input, write, |b, _| Self::replacement(b))
+    }
+}
+
+impl Escape for NumericEscaper {
+    fn escape_content<W: UnicodeWrite>(&self, input: &str, write: &mut W) -> io::Result<()> {
+        Self::escape(input, write)
+    }
+
+    fn escape_attr_value_apos<W: UnicodeWrite>(
+        &self,
+        input: &str,
+        write: &mut W,
+    ) -> io::Result<()> {
+        Self::escape(input, write)
+    }
+
+    fn escape_attr_value_quot<W: UnicodeWrite>(
+        &self,
+        input: &str,
+        write: &mut W,
+    ) -> io::Result<()> {
+        Self::escape(input, write)
+    }
+}
+
+/// Escaper writing every non-ASCII character as hexadecimal character reference
 pub struct AsciiEscaper;
 
 impl AsciiEscaper {
@@ -150,7 +185,7 @@ impl AsciiEscaper {
             } else if r == "\"" {
                 write.write_all("&quot;")?;
             } else {
-                write.write_fmt(format_args!("&#{};", r.chars().next().unwrap() as u32))?;
+                write.write_fmt(format_args!("&#x{:X};", r.chars().next().unwrap() as u32))?;
             }
             p = i + r.len();
         }
@@ -192,6 +227,18 @@ pub fn escape(input: &str) -> String {
     output
 }
 
+/// Escape like [`escape`], but borrow `input` when it has nothing to escape
+pub fn escape_cow(input: &str) -> Cow<'_, str> {
+    if input
+        .bytes()
+        .any(|b| DefaultEscaper::replacement(b).is_some())
+    {
+        Cow::Owned(escape(input))
+    } else {
+        Cow::Borrowed(input)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -210,6 +257,32 @@ mod tests {
         output
     }
 
+    #[test]
+    fn mixed() {
+        let input = "Tom & \"Jerry\" <cat> café ]]> 'x'";
+        assert_eq!(
+            "Tom &amp; &quot;Jerry&quot; &lt;cat&gt; café ]]&gt; &apos;x&apos;",
+            escape_content(DefaultEscaper, input)
+        );
+        assert_eq!(
+            "Tom &amp; \"Jerry\" &lt;cat> café ]]&gt; 'x'",
+            escape_content(MinimalEscaper, input)
+        );
+        assert_eq!(
+            "Tom &amp; &#34;Jerry&#34; &lt;cat> café ]]> 'x'",
+            escape_attr_value(MinimalEscaper, input)
+        );
+    }
+
+    #[test]
+    fn borrow_without_escapes() {
+        assert!(matches!(
+            escape_cow("plain café"),
+            Cow::Borrowed("plain café")
+        ));
+        assert_eq!("a &lt; b", escape_cow("a < b"));
+    }
+
     mod minimal {
         use super::*;
 
@@ -308,6 +381,45 @@ mod tests {
         }
     }
 
+    mod numeric {
+        use super::*;
+
+        #[test]
+        fn lt() {
+            assert_eq!("&#60;", escape_content(NumericEscaper, "<"));
+        }
+
+        #[test]
+        fn gt() {
+            assert_eq!("&#62;", escape_content(NumericEscaper, ">"));
+        }
+
+        #[test]
+        fn amp() {
+            assert_eq!("&#38;", escape_content(NumericEscaper, "&"));
+        }
+
+        #[test]
+        fn apos() {
+            assert_eq!("&#39;", escape_content(NumericEscaper, "'"));
+        }
+
+        #[test]
+        fn quot() {
+            assert_eq!("&#34;", escape_content(NumericEscaper, "\""));
+        }
+
+        #[test]
+        fn non_ascii() {
+            assert_eq!("café", escape_content(NumericEscaper, "café"));
+        }
+
+        #[test]
+        fn attr_value() {
+            assert_eq!("a&#60;&#34;b", escape_attr_value(NumericEscaper, "a<\"b"));
+        }
+    }
+
     mod ascii {
         use super::*;
 
@@ -343,12 +455,17 @@ mod tests {
 
         #[test]
         fn small_unicode_char() {
-            assert_eq!("&#128;", escape_content(AsciiEscaper, "\u{80}"));
+            assert_eq!("&#x80;", escape_content(AsciiEscaper, "\u{80}"));
         }
 
         #[test]
         fn large_unicode_char() {
-            assert_eq!("&#1114111;", escape_content(AsciiEscaper, "\u{10FFFF}"));
+            assert_eq!("&#x10FFFF;", escape_content(AsciiEscaper, "\u{10FFFF}"));
+        }
+
+        #[test]
+        fn attr_value() {
+            assert_eq!("&#xE4;&quot;", escape_attr_value(AsciiEscaper, "ä\""));
         }
     }
 }
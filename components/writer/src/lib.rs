@@ -1,14 +1,23 @@
 extern crate core;
 
-use std::borrow::Cow;
 use std::{fmt, io};
 
+use xrs_chars::XmlChar;
+use xrs_parser::XmlDecl;
+
 use crate::escape::{DefaultEscaper, Escape};
-use crate::write::UnicodeWrite;
 
 pub mod escape;
+pub mod minify;
+pub mod namespace;
+pub mod pretty;
 pub mod write;
 
+pub use minify::{minify, minify_keeping_comments};
+pub use namespace::{NamespaceWriter, NsElementWriter};
+pub use pretty::pretty_print;
+pub use write::{IoWriteAdapter, UnicodeWrite};
+
 pub trait XmlStagWrite {
     type Error;
 
@@ -34,28 +43,90 @@ pub trait XmlWrite {
         standalone: Option<bool>,
         write_encoding: bool,
     ) -> Result<(), Self::Error>;
+    /// Write a document type declaration
+    ///
+    /// By default the declaration is written with [`XmlWrite::write_raw`].
+    fn write_doctype(
+        &mut self,
+        name: &str,
+        external_id: Option<&ExternalId>,
+        internal_subset: Option<&str>,
+    ) -> Result<(), Self::Error>
+    where
+        Self::Error: From<io::Error>,
+    {
+        let mut decl = String::new();
+        write_doctype_decl(&mut &mut decl, name, external_id, internal_subset)?;
+        self.write_raw(&decl)
+    }
     fn write_etag(&mut self, name: &str) -> Result<(), Self::Error>;
+    /// Write already escaped markup as it is
+    ///
+    /// Writers which can't write markup verbatim keep the default, which returns an error of
+    /// kind [`io::ErrorKind::Unsupported`].
+    fn write_raw(&mut self, _xml: &str) -> Result<(), Self::Error>
+    where
+        Self::Error: From<io::Error>,
+    {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "raw markup not supported").into())
+    }
+    /// Flush buffered output, by default there is none
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// External identifier of a document type declaration
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExternalId {
+    /// `SYSTEM "uri"`
+    System(String),
+    /// `PUBLIC "public id" "uri"`
+    Public(String, String),
 }
 
-pub struct CompactXmlWrite<W: UnicodeWrite> {
+pub struct CompactXmlWrite<W: UnicodeWrite, E: Escape = DefaultEscaper> {
     write: W,
+    escaper: E,
 }
 
 impl<W: UnicodeWrite> CompactXmlWrite<W> {
     pub fn new(write: W) -> Self {
-        Self { write }
+        Self::with_escaper(write, DefaultEscaper)
     }
 }
 
-impl<W: UnicodeWrite> XmlWrite for CompactXmlWrite<W> {
+impl<W: UnicodeWrite, E: Escape> CompactXmlWrite<W, E> {
+    /// Writer escaping content and attribute values with `escaper`
+    pub fn with_escaper(write: W, escaper: E) -> Self {
+        Self { write, escaper }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.write
+    }
+
+    pub fn into_inner(self) -> W {
+        self.write
+    }
+}
+
+impl<W: UnicodeWrite, E: Escape> XmlWrite for CompactXmlWrite<W, E> {
     type Error = io::Error;
-    type StagWrite<'w> = CompactXmlStagWrite<'w, W> where Self: 'w;
+    type StagWrite<'w>
+        = CompactXmlStagWrite<'w, W, E>
+    where
+        Self: 'w;
 
-    fn write_stag<'w>(&'w mut self, name: &str) -> Result<CompactXmlStagWrite<'w, W>, Self::Error> {
+    fn write_stag<'w>(
+        &'w mut self,
+        name: &str,
+    ) -> Result<CompactXmlStagWrite<'w, W, E>, Self::Error> {
         self.write
             .write_fmt(format_args!("<{}", name))
             .map(|_| CompactXmlStagWrite {
                 write: &mut self.write,
+                escaper: &self.escaper,
             })
     }
 
@@ -64,7 +135,7 @@ impl<W: UnicodeWrite> XmlWrite for CompactXmlWrite<W> {
     }
 
     fn write_characters(&mut self, characters: &str) -> Result<(), Self::Error> {
-        DefaultEscaper.escape_content(characters, &mut self.write)
+        self.escaper.escape_content(characters, &mut self.write)
     }
 
     fn write_cdata(&mut self, cdata: &str) -> Result<(), Self::Error> {
@@ -85,26 +156,100 @@ impl<W: UnicodeWrite> XmlWrite for CompactXmlWrite<W> {
         standalone: Option<bool>,
         write_encoding: bool,
     ) -> Result<(), Self::Error> {
-        todo!()
+        write!(self.write, "<?xml version=\"{}\"", version.unwrap_or("1.0"))?;
+        if write_encoding {
+            self.write.write_all(" encoding=\"UTF-8\"")?;
+        }
+        if let Some(standalone) = standalone {
+            let standalone = if standalone { "yes" } else { "no" };
+            write!(self.write, " standalone=\"{}\"", standalone)?;
+        }
+        self.write.write_all("?>")
+    }
+
+    fn write_doctype(
+        &mut self,
+        name: &str,
+        external_id: Option<&ExternalId>,
+        internal_subset: Option<&str>,
+    ) -> Result<(), Self::Error> {
+        write_doctype_decl(&mut self.write, name, external_id, internal_subset)
     }
 
     fn write_etag(&mut self, name: &str) -> Result<(), Self::Error> {
         self.write.write_fmt(format_args!("</{}>", name))
     }
+
+    fn write_raw(&mut self, xml: &str) -> Result<(), Self::Error> {
+        self.write.write_all(xml)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.write.flush()
+    }
+}
+
+/// Write a document type declaration, checking that the literals of the external identifier
+/// can be written
+fn write_doctype_decl<W: UnicodeWrite>(
+    write: &mut W,
+    name: &str,
+    external_id: Option<&ExternalId>,
+    internal_subset: Option<&str>,
+) -> io::Result<()> {
+    write!(write, "<!DOCTYPE {}", name)?;
+    match external_id {
+        Some(ExternalId::System(system)) => {
+            write.write_all(" SYSTEM ")?;
+            write_system_literal(write, system)?;
+        }
+        Some(ExternalId::Public(public, system)) => {
+            if let Some(c) = public.chars().find(|c| !c.is_xml_pubid_char()) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("public identifier contains illegal character {:?}", c),
+                ));
+            }
+            // apostrophes are allowed in public identifiers, quotation marks are not
+            write!(write, " PUBLIC \"{}\" ", public)?;
+            write_system_literal(write, system)?;
+        }
+        None => {}
+    }
+    if let Some(internal_subset) = internal_subset {
+        write!(write, " [{}]", internal_subset)?;
+    }
+    write.write_all(">")
 }
 
-pub struct CompactXmlStagWrite<'w, W: UnicodeWrite> {
+/// Write system literal, quoted with apostrophes when it contains quotation marks
+///
+/// A literal with both quotation marks and apostrophes can't be written.
+fn write_system_literal<W: UnicodeWrite>(write: &mut W, literal: &str) -> io::Result<()> {
+    match (literal.contains('"'), literal.contains('\'')) {
+        (true, true) => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "system literal contains both quotation marks and apostrophes",
+        )),
+        (true, false) => write!(write, "'{}'", literal),
+        (false, _) => write!(write, "\"{}\"", literal),
+    }
+}
+
+pub struct CompactXmlStagWrite<'w, W: UnicodeWrite, E: Escape = DefaultEscaper> {
     write: &'w mut W,
+    escaper: &'w E,
 }
 
-impl<'w, W: UnicodeWrite> XmlStagWrite for CompactXmlStagWrite<'w, W> {
+impl<'w, W: UnicodeWrite, E: Escape> XmlStagWrite for CompactXmlStagWrite<'w, W, E> {
     type Error = io::Error;
 
     fn write_attribute(&mut self, key: &str, value: &str) -> Result<(), Self::Error> {
         self.write.write_all(" ")?;
         self.write.write_all(key)?;
         self.write.write_all("=\"")?;
-        DefaultEscaper.escape_attr_value_quot(value, &mut self.write)?;
+        self.escaper
+            .escape_attr_value_quot(value, &mut self.write)?;
         self.write.write_all("\"")
     }
 
@@ -123,42 +268,92 @@ enum State {
     Epilog,
 }
 
-pub struct XmlWriter<'o, W: XmlWrite> {
+pub struct XmlWriter<W: XmlWrite> {
     state: State,
-    stack: Vec<Cow<'o, str>>,
+    doctype: bool,
+    /// Names of the open elements one after another
+    names: String,
+    /// Start of the name of each open element in `names`
+    stack: Vec<usize>,
     write: W,
 }
 
-impl<'o, W: XmlWrite> XmlWriter<'o, W> {
+impl<W: XmlWrite> XmlWriter<W> {
     pub fn with_decl(
         mut write: W,
         version: Option<&str>,
         standalone: Option<bool>,
         write_encoding: bool,
-    ) -> Result<XmlWriter<'o, W>, W::Error> {
+    ) -> Result<XmlWriter<W>, W::Error> {
         write.write_xmldecl(version, standalone, write_encoding)?;
         Ok(XmlWriter::without_decl(write))
     }
 
-    pub fn without_decl(mut write: W) -> XmlWriter<'o, W> {
+    /// Write the XML declaration `decl` of a parsed document
+    ///
+    /// The version and the standalone declaration are kept. A declared encoding is written as
+    /// `UTF-8`, because the writer always writes Unicode.
+    pub fn from_decl(write: W, decl: &XmlDecl) -> Result<XmlWriter<W>, W::Error> {
+        Self::with_decl(
+            write,
+            Some(decl.version()),
+            decl.standalone(),
+            decl.encoding().is_some(),
+        )
+    }
+
+    /// Write document type declaration, which is only allowed before the root element
+    pub fn doctype(
+        &mut self,
+        name: &str,
+        external_id: Option<ExternalId>,
+        internal_subset: Option<&str>,
+    ) -> Result<(), W::Error>
+    where
+        W::Error: From<io::Error>,
+    {
+        if !matches!(self.state, State::Prolog) || self.doctype {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "document type declaration only allowed once before the root element",
+            )
+            .into());
+        }
+        self.doctype = true;
+        self.write
+            .write_doctype(name, external_id.as_ref(), internal_subset)
+    }
+
+    pub fn without_decl(write: W) -> XmlWriter<W> {
         XmlWriter {
             state: State::Prolog,
+            doctype: false,
+            names: String::new(),
             stack: vec![],
             write,
         }
     }
 
-    pub fn element<'w>(&'w mut self, name: &'o str) -> Result<XmlElementWriter<'w, W>, W::Error> {
+    /// Start element `name`
+    ///
+    /// The name is copied into a buffer shared by all open elements, so it can come from a
+    /// transient buffer and writing does not allocate once the buffer is large enough for the
+    /// deepest nesting.
+    pub fn element<'w>(&'w mut self, name: &str) -> Result<XmlElementWriter<'w, W>, W::Error> {
         // TODO: check name
-        self.stack.push(name.into());
+        self.state = State::Main;
+        let start = self.names.len();
+        self.names.push_str(name);
+        self.stack.push(start);
         Ok(XmlElementWriter {
-            stag_write: self.write.write_stag(name)?,
+            stag_write: self.write.write_stag(&self.names[start..])?,
         })
     }
 
     pub fn end_element(&mut self) -> Result<(), W::Error> {
-        if let Some(name) = self.stack.pop() {
-            self.write.write_etag(&name)?;
+        if let Some(start) = self.stack.pop() {
+            self.write.write_etag(&self.names[start..])?;
+            self.names.truncate(start);
             if self.stack.is_empty() {
                 self.state = State::Epilog;
             }
@@ -189,10 +384,59 @@ impl<'o, W: XmlWrite> XmlWriter<'o, W> {
         self.write.write_pi(name, data)
     }
 
-    pub fn finish(self) {
+    /// Write a fragment of already escaped markup verbatim
+    ///
+    /// The fragment is neither escaped nor checked and does not update the stack of open
+    /// elements, so elements started in it must also be ended in it. The caller is responsible
+    /// for the fragment being well-formed content. Outside of the root element an error is
+    /// returned, because the fragment could add a second root element there.
+    pub fn raw(&mut self, xml: &str) -> Result<(), W::Error>
+    where
+        W::Error: From<io::Error>,
+    {
+        if self.stack.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "raw markup only allowed inside the root element",
+            )
+            .into());
+        }
+        self.write.write_raw(xml)
+    }
+
+    /// Check that all elements are ended, flush and return the underlying writer
+    ///
+    /// Missing [`XmlWriter::end_element`] calls are reported as error and panic in debug
+    /// builds.
+    pub fn finish(mut self) -> Result<W, W::Error>
+    where
+        W::Error: From<io::Error>,
+    {
+        debug_assert!(
+            self.stack.is_empty(),
+            "missing end_element call(s): {}",
+            self.open_names()
+        );
         if !self.stack.is_empty() {
-            panic!("missing end_element call(s): {}", self.stack.join(", "));
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("missing end_element call(s): {}", self.open_names()),
+            )
+            .into());
         }
+        self.write.flush()?;
+        Ok(self.write)
+    }
+
+    /// Names of the open elements separated by commas
+    fn open_names(&self) -> String {
+        let ends = self.stack.iter().skip(1).copied();
+        self.stack
+            .iter()
+            .zip(ends.chain(Some(self.names.len())))
+            .map(|(&start, end)| &self.names[start..end])
+            .collect::<Vec<_>>()
+            .join(", ")
     }
 }
 
@@ -219,7 +463,7 @@ impl<'w, W: XmlWrite> XmlElementWriter<'w, W> {
 
 #[cfg(test)]
 mod tests {
-    use crate::escape::DefaultEscaper;
+    use crate::escape::{AsciiEscaper, DefaultEscaper, NumericEscaper};
 
     use super::*;
 
@@ -306,4 +550,270 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_escaper() -> io::Result<()> {
+        fn write(xml_write: impl XmlWrite<Error = io::Error>) -> io::Result<()> {
+            let mut xml_writer = XmlWriter::without_decl(xml_write);
+            xml_writer
+                .element("xrs".into())?
+                .attribute("attr", "é")?
+                .finish()?;
+            xml_writer.characters("Grüße <")?;
+            xml_writer.end_element()
+        }
+
+        let mut buf = String::new();
+        write(CompactXmlWrite::new(&mut buf))?;
+        assert_eq!("<xrs attr=\"é\">Grüße &lt;</xrs>", &buf);
+
+        let mut buf = String::new();
+        write(CompactXmlWrite::with_escaper(&mut buf, AsciiEscaper))?;
+        assert_eq!("<xrs attr=\"&#xE9;\">Gr&#xFC;&#xDF;e &lt;</xrs>", &buf);
+
+        let mut buf = String::new();
+        write(CompactXmlWrite::with_escaper(&mut buf, NumericEscaper))?;
+        assert_eq!("<xrs attr=\"é\">Grüße &#60;</xrs>", &buf);
+
+        Ok(())
+    }
+
+    fn write_doctype(external_id: Option<ExternalId>, internal_subset: Option<&str>) -> String {
+        let mut buf = String::new();
+        let mut xml_writer = XmlWriter::without_decl(CompactXmlWrite::new(&mut buf));
+        xml_writer
+            .doctype("html", external_id, internal_subset)
+            .unwrap();
+        xml_writer.element("html").unwrap().finish_empty().unwrap();
+        buf
+    }
+
+    #[test]
+    fn test_doctype() {
+        assert_eq!("<!DOCTYPE html><html/>", write_doctype(None, None));
+    }
+
+    #[test]
+    fn test_doctype_system() {
+        assert_eq!(
+            "<!DOCTYPE html SYSTEM \"about:legacy-compat\"><html/>",
+            write_doctype(
+                Some(ExternalId::System("about:legacy-compat".to_string())),
+                None
+            )
+        );
+    }
+
+    #[test]
+    fn test_doctype_public() {
+        assert_eq!(
+            "<!DOCTYPE html PUBLIC \"-//W3C//DTD XHTML 1.0 Strict//EN\" \
+             \"http://www.w3.org/TR/xhtml1/DTD/xhtml1-strict.dtd\"><html/>",
+            write_doctype(
+                Some(ExternalId::Public(
+                    "-//W3C//DTD XHTML 1.0 Strict//EN".to_string(),
+                    "http://www.w3.org/TR/xhtml1/DTD/xhtml1-strict.dtd".to_string()
+                )),
+                None
+            )
+        );
+    }
+
+    #[test]
+    fn test_doctype_internal_subset() {
+        assert_eq!(
+            "<!DOCTYPE html SYSTEM 'say \"hi\"' [<!ENTITY e \"x\">]><html/>",
+            write_doctype(
+                Some(ExternalId::System("say \"hi\"".to_string())),
+                Some("<!ENTITY e \"x\">")
+            )
+        );
+    }
+
+    #[test]
+    fn test_doctype_invalid_literals() {
+        for external_id in [
+            ExternalId::System("\"'".to_string()),
+            ExternalId::Public("\"".to_string(), "uri".to_string()),
+            ExternalId::Public("-//ä//EN".to_string(), "uri".to_string()),
+        ] {
+            let mut buf = String::new();
+            let mut xml_writer = XmlWriter::without_decl(CompactXmlWrite::new(&mut buf));
+            let err = xml_writer
+                .doctype("html", Some(external_id.clone()), None)
+                .unwrap_err();
+            assert_eq!(io::ErrorKind::InvalidInput, err.kind(), "{:?}", external_id);
+        }
+    }
+
+    #[test]
+    fn test_raw() -> io::Result<()> {
+        let mut buf = String::new();
+        let mut xml_writer = XmlWriter::without_decl(CompactXmlWrite::new(&mut buf));
+        xml_writer.element("xrs")?.finish()?;
+        xml_writer.element("a")?.finish()?;
+        xml_writer.end_element()?;
+        xml_writer.raw("<b x='&amp;'>1 &lt; 2</b>")?;
+        xml_writer.element("c")?.finish()?;
+        xml_writer.end_element()?;
+        xml_writer.end_element()?;
+        let buf = xml_writer.finish()?.into_inner();
+
+        assert_eq!("<xrs><a></a><b x='&amp;'>1 &lt; 2</b><c></c></xrs>", buf);
+        Ok(())
+    }
+
+    #[test]
+    fn test_finish() -> io::Result<()> {
+        let mut xml_writer =
+            XmlWriter::without_decl(CompactXmlWrite::new(IoWriteAdapter::new(Vec::new())));
+        xml_writer.element("xrs")?.finish()?;
+        xml_writer.characters("text")?;
+        xml_writer.end_element()?;
+        let output = xml_writer.finish()?.into_inner().into_inner();
+
+        assert_eq!(b"<xrs>text</xrs>", &output[..]);
+        Ok(())
+    }
+
+    #[test]
+    #[cfg_attr(
+        debug_assertions,
+        should_panic(expected = "missing end_element call(s): xrs")
+    )]
+    fn test_finish_unclosed() {
+        let mut buf = String::new();
+        let mut xml_writer = XmlWriter::without_decl(CompactXmlWrite::new(&mut buf));
+        xml_writer.element("xrs").unwrap().finish().unwrap();
+        assert!(xml_writer.finish().is_err());
+    }
+
+    #[test]
+    fn test_decl_round_trip() -> io::Result<()> {
+        for input in [
+            r#"<?xml version="1.1" encoding="UTF-8" standalone="no"?><r/>"#,
+            r#"<?xml version="1.0" standalone="yes"?><r/>"#,
+            r#"<?xml version="1.0"?><r/>"#,
+        ] {
+            let mut reader = xrs_parser::Reader::new(input);
+            let decl = match reader.next() {
+                Ok(Some(xrs_parser::XmlEvent::XmlDecl(decl))) => decl,
+                evt => panic!("expected XML declaration, got {:?}", evt),
+            };
+
+            let mut buf = String::new();
+            let mut xml_writer = XmlWriter::from_decl(CompactXmlWrite::new(&mut buf), &decl)?;
+            xml_writer.element("r")?.finish_empty()?;
+            assert_eq!(input, &buf);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_transient_names() -> io::Result<()> {
+        let mut buf = String::new();
+        let mut xml_writer = XmlWriter::without_decl(CompactXmlWrite::new(&mut buf));
+        let mut name = String::new();
+        for i in 0..3 {
+            name.clear();
+            name.push_str(&format!("e{}", i));
+            xml_writer.element(&name)?.finish()?;
+        }
+        for _ in 0..3 {
+            xml_writer.end_element()?;
+        }
+
+        assert_eq!("<e0><e1><e2></e2></e1></e0>", &buf);
+        Ok(())
+    }
+
+    #[test]
+    fn test_open_names() {
+        let mut xml_writer =
+            XmlWriter::without_decl(CompactXmlWrite::new(IoWriteAdapter::new(Vec::new())));
+        xml_writer.element("a").unwrap().finish().unwrap();
+        xml_writer.element("bc").unwrap().finish().unwrap();
+        assert_eq!("a, bc", xml_writer.open_names());
+    }
+
+    /// Writer implementing only the required methods of `XmlWrite`
+    struct RequiredOnly(CompactXmlWrite<IoWriteAdapter<Vec<u8>>>);
+
+    impl XmlWrite for RequiredOnly {
+        type Error = io::Error;
+        type StagWrite<'w> = CompactXmlStagWrite<'w, IoWriteAdapter<Vec<u8>>>;
+
+        fn write_stag(&mut self, name: &str) -> io::Result<Self::StagWrite<'_>> {
+            self.0.write_stag(name)
+        }
+
+        fn write_comment(&mut self, comment: &str) -> io::Result<()> {
+            self.0.write_comment(comment)
+        }
+
+        fn write_characters(&mut self, characters: &str) -> io::Result<()> {
+            self.0.write_characters(characters)
+        }
+
+        fn write_cdata(&mut self, cdata: &str) -> io::Result<()> {
+            self.0.write_cdata(cdata)
+        }
+
+        fn write_pi(&mut self, target: &str, data: Option<&str>) -> io::Result<()> {
+            self.0.write_pi(target, data)
+        }
+
+        fn write_xmldecl(
+            &mut self,
+            version: Option<&str>,
+            standalone: Option<bool>,
+            write_encoding: bool,
+        ) -> io::Result<()> {
+            self.0.write_xmldecl(version, standalone, write_encoding)
+        }
+
+        fn write_etag(&mut self, name: &str) -> io::Result<()> {
+            self.0.write_etag(name)
+        }
+    }
+
+    #[test]
+    fn test_raw_unsupported() -> io::Result<()> {
+        let mut xml_writer = XmlWriter::without_decl(RequiredOnly(CompactXmlWrite::new(
+            IoWriteAdapter::new(Vec::new()),
+        )));
+        let err = xml_writer.doctype("xrs", None, None).unwrap_err();
+        assert_eq!(io::ErrorKind::Unsupported, err.kind());
+        xml_writer.element("xrs")?.finish()?;
+        let err = xml_writer.raw("<a/>").unwrap_err();
+        assert_eq!(io::ErrorKind::Unsupported, err.kind());
+        xml_writer.end_element()?;
+        xml_writer.finish()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_raw_outside_root() -> io::Result<()> {
+        let mut buf = String::new();
+        let mut xml_writer = XmlWriter::without_decl(CompactXmlWrite::new(&mut buf));
+        assert!(xml_writer.raw("<a/>").is_err());
+        xml_writer.element("xrs")?.finish()?;
+        xml_writer.end_element()?;
+        assert!(xml_writer.raw("<a/>").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_doctype_after_root() -> io::Result<()> {
+        let mut buf = String::new();
+        let mut xml_writer = XmlWriter::without_decl(CompactXmlWrite::new(&mut buf));
+        xml_writer.element("xrs")?.finish()?;
+        assert!(xml_writer.doctype("xrs", None, None).is_err());
+        xml_writer.end_element()?;
+        assert!(xml_writer.doctype("xrs", None, None).is_err());
+
+        Ok(())
+    }
 }
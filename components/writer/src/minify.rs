@@ -0,0 +1,126 @@
+//! Minification of documents
+
+use xrs_parser::XmlError;
+
+use crate::pretty::{mixed_content, read_items, write_item, Item};
+use crate::CompactXmlWrite;
+
+/// Parse a document and write it again without comments and whitespace between markup
+///
+/// Elements with text content and CDATA sections are written unchanged to not alter the
+/// text.
+pub fn minify(input: &str) -> Result<String, XmlError> {
+    minify_document(input, false)
+}
+
+/// Like [`minify`], but keep the comments
+pub fn minify_keeping_comments(input: &str) -> Result<String, XmlError> {
+    minify_document(input, true)
+}
+
+fn minify_document(input: &str, keep_comments: bool) -> Result<String, XmlError> {
+    let items = read_items(input)?;
+    let mixed = mixed_content(&items);
+
+    let mut result = String::with_capacity(input.len());
+    let mut write = CompactXmlWrite::new(&mut result);
+    // depth of elements inside mixed content
+    let mut verbatim = 0usize;
+
+    for (i, item) in items.iter().enumerate() {
+        match item {
+            Item::Comment(_) if !keep_comments => continue,
+            // whitespace between markup
+            Item::Text(_) if verbatim == 0 => continue,
+            Item::STag { empty: false, .. } if verbatim > 0 || mixed[i] => verbatim += 1,
+            Item::ETag(_) if verbatim > 0 => verbatim -= 1,
+            _ => {}
+        }
+        write_item(&mut write, item)?;
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use xrs_parser::{InsignificantWhitespace, Reader, XmlEvent};
+
+    use super::*;
+
+    /// Events of a document without comments and insignificant whitespace
+    fn canonical(input: &str) -> Vec<String> {
+        let mut reader = Reader::new(input)
+            .with_coalescing()
+            .with_insignificant_whitespace(InsignificantWhitespace::Skip);
+        let mut result: Vec<String> = vec![];
+        while let Some(evt) = reader.next().unwrap() {
+            let token = match evt {
+                XmlEvent::STag(stag) => {
+                    let mut attributes: Vec<String> = reader
+                        .attributes()
+                        .iter()
+                        .map(|attr| format!("{}={}", attr.name, attr.value))
+                        .collect();
+                    attributes.sort();
+                    format!("<{} {}>", stag.name, attributes.join(" "))
+                }
+                XmlEvent::ETag(etag) => format!("</{}>", etag.name),
+                XmlEvent::Characters(chars) | XmlEvent::CData(chars) => {
+                    if let Some(text) = result.last_mut().filter(|last| last.starts_with('"')) {
+                        text.pop();
+                        text.push_str(&chars);
+                        text.push('"');
+                        continue;
+                    }
+                    format!("\"{}\"", chars)
+                }
+                XmlEvent::PI(pi) => format!("<?{} {:?}?>", pi.target, pi.data),
+                XmlEvent::Comment(_) | XmlEvent::XmlDecl(_) | XmlEvent::Dtd(_) => continue,
+                evt => panic!("unexpected event {:?}", evt),
+            };
+            result.push(token);
+        }
+        result
+    }
+
+    #[test]
+    fn indented_document() {
+        let input = "<?xml version=\"1.0\"?>\n\
+                     <!-- list -->\n\
+                     <list>\n  \
+                       <item id=\"1\">\n    \
+                         <name>one</name>\n  \
+                       </item>\n  \
+                       <!-- removed -->\n  \
+                       <item id=\"2\"/>\n\
+                     </list>\n";
+        let minified = minify(input).unwrap();
+        assert_eq!(
+            "<?xml version=\"1.0\"?><list><item id=\"1\"><name>one</name></item><item id=\"2\"/></list>",
+            minified
+        );
+        assert_eq!(canonical(input), canonical(&minified));
+    }
+
+    #[test]
+    fn keeps_mixed_content() {
+        let input =
+            "<doc>\n  <p>Some <b>bold</b> <i> text </i></p>\n  <c><![CDATA[ ]]></c>\n</doc>";
+        let minified = minify(input).unwrap();
+        assert_eq!(
+            "<doc><p>Some <b>bold</b> <i> text </i></p><c><![CDATA[ ]]></c></doc>",
+            minified
+        );
+        assert_eq!(canonical(input), canonical(&minified));
+    }
+
+    #[test]
+    fn keep_comments() {
+        let input = "<!-- a -->\n<a>\n  <!-- b -->\n  <b/>\n</a>";
+        assert_eq!(
+            "<!-- a --><a><!-- b --><b/></a>",
+            minify_keeping_comments(input).unwrap()
+        );
+    }
+}
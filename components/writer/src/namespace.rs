@@ -0,0 +1,369 @@
+//! Namespace-aware writing of elements and attributes
+
+use std::io;
+
+use crate::{XmlElementWriter, XmlWrite, XmlWriter};
+
+/// Namespace bound to the `xml` prefix without declaration
+const XML_NS: &str = "http://www.w3.org/XML/1998/namespace";
+/// Namespace of the `xmlns` attributes, which must not be declared
+const XMLNS_NS: &str = "http://www.w3.org/2000/xmlns/";
+
+/// Namespace declaration of an element
+struct Declaration {
+    /// Prefix or `None` for the default namespace
+    prefix: Option<String>,
+    uri: String,
+}
+
+struct Namespaces {
+    /// Declarations of the open elements
+    scopes: Vec<Vec<Declaration>>,
+    /// Preferred prefixes for namespace URIs
+    prefixes: Vec<(String, String)>,
+    default_namespace: bool,
+}
+
+impl Namespaces {
+    fn resolve(&self, prefix: Option<&str>) -> Option<&str> {
+        self.scopes
+            .iter()
+            .rev()
+            .flatten()
+            .find(|decl| decl.prefix.as_deref() == prefix)
+            .map(|decl| decl.uri.as_str())
+    }
+
+    fn declare(&mut self, prefix: Option<String>, uri: &str) {
+        self.scopes
+            .last_mut()
+            .expect("namespace declaration outside of element")
+            .push(Declaration {
+                prefix,
+                uri: uri.to_string(),
+            });
+    }
+
+    /// Prefix bound to the namespace URI and whether it has to be declared
+    ///
+    /// The XML namespace uses the predeclared `xml` prefix. The `xmlns` namespace and the
+    /// reserved prefixes can't be bound.
+    fn prefix(&self, uri: &str) -> Result<(String, bool), io::Error> {
+        match uri {
+            XML_NS => return Ok(("xml".to_string(), false)),
+            XMLNS_NS => return Err(reserved_namespace(uri)),
+            _ => {}
+        }
+
+        let bound = self
+            .scopes
+            .iter()
+            .rev()
+            .flatten()
+            .filter_map(|decl| decl.prefix.as_deref().filter(|_| decl.uri == uri))
+            .find(|prefix| self.resolve(Some(prefix)) == Some(uri));
+        if let Some(prefix) = bound {
+            return Ok((prefix.to_string(), false));
+        }
+
+        let preferred = self
+            .prefixes
+            .iter()
+            .find(|(ns, _)| ns == uri)
+            .map(|(_, prefix)| prefix.clone())
+            .filter(|prefix| self.resolve(Some(prefix)).is_none());
+        if let Some(prefix @ ("xml" | "xmlns")) = preferred.as_deref() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("prefix {} can't be bound to {}", prefix, uri),
+            ));
+        }
+        let prefix = preferred.unwrap_or_else(|| {
+            (0..)
+                .map(|i| format!("ns{}", i))
+                .find(|prefix| self.resolve(Some(prefix)).is_none())
+                .unwrap()
+        });
+        Ok((prefix, true))
+    }
+}
+
+fn reserved_namespace(uri: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("namespace {} can't be declared", uri),
+    )
+}
+
+/// Writer allocating namespace prefixes and writing the needed `xmlns` declarations
+///
+/// A namespace is declared on the element where it is used first and is reused by all
+/// descendants. In default namespace mode the namespace of an element is declared as default
+/// namespace instead of with a prefix.
+pub struct NamespaceWriter<W: XmlWrite> {
+    writer: XmlWriter<W>,
+    namespaces: Namespaces,
+}
+
+impl<W: XmlWrite> NamespaceWriter<W> {
+    pub fn new(writer: XmlWriter<W>) -> Self {
+        Self {
+            writer,
+            namespaces: Namespaces {
+                scopes: vec![],
+                prefixes: vec![],
+                default_namespace: false,
+            },
+        }
+    }
+
+    /// Use default namespace declarations (`xmlns="..."`) for element namespaces
+    pub fn with_default_namespace(mut self) -> Self {
+        self.namespaces.default_namespace = true;
+        self
+    }
+
+    /// Use `prefix` for the namespace `uri` instead of a generated prefix when possible
+    pub fn with_prefix(mut self, uri: impl Into<String>, prefix: impl Into<String>) -> Self {
+        self.namespaces.prefixes.push((uri.into(), prefix.into()));
+        self
+    }
+
+    /// Start element `local` in the namespace `uri`, an empty URI means no namespace
+    ///
+    /// The XML namespace is written with the `xml` prefix. The `xmlns` namespace is rejected.
+    pub fn element_ns<'w>(
+        &'w mut self,
+        uri: &str,
+        local: &str,
+    ) -> Result<NsElementWriter<'w, W>, W::Error>
+    where
+        W::Error: From<io::Error>,
+    {
+        let namespaces = &self.namespaces;
+        let default = namespaces.resolve(None).unwrap_or("");
+        let (name, declaration) = if uri == default {
+            (local.to_string(), None)
+        } else if (namespaces.default_namespace || uri.is_empty()) && uri != XML_NS {
+            if uri == XMLNS_NS {
+                return Err(reserved_namespace(uri).into());
+            }
+            (local.to_string(), Some(None))
+        } else {
+            match namespaces.prefix(uri)? {
+                (prefix, true) => (format!("{}:{}", prefix, local), Some(Some(prefix))),
+                (prefix, false) => (format!("{}:{}", prefix, local), None),
+            }
+        };
+
+        let mut stag = self.writer.element(&name)?;
+        self.namespaces.scopes.push(vec![]);
+        if let Some(prefix) = declaration {
+            let name = match &prefix {
+                Some(prefix) => format!("xmlns:{}", prefix),
+                None => "xmlns".to_string(),
+            };
+            self.namespaces.declare(prefix, uri);
+            stag = stag.attribute(&name, uri)?;
+        }
+        Ok(NsElementWriter {
+            stag,
+            namespaces: &mut self.namespaces,
+        })
+    }
+
+    pub fn end_element(&mut self) -> Result<(), W::Error> {
+        self.writer.end_element()?;
+        self.namespaces.scopes.pop();
+        Ok(())
+    }
+
+    pub fn characters(&mut self, characters: &str) -> Result<(), W::Error> {
+        self.writer.characters(characters)
+    }
+
+    pub fn cdata(&mut self, characters: &str) -> Result<(), W::Error> {
+        self.writer.cdata(characters)
+    }
+
+    pub fn comment(&mut self, comment: &str) -> Result<(), W::Error> {
+        self.writer.comment(comment)
+    }
+
+    pub fn pi(&mut self, name: &str, data: Option<&str>) -> Result<(), W::Error> {
+        self.writer.pi(name, data)
+    }
+
+    /// See [`XmlWriter::finish`]
+    pub fn finish(self) -> Result<W, W::Error>
+    where
+        W::Error: From<io::Error>,
+    {
+        self.writer.finish()
+    }
+}
+
+pub struct NsElementWriter<'w, W: XmlWrite + 'w> {
+    stag: XmlElementWriter<'w, W>,
+    namespaces: &'w mut Namespaces,
+}
+
+impl<'w, W: XmlWrite> NsElementWriter<'w, W> {
+    /// Write attribute without namespace
+    pub fn attribute(mut self, key: &str, value: &str) -> Result<Self, W::Error> {
+        self.stag = self.stag.attribute(key, value)?;
+        Ok(self)
+    }
+
+    /// Write attribute `local` in the namespace `uri`
+    ///
+    /// Attributes always need a prefix, because the default namespace does not apply to them.
+    /// The XML namespace is written with the `xml` prefix, e.g. for `xml:lang`.
+    pub fn attribute_ns(mut self, uri: &str, local: &str, value: &str) -> Result<Self, W::Error>
+    where
+        W::Error: From<io::Error>,
+    {
+        if uri.is_empty() {
+            return self.attribute(local, value);
+        }
+
+        let (prefix, declare) = self.namespaces.prefix(uri)?;
+        if declare {
+            self.stag = self.stag.attribute(&format!("xmlns:{}", prefix), uri)?;
+            self.namespaces.declare(Some(prefix.clone()), uri);
+        }
+        self.stag = self
+            .stag
+            .attribute(&format!("{}:{}", prefix, local), value)?;
+        Ok(self)
+    }
+
+    pub fn finish(self) -> Result<(), W::Error> {
+        self.stag.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::*;
+    use crate::CompactXmlWrite;
+
+    const ATOM: &str = "http://www.w3.org/2005/Atom";
+    const XLINK: &str = "http://www.w3.org/1999/xlink";
+
+    #[test]
+    fn declare_once() -> io::Result<()> {
+        let mut buf = String::new();
+        let mut writer =
+            NamespaceWriter::new(XmlWriter::without_decl(CompactXmlWrite::new(&mut buf)));
+        writer.element_ns("", "doc")?.finish()?;
+        writer.element_ns(ATOM, "feed")?.finish()?;
+        writer.element_ns(ATOM, "entry")?.finish()?;
+        writer.end_element()?;
+        writer.end_element()?;
+        writer.element_ns(ATOM, "feed")?.finish()?;
+        writer.end_element()?;
+        writer.end_element()?;
+
+        assert_eq!(
+            "<doc><ns0:feed xmlns:ns0=\"http://www.w3.org/2005/Atom\"><ns0:entry></ns0:entry></ns0:feed>\
+             <ns0:feed xmlns:ns0=\"http://www.w3.org/2005/Atom\"></ns0:feed></doc>",
+            &buf
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn two_namespaces() -> io::Result<()> {
+        let mut buf = String::new();
+        let mut writer =
+            NamespaceWriter::new(XmlWriter::without_decl(CompactXmlWrite::new(&mut buf)))
+                .with_prefix(XLINK, "xlink");
+        writer
+            .element_ns(ATOM, "link")?
+            .attribute_ns(XLINK, "href", "/")?
+            .attribute("rel", "self")?
+            .finish()?;
+        writer.element_ns(XLINK, "title")?.finish()?;
+        writer.end_element()?;
+        writer.end_element()?;
+
+        assert_eq!(
+            "<ns0:link xmlns:ns0=\"http://www.w3.org/2005/Atom\" \
+             xmlns:xlink=\"http://www.w3.org/1999/xlink\" xlink:href=\"/\" rel=\"self\">\
+             <xlink:title></xlink:title></ns0:link>",
+            &buf
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn default_namespace() -> io::Result<()> {
+        let mut buf = String::new();
+        let mut writer =
+            NamespaceWriter::new(XmlWriter::without_decl(CompactXmlWrite::new(&mut buf)))
+                .with_default_namespace();
+        writer
+            .element_ns(ATOM, "feed")?
+            .attribute_ns(ATOM, "lang", "en")?
+            .finish()?;
+        writer.element_ns(ATOM, "entry")?.finish()?;
+        writer.element_ns("", "raw")?.finish()?;
+        writer.end_element()?;
+        writer.end_element()?;
+        writer.end_element()?;
+
+        assert_eq!(
+            "<feed xmlns=\"http://www.w3.org/2005/Atom\" \
+             xmlns:ns0=\"http://www.w3.org/2005/Atom\" ns0:lang=\"en\">\
+             <entry><raw xmlns=\"\"></raw></entry></feed>",
+            &buf
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn xml_namespace() -> io::Result<()> {
+        let mut buf = String::new();
+        let mut writer =
+            NamespaceWriter::new(XmlWriter::without_decl(CompactXmlWrite::new(&mut buf)))
+                .with_default_namespace();
+        writer
+            .element_ns(ATOM, "feed")?
+            .attribute_ns(XML_NS, "lang", "en")?
+            .finish()?;
+        writer.element_ns(XML_NS, "x")?.finish()?;
+        writer.end_element()?;
+        writer.end_element()?;
+
+        assert_eq!(
+            "<feed xmlns=\"http://www.w3.org/2005/Atom\" xml:lang=\"en\"><xml:x></xml:x></feed>",
+            &buf
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn reserved_bindings() -> io::Result<()> {
+        let mut buf = String::new();
+        let mut writer =
+            NamespaceWriter::new(XmlWriter::without_decl(CompactXmlWrite::new(&mut buf)))
+                .with_prefix(ATOM, "xml")
+                .with_prefix(XLINK, "xmlns");
+        let err = writer.element_ns(XMLNS_NS, "x").err().unwrap();
+        assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+
+        writer.element_ns("", "doc")?.finish()?;
+        let err = writer.element_ns(ATOM, "feed").err().unwrap();
+        assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+        let err = writer
+            .element_ns("", "link")?
+            .attribute_ns(XLINK, "href", "/")
+            .err()
+            .unwrap();
+        assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+        Ok(())
+    }
+}
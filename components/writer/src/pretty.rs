@@ -0,0 +1,331 @@
+//! Pretty printing of documents
+
+use xrs_chars::XmlAsciiChar;
+use xrs_parser::{Reader, XmlError, XmlEvent};
+
+use crate::write::UnicodeWrite;
+use crate::{CompactXmlWrite, XmlStagWrite, XmlWrite};
+
+/// Owned event of the document to print
+pub(crate) enum Item {
+    Decl {
+        version: String,
+        encoding: Option<String>,
+        standalone: Option<bool>,
+    },
+    /// Document type declaration as written in the input
+    Doctype(String),
+    STag {
+        name: String,
+        attributes: Vec<(String, String)>,
+        empty: bool,
+    },
+    ETag(String),
+    Text(String),
+    /// Character reference as written in the input, which is content even when it references
+    /// white space
+    CharRef(String),
+    CData(String),
+    Comment(String),
+    PI(String, Option<String>),
+}
+
+/// Parse a document and write it again with one markup item per line and indented elements
+///
+/// Comments and processing instructions are kept. Whitespace-only text in element-only
+/// content is replaced by the indentation, while elements with text content, including
+/// character references like `&#32;`, are written unchanged to not alter the text. The XML
+/// declaration keeps the declared encoding. Printing the result again yields the same string.
+pub fn pretty_print(input: &str, indent: &str) -> Result<String, XmlError> {
+    let items = read_items(input)?;
+    let mixed = mixed_content(&items);
+
+    let mut result = String::with_capacity(input.len());
+    let mut write = CompactXmlWrite::new(&mut result);
+    let mut depth = 0usize;
+    // depth of elements inside mixed content
+    let mut verbatim = 0usize;
+    let mut just_opened = false;
+    let mut first = true;
+
+    for (i, item) in items.iter().enumerate() {
+        if verbatim > 0 {
+            match item {
+                Item::STag { empty: false, .. } => verbatim += 1,
+                Item::ETag(_) => {
+                    verbatim -= 1;
+                    if verbatim == 0 {
+                        depth -= 1;
+                    }
+                }
+                _ => {}
+            }
+            write_item(&mut write, item)?;
+            continue;
+        }
+
+        match item {
+            Item::Text(_) => continue,
+            Item::ETag(_) => {
+                depth -= 1;
+                if !just_opened {
+                    new_line(&mut write, indent, depth)?;
+                }
+            }
+            _ if first => {}
+            _ => new_line(&mut write, indent, depth)?,
+        }
+        write_item(&mut write, item)?;
+        first = false;
+        just_opened = false;
+
+        if let Item::STag { empty: false, .. } = item {
+            depth += 1;
+            if mixed[i] {
+                verbatim = 1;
+            } else {
+                just_opened = true;
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+pub(crate) fn read_items(input: &str) -> Result<Vec<Item>, XmlError> {
+    let mut reader = Reader::new(input).with_preserved_char_refs();
+    let mut items = vec![];
+    let mut empty = false;
+    loop {
+        let start = reader.cursor_offset();
+        let evt = match reader.next()? {
+            Some(evt) => evt,
+            None => return Ok(items),
+        };
+        let item = match evt {
+            XmlEvent::XmlDecl(decl) => Item::Decl {
+                version: decl.version().to_string(),
+                encoding: decl.encoding().map(|encoding| encoding.to_string()),
+                standalone: decl.standalone(),
+            },
+            XmlEvent::Dtd(_) => {
+                Item::Doctype(input[start..reader.cursor_offset()].trim().to_string())
+            }
+            XmlEvent::STag(stag) if stag.empty => {
+                empty = true;
+                Item::STag {
+                    name: stag.name.to_string(),
+                    attributes: read_attributes(&reader),
+                    empty: true,
+                }
+            }
+            XmlEvent::EmptyElement(stag) => Item::STag {
+                name: stag.name.to_string(),
+                attributes: read_attributes(&reader),
+                empty: true,
+            },
+            XmlEvent::STag(stag) => Item::STag {
+                name: stag.name.to_string(),
+                attributes: read_attributes(&reader),
+                empty: false,
+            },
+            // an empty element tag has no end tag
+            XmlEvent::ETag(_) if empty => {
+                empty = false;
+                continue;
+            }
+            XmlEvent::ETag(etag) => Item::ETag(etag.name.to_string()),
+            XmlEvent::Characters(chars) | XmlEvent::Whitespace(chars) => {
+                if let Some(Item::Text(text)) = items.last_mut() {
+                    text.push_str(&chars);
+                    continue;
+                }
+                Item::Text(chars.to_string())
+            }
+            XmlEvent::Error(err) => return Err(err),
+            XmlEvent::CharRef(char_ref) => Item::CharRef(char_ref.reference.to_string()),
+            XmlEvent::CData(cdata) => Item::CData(cdata.to_string()),
+            XmlEvent::Comment(comment) => Item::Comment(comment.to_string()),
+            XmlEvent::PI(pi) => {
+                Item::PI(pi.target.to_string(), pi.data.map(|data| data.to_string()))
+            }
+        };
+        items.push(item);
+    }
+}
+
+fn read_attributes(reader: &Reader) -> Vec<(String, String)> {
+    reader
+        .attributes()
+        .iter()
+        .map(|attr| (attr.name.to_string(), attr.value.to_string()))
+        .collect()
+}
+
+/// Whether the element started at each index directly contains text
+pub(crate) fn mixed_content(items: &[Item]) -> Vec<bool> {
+    let mut mixed = vec![false; items.len()];
+    let mut stack = vec![];
+    for (i, item) in items.iter().enumerate() {
+        match item {
+            Item::STag { empty: false, .. } => stack.push(i),
+            Item::ETag(_) => {
+                stack.pop();
+            }
+            Item::Text(text) if text.bytes().all(|c| c.is_xml_whitespace()) => {}
+            Item::Text(_) | Item::CharRef(_) | Item::CData(_) => {
+                if let Some(&top) = stack.last() {
+                    mixed[top] = true;
+                }
+            }
+            _ => {}
+        }
+    }
+    mixed
+}
+
+fn new_line<W: UnicodeWrite>(
+    write: &mut CompactXmlWrite<W>,
+    indent: &str,
+    depth: usize,
+) -> Result<(), XmlError> {
+    write.write.write_all("\n")?;
+    for _ in 0..depth {
+        write.write.write_all(indent)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn write_item<W: UnicodeWrite>(
+    write: &mut CompactXmlWrite<W>,
+    item: &Item,
+) -> Result<(), XmlError> {
+    match item {
+        // the declaration is kept as parsed, because the encoding of the input is unknown here
+        Item::Decl {
+            version,
+            encoding,
+            standalone,
+        } => {
+            write!(write.write, "<?xml version=\"{}\"", version)?;
+            if let Some(encoding) = encoding {
+                write!(write.write, " encoding=\"{}\"", encoding)?;
+            }
+            if let Some(standalone) = standalone {
+                let standalone = if *standalone { "yes" } else { "no" };
+                write!(write.write, " standalone=\"{}\"", standalone)?;
+            }
+            write.write.write_all("?>")?
+        }
+        Item::Doctype(doctype) => write.write.write_all(doctype)?,
+        Item::STag {
+            name,
+            attributes,
+            empty,
+        } => {
+            let mut stag = write.write_stag(name)?;
+            for (key, value) in attributes {
+                stag.write_attribute(key, value)?;
+            }
+            if *empty {
+                stag.finish_empty()?
+            } else {
+                stag.finish()?
+            }
+        }
+        Item::ETag(name) => write.write_etag(name)?,
+        Item::Text(text) => write.write_characters(text)?,
+        Item::CharRef(reference) => write.write.write_all(reference)?,
+        Item::CData(cdata) => write.write_cdata(cdata)?,
+        Item::Comment(comment) => write.write_comment(comment)?,
+        Item::PI(target, data) => write.write_pi(target, data.as_deref())?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compact_document() {
+        let input = "<?xml version=\"1.0\"?><!-- c --><a x=\"1\"><b/><c><d>text</d></c><e></e><?pi data?></a>";
+        assert_eq!(
+            "<?xml version=\"1.0\"?>\n\
+             <!-- c -->\n\
+             <a x=\"1\">\n  \
+               <b/>\n  \
+               <c>\n    \
+                 <d>text</d>\n  \
+               </c>\n  \
+               <e></e>\n  \
+               <?pi data?>\n\
+             </a>",
+            pretty_print(input, "  ").unwrap()
+        );
+    }
+
+    #[test]
+    fn replaces_whitespace() {
+        let input = "<a>\n<b>\n        <c/>   </b>\n</a>\n";
+        assert_eq!(
+            "<a>\n\t<b>\n\t\t<c/>\n\t</b>\n</a>",
+            pretty_print(input, "\t").unwrap()
+        );
+    }
+
+    #[test]
+    fn keeps_mixed_content() {
+        let input = "<a><p>Some <b> bold <i>and</i></b> text</p><q><![CDATA[ ]]><r/></q></a>";
+        assert_eq!(
+            "<a>\n  \
+               <p>Some <b> bold <i>and</i></b> text</p>\n  \
+               <q><![CDATA[ ]]><r/></q>\n\
+             </a>",
+            pretty_print(input, "  ").unwrap()
+        );
+    }
+
+    #[test]
+    fn doctype() {
+        let input = "<!DOCTYPE a [<!ENTITY x 'y'>]><a>&x;</a>";
+        assert_eq!(
+            "<!DOCTYPE a [<!ENTITY x 'y'>]>\n<a>y</a>",
+            pretty_print(input, "  ").unwrap()
+        );
+    }
+
+    #[test]
+    fn keeps_char_ref_whitespace() {
+        let input = "<a><b>&#32;</b><c>&#x9;<d/></c></a>";
+        let once = pretty_print(input, "  ").unwrap();
+        assert_eq!("<a>\n  <b>&#32;</b>\n  <c>&#x9;<d/></c>\n</a>", once);
+        assert_eq!(once, pretty_print(&once, "  ").unwrap());
+    }
+
+    #[test]
+    fn keeps_declared_encoding() {
+        let input = "<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><a/>";
+        assert_eq!(
+            "<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?>\n<a/>",
+            pretty_print(input, "  ").unwrap()
+        );
+    }
+
+    #[test]
+    fn idempotent() {
+        let input = "<?xml version='1.0' standalone='yes'?><a><!--x--><b k='&lt;'>t &amp; u</b><c><d/></c></a><?end?>";
+        let once = pretty_print(input, "  ").unwrap();
+        assert_eq!(once, pretty_print(&once, "  ").unwrap());
+    }
+
+    #[test]
+    fn error() {
+        assert_eq!(
+            Err(XmlError::WrongETagName {
+                expected_name: "a".to_string()
+            }),
+            pretty_print("<a></b>", "  ")
+        );
+    }
+}
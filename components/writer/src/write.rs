@@ -68,15 +68,51 @@ impl<'a, T: ?Sized + UnicodeWrite> UnicodeWrite for &mut T {
     }
 }
 
-pub struct Utf8Writer<T: io::Write>(T);
-
-impl<T: io::Write> Utf8Writer<T> {
+/// Adapter to write UTF-8 encoded XML to a byte-oriented [`io::Write`]
+///
+/// Partial writes are retried until everything is written and errors of the inner writer are
+/// returned unchanged.
+///
+/// ```
+/// use xrs_writer::{CompactXmlWrite, IoWriteAdapter, XmlWriter};
+///
+/// let write = CompactXmlWrite::new(IoWriteAdapter::new(Vec::new()));
+/// let mut writer = XmlWriter::with_decl(write, None, None, true)?;
+/// writer.element("greeting")?.attribute("lang", "de")?.finish()?;
+/// writer.characters("Grüße")?;
+/// writer.end_element()?;
+/// let output: Vec<u8> = writer.finish()?.into_inner().into_inner();
+///
+/// assert_eq!(
+///     "<?xml version=\"1.0\" encoding=\"UTF-8\"?><greeting lang=\"de\">Grüße</greeting>",
+///     String::from_utf8(output).unwrap()
+/// );
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct IoWriteAdapter<T: io::Write>(T);
+
+/// Former name of [`IoWriteAdapter`]
+pub type Utf8Writer<T> = IoWriteAdapter<T>;
+
+impl<T: io::Write> IoWriteAdapter<T> {
     pub fn new(write: T) -> Self {
         Self(write)
     }
+
+    pub fn get_ref(&self) -> &T {
+        &self.0
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+
+    pub fn into_inner(self) -> T {
+        self.0
+    }
 }
 
-impl<T: io::Write> UnicodeWrite for Utf8Writer<T> {
+impl<T: io::Write> UnicodeWrite for IoWriteAdapter<T> {
     fn write_all(&mut self, s: &str) -> io::Result<()> {
         self.0.write_all(s.as_bytes())
     }
@@ -89,3 +125,53 @@ impl<T: io::Write> UnicodeWrite for Utf8Writer<T> {
         self.0.write_fmt(fmt)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writer accepting at most two bytes per call and failing after `limit` bytes
+    struct Chunked {
+        written: Vec<u8>,
+        limit: usize,
+    }
+
+    impl io::Write for Chunked {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.written.len() >= self.limit {
+                return Err(io::Error::other("full"));
+            }
+            let len = buf.len().min(2);
+            self.written.extend_from_slice(&buf[..len]);
+            Ok(len)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn partial_writes() {
+        let mut write = IoWriteAdapter::new(Chunked {
+            written: vec![],
+            limit: 100,
+        });
+        write.write_all("<ä>").unwrap();
+        write.write_fmt(format_args!("{}", 42)).unwrap();
+        assert_eq!(
+            "<ä>42",
+            std::str::from_utf8(&write.into_inner().written).unwrap()
+        );
+    }
+
+    #[test]
+    fn error() {
+        let mut write = IoWriteAdapter::new(Chunked {
+            written: vec![],
+            limit: 2,
+        });
+        let err = write.write_all("<abc/>").unwrap_err();
+        assert_eq!("full", err.to_string());
+    }
+}
@@ -11,7 +11,7 @@ use serde::{Deserialize, Serialize};
 use xrs_parser::encoding::decode;
 
 use crate::de::DeError;
-use crate::{de, MethodResponse, XmlRpcError};
+use crate::{de, XmlRpcError};
 
 static DEFAULT_USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"),);
 
@@ -140,13 +140,7 @@ impl XmlRpcClient {
         let (text, _, _) = decode(&content, encoding).map_err(|err| DeError::from(err))?;
         debug!("response: {}", text);
         *buffer = text.into_owned();
-        match crate::de::method_response_from_str(buffer)? {
-            MethodResponse::Success(result) => Ok(result),
-            MethodResponse::Fault(fault) => Err(XmlRpcError::new_fault(
-                fault.fault_code,
-                fault.fault_string.to_string(),
-            )),
-        }
+        Ok(crate::de::method_response_from_str(buffer)?.into_result()?)
     }
 }
 
@@ -12,7 +12,7 @@ use xrs_parser::{Reader, STag, XmlEvent, XmlNsEvent};
 use crate::de::cow::{CowStrExt, StrExt};
 use crate::de::error::{DeError as Error, DeReason as Reason, DeReason};
 use crate::value::Value;
-use crate::{Fault, MethodCall, MethodResponse};
+use crate::{Fault, MethodCall, MethodResponse, XmlRpcError};
 
 mod cow;
 mod error;
@@ -132,6 +132,25 @@ pub fn method_response_from_reader<R: BufRead, T: DeserializeOwned>(
     method_response_from_str(&buf).map(|ok| ok.into_owned())
 }
 
+/// Read a `<methodResponse>` with the value of its single param as `T` or its fault
+///
+/// Use [`MethodResponse::into_result`] to handle the fault as error.
+pub fn from_response_str<'a, T: Deserialize<'a>>(
+    xml: &'a str,
+) -> Result<MethodResponse<'a, T>, XmlRpcError> {
+    Ok(method_response_from_str(xml)?)
+}
+
+/// Read a `<methodCall>` with its params as `T`
+///
+/// The params are deserialized as sequence, so `T` is usually a tuple or a struct with one
+/// field per param.
+pub fn from_call_str<'a, T: Deserialize<'a>>(
+    xml: &'a str,
+) -> Result<MethodCall<'a, T>, XmlRpcError> {
+    Ok(method_call_from_str(xml)?)
+}
+
 fn is_ignorable(evt: &XmlEvent) -> bool {
     match evt {
         XmlEvent::STag(_) | XmlEvent::ETag(_) => false,
@@ -162,11 +181,14 @@ impl<'a> Deserializer<'a> {
         }
 
         while let Some(evt) = self.reader.next()? {
-            if matches!(
-                &evt,
-                XmlEvent::STag(_) | XmlEvent::ETag(_) | XmlEvent::Characters(_)
-            ) {
-                return Ok(evt);
+            match evt {
+                XmlEvent::STag(_) | XmlEvent::ETag(_) | XmlEvent::Characters(_) => return Ok(evt),
+                XmlEvent::CData(cdata) => return Ok(XmlEvent::Characters(cdata)),
+                XmlEvent::Error(err) => return Err(err.into()),
+                XmlEvent::CharRef(char_ref) => {
+                    return Ok(XmlEvent::Characters(char_ref.character.to_string().into()))
+                }
+                _ => (),
             }
         }
 
@@ -181,6 +203,14 @@ impl<'a> Deserializer<'a> {
         }
 
         while let Some(evt) = self.reader.next()? {
+            let evt = match evt {
+                XmlEvent::CData(cdata) => XmlEvent::Characters(cdata),
+                XmlEvent::Error(err) => return Err(err.into()),
+                XmlEvent::CharRef(char_ref) => {
+                    XmlEvent::Characters(char_ref.character.to_string().into())
+                }
+                evt => evt,
+            };
             if matches!(&evt, XmlEvent::STag(_) | XmlEvent::ETag(_))
                 || matches!(&evt, XmlEvent::Characters(c) if !c.as_ref().is_xml_whitespace())
             {
@@ -271,6 +301,11 @@ impl<'a> Deserializer<'a> {
         visitor.visit_i32(self.fix_result(result)?)
     }
 
+    fn read_long<V: de::Visitor<'a>>(&mut self, visitor: V) -> Result<V::Value, Error> {
+        let result = self.next_scalar_str()?.parse();
+        visitor.visit_i64(self.fix_result(result)?)
+    }
+
     fn read_boolean<V: de::Visitor<'a>>(&mut self, visitor: V) -> Result<V::Value, Error> {
         match self.next_scalar_str()?.as_ref() {
             "0" => visitor.visit_bool(false),
@@ -355,11 +390,14 @@ macro_rules! deserialize_int {
         fn $deserialize<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
             let res = match self.next_ignore_whitespace()? {
                 XmlEvent::STag(stag) => match stag.name() {
-                    "i4" | "int" => {
+                    "i4" | "int" | "i8" => {
                         let res = self.next_scalar_str()?.parse::<$ty>();
                         visitor.$visit(self.fix_result(res)?)
                     }
-                    _ => Err(self.error(Reason::WrongType("`i4` or `int`", stag.name.to_string()))),
+                    _ => Err(self.error(Reason::WrongType(
+                        "`i4`, `int` or `i8`",
+                        stag.name.to_string(),
+                    ))),
                 },
                 _ => Err(self.error(Reason::ValueExpected)),
             }?;
@@ -388,6 +426,7 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
         let res = match self.next_ignore_whitespace()? {
             XmlEvent::STag(stag) => match stag.name() {
                 "i4" | "int" => self.read_int(visitor),
+                "i8" => self.read_long(visitor),
                 "nil" => self.read_nil(visitor),
                 "boolean" => self.read_boolean(visitor),
                 "string" => self.read_string(visitor),
@@ -1193,7 +1232,100 @@ mod tests {
         }
     }
 
+    mod typed {
+        use super::*;
+
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Weather {
+            city: String,
+            temperature: f64,
+        }
+
+        #[test]
+        fn success_into_struct() {
+            let input = r#"<?xml version="1.0"?>
+                <methodResponse>
+                    <params>
+                        <param>
+                            <value>
+                                <struct>
+                                    <member>
+                                        <name>city</name>
+                                        <value><string>Berlin</string></value>
+                                    </member>
+                                    <member>
+                                        <name>temperature</name>
+                                        <value><double>21.5</double></value>
+                                    </member>
+                                </struct>
+                            </value>
+                        </param>
+                    </params>
+                </methodResponse>"#;
+
+            let response: MethodResponse<Weather> = from_response_str(input).unwrap();
+
+            assert_eq!(
+                Ok(Weather {
+                    city: "Berlin".to_string(),
+                    temperature: 21.5
+                }),
+                response.into_result()
+            );
+        }
+
+        #[test]
+        fn fault() {
+            let input = r#"<?xml version="1.0"?>
+                <methodResponse>
+                    <fault>
+                        <value>
+                            <struct>
+                                <member>
+                                    <name>faultCode</name>
+                                    <value><int>404</int></value>
+                                </member>
+                                <member>
+                                    <name>faultString</name>
+                                    <value><string>unknown city</string></value>
+                                </member>
+                            </struct>
+                        </value>
+                    </fault>
+                </methodResponse>"#;
+
+            let response: MethodResponse<Weather> = from_response_str(input).unwrap();
+
+            assert_eq!(Err(Fault::new(404, "unknown city")), response.into_result());
+        }
+
+        #[test]
+        fn call() {
+            let input = r#"<?xml version="1.0"?>
+                <methodCall>
+                    <methodName>weather.get</methodName>
+                    <params>
+                        <param><value><string>Berlin</string></value></param>
+                        <param><value><boolean>1</boolean></value></param>
+                    </params>
+                </methodCall>"#;
+
+            let call: MethodCall<(String, bool)> = from_call_str(input).unwrap();
+
+            assert_eq!("weather.get", call.method_name());
+            assert_eq!(&("Berlin".to_string(), true), call.params());
+        }
+
+        #[test]
+        fn not_a_response() {
+            let input = "<methodCall><methodName>m</methodName></methodCall>";
+            assert!(from_response_str::<i32>(input).is_err());
+        }
+    }
+
     mod int_value {
+        use crate::value::Value;
+
         use super::*;
 
         #[test]
@@ -1240,6 +1372,66 @@ mod tests {
 
             assert_eq!(actual, -9)
         }
+
+        #[test]
+        fn i8() {
+            let input = r#"<value><i8>-5000000000</i8></value>"#;
+
+            let actual: i64 = value_from_str(input).unwrap();
+
+            assert_eq!(actual, -5000000000)
+        }
+
+        #[test]
+        fn i8_overflow() {
+            let input = r#"<value><i8>5000000000</i8></value>"#;
+
+            let actual: Result<i32, Error> = value_from_str(input);
+
+            assert!(matches!(actual, Err(_)));
+        }
+
+        #[test]
+        fn small_i8() {
+            let input = r#"<value><i8>7</i8></value>"#;
+
+            let actual: i32 = value_from_str(input).unwrap();
+
+            assert_eq!(actual, 7)
+        }
+
+        fn assert_round_trip(input: &str, expected: Value) {
+            let value: Value = value_from_str(input).unwrap();
+            assert_eq!(value, expected);
+
+            let mut output = String::new();
+            crate::ser::method_response(
+                &crate::MethodResponse::Success(&value),
+                xrs_writer::CompactXmlWrite::new(&mut output),
+            )
+            .unwrap();
+            let actual: crate::MethodResponse<Value> = method_response_from_str(&output).unwrap();
+
+            assert_eq!(actual, crate::MethodResponse::Success(expected));
+        }
+
+        #[test]
+        fn round_trip_int() {
+            assert_round_trip("<value><int>-3</int></value>", Value::Int(-3));
+        }
+
+        #[test]
+        fn round_trip_i4() {
+            assert_round_trip("<value><i4>3</i4></value>", Value::Int(3));
+        }
+
+        #[test]
+        fn round_trip_i8() {
+            assert_round_trip(
+                "<value><i8>5000000000</i8></value>",
+                Value::Long(5000000000),
+            );
+        }
     }
 
     mod string_value {
@@ -1578,6 +1770,65 @@ mod tests {
         }
     }
 
+    mod value {
+        use crate::value::{DateTimeRawIso8601, Value};
+
+        use super::*;
+
+        #[test]
+        fn struct_and_array() {
+            let input = r#"<value><struct>
+                <member><name>id</name><value><i4>42</i4></value></member>
+                <member><name>tags</name><value><array><data>
+                    <value>a</value>
+                    <value><boolean>1</boolean></value>
+                </data></array></value></member>
+                <member><name>at</name><value>
+                    <dateTime.iso8601>19980717T14:08:55</dateTime.iso8601>
+                </value></member>
+            </struct></value>"#;
+
+            let actual: Value = value_from_str(input).unwrap();
+
+            assert_eq!(actual.get("id").and_then(Value::as_i32), Some(42));
+            let tags = actual.get("tags").unwrap();
+            assert_eq!(tags.index(0).and_then(Value::as_str), Some("a"));
+            assert_eq!(tags.index(1).and_then(Value::as_bool), Some(true));
+            assert_eq!(tags.index(2), None);
+            assert_eq!(
+                actual.get("at").and_then(Value::as_date_time),
+                Some(&DateTimeRawIso8601::new("19980717T14:08:55"))
+            );
+            assert_eq!(actual.get("unknown"), None);
+            assert_eq!(actual.as_i32(), None);
+        }
+
+        #[test]
+        fn round_trip() {
+            let value = Value::Struct(vec![
+                ("n".into(), Value::Nil),
+                (
+                    "at".into(),
+                    Value::DateTimeIso8601(DateTimeRawIso8601::new("19980717T14:08:55")),
+                ),
+                (
+                    "list".into(),
+                    Value::Array(vec![Value::Double(0.5), Value::Base64(vec![1, 2])]),
+                ),
+            ]);
+            let mut output = String::new();
+            crate::ser::method_response(
+                &crate::MethodResponse::Success(&value),
+                xrs_writer::CompactXmlWrite::new(&mut output),
+            )
+            .unwrap();
+
+            let actual: crate::MethodResponse<Value> = method_response_from_str(&output).unwrap();
+
+            assert_eq!(actual, crate::MethodResponse::Success(value));
+        }
+    }
+
     mod ignore_any {
         use serde::de::IgnoredAny;
 
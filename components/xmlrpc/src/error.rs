@@ -1,4 +1,5 @@
 use crate::de::DeError;
+use crate::Fault;
 use std::fmt::{Debug, Display, Formatter, Write};
 use std::ops::Deref;
 
@@ -79,6 +80,12 @@ impl From<std::io::Error> for XmlRpcError {
     }
 }
 
+impl<'a> From<Fault<'a>> for XmlRpcError {
+    fn from(fault: Fault<'a>) -> Self {
+        Self::new_fault(fault.code(), fault.message())
+    }
+}
+
 impl From<DeError> for XmlRpcError {
     fn from(err: DeError) -> Self {
         Self(Box::new(Repr::De(err)))
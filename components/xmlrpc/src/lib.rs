@@ -9,6 +9,7 @@ use serde::{Deserialize, Serialize};
 pub mod client;
 pub mod de;
 mod error;
+pub mod multicall;
 pub mod ser;
 pub mod value;
 
@@ -76,6 +77,10 @@ where
     }
 }
 
+/// Fault returned instead of a result
+///
+/// (De)serialized as `<struct>` with the members `faultCode` and `faultString`, which is the
+/// content of `<fault><value>` in a method response.
 #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
 pub struct Fault<'a> {
     #[serde(rename = "faultCode")]
@@ -85,6 +90,21 @@ pub struct Fault<'a> {
 }
 
 impl<'a> Fault<'a> {
+    pub fn new(code: i32, message: impl Into<Cow<'a, str>>) -> Self {
+        Self {
+            fault_code: code,
+            fault_string: message.into(),
+        }
+    }
+
+    pub fn code(&self) -> i32 {
+        self.fault_code
+    }
+
+    pub fn message(&self) -> &str {
+        &self.fault_string
+    }
+
     pub fn into_owned(self) -> Fault<'static> {
         Fault {
             fault_code: self.fault_code,
@@ -111,6 +131,14 @@ impl<'a, T> MethodResponse<'a, T> {
             MethodResponse::Fault(fault) => MethodResponse::Fault(fault.into_owned()),
         }
     }
+
+    /// Result of the call, a fault converts into [`XmlRpcError`] with `?`
+    pub fn into_result(self) -> Result<T, Fault<'a>> {
+        match self {
+            MethodResponse::Success(success) => Ok(success),
+            MethodResponse::Fault(fault) => Err(fault),
+        }
+    }
 }
 
 impl<'a, T> Clone for MethodResponse<'a, T>
@@ -167,4 +195,74 @@ mod tests {
         let result = 2 + 2;
         assert_eq!(result, 4);
     }
+
+    mod fault {
+        use xrs_writer::CompactXmlWrite;
+
+        use crate::de::method_response_from_str;
+        use crate::ser::method_response;
+        use crate::{Fault, MethodResponse, XmlRpcError};
+
+        const RESPONSE: &str = r#"<?xml version="1.0"?>
+            <methodResponse>
+                <fault>
+                    <value>
+                        <struct>
+                            <member>
+                                <name>faultCode</name>
+                                <value><int>4</int></value>
+                            </member>
+                            <member>
+                                <name>faultString</name>
+                                <value><string>Too many parameters.</string></value>
+                            </member>
+                        </struct>
+                    </value>
+                </fault>
+            </methodResponse>"#;
+
+        #[test]
+        fn into_result() {
+            let response: MethodResponse<i32> = method_response_from_str(RESPONSE).unwrap();
+            let fault = response.into_result().unwrap_err();
+            assert_eq!(Fault::new(4, "Too many parameters."), fault);
+            assert_eq!(4, fault.code());
+            assert_eq!("Too many parameters.", fault.message());
+
+            let response: MethodResponse<i32> = MethodResponse::Success(42);
+            assert_eq!(Ok(42), response.into_result());
+        }
+
+        #[test]
+        fn into_error() {
+            fn call() -> Result<i32, XmlRpcError> {
+                let response: MethodResponse<i32> = method_response_from_str(RESPONSE)?;
+                Ok(response.into_result()?)
+            }
+
+            assert_eq!(
+                "XML-RPC fault 4: Too many parameters.",
+                call().unwrap_err().to_string()
+            );
+        }
+
+        #[test]
+        fn round_trip() {
+            let mut buf = String::new();
+            let response: MethodResponse<i32> =
+                MethodResponse::Fault(Fault::new(4, "Too many parameters."));
+            method_response(&response, CompactXmlWrite::new(&mut buf)).unwrap();
+            assert_eq!(
+                "<methodResponse><fault><value><struct>\
+                 <member><name>faultCode</name><value><i4>4</i4></value></member>\
+                 <member><name>faultString</name>\
+                 <value><string>Too many parameters.</string></value></member>\
+                 </struct></value></fault></methodResponse>",
+                buf
+            );
+
+            let decoded: MethodResponse<i32> = method_response_from_str(&buf).unwrap();
+            assert_eq!(response, decoded);
+        }
+    }
 }
@@ -0,0 +1,211 @@
+//! Support for the `system.multicall` convention to bundle several calls in one request
+
+use std::borrow::Cow;
+
+use serde::de::Error as _;
+use serde::Serialize;
+use xrs_writer::XmlWrite;
+
+use crate::de::DeError;
+use crate::value::Value;
+use crate::{Fault, MethodResponse, XmlRpcError};
+
+/// Method name of a multicall
+pub const MULTICALL_METHOD_NAME: &str = "system.multicall";
+
+#[derive(Serialize)]
+struct Call<'a> {
+    #[serde(rename = "methodName")]
+    method_name: Cow<'a, str>,
+    params: Vec<Value<'a>>,
+}
+
+/// Builder for a `system.multicall` method call
+#[derive(Default)]
+pub struct MultiCall<'a> {
+    calls: Vec<Call<'a>>,
+}
+
+impl<'a> MultiCall<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a call to the multicall
+    pub fn call(mut self, method_name: impl Into<Cow<'a, str>>, params: Vec<Value<'a>>) -> Self {
+        self.push(method_name, params);
+        self
+    }
+
+    /// Add a call to the multicall
+    pub fn push(&mut self, method_name: impl Into<Cow<'a, str>>, params: Vec<Value<'a>>) {
+        self.calls.push(Call {
+            method_name: method_name.into(),
+            params,
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.calls.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.calls.is_empty()
+    }
+
+    pub fn to_string(&self) -> Result<String, XmlRpcError> {
+        self.check()?;
+        crate::ser::method_call_to_string(MULTICALL_METHOD_NAME, &(&self.calls,))
+    }
+
+    pub fn to_writer<W: std::io::Write>(&self, writer: W) -> Result<(), XmlRpcError> {
+        self.check()?;
+        crate::ser::method_call_to_writer(writer, MULTICALL_METHOD_NAME, &(&self.calls,))
+    }
+
+    pub fn write(
+        &self,
+        xml_write: impl XmlWrite<Error = std::io::Error>,
+    ) -> Result<(), XmlRpcError> {
+        self.check()?;
+        crate::ser::method_call(MULTICALL_METHOD_NAME, &(&self.calls,), xml_write)
+    }
+
+    fn check(&self) -> Result<(), XmlRpcError> {
+        if self.calls.is_empty() {
+            Err(XmlRpcError::new_ser("multicall without calls"))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Split the response of a multicall into the responses of the single calls
+///
+/// A fault of a single call is returned as [`MethodResponse::Fault`] at its position, while
+/// a fault of the multicall itself is returned as error.
+pub fn multicall_response_from_str(
+    s: &str,
+) -> Result<Vec<MethodResponse<'_, Value<'_>>>, XmlRpcError> {
+    let responses = crate::de::method_response_from_str::<Vec<Value>>(s)?.into_result()?;
+
+    responses.into_iter().map(call_response).collect()
+}
+
+fn call_response(value: Value<'_>) -> Result<MethodResponse<'_, Value<'_>>, XmlRpcError> {
+    match value {
+        Value::Array(mut values) if values.len() == 1 => {
+            Ok(MethodResponse::Success(values.pop().unwrap()))
+        }
+        Value::Struct(members) => {
+            let mut fault_code = None;
+            let mut fault_string = None;
+            for (name, value) in members {
+                match (name.as_ref(), value) {
+                    ("faultCode", Value::Int(code)) => fault_code = Some(code),
+                    ("faultString", Value::String(string)) => fault_string = Some(string),
+                    _ => {}
+                }
+            }
+            match (fault_code, fault_string) {
+                (Some(fault_code), Some(fault_string)) => Ok(MethodResponse::Fault(Fault {
+                    fault_code,
+                    fault_string,
+                })),
+                _ => Err(
+                    DeError::custom("multicall fault without `faultCode` or `faultString`").into(),
+                ),
+            }
+        }
+        _ => Err(DeError::custom(
+            "expected one-element array or fault struct in multicall response",
+        )
+        .into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn call() {
+        let call = MultiCall::new()
+            .call("add", vec![Value::Int(1), Value::Int(2)])
+            .call("ping", vec![])
+            .to_string()
+            .unwrap();
+
+        assert_eq!(
+            call,
+            "<methodCall><methodName>system.multicall</methodName>\
+             <params><param><value><array><data>\
+             <value><struct>\
+             <member><name>methodName</name><value><string>add</string></value></member>\
+             <member><name>params</name><value><array><data>\
+             <value><i4>1</i4></value><value><i4>2</i4></value>\
+             </data></array></value></member>\
+             </struct></value>\
+             <value><struct>\
+             <member><name>methodName</name><value><string>ping</string></value></member>\
+             <member><name>params</name><value><array><data></data></array></value></member>\
+             </struct></value>\
+             </data></array></value></param></params></methodCall>"
+        );
+    }
+
+    #[test]
+    fn empty_call() {
+        assert!(MultiCall::new().to_string().is_err());
+    }
+
+    #[test]
+    fn response() {
+        let input = r#"<?xml version="1.0"?>
+            <methodResponse>
+                <params>
+                    <param>
+                        <value><array><data>
+                            <value><array><data><value><i4>3</i4></value></data></array></value>
+                            <value><struct>
+                                <member><name>faultCode</name><value><int>4</int></value></member>
+                                <member><name>faultString</name><value>Too many params</value></member>
+                            </struct></value>
+                        </data></array></value>
+                    </param>
+                </params>
+            </methodResponse>"#;
+
+        let actual = multicall_response_from_str(input).unwrap();
+
+        assert_eq!(
+            actual,
+            vec![
+                MethodResponse::Success(Value::Int(3)),
+                MethodResponse::Fault(Fault {
+                    fault_code: 4,
+                    fault_string: "Too many params".into()
+                })
+            ]
+        );
+    }
+
+    #[test]
+    fn fault_response() {
+        let input = r#"<methodResponse><fault><value><struct>
+                <member><name>faultCode</name><value><int>1</int></value></member>
+                <member><name>faultString</name><value>Unknown method</value></member>
+            </struct></value></fault></methodResponse>"#;
+
+        assert!(multicall_response_from_str(input).is_err());
+    }
+
+    #[test]
+    fn invalid_response() {
+        let input = r#"<methodResponse><params><param><value><array><data>
+                <value><i4>3</i4></value>
+            </data></array></value></param></params></methodResponse>"#;
+
+        assert!(multicall_response_from_str(input).is_err());
+    }
+}
@@ -5,8 +5,10 @@ use serde::ser::{
 };
 use serde::{Serialize, Serializer};
 use std::fmt::Display;
+use std::io;
+use std::ops::Range;
 use xrs_writer::write::Utf8Writer;
-use xrs_writer::{CompactXmlWrite, XmlWrite, XmlWriter};
+use xrs_writer::{CompactXmlWrite, XmlStagWrite, XmlWrite, XmlWriter};
 
 pub fn method_call_to_string<T>(method_name: &str, params: &T) -> Result<String, XmlRpcError>
 where
@@ -103,7 +105,7 @@ impl serde::ser::Error for XmlRpcError {
 }
 
 struct XmlRpcSerializer<W: XmlWrite> {
-    writer: XmlWriter<'static, W>,
+    writer: XmlWriter<W>,
 }
 
 impl<'w, W: XmlWrite<Error = std::io::Error>> Serializer for &'w mut XmlRpcSerializer<W> {
@@ -139,8 +141,11 @@ impl<'w, W: XmlWrite<Error = std::io::Error>> Serializer for &'w mut XmlRpcSeria
         Ok(())
     }
 
-    fn serialize_i64(self, _: i64) -> Result<Self::Ok, Self::Error> {
-        panic!("64-bit integer not supported")
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
+        self.writer.element("i8")?.finish()?;
+        self.writer.characters(&v.to_string())?;
+        self.writer.end_element()?;
+        Ok(())
     }
 
     fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
@@ -151,12 +156,21 @@ impl<'w, W: XmlWrite<Error = std::io::Error>> Serializer for &'w mut XmlRpcSeria
         self.serialize_i32(v as i32)
     }
 
-    fn serialize_u32(self, _: u32) -> Result<Self::Ok, Self::Error> {
-        panic!("unsigned 32-bit integer not supported")
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
+        match i32::try_from(v) {
+            Ok(v) => self.serialize_i32(v),
+            Err(_) => self.serialize_i64(v as i64),
+        }
     }
 
-    fn serialize_u64(self, _: u64) -> Result<Self::Ok, Self::Error> {
-        panic!("unsigned 64-bit integer not supported")
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
+        match i64::try_from(v) {
+            Ok(v) => self.serialize_i64(v),
+            Err(_) => Err(XmlRpcError::new_ser(format!(
+                "unsigned integer {} exceeds 64-bit signed integer",
+                v
+            ))),
+        }
     }
 
     fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
@@ -223,12 +237,21 @@ impl<'w, W: XmlWrite<Error = std::io::Error>> Serializer for &'w mut XmlRpcSeria
 
     fn serialize_newtype_struct<T: ?Sized>(
         self,
-        _name: &'static str,
+        name: &'static str,
         value: &T,
     ) -> Result<Self::Ok, Self::Error>
     where
         T: Serialize,
     {
+        if name == "dateTime.iso8601" {
+            let mut buf = String::new();
+            value.serialize(MemberNameSerializer::new(&mut buf))?;
+            self.writer.element("dateTime.iso8601")?.finish()?;
+            self.writer.characters(&buf)?;
+            self.writer.end_element()?;
+            return Ok(());
+        }
+
         value.serialize(self)
     }
 
@@ -271,8 +294,8 @@ impl<'w, W: XmlWrite<Error = std::io::Error>> Serializer for &'w mut XmlRpcSeria
         ArraySerializer::start(self)
     }
 
-    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
-        StructSerializer::start(self)
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        StructSerializer::start_sorted(self, len)
     }
 
     fn serialize_struct(
@@ -386,14 +409,63 @@ impl<'s, W: XmlWrite<Error = std::io::Error>> SerializeTupleStruct for ArraySeri
     }
 }
 
+/// Serializer for a `<struct>`
+///
+/// Fields of structs are written in declaration order. The values of map members are recorded
+/// and written sorted by name with the writer of the serializer when the map ends, so the
+/// output does not depend on the iteration order of the map: a `HashMap` is written the same
+/// way as the equivalent `BTreeMap`. Members with equal names keep their order.
 pub struct StructSerializer<'s, W: XmlWrite> {
     ser: &'s mut XmlRpcSerializer<W>,
+    /// Name of the members of a map and the range of their `<value>` in `markup`
+    sorted: Option<Vec<(String, Range<usize>)>>,
+    /// Recorded `<value>` elements of the members of a map
+    markup: Vec<Markup>,
+    /// Name of the map member whose value is serialized next
+    key: Option<String>,
 }
 
 impl<'s, W: XmlWrite<Error = std::io::Error>> StructSerializer<'s, W> {
     fn start(ser: &'s mut XmlRpcSerializer<W>) -> Result<Self, XmlRpcError> {
         ser.writer.element("struct")?.finish()?;
-        Ok(Self { ser })
+        Ok(Self {
+            ser,
+            sorted: None,
+            markup: vec![],
+            key: None,
+        })
+    }
+
+    fn start_sorted(
+        ser: &'s mut XmlRpcSerializer<W>,
+        len: Option<usize>,
+    ) -> Result<Self, XmlRpcError> {
+        ser.writer.element("struct")?.finish()?;
+        Ok(Self {
+            ser,
+            sorted: Some(Vec::with_capacity(len.unwrap_or(0))),
+            markup: vec![],
+            key: None,
+        })
+    }
+
+    /// Record a map member to write it sorted in `end`
+    fn push_member<T: ?Sized + Serialize>(
+        &mut self,
+        name: String,
+        value: &T,
+    ) -> Result<(), XmlRpcError> {
+        let start = self.markup.len();
+        let mut ser = XmlRpcSerializer {
+            writer: XmlWriter::without_decl(MarkupRecorder(&mut self.markup)),
+        };
+        ser.writer.element("value")?.finish()?;
+        value.serialize(&mut ser)?;
+        ser.writer.end_element()?;
+
+        let range = start..self.markup.len();
+        self.sorted.get_or_insert_with(Vec::new).push((name, range));
+        Ok(())
     }
 
     fn serialize_member<T: ?Sized>(
@@ -418,12 +490,10 @@ impl<'s, W: XmlWrite<Error = std::io::Error>> StructSerializer<'s, W> {
         Ok(())
     }
 
-    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), XmlRpcError> {
-        self.ser.writer.element("name")?.finish()?;
+    fn member_name<T: ?Sized + Serialize>(key: &T) -> Result<String, XmlRpcError> {
         let mut buf = String::new();
         key.serialize(MemberNameSerializer(&mut buf))?;
-        self.ser.writer.end_element()?;
-        Ok(())
+        Ok(buf)
     }
 
     fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), XmlRpcError> {
@@ -434,6 +504,19 @@ impl<'s, W: XmlWrite<Error = std::io::Error>> StructSerializer<'s, W> {
     }
 
     fn end(self) -> Result<(), XmlRpcError> {
+        if let Some(mut members) = self.sorted {
+            members.sort_by(|(a, _), (b, _)| a.cmp(b));
+            for (name, range) in members {
+                self.ser.writer.element("member")?.finish()?;
+                self.ser.writer.element("name")?.finish()?;
+                self.ser.writer.characters(&name)?;
+                self.ser.writer.end_element()?;
+                for markup in &self.markup[range] {
+                    markup.write(&mut self.ser.writer)?;
+                }
+                self.ser.writer.end_element()?;
+            }
+        }
         self.ser.writer.end_element()?;
         Ok(())
     }
@@ -467,17 +550,19 @@ impl<'s, W: XmlWrite<Error = std::io::Error>> SerializeMap for StructSerializer<
     where
         T: Serialize,
     {
-        self.ser.writer.element("member")?.finish()?;
-        self.serialize_key(key)
+        self.key = Some(Self::member_name(key)?);
+        Ok(())
     }
 
     fn serialize_value<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
     where
         T: Serialize,
     {
-        self.serialize_value(value)?;
-        self.ser.writer.end_element()?;
-        Ok(())
+        let name = self
+            .key
+            .take()
+            .ok_or_else(|| XmlRpcError::new_ser("map value without key"))?;
+        self.push_member(name, value)
     }
 
     fn serialize_entry<K: ?Sized, V: ?Sized>(
@@ -489,16 +574,12 @@ impl<'s, W: XmlWrite<Error = std::io::Error>> SerializeMap for StructSerializer<
         K: Serialize,
         V: Serialize,
     {
-        self.ser.writer.element("member")?.finish()?;
-        self.serialize_key(key)?;
-        self.serialize_value(value)?;
-        self.ser.writer.end_element()?;
-        Ok(())
+        let name = Self::member_name(key)?;
+        self.push_member(name, value)
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        self.ser.writer.end_element()?;
-        Ok(())
+        self.end()
     }
 }
 
@@ -522,6 +603,126 @@ impl<'s, W: XmlWrite<Error = std::io::Error>> SerializeStructVariant for StructS
     }
 }
 
+/// Markup recorded by [`MarkupRecorder`]
+enum Markup {
+    STag {
+        name: String,
+        attributes: Vec<(String, String)>,
+        empty: bool,
+    },
+    Characters(String),
+    ETag,
+}
+
+impl Markup {
+    fn write<W: XmlWrite<Error = io::Error>>(&self, writer: &mut XmlWriter<W>) -> io::Result<()> {
+        match self {
+            Markup::STag {
+                name,
+                attributes,
+                empty,
+            } => {
+                let mut element = writer.element(name)?;
+                for (key, value) in attributes {
+                    element = element.attribute(key, value)?;
+                }
+                if *empty {
+                    element.finish_empty()
+                } else {
+                    element.finish()
+                }
+            }
+            Markup::Characters(characters) => writer.characters(characters),
+            Markup::ETag => writer.end_element(),
+        }
+    }
+}
+
+/// Writer recording the markup of values to write it later
+struct MarkupRecorder<'m>(&'m mut Vec<Markup>);
+
+impl<'m> MarkupRecorder<'m> {
+    fn unsupported(what: &str) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("{} in XML-RPC value", what),
+        )
+    }
+}
+
+impl<'m> XmlWrite for MarkupRecorder<'m> {
+    type Error = io::Error;
+    type StagWrite<'w>
+        = StagRecorder<'w>
+    where
+        Self: 'w;
+
+    fn write_stag(&mut self, name: &str) -> io::Result<StagRecorder<'_>> {
+        self.0.push(Markup::STag {
+            name: name.to_string(),
+            attributes: vec![],
+            empty: false,
+        });
+        Ok(StagRecorder(&mut *self.0))
+    }
+
+    fn write_comment(&mut self, _comment: &str) -> io::Result<()> {
+        Err(Self::unsupported("comment"))
+    }
+
+    fn write_characters(&mut self, characters: &str) -> io::Result<()> {
+        self.0.push(Markup::Characters(characters.to_string()));
+        Ok(())
+    }
+
+    fn write_cdata(&mut self, _cdata: &str) -> io::Result<()> {
+        Err(Self::unsupported("CDATA section"))
+    }
+
+    fn write_pi(&mut self, _target: &str, _data: Option<&str>) -> io::Result<()> {
+        Err(Self::unsupported("processing instruction"))
+    }
+
+    fn write_xmldecl(
+        &mut self,
+        _version: Option<&str>,
+        _standalone: Option<bool>,
+        _write_encoding: bool,
+    ) -> io::Result<()> {
+        Err(Self::unsupported("XML declaration"))
+    }
+
+    fn write_etag(&mut self, _name: &str) -> io::Result<()> {
+        self.0.push(Markup::ETag);
+        Ok(())
+    }
+}
+
+/// Start tag writer of [`MarkupRecorder`], which adds to the recorded start tag
+struct StagRecorder<'w>(&'w mut Vec<Markup>);
+
+impl<'w> XmlStagWrite for StagRecorder<'w> {
+    type Error = io::Error;
+
+    fn write_attribute(&mut self, key: &str, value: &str) -> io::Result<()> {
+        if let Some(Markup::STag { attributes, .. }) = self.0.last_mut() {
+            attributes.push((key.to_string(), value.to_string()));
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn finish_empty(&mut self) -> io::Result<()> {
+        if let Some(Markup::STag { empty, .. }) = self.0.last_mut() {
+            *empty = true;
+        }
+        Ok(())
+    }
+}
+
 struct MemberNameSerializer<'a>(&'a mut String);
 
 impl<'a> MemberNameSerializer<'a> {
@@ -1063,3 +1264,83 @@ impl<'s, W: XmlWrite<Error = std::io::Error>> SerializeStructVariant
         self.end()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap};
+
+    use xrs_writer::escape::AsciiEscaper;
+
+    use super::*;
+
+    const SORTED: &str = "<methodCall><methodName>m</methodName><params><param><value>\
+        <struct>\
+        <member><name>a</name><value><i4>1</i4></value></member>\
+        <member><name>b</name><value><i4>2</i4></value></member>\
+        <member><name>c</name><value><i4>3</i4></value></member>\
+        <member><name>d</name><value><i4>4</i4></value></member>\
+        </struct>\
+        </value></param></params></methodCall>";
+
+    #[test]
+    fn hash_map_sorted() {
+        let map: HashMap<&str, i32> = [("c", 3), ("a", 1), ("d", 4), ("b", 2)]
+            .into_iter()
+            .collect();
+        let first = method_call_to_string("m", &(&map,)).unwrap();
+        let second = method_call_to_string("m", &(&map,)).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(SORTED, first);
+    }
+
+    #[test]
+    fn btree_map() {
+        let map: BTreeMap<&str, i32> = [("c", 3), ("a", 1), ("d", 4), ("b", 2)]
+            .into_iter()
+            .collect();
+        assert_eq!(SORTED, method_call_to_string("m", &(&map,)).unwrap());
+    }
+
+    #[test]
+    fn nested_map_values() {
+        let mut inner = HashMap::new();
+        inner.insert("y", "<2>");
+        inner.insert("x", "1");
+        let mut map = HashMap::new();
+        map.insert("b", inner);
+        map.insert("a", HashMap::new());
+        assert_eq!(
+            "<methodCall><methodName>m</methodName><params><param><value>\
+             <struct>\
+             <member><name>a</name><value><struct></struct></value></member>\
+             <member><name>b</name><value><struct>\
+             <member><name>x</name><value><string>1</string></value></member>\
+             <member><name>y</name><value><string>&lt;2&gt;</string></value></member>\
+             </struct></value></member>\
+             </struct>\
+             </value></param></params></methodCall>",
+            method_call_to_string("m", &(&map,)).unwrap()
+        );
+    }
+
+    #[test]
+    fn map_values_with_serializer_writer() {
+        let map: HashMap<&str, &str> = [("b", "ü"), ("a", "é")].into_iter().collect();
+        let mut buf = String::new();
+        method_call(
+            "m",
+            &(&map,),
+            CompactXmlWrite::with_escaper(&mut buf, AsciiEscaper),
+        )
+        .unwrap();
+        assert_eq!(
+            "<methodCall><methodName>m</methodName><params><param><value>\
+             <struct>\
+             <member><name>a</name><value><string>&#xE9;</string></value></member>\
+             <member><name>b</name><value><string>&#xFC;</string></value></member>\
+             </struct>\
+             </value></param></params></methodCall>",
+            buf
+        );
+    }
+}
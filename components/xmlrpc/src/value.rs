@@ -15,6 +15,19 @@ impl<'a> DateTimeRawIso8601<'a> {
     pub fn new(date_time: impl Into<Cow<'a, str>>) -> Self {
         Self(date_time.into())
     }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl<'a> Serialize for DateTimeRawIso8601<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_newtype_struct("dateTime.iso8601", self.as_str())
+    }
 }
 
 #[cfg(datetime)]
@@ -52,6 +65,8 @@ impl<'de> Deserialize<'de> for DateTimeIso8601 {
 #[derive(Debug, PartialEq)]
 pub enum Value<'a> {
     Int(i32),
+    /// 64-bit integer of the `<i8>` extension
+    Long(i64),
     Boolean(bool),
     String(Cow<'a, str>),
     Double(f64),
@@ -62,6 +77,91 @@ pub enum Value<'a> {
     Nil,
 }
 
+impl<'a> Value<'a> {
+    pub fn as_i32(&self) -> Option<i32> {
+        match self {
+            Value::Int(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Value of `Int` or `Long`
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Int(v) => Some(*v as i64),
+            Value::Long(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Boolean(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Double(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_date_time(&self) -> Option<&DateTimeRawIso8601<'a>> {
+        match self {
+            Value::DateTimeIso8601(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Value::Base64(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value<'a>]> {
+        match self {
+            Value::Array(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_struct(&self) -> Option<&[(Cow<'a, str>, Value<'a>)]> {
+        match self {
+            Value::Struct(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn is_nil(&self) -> bool {
+        matches!(self, Value::Nil)
+    }
+
+    /// Value of the first struct member with the name
+    pub fn get(&self, name: &str) -> Option<&Value<'a>> {
+        self.as_struct()?
+            .iter()
+            .find(|(member, _)| member == name)
+            .map(|(_, value)| value)
+    }
+
+    /// Array element at the index
+    #[allow(clippy::should_implement_trait)]
+    pub fn index(&self, index: usize) -> Option<&Value<'a>> {
+        self.as_array()?.get(index)
+    }
+}
+
 // Deserialize
 
 struct ValueDeserializeVisitor;
@@ -105,13 +205,7 @@ impl<'de> Visitor<'de> for ValueDeserializeVisitor {
     where
         E: Error,
     {
-        match i32::try_from(v) {
-            Ok(v) => Ok(Value::Int(v)),
-            Err(_) => Err(Error::invalid_value(
-                Unexpected::Signed(v),
-                &"signed 32-bit integer value",
-            )),
-        }
+        Ok(Value::Long(v))
     }
 
     fn visit_u8<E>(self, v: u8) -> Result<Self::Value, E>
@@ -134,10 +228,7 @@ impl<'de> Visitor<'de> for ValueDeserializeVisitor {
     {
         match i32::try_from(v) {
             Ok(v) => Ok(Value::Int(v)),
-            Err(_) => Err(Error::invalid_value(
-                Unexpected::Unsigned(v as u64),
-                &"signed 32-bit integer value",
-            )),
+            Err(_) => Ok(Value::Long(v as i64)),
         }
     }
 
@@ -145,12 +236,15 @@ impl<'de> Visitor<'de> for ValueDeserializeVisitor {
     where
         E: Error,
     {
-        match i32::try_from(v) {
-            Ok(v) => Ok(Value::Int(v)),
-            Err(_) => Err(Error::invalid_value(
+        if let Ok(v) = i32::try_from(v) {
+            Ok(Value::Int(v))
+        } else if let Ok(v) = i64::try_from(v) {
+            Ok(Value::Long(v))
+        } else {
+            Err(Error::invalid_value(
                 Unexpected::Unsigned(v),
-                &"signed 32-bit integer value",
-            )),
+                &"signed 64-bit integer value",
+            ))
         }
     }
 
@@ -242,7 +336,9 @@ impl<'de> Visitor<'de> for ValueDeserializeVisitor {
     where
         D: Deserializer<'de>,
     {
-        deserializer.deserialize_any(self)
+        // only date time values are newtype structs
+        let date_time = String::deserialize(deserializer)?;
+        Ok(Value::DateTimeIso8601(DateTimeRawIso8601::new(date_time)))
     }
 
     fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
@@ -286,12 +382,11 @@ impl<'de> Serialize for Value<'de> {
     {
         match self {
             Value::Int(v) => serializer.serialize_i32(*v),
+            Value::Long(v) => serializer.serialize_i64(*v),
             Value::Boolean(v) => serializer.serialize_bool(*v),
             Value::String(v) => serializer.serialize_str(v.as_ref()),
             Value::Double(v) => serializer.serialize_f64(*v),
-            Value::DateTimeIso8601(_v) => {
-                todo!()
-            }
+            Value::DateTimeIso8601(v) => v.serialize(serializer),
             Value::Base64(v) => serializer.serialize_bytes(v),
             Value::Struct(v) => {
                 let mut ser = serializer.serialize_map(Some(v.len()))?;
@@ -1,10 +1,16 @@
 use std::collections::HashMap;
 
+use xrs_parser::QName;
+
 use crate::datamodel::Node;
 use crate::functions::FunctionLibrary;
 use crate::object::Object;
 use crate::XPathError;
 
+/// Function registered with [`Context::register_function`]
+pub type CustomFunction<'i, 't> =
+    Box<dyn Fn(&[Object<'i, 't>]) -> Result<Object<'i, 't>, XPathError>>;
+
 pub struct Context<'i, 't> {
     /// context node
     node: &'t Node<'i>,
@@ -16,16 +22,57 @@ pub struct Context<'i, 't> {
     variable_bindings: HashMap<String, Object<'i, 't>>,
     /// function library
     function_library: FunctionLibrary,
+    /// functions registered by the user
+    custom_functions: HashMap<String, CustomFunction<'i, 't>>,
     /// set of namespace declarations in scope for the expression
     namespaces: HashMap<String, String>,
 }
 
 impl<'i, 't> Context<'i, 't> {
+    /// Context for evaluating an expression at `node` with the core function library
+    pub fn new(node: &'t Node<'i>) -> Self {
+        Self {
+            node,
+            position: 1,
+            size: 1,
+            variable_bindings: HashMap::new(),
+            function_library: FunctionLibrary::default(),
+            custom_functions: HashMap::new(),
+            namespaces: HashMap::new(),
+        }
+    }
+
+    /// Bind the variable `name`, replacing an existing binding
+    ///
+    /// Variables are bound by their qualified name, `$p:x` refers to the name `p:x`.
+    pub fn set_variable(&mut self, name: QName, value: Object<'i, 't>) {
+        self.variable_bindings.insert(name.into(), value);
+    }
+
+    /// Value of the variable `name`
+    pub fn variable(&self, name: &str) -> Result<Object<'i, 't>, XPathError> {
+        self.variable_bindings
+            .get(name)
+            .cloned()
+            .ok_or_else(|| XPathError::UndefinedVariable(name.to_string()))
+    }
+
     pub fn call_function(
         &self,
         name: &str,
         args: Vec<Object<'i, 't>>,
     ) -> Result<Object<'i, 't>, XPathError> {
-        self.function_library.call(name, args)
+        match self.custom_functions.get(name) {
+            Some(fun) if !self.function_library.contains(name) => fun(&args),
+            _ => self.function_library.call(name, args),
+        }
+    }
+
+    /// Register the function `name` called with the evaluated arguments
+    ///
+    /// Functions of the core library take precedence over registered functions with the same
+    /// name. A registered function replaces a previously registered one.
+    pub fn register_function(&mut self, name: QName, fun: CustomFunction<'i, 't>) {
+        self.custom_functions.insert(name.into(), fun);
     }
 }
@@ -1,11 +1,12 @@
 use std::borrow::Cow;
+use std::rc::Rc;
 
 /// Expanded name
 ///
 /// See https://www.w3.org/TR/REC-xml-names/#dt-expname
 #[derive(Debug, PartialEq, Clone)]
 pub struct ExpandedName<'a> {
-    pub namespace: Option<Cow<'a, str>>,
+    pub namespace: Option<Rc<str>>,
     pub local: Cow<'a, str>,
 }
 
@@ -20,8 +21,8 @@ pub struct RootNode<'a> {
 /// See https://www.w3.org/TR/1999/REC-xpath-19991116/#element-nodes
 #[derive(Debug, PartialEq, Clone)]
 pub struct ElementNode<'a> {
-    pub qname: Cow<'a, str>,
-    pub namespace_name: Option<Cow<'a, str>>,
+    pub qname: Rc<str>,
+    pub namespace_name: Option<Rc<str>>,
     pub prefix: Option<Cow<'a, str>>,
     pub local_name: Cow<'a, str>,
     pub attributes: Vec<AttributeNode<'a>>,
@@ -33,8 +34,8 @@ pub struct ElementNode<'a> {
 /// See https://www.w3.org/TR/1999/REC-xpath-19991116/#attribute-nodes
 #[derive(Debug, PartialEq, Clone)]
 pub struct AttributeNode<'a> {
-    pub qname: Cow<'a, str>,
-    pub namespace_name: Option<Cow<'a, str>>,
+    pub qname: Rc<str>,
+    pub namespace_name: Option<Rc<str>>,
     pub prefix: Option<Cow<'a, str>>,
     pub local_name: Cow<'a, str>,
     pub value: Cow<'a, str>,
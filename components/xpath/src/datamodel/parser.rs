@@ -1,12 +1,37 @@
-use xrs_parser::{NsReader, XmlError, XmlNsEvent};
+use std::rc::Rc;
+
+use xrs_parser::{Interner, NsReader, QName, XmlError, XmlNsEvent};
 
 use crate::datamodel::{AttributeNode, Child, ElementNode, PINode, RootNode};
 use crate::utils::CowStrHelpers;
 
-pub struct DomBuilder {}
+/// Builder of the data model of a document
+///
+/// With an interner the qualified names and namespace names of elements and attributes share
+/// one allocation per distinct name.
+#[derive(Default)]
+pub struct DomBuilder {
+    interner: Option<Interner>,
+    /// Buffer for qualified names
+    name: String,
+}
 
 impl DomBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Share allocations between equal names
+    pub fn with_interner(mut self) -> Self {
+        self.interner = Some(Interner::new());
+        self
+    }
+
     pub fn build<'a>(input: &'a str) -> Result<RootNode<'a>, XmlError> {
+        Self::new().parse(input)
+    }
+
+    pub fn parse<'a>(&mut self, input: &'a str) -> Result<RootNode<'a>, XmlError> {
         let mut reader = NsReader::new(input);
 
         let mut root: Vec<Child<'a>> = vec![];
@@ -17,18 +42,18 @@ impl DomBuilder {
                 XmlNsEvent::STag(stag) => stack.push(ElementNode {
                     namespace_name: reader
                         .resolve_element_namespace(&stag.qname)?
-                        .map(|x| x.to_string().into()),
-                    qname: stag.qname.to_string().into(),
+                        .map(|x| self.intern(x)),
+                    qname: self.intern_qname(&stag.qname),
                     prefix: stag.qname.prefix.clone(),
                     local_name: stag.qname.local_part.clone(),
                     attributes: {
                         let mut attrs = Vec::with_capacity(reader.attributes().len());
                         for attr in reader.attributes() {
                             attrs.push(AttributeNode {
-                                qname: attr.qname.to_string().into(),
+                                qname: self.intern_qname(&attr.qname),
                                 namespace_name: reader
                                     .resolve_attribute_namespace(&attr.qname)?
-                                    .map(|x| x.to_string().into()),
+                                    .map(|x| self.intern(x)),
                                 prefix: attr.qname.prefix.clone(),
                                 local_name: attr.qname.local_part.clone(),
                                 value: attr.value.clone(),
@@ -50,7 +75,9 @@ impl DomBuilder {
                         unreachable!();
                     }
                 }
-                XmlNsEvent::Characters(cdata) => {
+                XmlNsEvent::Characters(cdata)
+                | XmlNsEvent::CData(cdata)
+                | XmlNsEvent::Whitespace(cdata) => {
                     if let Some(top) = stack.last_mut() {
                         if let Some(Child::Text(ref mut text)) = top.children.last_mut() {
                             text.push_cow(cdata);
@@ -59,6 +86,7 @@ impl DomBuilder {
                         }
                     }
                 }
+                XmlNsEvent::Error(err) => return Err(err),
                 XmlNsEvent::PI(pi) => stack
                     .last_mut()
                     .map(|elem| &mut elem.children)
@@ -78,14 +106,66 @@ impl DomBuilder {
 
         Ok(RootNode { children: root })
     }
+
+    fn intern(&mut self, s: &str) -> Rc<str> {
+        match &mut self.interner {
+            Some(interner) => interner.intern(s),
+            None => Rc::from(s),
+        }
+    }
+
+    fn intern_qname(&mut self, qname: &QName) -> Rc<str> {
+        let mut name = std::mem::take(&mut self.name);
+        name.clear();
+        if let Some(prefix) = &qname.prefix {
+            name.push_str(prefix);
+            name.push(':');
+        }
+        name.push_str(&qname.local_part);
+        let interned = self.intern(&name);
+        self.name = name;
+        interned
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::rc::Rc;
+
     use crate::datamodel::parser::DomBuilder;
+    use crate::datamodel::Child;
 
     #[test]
     fn test() {
         println!("{:#?}", DomBuilder::build("<a attr='value'>x&lt;y<b/></a>"));
     }
+
+    #[test]
+    fn interned_names() {
+        let root = DomBuilder::new()
+            .with_interner()
+            .parse("<r xmlns='urn:r'><a k='1'/><a k='2'/></r>")
+            .unwrap();
+        let elems: Vec<_> = match &root.children[0] {
+            Child::Element(r) => r
+                .children
+                .iter()
+                .filter_map(|child| match child {
+                    Child::Element(elem) => Some(elem),
+                    _ => None,
+                })
+                .collect(),
+            _ => panic!("expected element"),
+        };
+        assert_eq!("a", &*elems[0].qname);
+        assert!(Rc::ptr_eq(&elems[0].qname, &elems[1].qname));
+        assert!(Rc::ptr_eq(
+            elems[0].namespace_name.as_ref().unwrap(),
+            elems[1].namespace_name.as_ref().unwrap()
+        ));
+        assert!(Rc::ptr_eq(
+            &elems[0].attributes[0].qname,
+            &elems[1].attributes[0].qname
+        ));
+    }
 }
@@ -35,6 +35,10 @@ impl FunctionLibrary {
         self.functions.insert(fun.name(), fun);
     }
 
+    pub fn contains(&self, name: &str) -> bool {
+        self.functions.contains_key(name)
+    }
+
     pub fn call<'i, 't>(
         &self,
         name: &str,
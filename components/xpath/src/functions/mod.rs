@@ -37,7 +37,7 @@ fn check_fixed_argument_size<'i, 't>(
     args: &Vec<Object<'i, 't>>,
     n: u16,
 ) -> Result<(), XPathError> {
-    if args.len() == n as usize {
+    if args.len() != n as usize {
         Err(function_argument_error(
             fun,
             args,
@@ -54,7 +54,7 @@ fn check_argument_size<'i, 't>(
     min: u16,
     max: u16,
 ) -> Result<(), XPathError> {
-    if args.len() < min as usize && args.len() > max as usize {
+    if args.len() < min as usize || args.len() > max as usize {
         Err(function_argument_error(
             fun,
             args,
@@ -1,6 +0,0 @@
-use crate::lex::span::Span;
-
-#[derive(Debug)]
-pub struct LexError {
-    span: Span,
-}
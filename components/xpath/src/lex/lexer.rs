@@ -3,29 +3,36 @@ use std::str::FromStr;
 use xrs_chars::{XmlAsciiChar, XmlChar};
 
 use crate::lex::token::{Token, Tokens};
-use crate::lex::{Ident, LexError, Literal, Number, Punct, Spacing};
+use crate::lex::{Ident, Literal, Number, Punct, Spacing, VariableReference};
+use crate::XPathError;
 
 use super::lex_cursor::LexCursor;
 
 struct Reject;
 
 impl FromStr for Tokens {
-    type Err = LexError;
+    type Err = XPathError;
 
     fn from_str(input: &str) -> Result<Self, Self::Err> {
         lex_expr(LexCursor::for_str(input))
     }
 }
 
-fn lex_expr(mut input: LexCursor) -> Result<Tokens, LexError> {
+fn lex_expr(mut input: LexCursor) -> Result<Tokens, XPathError> {
     let mut tokens: Vec<Token> = vec![];
 
     while !input.eof() {
         input = skip_whitespace(&input);
 
-        if let Ok((cursor, punct)) = lex_number(&input) {
-            tokens.push(Token::Number(punct));
+        if let Ok((cursor, number)) = lex_number(&input) {
+            check_number_end(&input, &cursor)?;
+            tokens.push(Token::Number(number));
             input = cursor;
+        } else if let Ok((cursor, var)) = lex_variable_reference(&input) {
+            tokens.push(Token::VariableReference(var));
+            input = cursor;
+        } else if input.next_byte() == Some(b'$') {
+            return Err(unexpected(&input));
         } else if let Ok((cursor, punct)) = lex_punct(&input) {
             tokens.push(Token::Punct(punct));
             input = cursor;
@@ -38,7 +45,7 @@ fn lex_expr(mut input: LexCursor) -> Result<Tokens, LexError> {
         } else if input.eof() {
             break;
         } else {
-            panic!("error: {:?}", input);
+            return Err(unexpected(&input));
         }
     }
 
@@ -96,14 +103,79 @@ fn lex_ncname<'a>(input: &LexCursor<'a>) -> Result<(LexCursor<'a>, Ident), Rejec
     Ok((input.advance(len), Ident::new(buffer, input.span(len))))
 }
 
-fn lex_literal<'a>(input: &LexCursor<'a>) -> Result<(LexCursor<'a>, Literal), Reject> {
-    let mut chars = input.chars();
+/// Error for input no token starts with
+fn unexpected(input: &LexCursor) -> XPathError {
+    match input.next_char() {
+        Some(quote @ '\'') | Some(quote @ '"') => XPathError::syntax(
+            format!("unterminated string literal, missing {}", quote),
+            input.span(input.len()),
+        ),
+        Some('$') => XPathError::syntax("expected variable name after `$`", input.span(1)),
+        Some(ch) => XPathError::syntax(
+            format!("unexpected character {:?}", ch),
+            input.span(ch.len_utf8()),
+        ),
+        None => XPathError::syntax("unexpected end of expression", input.span(0)),
+    }
+}
+
+/// Numbers must not be directly followed by a dot or name, e.g. `1.2.3`, `1e5` or `2px`
+///
+/// Other name characters like `-` start the next token, as in `1-2`.
+fn check_number_end(start: &LexCursor, end: &LexCursor) -> Result<(), XPathError> {
+    match end.next_char() {
+        Some(ch) if ch == '.' || ch.is_xml_name_start_char() => {
+            let len = end
+                .chars()
+                .take_while(|&ch| ch == '.' || ch.is_xml_name_char())
+                .map(char::len_utf8)
+                .sum::<usize>();
+            Err(XPathError::syntax(
+                "malformed number",
+                start.span(start.len() - end.len() + len),
+            ))
+        }
+        _ => Ok(()),
+    }
+}
 
-    let literal: String = match chars.next() {
-        Some('\'') => chars.take_while(|&ch| ch != '\'').collect(),
-        Some('\"') => chars.take_while(|&ch| ch != '\"').collect(),
+fn lex_variable_reference<'a>(
+    input: &LexCursor<'a>,
+) -> Result<(LexCursor<'a>, VariableReference), Reject> {
+    if input.next_byte() != Some(b'$') {
+        return Err(Reject);
+    }
+
+    let (mut cursor, prefix) = lex_ncname(&input.advance(1))?;
+    let mut name = prefix.as_str().to_string();
+    if cursor.next_byte() == Some(b':') {
+        if let Ok((local_cursor, local_name)) = lex_ncname(&cursor.advance(1)) {
+            name.push(':');
+            name.push_str(local_name.as_str());
+            cursor = local_cursor;
+        }
+    }
+
+    let len = input.len() - cursor.len();
+    Ok((
+        cursor,
+        VariableReference {
+            name,
+            span: input.span(len),
+        },
+    ))
+}
+
+fn lex_literal<'a>(input: &LexCursor<'a>) -> Result<(LexCursor<'a>, Literal), Reject> {
+    let quote = match input.next_char() {
+        Some(quote @ '\'') | Some(quote @ '"') => quote,
         _ => return Err(Reject),
     };
+    let end = input.chars().skip(1).position(|ch| ch == quote);
+    let literal: String = match end {
+        Some(end) => input.chars().skip(1).take(end).collect(),
+        None => return Err(Reject),
+    };
 
     let len = literal.as_bytes().len() + 2;
     Ok((
@@ -168,7 +240,7 @@ fn lex_number<'a>(input: &LexCursor<'a>) -> Result<(LexCursor<'a>, Number), Reje
 #[cfg(test)]
 mod tests {
     use crate::lex::lex_cursor::LexCursor;
-    use crate::lex::Tokens;
+    use crate::lex::{Span, Tokens};
 
     use super::*;
 
@@ -240,6 +312,84 @@ mod tests {
         );
     }
 
+    fn spans(input: &str) -> Vec<(String, Span)> {
+        let tokens: Tokens = input.parse().unwrap();
+        tokens
+            .into_iter()
+            .map(|token| (format!("{:?}", token), token.span()))
+            .collect()
+    }
+
+    fn error_span(input: &str) -> Span {
+        match input.parse::<Tokens>() {
+            Err(XPathError::SyntaxError(err)) => err.span,
+            other => panic!("expected syntax error for {:?}, got {:?}", input, other),
+        }
+    }
+
+    #[test]
+    fn number_spans() {
+        assert_eq!(
+            vec![
+                ("Number(1.5)".to_string(), Span::new(2, 5)),
+                ("Punct('+', Alone)".to_string(), Span::new(6, 7)),
+                ("Number(0.25)".to_string(), Span::new(8, 11)),
+                ("Number(7.0)".to_string(), Span::new(12, 14)),
+            ],
+            spans("  1.5 + .25 7.")
+        );
+    }
+
+    #[test]
+    fn literal_spans() {
+        assert_eq!(
+            vec![
+                ("Literal(\"a\\\"b\")".to_string(), Span::new(0, 5)),
+                ("Literal(\"c'd\")".to_string(), Span::new(6, 11)),
+                ("Literal(\"\")".to_string(), Span::new(11, 13)),
+            ],
+            spans("'a\"b' \"c'd\"''")
+        );
+    }
+
+    #[test]
+    fn variable_reference_spans() {
+        assert_eq!(
+            vec![
+                ("VariableReference(\"x\")".to_string(), Span::new(0, 2)),
+                ("Punct('+', Alone)".to_string(), Span::new(3, 4)),
+                ("VariableReference(\"ns:y\")".to_string(), Span::new(5, 10)),
+            ],
+            spans("$x + $ns:y")
+        );
+        assert_tokens("$a*$b".parse().unwrap(), &["$a", "*", "$b"]);
+    }
+
+    #[test]
+    fn unterminated_literal() {
+        assert_eq!(Span::new(2, 6), error_span("1 'abc"));
+        assert_eq!(Span::new(0, 3), error_span("\"a'"));
+    }
+
+    #[test]
+    fn malformed_number() {
+        assert_eq!(Span::new(0, 5), error_span("1.2.3"));
+        assert_eq!(Span::new(2, 5), error_span("1 1e5"));
+        assert_eq!(Span::new(0, 3), error_span("2px + 1"));
+    }
+
+    #[test]
+    fn number_followed_by_minus() {
+        assert_tokens("1-2".parse().unwrap(), &["1", "-", "2"]);
+        assert_tokens("1.5-x".parse().unwrap(), &["1.5", "-", "x"]);
+    }
+
+    #[test]
+    fn invalid_variable_reference() {
+        assert_eq!(Span::new(0, 1), error_span("$ x"));
+        assert_eq!(Span::new(0, 1), error_span("$1"));
+    }
+
     #[test]
     fn tokenize_skip_whitespace() {
         assert_tokens(
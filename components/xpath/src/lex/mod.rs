@@ -1,9 +1,7 @@
-pub use error::LexError;
 pub use span::Span;
 pub use token::*;
 pub use token_cursor::TokenCursor;
 
-mod error;
 mod lex_cursor;
 mod lexer;
 mod span;
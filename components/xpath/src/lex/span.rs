@@ -8,4 +8,9 @@ impl Span {
     pub(crate) fn new(lo: usize, hi: usize) -> Self {
         Self { lo, hi }
     }
+
+    /// Span from the start of `self` to the end of `other`
+    pub(crate) fn to(self, other: Span) -> Self {
+        Self::new(self.lo, other.hi)
+    }
 }
@@ -20,6 +20,10 @@ impl Ident {
     pub fn as_str(&self) -> &str {
         &self.sym
     }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
 }
 
 impl fmt::Display for Ident {
@@ -70,6 +74,28 @@ impl fmt::Debug for Number {
     }
 }
 
+/// `$` followed by a QName
+#[derive(Clone)]
+pub struct VariableReference {
+    /// QName without `$`
+    pub name: String,
+    pub span: Span,
+}
+
+impl fmt::Display for VariableReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "${}", self.name)
+    }
+}
+
+impl fmt::Debug for VariableReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("VariableReference")
+            .field(&self.name)
+            .finish()
+    }
+}
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum Spacing {
     Joined,
@@ -122,6 +148,7 @@ pub enum Token {
     Literal(Literal),
     Number(Number),
     Punct(Punct),
+    VariableReference(VariableReference),
 }
 
 impl Token {
@@ -131,6 +158,7 @@ impl Token {
             Token::Literal(literal) => literal.span,
             Token::Number(number) => number.span,
             Token::Punct(punct) => punct.span,
+            Token::VariableReference(var) => var.span,
         }
     }
 }
@@ -142,6 +170,7 @@ impl fmt::Display for Token {
             Token::Literal(literal) => fmt::Display::fmt(literal, f),
             Token::Number(number) => fmt::Display::fmt(number, f),
             Token::Punct(punct) => fmt::Display::fmt(punct, f),
+            Token::VariableReference(var) => fmt::Display::fmt(var, f),
         }
     }
 }
@@ -153,6 +182,7 @@ impl fmt::Debug for Token {
             Token::Literal(literal) => fmt::Debug::fmt(literal, f),
             Token::Number(number) => fmt::Debug::fmt(number, f),
             Token::Punct(punct) => fmt::Debug::fmt(punct, f),
+            Token::VariableReference(var) => fmt::Debug::fmt(var, f),
         }
     }
 }
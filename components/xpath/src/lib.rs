@@ -1,7 +1,7 @@
 #![allow(dead_code)]
 
-use crate::lex::Span;
-use crate::parser::Expr;
+use crate::lex::{Span, TokenCursor, Tokens};
+use crate::parser::{Expr, RecursiveShuntingYardParser};
 
 macro_rules! token {
     (*) => {
@@ -92,6 +92,7 @@ pub struct SyntaxError {
 pub enum XPathError {
     WrongFunctionArgument(String),
     CallToUndefinedFunction(String),
+    UndefinedVariable(String),
     SyntaxError(SyntaxError),
 }
 
@@ -107,3 +108,11 @@ impl XPathError {
 struct XPath {
     expr: Expr,
 }
+
+impl XPath {
+    pub fn parse(expr: &str) -> Result<Self, XPathError> {
+        let tokens: Tokens = expr.parse()?;
+        let expr = RecursiveShuntingYardParser::new(TokenCursor::new(&tokens)).parse()?;
+        Ok(Self { expr })
+    }
+}
@@ -1,6 +1,6 @@
 use std::fmt::{Display, Formatter};
 
-use crate::lex::{Ident, Literal, Number};
+use crate::lex::{Ident, Literal, Number, VariableReference};
 
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum BinOp {
@@ -108,6 +108,7 @@ pub enum Expr {
     Ident(Ident),
     Literal(Literal),
     Number(Number),
+    VariableReference(VariableReference),
     FunctionCall(FunctionCall),
 }
 
@@ -119,6 +120,7 @@ impl Display for Expr {
             Expr::Ident(ident) => Display::fmt(ident, f),
             Expr::Literal(lit) => Display::fmt(lit, f),
             Expr::Number(num) => Display::fmt(num, f),
+            Expr::VariableReference(var) => Display::fmt(var, f),
             Expr::FunctionCall(func_call) => Display::fmt(func_call, f),
         }
     }
@@ -1,4 +1,4 @@
-use crate::lex::{CloseParenthesisToken, OpenParenthesisToken, Token, TokenCursor};
+use crate::lex::{CloseParenthesisToken, Ident, OpenParenthesisToken, Token, TokenCursor};
 use crate::parser::expr::{BinOp, Expr, ExprBinary, ExprUnary, FunctionCall, UnaryOp};
 use crate::{Span, XPathError};
 
@@ -64,7 +64,7 @@ pub struct RecursiveShuntingYardParser<'a> {
 }
 
 impl<'a> RecursiveShuntingYardParser<'a> {
-    fn new(cur: TokenCursor<'a>) -> Self {
+    pub(crate) fn new(cur: TokenCursor<'a>) -> Self {
         Self {
             cur,
             operands: vec![],
@@ -72,7 +72,7 @@ impl<'a> RecursiveShuntingYardParser<'a> {
         }
     }
 
-    fn parse(mut self) -> Result<Expr, XPathError> {
+    pub(crate) fn parse(mut self) -> Result<Expr, XPathError> {
         self.operators.push(Operator::Sentinel);
         self.e()?;
         if let Some(token) = self.cur.next() {
@@ -105,7 +105,10 @@ impl<'a> RecursiveShuntingYardParser<'a> {
         if let Some(next) = self.cur.next() {
             match next {
                 Token::Ident(ident) => {
-                    let cur = self.cur.consume_first();
+                    let (ident, cur) = match prefixed_function_name(&self.cur) {
+                        Some(name) => name,
+                        None => (ident.clone(), self.cur.consume_first()),
+                    };
                     if let Some(_) = cur.try_consume::<OpenParenthesisToken>() {
                         // function call
                         self.cur = cur.consume_first();
@@ -129,13 +132,11 @@ impl<'a> RecursiveShuntingYardParser<'a> {
                         };
 
                         self.cur = self.cur.expect::<CloseParenthesisToken>();
-                        self.operands.push(Expr::FunctionCall(FunctionCall {
-                            ident: ident.clone(),
-                            args,
-                        }))
+                        self.operands
+                            .push(Expr::FunctionCall(FunctionCall { ident, args }))
                     } else {
                         self.cur = cur;
-                        self.operands.push(Expr::Ident(ident.clone()));
+                        self.operands.push(Expr::Ident(ident));
                     }
                     Ok(())
                 }
@@ -149,6 +150,11 @@ impl<'a> RecursiveShuntingYardParser<'a> {
                     self.cur = self.cur.consume_first();
                     Ok(())
                 }
+                Token::VariableReference(var) => {
+                    self.operands.push(Expr::VariableReference(var.clone()));
+                    self.cur = self.cur.consume_first();
+                    Ok(())
+                }
                 Token::Punct(punct) if punct.as_char() == '(' => {
                     self.cur = self.cur.consume_first();
                     self.operators.push(Operator::Sentinel);
@@ -220,6 +226,21 @@ fn compare_ops(left: &Operator, right: &Operator) -> bool {
     }
 }
 
+/// Function name `prefix:local` followed by `(`
+fn prefixed_function_name<'a>(cursor: &TokenCursor<'a>) -> Option<(Ident, TokenCursor<'a>)> {
+    let prefix = cursor.ident()?;
+    let cursor = cursor.consume_first();
+    if !matches!(cursor.punct(), Some(punct) if punct.as_char() == ':') {
+        return None;
+    }
+    let cursor = cursor.consume_first();
+    let local = cursor.ident()?;
+    let cursor = cursor.consume_first();
+    cursor.try_consume::<OpenParenthesisToken>()?;
+    let name = format!("{}:{}", prefix, local);
+    Some((Ident::new(name, prefix.span().to(local.span())), cursor))
+}
+
 #[allow(clippy::manual_map)]
 fn try_consume_binary_op<'a>(cursor: &TokenCursor<'a>) -> Option<(BinOp, TokenCursor<'a>)> {
     if let Some(cursor) = cursor.try_consume::<token!(+)>() {
@@ -317,6 +338,11 @@ mod tests {
         assert_eq!(Ok("1".to_string()), parse("1"),);
     }
 
+    #[test]
+    fn variable_reference() {
+        assert_eq!(Ok("($a + $b:c)".to_string()), parse("$a + $b:c"),);
+    }
+
     mod functions {
         use super::*;
 
@@ -330,6 +356,11 @@ mod tests {
             assert_eq!(Ok("max(1)".to_string()), parse("max(1)"),);
         }
 
+        #[test]
+        fn prefixed() {
+            assert_eq!(Ok("my:format(1, x)".to_string()), parse("my:format(1, x)"),);
+        }
+
         #[test]
         fn no_arg() {
             assert_eq!(Ok("max()".to_string()), parse("max()"),);
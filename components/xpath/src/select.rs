@@ -21,6 +21,7 @@ impl Selector for Expr {
             Expr::Ident(_) => todo!(),
             Expr::Literal(string) => Ok(Object::String(string.value.clone().into())),
             Expr::Number(number) => Ok(Object::Number(number.value)),
+            Expr::VariableReference(var) => ctx.variable(&var.name),
             Expr::FunctionCall(func_call) => ctx.call_function(
                 func_call.ident.as_str(),
                 func_call
@@ -58,3 +59,77 @@ impl Selector for ExprBinary {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use xrs_parser::QName;
+
+    use crate::datamodel::{Node, RootNode};
+
+    use super::*;
+
+    fn eval<'i, 't>(expr: &str, ctx: &Context<'i, 't>) -> Result<Object<'i, 't>, XPathError> {
+        XPath::parse(expr)?.select(ctx)
+    }
+
+    #[test]
+    fn variables() {
+        let root = Node::Root(RootNode { children: vec![] });
+        let mut ctx = Context::new(&root);
+        ctx.set_variable(QName::from_str("n").unwrap(), Object::Number(2.0));
+        ctx.set_variable(QName::from_str("my:s").unwrap(), Object::new_string("abc"));
+
+        assert_eq!(Ok(Object::Number(2.0)), eval("$n", &ctx));
+        assert_eq!(Ok(Object::Boolean(true)), eval("boolean($n)", &ctx));
+        assert_eq!(
+            Ok(Object::new_string("abc!")),
+            eval("concat($my:s, '!')", &ctx)
+        );
+
+        ctx.set_variable(QName::from_str("n").unwrap(), Object::Number(0.0));
+        assert_eq!(Ok(Object::Boolean(false)), eval("boolean($n)", &ctx));
+    }
+
+    #[test]
+    fn custom_function() {
+        let root = Node::Root(RootNode { children: vec![] });
+        let mut ctx = Context::new(&root);
+        ctx.register_function(
+            QName::from_str("my:upper").unwrap(),
+            Box::new(|args| match args {
+                [Object::String(s)] => Ok(Object::new_string(s.to_uppercase())),
+                _ => Err(XPathError::WrongFunctionArgument(
+                    "my:upper(string)".to_string(),
+                )),
+            }),
+        );
+        ctx.set_variable(QName::from_str("x").unwrap(), Object::new_string("b"));
+
+        assert_eq!(
+            Ok(Object::new_string("AB")),
+            eval("my:upper(concat('a', $x))", &ctx)
+        );
+        assert_eq!(
+            Ok(Object::new_string("A!")),
+            eval("concat(my:upper('a'), '!')", &ctx)
+        );
+        assert!(matches!(
+            eval("my:upper(1)", &ctx),
+            Err(XPathError::WrongFunctionArgument(_))
+        ));
+        assert_eq!(
+            Err(XPathError::CallToUndefinedFunction("my:lower".to_string())),
+            eval("my:lower('A')", &ctx)
+        );
+    }
+
+    #[test]
+    fn undefined_variable() {
+        let root = Node::Root(RootNode { children: vec![] });
+        let ctx = Context::new(&root);
+        assert_eq!(
+            Err(XPathError::UndefinedVariable("s".to_string())),
+            eval("concat($s, 'x')", &ctx)
+        );
+    }
+}
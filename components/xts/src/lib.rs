@@ -9,6 +9,10 @@ use std::{fs, panic};
 use serde::{Deserialize, Serialize};
 use xserde::from_reader;
 
+pub use native::XrsParser;
+
+mod native;
+
 #[derive(Deserialize, Serialize)]
 #[serde(rename = "TESTSUITE")]
 pub struct TestSuite {
@@ -136,7 +140,7 @@ impl Default for Entities {
     }
 }
 
-#[derive(Deserialize, Serialize, Debug, Hash, Eq, PartialEq)]
+#[derive(Deserialize, Serialize, Debug, Hash, Eq, PartialEq, Copy, Clone)]
 #[allow(non_camel_case_types)]
 pub enum Recommendation {
     #[serde(rename = "XML1.0")]
@@ -163,9 +167,80 @@ impl Default for Recommendation {
     }
 }
 
+impl Recommendation {
+    pub const ALL: [Recommendation; 8] = [
+        Recommendation::Xml_1_0,
+        Recommendation::Xml_1_0_errata2e,
+        Recommendation::Xml_1_0_errata3e,
+        Recommendation::Xml_1_0_errata4e,
+        Recommendation::Xml_1_1,
+        Recommendation::Ns_1_0,
+        Recommendation::Ns_1_0_errata1e,
+        Recommendation::Ns_1_1,
+    ];
+
+    /// Name as used in the `RECOMMENDATION` attribute of the test suite
+    pub fn name(self) -> &'static str {
+        match self {
+            Recommendation::Xml_1_0 => "XML1.0",
+            Recommendation::Xml_1_1 => "XML1.1",
+            Recommendation::Ns_1_0 => "NS1.0",
+            Recommendation::Ns_1_1 => "NS1.1",
+            Recommendation::Xml_1_0_errata2e => "XML1.0-errata2e",
+            Recommendation::Xml_1_0_errata3e => "XML1.0-errata3e",
+            Recommendation::Xml_1_0_errata4e => "XML1.0-errata4e",
+            Recommendation::Ns_1_0_errata1e => "NS1.0-errata1e",
+        }
+    }
+}
+
+/// Result of the well-formedness check of a [`TestableParser`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Support {
+    WellFormed,
+    /// Document is not well-formed with the byte offset of the first error
+    NotWellFormed {
+        message: String,
+        offset: usize,
+    },
+    /// Parser does not support the document, e.g. because it needs an external DTD
+    Unsupported,
+}
+
+/// Result of the validity check of a [`TestableParser`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Validity {
+    Valid,
+    /// Document is not valid with the byte offset of the first error
+    Invalid {
+        message: String,
+        offset: usize,
+    },
+    /// Parser does not validate the document
+    Unsupported,
+}
+
 pub trait TestableParser {
-    fn check_well_formed(&self, input: &[u8], namespace: bool) -> Result<(), (String, usize)>;
+    fn check_well_formed(&self, input: &[u8], namespace: bool) -> Support;
     fn canonxml(&self, input: &[u8], namespace: bool) -> Result<String, Box<dyn Debug>>;
+
+    /// Check the validity of a well-formed document against its DTD
+    ///
+    /// Non-validating parsers keep the default, which ignores the validity of `valid` and
+    /// `invalid` tests.
+    fn check_valid(&self, _input: &[u8], _namespace: bool) -> Validity {
+        Validity::Unsupported
+    }
+}
+
+/// How a test was exercised by [`XmlTester::execute_test`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Execution {
+    Executed,
+    /// Test is of type `error`, which is not checked
+    Skipped,
+    /// Parser reported the test document as [`Support::Unsupported`]
+    Unsupported,
 }
 
 pub struct XmlTester {
@@ -274,42 +349,50 @@ impl XmlTester {
     ) {
         println!("## {}", test.uri);
 
-        let success = panic::catch_unwind(|| Self::execute_test(parser, test, base)).is_ok();
+        let (success, execution) =
+            match panic::catch_unwind(|| Self::execute_test(parser, test, base)) {
+                Ok(execution) => (true, execution),
+                Err(_) => (false, Execution::Executed),
+            };
 
-        report.results.push(XmlTestResult {
+        let result = XmlTestResult {
             name: test.uri.to_string(),
             description: test.description[0].replace('\n', " "),
             ty: test.ty,
+            recommendation: test.recommendation,
             namespace: test.namespace.into(),
             success,
-        });
+            skipped: execution == Execution::Skipped,
+            unsupported: execution == Execution::Unsupported,
+        };
 
-        report.statistic.inc_result(success);
+        report.statistic.add_result(&result);
         report
             .type_statistic
             .entry(test.ty)
-            .and_modify(|s| s.inc_result(success))
-            .or_default();
+            .or_default()
+            .add_result(&result);
+        report.results.push(result);
     }
 
-    pub fn execute_test(parser: &dyn TestableParser, test: &Test, base: &Path) {
+    /// Execute a single test and panic when it fails
+    ///
+    /// Returns whether the test was exercised, skipped because it is of type `error` or not
+    /// supported by the parser.
+    pub fn execute_test(parser: &dyn TestableParser, test: &Test, base: &Path) -> Execution {
         let path = base.join(&test.uri);
         let content = fs::read(&path).unwrap();
 
         let well_formed = parser.check_well_formed(&content, test.namespace.into());
-
-        match well_formed.clone() {
-            Err((message, _)) if message == "<IGNORE>" => return,
-            _ => (),
+        if well_formed == Support::Unsupported {
+            return Execution::Unsupported;
         }
 
         match test.ty {
-            Type::Valid | Type::Invalid => match well_formed {
-                Ok(()) => (),
-                Err((message, offset)) => {
+            Type::Valid | Type::Invalid => {
+                if let Support::NotWellFormed { message, offset } = well_formed {
                     let (line, column) = offset_to_line_and_column(&content, offset).unwrap();
-                    assert!(
-                        false,
+                    panic!(
                         "{}:{}:{}: should be well-formed ({}) [{}]: {}",
                         path.display(),
                         line,
@@ -319,10 +402,10 @@ impl XmlTester {
                         message
                     );
                 }
-            },
-            Type::Error => return,
+            }
+            Type::Error => return Execution::Skipped,
             Type::NotWf => assert!(
-                well_formed.is_err(),
+                matches!(well_formed, Support::NotWellFormed { .. }),
                 "{}:0:0: should not be well-formed ({}) [{}]",
                 path.display(),
                 &test.description[0],
@@ -330,6 +413,30 @@ impl XmlTester {
             ),
         };
 
+        if matches!(test.ty, Type::Valid | Type::Invalid) {
+            match parser.check_valid(&content, test.namespace.into()) {
+                Validity::Invalid { message, offset } if test.ty == Type::Valid => {
+                    let (line, column) = offset_to_line_and_column(&content, offset).unwrap();
+                    panic!(
+                        "{}:{}:{}: should be valid ({}) [{}]: {}",
+                        path.display(),
+                        line,
+                        column,
+                        &test.description[0],
+                        &test.sections,
+                        message
+                    );
+                }
+                Validity::Valid if test.ty == Type::Invalid => panic!(
+                    "{}:0:0: should not be valid ({}) [{}]",
+                    path.display(),
+                    &test.description[0],
+                    &test.sections
+                ),
+                _ => (),
+            }
+        }
+
         if let Some(output) = &test.output {
             match parser.canonxml(&content, test.namespace.into()) {
                 Ok(out) => {
@@ -347,6 +454,8 @@ impl XmlTester {
                 }
             }
         }
+
+        Execution::Executed
     }
 
     pub fn xmlts_root(&self) -> &Path {
@@ -354,17 +463,37 @@ impl XmlTester {
     }
 }
 
+fn type_name(ty: Type) -> &'static str {
+    match ty {
+        Type::Valid => "valid",
+        Type::Invalid => "invalid",
+        Type::NotWf => "not-wf",
+        Type::Error => "error",
+    }
+}
+
+fn yaml_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
 pub struct XmlTestResult {
     pub name: String,
     pub description: String,
     pub ty: Type,
+    pub recommendation: Recommendation,
     pub namespace: bool,
     pub success: bool,
+    /// Test was not exercised
+    pub skipped: bool,
+    /// Parser does not support the test
+    pub unsupported: bool,
 }
 
-#[derive(Clone, Default)]
+#[derive(Clone, Default, Debug, PartialEq)]
 pub struct TestStatistic {
     failed: usize,
+    skipped: usize,
+    unsupported: usize,
     count: usize,
 }
 
@@ -376,10 +505,57 @@ impl TestStatistic {
         }
     }
 
+    pub fn inc_skipped(&mut self) {
+        self.count += 1;
+        self.skipped += 1;
+    }
+
+    pub fn inc_unsupported(&mut self) {
+        self.count += 1;
+        self.unsupported += 1;
+    }
+
+    pub fn add_result(&mut self, result: &XmlTestResult) {
+        if result.skipped {
+            self.inc_skipped();
+        } else if result.unsupported {
+            self.inc_unsupported();
+        } else {
+            self.inc_result(result.success);
+        }
+    }
+
     pub fn merge_with(&mut self, other: &TestStatistic) {
         self.failed += other.failed;
+        self.skipped += other.skipped;
+        self.unsupported += other.unsupported;
         self.count += other.count;
     }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn failed(&self) -> usize {
+        self.failed
+    }
+
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    pub fn unsupported(&self) -> usize {
+        self.unsupported
+    }
+
+    pub fn passed(&self) -> usize {
+        self.executed() - self.failed
+    }
+
+    /// Number of tests which were actually exercised
+    pub fn executed(&self) -> usize {
+        self.count - self.skipped - self.unsupported
+    }
 }
 
 pub struct XmlConfirmReport {
@@ -412,26 +588,29 @@ impl XmlConfirmReport {
     }
 
     fn print_single_statistic(name: &'static str, stat: &TestStatistic) {
-        let success = stat.count - stat.failed;
         println!(
-            "{:20}: {:5} / {:5} ({:3.2}%)",
+            "{:20}: {:5} / {:5} ({:3.2}%), {:5} skipped, {:5} unsupported",
             name,
-            success,
-            stat.count,
-            if stat.count > 0 {
-                (success as f32) / (stat.count as f32) * 100.0
+            stat.passed(),
+            stat.executed(),
+            if stat.executed() > 0 {
+                (stat.passed() as f32) / (stat.executed() as f32) * 100.0
             } else {
                 0.0
-            }
+            },
+            stat.skipped(),
+            stat.unsupported()
         );
     }
 
     pub fn print_statistic(&self) {
         println!(
-            "{} ({}/{})",
+            "{} ({}/{}, {} skipped, {} unsupported)",
             self.name,
-            self.statistic.count - self.statistic.failed,
-            self.statistic.count
+            self.statistic.passed(),
+            self.statistic.executed(),
+            self.statistic.skipped(),
+            self.statistic.unsupported()
         );
         println!();
 
@@ -481,6 +660,17 @@ impl XmlConfirmReport {
                 .get(&false)
                 .unwrap_or(&TestStatistic::default()),
         );
+        println!();
+
+        let mut failures_by_recommendation = HashMap::new();
+        self.compute_failures_by_recommendation(&mut failures_by_recommendation);
+        println!("FAILURES BY RECOMMENDATION");
+        println!("--------------------------\n");
+        for recommendation in &Recommendation::ALL {
+            if let Some(stat) = failures_by_recommendation.get(recommendation) {
+                Self::print_single_statistic(recommendation.name(), stat);
+            }
+        }
     }
 
     pub fn print(&self) {
@@ -494,11 +684,13 @@ impl XmlConfirmReport {
 
         writeln!(
             writer,
-            "{}- {} ({}/{})",
+            "{}- {} ({}/{}, {} skipped, {} unsupported)",
             " ".repeat(indention),
             self.name,
-            self.statistic.count - self.statistic.failed,
-            self.statistic.count
+            self.statistic.passed(),
+            self.statistic.executed(),
+            self.statistic.skipped(),
+            self.statistic.unsupported()
         )
         .unwrap();
 
@@ -519,12 +711,60 @@ impl XmlConfirmReport {
         }
     }
 
+    /// Render all results in the Test Anything Protocol (TAP) version 13
+    ///
+    /// Failed tests get a YAML diagnostic block with the details of the test.
+    pub fn to_tap(&self) -> String {
+        use std::fmt::Write;
+
+        let mut results = Vec::new();
+        self.collect_results(&mut results);
+
+        let mut tap = String::new();
+        writeln!(tap, "TAP version 13").unwrap();
+        writeln!(tap, "1..{}", results.len()).unwrap();
+        for (i, result) in results.iter().enumerate() {
+            writeln!(
+                tap,
+                "{} {} - {}{}",
+                if result.success { "ok" } else { "not ok" },
+                i + 1,
+                result.description.replace('#', "\\#"),
+                if result.skipped {
+                    " # SKIP"
+                } else if result.unsupported {
+                    " # SKIP unsupported"
+                } else {
+                    ""
+                }
+            )
+            .unwrap();
+
+            if !result.success {
+                writeln!(tap, "  ---").unwrap();
+                writeln!(tap, "  name: {}", yaml_quote(&result.name)).unwrap();
+                writeln!(tap, "  type: {}", type_name(result.ty)).unwrap();
+                writeln!(tap, "  namespace: {}", result.namespace).unwrap();
+                writeln!(tap, "  description: {}", yaml_quote(&result.description)).unwrap();
+                writeln!(tap, "  ...").unwrap();
+            }
+        }
+        tap
+    }
+
+    fn collect_results<'a>(&'a self, results: &mut Vec<&'a XmlTestResult>) {
+        for report in &self.subtests {
+            report.collect_results(results);
+        }
+        results.extend(self.results.iter());
+    }
+
     fn compute_failures_by_type(&self, failures: &mut HashMap<Type, TestStatistic>) {
         for result in &self.results {
             failures
                 .entry(result.ty)
                 .or_insert_with(TestStatistic::default)
-                .inc_result(result.success);
+                .add_result(result);
         }
 
         for report in &self.subtests {
@@ -532,12 +772,28 @@ impl XmlConfirmReport {
         }
     }
 
+    fn compute_failures_by_recommendation(
+        &self,
+        failures: &mut HashMap<Recommendation, TestStatistic>,
+    ) {
+        for result in &self.results {
+            failures
+                .entry(result.recommendation)
+                .or_insert_with(TestStatistic::default)
+                .add_result(result);
+        }
+
+        for report in &self.subtests {
+            report.compute_failures_by_recommendation(failures)
+        }
+    }
+
     fn compute_failures_by_namespace(&self, failures: &mut HashMap<bool, TestStatistic>) {
         for result in &self.results {
             failures
                 .entry(result.namespace)
                 .or_insert_with(TestStatistic::default)
-                .inc_result(result.success);
+                .add_result(result);
         }
 
         for report in &self.subtests {
@@ -545,3 +801,170 @@ impl XmlConfirmReport {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(name: &str, description: &str, ty: Type, success: bool) -> XmlTestResult {
+        XmlTestResult {
+            name: name.to_string(),
+            description: description.to_string(),
+            ty,
+            recommendation: Recommendation::Xml_1_0,
+            namespace: true,
+            success,
+            skipped: false,
+            unsupported: false,
+        }
+    }
+
+    fn skipped(name: &str, ty: Type) -> XmlTestResult {
+        XmlTestResult {
+            skipped: true,
+            ..result(name, name, ty, true)
+        }
+    }
+
+    #[test]
+    fn skipped_statistic() {
+        let mut report = XmlConfirmReport::new("root");
+        for result in vec![
+            result("a.xml", "a", Type::Valid, true),
+            result("b.xml", "b", Type::Valid, false),
+            skipped("c.xml", Type::Valid),
+            skipped("d.xml", Type::Error),
+        ] {
+            report.statistic.add_result(&result);
+            report.results.push(result);
+        }
+
+        assert_eq!(4, report.statistic.count());
+        assert_eq!(1, report.statistic.passed());
+        assert_eq!(1, report.statistic.failed());
+        assert_eq!(2, report.statistic.skipped());
+        assert_eq!(2, report.statistic.executed());
+
+        let mut by_type = HashMap::new();
+        report.compute_failures_by_type(&mut by_type);
+        let valid = &by_type[&Type::Valid];
+        assert_eq!((1, 1, 1), (valid.passed(), valid.failed(), valid.skipped()));
+        let error = &by_type[&Type::Error];
+        assert_eq!((0, 0, 1), (error.passed(), error.failed(), error.skipped()));
+    }
+
+    fn unsupported(name: &str, ty: Type) -> XmlTestResult {
+        XmlTestResult {
+            unsupported: true,
+            ..result(name, name, ty, true)
+        }
+    }
+
+    #[test]
+    fn unsupported_statistic() {
+        let mut report = XmlConfirmReport::new("root");
+        for result in vec![
+            result("a.xml", "a", Type::NotWf, true),
+            unsupported("b.xml", Type::NotWf),
+            skipped("c.xml", Type::Error),
+        ] {
+            report.statistic.add_result(&result);
+            report.results.push(result);
+        }
+
+        assert_eq!(3, report.statistic.count());
+        assert_eq!(1, report.statistic.passed());
+        assert_eq!(1, report.statistic.skipped());
+        assert_eq!(1, report.statistic.unsupported());
+        assert_eq!(1, report.statistic.executed());
+
+        let mut by_type = HashMap::new();
+        report.compute_failures_by_type(&mut by_type);
+        let not_wf = &by_type[&Type::NotWf];
+        assert_eq!(
+            (1, 0, 1),
+            (not_wf.passed(), not_wf.failed(), not_wf.unsupported())
+        );
+    }
+
+    #[test]
+    fn recommendation_statistic() {
+        let mut sub = XmlConfirmReport::new("sub");
+        sub.results.push(XmlTestResult {
+            recommendation: Recommendation::Xml_1_1,
+            ..result("a.xml", "a", Type::Valid, false)
+        });
+        let mut report = XmlConfirmReport::new("root");
+        report.subtests.push(sub);
+        report.results.push(result("b.xml", "b", Type::Valid, true));
+        report.results.push(XmlTestResult {
+            recommendation: Recommendation::Xml_1_1,
+            ..result("c.xml", "c", Type::NotWf, true)
+        });
+
+        let mut by_recommendation = HashMap::new();
+        report.compute_failures_by_recommendation(&mut by_recommendation);
+        assert_eq!(2, by_recommendation.len());
+        let xml10 = &by_recommendation[&Recommendation::Xml_1_0];
+        assert_eq!((1, 0), (xml10.passed(), xml10.failed()));
+        let xml11 = &by_recommendation[&Recommendation::Xml_1_1];
+        assert_eq!((1, 1), (xml11.passed(), xml11.failed()));
+        assert_eq!("XML1.1", Recommendation::Xml_1_1.name());
+    }
+
+    #[test]
+    fn tap_unsupported() {
+        let mut report = XmlConfirmReport::new("root");
+        report.results.push(unsupported("a.xml", Type::Valid));
+        assert_eq!(
+            report.to_tap(),
+            "TAP version 13\n1..1\nok 1 - a.xml # SKIP unsupported\n"
+        );
+    }
+
+    #[test]
+    fn merge_keeps_skipped() {
+        let mut stat = TestStatistic::default();
+        stat.inc_result(true);
+        let mut other = TestStatistic::default();
+        other.inc_skipped();
+        stat.merge_with(&other);
+        assert_eq!((2, 1, 1), (stat.count(), stat.passed(), stat.skipped()));
+    }
+
+    #[test]
+    fn tap_skip() {
+        let mut report = XmlConfirmReport::new("root");
+        report.results.push(skipped("a.xml", Type::Error));
+        assert_eq!(
+            report.to_tap(),
+            "TAP version 13\n1..1\nok 1 - a.xml # SKIP\n"
+        );
+    }
+
+    #[test]
+    fn tap() {
+        let mut sub = XmlConfirmReport::new("sub");
+        sub.results
+            .push(result("a.xml", "first test", Type::Valid, true));
+        let mut report = XmlConfirmReport::new("root");
+        report.subtests.push(sub);
+        report
+            .results
+            .push(result("b.xml", "second #2 'test'", Type::NotWf, false));
+
+        assert_eq!(
+            report.to_tap(),
+            "TAP version 13\n\
+             1..2\n\
+             ok 1 - first test\n\
+             not ok 2 - second \\#2 'test'\n\
+             \x20 ---\n\
+             \x20 name: 'b.xml'\n\
+             \x20 type: not-wf\n\
+             \x20 namespace: true\n\
+             \x20 description: 'second #2 ''test'''\n\
+             \x20 ...\n"
+        );
+    }
+}
@@ -0,0 +1,103 @@
+//! [`TestableParser`] for the parsers of this workspace
+
+use std::fmt::Debug;
+
+use xrs_dom::DomBuilder;
+use xrs_parser::encoding;
+use xrs_parser::{Reader, XmlEvent};
+
+use crate::{Support, TestableParser};
+
+/// Well-formedness by [`xrs_parser::Reader`] and canonical XML by [`xrs_dom::OwnedDocument`]
+///
+/// The document for canonical XML is built from the events of the reader, so entities and
+/// default attributes declared in the internal subset are applied.
+///
+/// Namespace constraints are not checked, the reader only checks XML 1.0.
+pub struct XrsParser;
+
+impl TestableParser for XrsParser {
+    fn check_well_formed(&self, input: &[u8], _namespace: bool) -> Support {
+        let bom_len = encoding::detect(input).bom_len();
+        let mut reader = Reader::from_bytes(input);
+        loop {
+            match reader.next() {
+                Ok(Some(_)) => {}
+                Ok(None) => return Support::WellFormed,
+                Err(err) => {
+                    return Support::NotWellFormed {
+                        message: format!("{:?}", err),
+                        offset: bom_len + reader.consumed_len(),
+                    }
+                }
+            }
+        }
+    }
+
+    fn canonxml(&self, input: &[u8], _namespace: bool) -> Result<String, Box<dyn Debug>> {
+        let mut reader = Reader::from_bytes(input);
+        let mut builder = DomBuilder::new();
+        loop {
+            let event = match reader.next() {
+                Ok(Some(XmlEvent::STag(mut stag))) => {
+                    stag.attrs = reader.drain_attributes();
+                    XmlEvent::STag(stag)
+                }
+                Ok(Some(event)) => event,
+                Ok(None) => break,
+                Err(err) => return Err(Box::new(err)),
+            };
+            builder
+                .push(event)
+                .map_err(|err| Box::new(err) as Box<dyn Debug>)?;
+        }
+        builder
+            .finish()
+            .map(|doc| doc.to_canonical_xml())
+            .map_err(|err| Box::new(err) as Box<dyn Debug>)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn well_formed() {
+        assert_eq!(
+            Support::WellFormed,
+            XrsParser.check_well_formed(b"<doc a='1'>text</doc>", false)
+        );
+    }
+
+    #[test]
+    fn error_offset() {
+        match XrsParser.check_well_formed(b"<doc><a></b></doc>", false) {
+            Support::NotWellFormed { offset, .. } => assert_eq!("<doc><a></b>".len(), offset),
+            support => panic!("unexpected {:?}", support),
+        }
+    }
+
+    #[test]
+    fn canonxml() {
+        assert_eq!(
+            "<doc a=\"1\" b=\"2\">&#10;<e></e></doc>",
+            XrsParser
+                .canonxml(b"<doc b='2' a='1'>\n<e/><!-- c --></doc>", false)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn canonxml_internal_subset() {
+        let input = b"<!DOCTYPE doc [\n\
+            <!ATTLIST doc a CDATA 'default'>\n\
+            <!ENTITY e 'text'>\n\
+            ]>\n\
+            <doc>&e;</doc>";
+        assert_eq!(
+            "<doc a=\"default\">text</doc>",
+            XrsParser.canonxml(input, false).unwrap()
+        );
+    }
+}
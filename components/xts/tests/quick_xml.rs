@@ -4,8 +4,8 @@ use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, Event};
 use quick_xml::{Error, Reader};
 use serde::de::Unexpected::Str;
 
-use xrs_xts::TestableParser;
 use xrs_xts::XmlTester;
+use xrs_xts::{Support, TestableParser};
 
 struct QuickXmlIT;
 
@@ -135,46 +135,47 @@ impl QuickXmlIT {
 }
 
 impl TestableParser for QuickXmlIT {
-    fn check_well_formed(&self, input: &[u8], namespace: bool) -> Result<(), (String, usize)> {
+    fn check_well_formed(&self, input: &[u8], namespace: bool) -> Support {
         let mut reader = Reader::from_reader(input);
         reader.trim_text(false);
         reader.check_comments(true);
         reader.check_end_names(true);
 
+        let not_wf = |message| Support::NotWellFormed { message, offset: 0 };
         let mut buf = Vec::new();
         if namespace {
             let mut ns_buf = Vec::new();
             loop {
                 match reader.read_namespaced_event(&mut buf, &mut ns_buf) {
-                    Ok((_, Event::Eof)) => return Ok(()),
+                    Ok((_, Event::Eof)) => return Support::WellFormed,
                     Ok((ns, Event::Start(start))) => {
                         if start
                             .attributes()
                             .with_checks(true)
                             .any(|attr| attr.is_err())
                         {
-                            return Err(("".to_string(), 0));
+                            return not_wf("".to_string());
                         }
                     }
                     Ok(_) => buf.clear(),
-                    Err(err) => return Err((format!("{:?}", err), 0)),
+                    Err(err) => return not_wf(format!("{:?}", err)),
                 }
             }
         } else {
             loop {
                 match reader.read_event(&mut buf) {
-                    Ok(Event::Eof) => return Ok(()),
+                    Ok(Event::Eof) => return Support::WellFormed,
                     Ok(Event::Start(start)) => {
                         if start
                             .attributes()
                             .with_checks(true)
                             .any(|attr| attr.is_err())
                         {
-                            return Err(("".to_string(), 0));
+                            return not_wf("".to_string());
                         }
                     }
                     Ok(_) => buf.clear(),
-                    Err(err) => return Err((format!("{:?}", err), 0)),
+                    Err(err) => return not_wf(format!("{:?}", err)),
                 }
             }
         }